@@ -24,6 +24,13 @@ pub struct EffortCurves {
 }
 
 impl EffortCurves {
+    pub fn new(modes: BTreeMap<String, ModeEffortCurves>, default_mode: String) -> Self {
+        Self {
+            modes,
+            default_mode,
+        }
+    }
+
     fn has_electric_curves(&self) -> bool {
         self.modes.values().any(|mode| mode.is_electric)
     }
@@ -31,6 +38,16 @@ impl EffortCurves {
     pub fn is_electric(&self) -> bool {
         self.has_electric_curves()
     }
+
+    /// Multiply all the max efforts, e.g. by the number of coupled units
+    pub fn scale_efforts(&mut self, factor: f64) {
+        for mode in self.modes.values_mut() {
+            mode.default_curve.scale_efforts(factor);
+            for conditional_curve in &mut mode.curves {
+                conditional_curve.curve.scale_efforts(factor);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema, Hash)]
@@ -41,6 +58,25 @@ pub struct ModeEffortCurves {
     pub is_electric: bool,
 }
 
+impl ModeEffortCurves {
+    /// The curves of a mode whose effort doesn't depend on any condition
+    pub fn new(default_curve: EffortCurve, is_electric: bool) -> Self {
+        Self {
+            curves: vec![],
+            default_curve,
+            is_electric,
+        }
+    }
+
+    pub fn default_curve(&self) -> &EffortCurve {
+        &self.default_curve
+    }
+
+    pub fn conditional_curves(&self) -> &[ConditionalEffortCurve] {
+        &self.curves
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema, Hash)]
 #[serde(deny_unknown_fields)]
 pub struct ConditionalEffortCurve {
@@ -48,6 +84,16 @@ pub struct ConditionalEffortCurve {
     curve: EffortCurve,
 }
 
+impl ConditionalEffortCurve {
+    pub fn cond(&self) -> &EffortCurveConditions {
+        &self.cond
+    }
+
+    pub fn curve(&self) -> &EffortCurve {
+        &self.curve
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema, Hash)]
 #[serde(deny_unknown_fields)]
 pub struct EffortCurveConditions {
@@ -59,6 +105,12 @@ pub struct EffortCurveConditions {
     power_restriction_code: Option<String>,
 }
 
+impl EffortCurveConditions {
+    pub fn power_restriction_code(&self) -> Option<&str> {
+        self.power_restriction_code.as_deref()
+    }
+}
+
 /// Train comfort that will be used for the simulation
 #[derive(
     Display, Clone, Default, Debug, EnumString, PartialEq, Deserialize, Serialize, ToSchema, Hash,
@@ -96,43 +148,56 @@ impl<'de> Deserialize<'de> for EffortCurve {
         }
 
         let inner = InnerParams::deserialize(deserializer)?;
+        EffortCurve::new(inner.speeds, inner.max_efforts).map_err(serde::de::Error::custom)
+    }
+}
 
-        // Validate the curve
-        if inner.max_efforts.len() != inner.speeds.len() {
-            return Err(serde::de::Error::custom(
+impl EffortCurve {
+    /// Build a curve from speeds in m/s and the matching max efforts in N, checking it's valid
+    pub fn new(speeds: Vec<f64>, max_efforts: Vec<f64>) -> Result<Self, &'static str> {
+        if max_efforts.len() != speeds.len() {
+            return Err(
                 "effort curve invalid, max_efforts and speeds arrays should have the same length",
-            ));
+            );
         }
 
-        if inner.max_efforts.len() < 2 {
-            return Err(serde::de::Error::custom(
-                "effort curve should have at least 2 points.",
-            ));
+        if max_efforts.len() < 2 {
+            return Err("effort curve should have at least 2 points.");
         }
 
-        if inner.max_efforts.iter().any(|&x| x < 0.0) {
-            return Err(serde::de::Error::custom(
-                "max_efforts values must be equal or greater than 0.",
-            ));
+        if max_efforts.iter().any(|&x| x < 0.0) {
+            return Err("max_efforts values must be equal or greater than 0.");
         };
 
-        if inner.speeds.iter().any(|&x| x < 0.0) {
-            return Err(serde::de::Error::custom(
-                "speeds values must be equal or greater than 0.",
-            ));
+        if speeds.iter().any(|&x| x < 0.0) {
+            return Err("speeds values must be equal or greater than 0.");
         };
 
-        if inner.speeds.windows(2).any(|window| window[0] >= window[1]) {
-            return Err(serde::de::Error::custom(
-                "speeds values must be strictly increasing.",
-            ));
+        if speeds.windows(2).any(|window| window[0] >= window[1]) {
+            return Err("speeds values must be strictly increasing.");
         }
 
         Ok(EffortCurve {
-            speeds: inner.speeds,
-            max_efforts: inner.max_efforts,
+            speeds,
+            max_efforts,
         })
     }
+
+    /// The speeds of the curve in m/s, strictly increasing
+    pub fn speeds(&self) -> &[f64] {
+        &self.speeds
+    }
+
+    /// The max effort in N at each speed of the curve
+    pub fn max_efforts(&self) -> &[f64] {
+        &self.max_efforts
+    }
+
+    fn scale_efforts(&mut self, factor: f64) {
+        for max_effort in &mut self.max_efforts {
+            *max_effort *= factor;
+        }
+    }
 }
 
 #[cfg(test)]
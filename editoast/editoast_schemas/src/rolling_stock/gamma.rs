@@ -17,3 +17,13 @@ pub struct Gamma {
     #[derivative(Hash(hash_with = "editoast_common::hash_float::<3,_>"))]
     value: f64,
 }
+
+impl Gamma {
+    /// A constant braking deceleration in m/s²
+    pub fn constant(value: f64) -> Self {
+        Self {
+            gamma_type: "CONST".to_string(),
+            value,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Add;
+
 use derivative::Derivative;
 use serde::Deserialize;
 use serde::Serialize;
@@ -21,3 +23,58 @@ pub struct RollingResistance {
     #[derivative(Hash(hash_with = "editoast_common::hash_float::<5,_>"))]
     C: f64,
 }
+
+impl RollingResistance {
+    /// A Davis formula resistance `A + B·v + C·v²`, in N with the speed in m/s
+    #[allow(non_snake_case)]
+    pub fn davis(A: f64, B: f64, C: f64) -> Self {
+        Self {
+            rolling_resistance_type: "davis".to_string(),
+            A,
+            B,
+            C,
+        }
+    }
+
+    /// The resistance of `factor` identical vehicles
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            rolling_resistance_type: self.rolling_resistance_type.clone(),
+            A: self.A * factor,
+            B: self.B * factor,
+            C: self.C * factor,
+        }
+    }
+
+    /// The resistance in N at a speed in m/s
+    pub fn resistance_at(&self, speed: f64) -> f64 {
+        self.A + self.B * speed + self.C * speed * speed
+    }
+
+    /// The speed in m/s, between 0 and `max_speed`, at which the resistance is the lowest
+    pub fn lowest_resistance_speed(&self, max_speed: f64) -> f64 {
+        let vertex = if self.C > 0. {
+            (-self.B / (2. * self.C)).clamp(0., max_speed)
+        } else {
+            0.
+        };
+        [0., max_speed, vertex]
+            .into_iter()
+            .min_by(|a, b| self.resistance_at(*a).total_cmp(&self.resistance_at(*b)))
+            .unwrap_or_default()
+    }
+}
+
+/// The resistance of two vehicles coupled together
+impl Add for RollingResistance {
+    type Output = RollingResistance;
+
+    fn add(self, other: RollingResistance) -> RollingResistance {
+        Self {
+            rolling_resistance_type: self.rolling_resistance_type,
+            A: self.A + other.A,
+            B: self.B + other.B,
+            C: self.C + other.C,
+        }
+    }
+}
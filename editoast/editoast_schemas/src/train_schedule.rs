@@ -24,6 +24,10 @@ pub use comfort::Comfort;
 mod train_schedule_base;
 pub use train_schedule_base::TrainScheduleBase;
 
+mod repetition;
+pub use repetition::DayOfWeek;
+pub use repetition::Repetition;
+
 mod allowance;
 pub use allowance::Allowance;
 pub use allowance::AllowanceDistribution;
@@ -44,6 +48,7 @@ editoast_common::schemas! {
     power_restriction_item::schemas(),
     distribution::schemas(),
     comfort::schemas(),
+    repetition::schemas(),
     // TODO TrainSchedule V1 (it will be removed)
     allowance::schemas(),
     rjs_power_restriction_range::schemas(),
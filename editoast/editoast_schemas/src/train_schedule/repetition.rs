@@ -0,0 +1,120 @@
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Utc;
+use chrono::Weekday;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::primitives::PositiveDuration;
+
+editoast_common::schemas! {
+    Repetition,
+    DayOfWeek,
+}
+
+/// How a train schedule template is repeated to generate a periodic service
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Repetition {
+    /// The time between the departures of two consecutive trains
+    #[schema(value_type = chrono::Duration, example = "PT1H")]
+    pub interval: PositiveDuration,
+    /// The departure time of the first train
+    pub start_time: DateTime<Utc>,
+    /// No train departs after this time
+    pub end_time: DateTime<Utc>,
+    /// The days of the week (in UTC) on which trains depart, every day if empty
+    #[serde(default)]
+    pub days_of_week: Vec<DayOfWeek>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DayOfWeek {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<Weekday> for DayOfWeek {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Mon => Self::Monday,
+            Weekday::Tue => Self::Tuesday,
+            Weekday::Wed => Self::Wednesday,
+            Weekday::Thu => Self::Thursday,
+            Weekday::Fri => Self::Friday,
+            Weekday::Sat => Self::Saturday,
+            Weekday::Sun => Self::Sunday,
+        }
+    }
+}
+
+impl Repetition {
+    /// The departure times of the trains, every `interval` from `start_time` to `end_time`
+    ///
+    /// The iterator never ends if `interval` is zero.
+    pub fn departure_times(&self) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let interval = *self.interval;
+        std::iter::successors(Some(self.start_time), move |time| Some(*time + interval))
+            .take_while(|time| *time <= self.end_time)
+            .filter(|time| {
+                self.days_of_week.is_empty() || self.days_of_week.contains(&time.weekday().into())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use chrono::Utc;
+    use serde_json::from_str;
+
+    use super::Repetition;
+
+    fn departure_times(repetition: &str) -> Vec<String> {
+        let repetition: Repetition = from_str(repetition).unwrap();
+        repetition
+            .departure_times()
+            .map(|time: DateTime<Utc>| time.format("%a %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn hourly_departure_times() {
+        let departure_times = departure_times(
+            r#"{
+                "interval": "PT1H",
+                "start_time": "2024-05-01T06:00:00Z",
+                "end_time": "2024-05-01T09:30:00Z"
+            }"#,
+        );
+
+        assert_eq!(
+            departure_times,
+            vec!["Wed 06:00", "Wed 07:00", "Wed 08:00", "Wed 09:00"]
+        );
+    }
+
+    #[test]
+    fn departure_times_on_days_of_week() {
+        let departure_times = departure_times(
+            r#"{
+                "interval": "PT12H",
+                "start_time": "2024-05-03T08:00:00Z",
+                "end_time": "2024-05-07T08:00:00Z",
+                "days_of_week": ["friday", "monday"]
+            }"#,
+        );
+
+        assert_eq!(
+            departure_times,
+            vec!["Fri 08:00", "Fri 20:00", "Mon 08:00", "Mon 20:00"]
+        );
+    }
+}
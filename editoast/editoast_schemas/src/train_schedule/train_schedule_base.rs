@@ -27,6 +27,15 @@ pub struct TrainScheduleBase {
     #[serde(default)]
     pub labels: Vec<String>,
     pub rolling_stock_name: String,
+    /// The rolling stock the train is pinned to whatever its name, which requires `rolling_stock_version`
+    #[serde(default)]
+    pub rolling_stock_id: Option<i64>,
+    /// The version of the rolling stock the train is pinned to, which requires `rolling_stock_id`
+    #[serde(default)]
+    pub rolling_stock_version: Option<i64>,
+    /// The composition the train is made of, simulated instead of the rolling stock if specified
+    #[serde(default)]
+    pub composition_id: Option<i64>,
     pub start_time: DateTime<Utc>,
     #[schema(inline)]
     pub path: Vec<PathItem>,
@@ -65,6 +74,12 @@ impl<'de> Deserialize<'de> for TrainScheduleBase {
             #[serde(default)]
             labels: Vec<String>,
             rolling_stock_name: String,
+            #[serde(default)]
+            rolling_stock_id: Option<i64>,
+            #[serde(default)]
+            rolling_stock_version: Option<i64>,
+            #[serde(default)]
+            composition_id: Option<i64>,
             start_time: DateTime<Utc>,
             path: Vec<PathItem>,
             #[serde(default)]
@@ -85,6 +100,12 @@ impl<'de> Deserialize<'de> for TrainScheduleBase {
         }
         let internal = Internal::deserialize(deserializer)?;
 
+        if internal.rolling_stock_id.is_some() != internal.rolling_stock_version.is_some() {
+            return Err(SerdeError::custom(
+                "The rolling stock id and version must be specified together",
+            ));
+        }
+
         // Look for invalid path waypoint reference
         let path_ids: HashSet<_> = internal.path.iter().map(|p| &p.id).collect();
         if path_ids.len() != internal.path.len() {
@@ -143,6 +164,9 @@ impl<'de> Deserialize<'de> for TrainScheduleBase {
             train_name: internal.train_name,
             labels: internal.labels,
             rolling_stock_name: internal.rolling_stock_name,
+            rolling_stock_id: internal.rolling_stock_id,
+            rolling_stock_version: internal.rolling_stock_version,
+            composition_id: internal.composition_id,
             start_time: internal.start_time,
             path: internal.path,
             schedule: internal.schedule,
@@ -157,6 +181,13 @@ impl<'de> Deserialize<'de> for TrainScheduleBase {
     }
 }
 
+impl TrainScheduleBase {
+    /// The id and the version of the rolling stock the train is pinned to, if any
+    pub fn rolling_stock_revision(&self) -> Option<(i64, i64)> {
+        self.rolling_stock_id.zip(self.rolling_stock_version)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::Duration;
@@ -177,6 +208,17 @@ mod tests {
         assert!(from_str::<TrainScheduleBase>(train_schedule).is_ok());
     }
 
+    /// Test deserialize a train schedule pinned to a version without rolling stock id
+    #[test]
+    fn deserialize_rolling_stock_version_without_id() {
+        let mut train_schedule: serde_json::Value =
+            from_str(include_str!("../tests/train_schedule_simple.json")).unwrap();
+        train_schedule["rolling_stock_version"] = 1.into();
+        assert!(serde_json::from_value::<TrainScheduleBase>(train_schedule.clone()).is_err());
+        train_schedule["rolling_stock_id"] = 1.into();
+        assert!(serde_json::from_value::<TrainScheduleBase>(train_schedule).is_ok());
+    }
+
     /// Test deserialize an invalid train schedule
     #[test]
     fn deserialize_duplicate_path_id_train_schedule() {
@@ -0,0 +1,11 @@
+CREATE TABLE infra_edit (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    infra_id int8 NOT NULL REFERENCES infra(id) ON DELETE CASCADE,
+    infra_version varchar(40) NOT NULL,
+    creation_date timestamptz NOT NULL DEFAULT NOW(),
+    operations jsonb NOT NULL,
+    inverse_operations jsonb NOT NULL,
+    undone boolean NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX "infra_edit_infra_id" ON "infra_edit" ("infra_id");
@@ -0,0 +1,2 @@
+DROP TABLE infra_snapshot_object;
+DROP TABLE infra_snapshot;
@@ -0,0 +1,18 @@
+CREATE TABLE infra_snapshot (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    infra_id int8 NOT NULL REFERENCES infra(id) ON DELETE CASCADE,
+    name varchar(255) NOT NULL,
+    infra_version varchar(40) NOT NULL,
+    creation_date timestamptz NOT NULL DEFAULT NOW(),
+    UNIQUE (infra_id, name)
+);
+
+CREATE TABLE infra_snapshot_object (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    snapshot_id int8 NOT NULL REFERENCES infra_snapshot(id) ON DELETE CASCADE,
+    obj_type varchar(32) NOT NULL,
+    obj_id varchar(255) NOT NULL,
+    data jsonb NOT NULL
+);
+
+CREATE INDEX "infra_snapshot_object_snapshot_id" ON "infra_snapshot_object" ("snapshot_id");
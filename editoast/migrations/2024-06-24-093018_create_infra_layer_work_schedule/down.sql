@@ -0,0 +1 @@
+DROP TABLE infra_layer_work_schedule;
@@ -0,0 +1,11 @@
+CREATE TABLE infra_layer_work_schedule (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    work_schedule_id int8 NOT NULL REFERENCES work_schedule(id) ON DELETE CASCADE,
+    geographic geometry(multilinestring, 3857) NOT NULL,
+    infra_id int8 NOT NULL REFERENCES infra(id) ON DELETE CASCADE,
+    UNIQUE (infra_id, work_schedule_id)
+);
+
+CREATE INDEX infra_layer_work_schedule_geographic ON infra_layer_work_schedule USING gist (geographic);
+CREATE INDEX infra_layer_work_schedule_infra_id ON infra_layer_work_schedule USING btree (infra_id);
+CREATE INDEX infra_layer_work_schedule_work_schedule_id ON infra_layer_work_schedule USING btree (work_schedule_id);
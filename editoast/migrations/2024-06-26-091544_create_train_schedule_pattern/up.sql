@@ -0,0 +1,11 @@
+CREATE TABLE train_schedule_pattern (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    timetable_id int8 NOT NULL REFERENCES timetable_v2(id) ON DELETE CASCADE,
+    template jsonb NOT NULL,
+    repetition jsonb NOT NULL
+);
+
+ALTER TABLE train_schedule_v2
+ADD COLUMN pattern_id int8 NULL REFERENCES train_schedule_pattern(id) ON DELETE CASCADE;
+
+CREATE INDEX "train_schedule_v2_pattern_id" ON "train_schedule_v2" ("pattern_id");
@@ -0,0 +1,3 @@
+ALTER TABLE train_schedule_v2 DROP COLUMN rolling_stock_id;
+ALTER TABLE train_schedule_v2 DROP COLUMN rolling_stock_version;
+DROP TABLE rolling_stock_revision;
@@ -0,0 +1,27 @@
+CREATE TABLE rolling_stock_revision (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    rolling_stock_id int8 NOT NULL REFERENCES rolling_stock(id) ON DELETE CASCADE,
+    version int8 NOT NULL,
+    name varchar(255) NOT NULL,
+    creation_date timestamptz NOT NULL DEFAULT NOW(),
+    rolling_stock jsonb NOT NULL,
+    UNIQUE (rolling_stock_id, version)
+);
+
+-- Train schedules are pinned to a version of a rolling stock, or to its latest version by name
+ALTER TABLE train_schedule_v2
+ADD COLUMN rolling_stock_version int8 NULL;
+ALTER TABLE train_schedule_v2
+ADD COLUMN rolling_stock_id int8 NULL;
+ALTER TABLE train_schedule_v2
+ADD CONSTRAINT train_schedule_v2_rolling_stock_revision_check CHECK (
+        (rolling_stock_id IS NULL) = (rolling_stock_version IS NULL)
+    );
+
+-- The current version of the existing rolling stocks is their first revision
+INSERT INTO rolling_stock_revision (rolling_stock_id, version, name, rolling_stock)
+SELECT id, version, name, to_jsonb(rs) || jsonb_build_object(
+        'loading_gauge',
+        (ARRAY['G1', 'G2', 'GA', 'GB', 'GB1', 'GC', 'FR3.3', 'FR3.3/GB/G2', 'GLOTT'])[rs.loading_gauge + 1]
+    )
+FROM rolling_stock rs;
@@ -0,0 +1,2 @@
+ALTER TABLE train_schedule_v2 DROP COLUMN composition_id;
+DROP TABLE train_composition;
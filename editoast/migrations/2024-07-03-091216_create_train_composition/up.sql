@@ -0,0 +1,10 @@
+CREATE TABLE train_composition (
+    id int8 PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    name varchar(255) NOT NULL UNIQUE,
+    rolling_stock_id int8 NOT NULL REFERENCES rolling_stock(id) ON DELETE CASCADE,
+    unit_count int4 NOT NULL CHECK (unit_count > 0),
+    wagons jsonb NOT NULL DEFAULT '[]'
+);
+
+ALTER TABLE train_schedule_v2
+ADD COLUMN composition_id int8 NULL;
@@ -0,0 +1 @@
+ALTER TABLE infra_edit DROP COLUMN author;
@@ -0,0 +1 @@
+ALTER TABLE infra_edit ADD COLUMN author varchar(255) NULL;
@@ -5,6 +5,7 @@ mod telemetry_config;
 use std::env;
 use std::path::PathBuf;
 
+use chrono::NaiveDate;
 use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
@@ -68,26 +69,69 @@ pub enum Commands {
 #[derive(Subcommand, Debug)]
 pub enum TimetablesCommands {
     Import(ImportTimetableArgs),
+    ImportGtfs(ImportGtfsArgs),
     Export(ExportTimetableArgs),
 }
 
+#[derive(ValueEnum, Debug, Derivative, Clone, Copy, PartialEq)]
+#[derivative(Default)]
+pub enum TimetableFormat {
+    /// A JSON array of train schedules
+    #[derivative(Default)]
+    Json,
+    /// A railML 3 document
+    Railml,
+}
+
 #[derive(Args, Debug, Derivative)]
 #[derivative(Default)]
-#[command(about, long_about = "Import a train schedule given a JSON file")]
+#[command(
+    about,
+    long_about = "Import a train schedule given a JSON or railML file"
+)]
 pub struct ImportTimetableArgs {
     /// The timetable id on which attach the trains to
     #[arg(long)]
     pub id: Option<i64>,
+    /// The format of the input file
+    #[arg(long, value_enum, default_value_t = TimetableFormat::Json)]
+    pub format: TimetableFormat,
     /// The input file path
     pub path: PathBuf,
 }
 
+#[derive(Args, Debug, Derivative)]
+#[derivative(Default)]
+#[command(
+    about,
+    long_about = "Import the trips of a GTFS feed running on a given date as train schedules"
+)]
+pub struct ImportGtfsArgs {
+    /// The timetable id on which attach the trains to
+    #[arg(long)]
+    pub id: Option<i64>,
+    /// The infra used to match the GTFS stops to operational points
+    #[arg(long)]
+    pub infra_id: i64,
+    /// The service date of the imported trips (YYYY-MM-DD)
+    #[arg(long)]
+    pub date: NaiveDate,
+    /// A JSON file giving the rolling stock name used for each GTFS route type
+    #[arg(long)]
+    pub rolling_stock_mapping: PathBuf,
+    /// The GTFS zip file path
+    pub path: PathBuf,
+}
+
 #[derive(Args, Debug, Derivative)]
 #[derivative(Default)]
 #[command(about, long_about = "Export the train schedules of a given timetable")]
 pub struct ExportTimetableArgs {
     /// The timetable id on which get the train schedules from
     pub id: i64,
+    /// The format of the output file
+    #[arg(long, value_enum, default_value_t = TimetableFormat::Json)]
+    pub format: TimetableFormat,
     /// The output file path
     pub path: PathBuf,
 }
@@ -112,6 +156,8 @@ pub enum InfraCommands {
     Clear(ClearArgs),
     Generate(GenerateArgs),
     ImportRailjson(ImportRailjsonArgs),
+    WarmTiles(WarmTilesArgs),
+    ExportMbtiles(ExportMbtilesArgs),
 }
 
 #[derive(Args, Debug, Derivative, Clone)]
@@ -124,6 +170,25 @@ pub struct MapLayersConfig {
     #[derivative(Default(value = "250_000"))]
     #[arg(long, env, default_value_t = 250_000)]
     pub max_tiles: u64,
+    #[command(flatten)]
+    pub tile_warming_config: TileWarmingConfig,
+}
+
+#[derive(Args, Debug, Derivative, Clone)]
+#[derivative(Default)]
+pub struct TileWarmingConfig {
+    /// First zoom level of the tiles generated when warming up the map layers cache
+    #[derivative(Default(value = "5"))]
+    #[arg(long, env, default_value_t = 5)]
+    pub warm_tiles_min_zoom: u64,
+    /// Last zoom level of the tiles generated when warming up the map layers cache
+    #[derivative(Default(value = "12"))]
+    #[arg(long, env, default_value_t = 12)]
+    pub warm_tiles_max_zoom: u64,
+    /// Number maximum of tiles generated concurrently when warming up the map layers cache
+    #[derivative(Default(value = "8"))]
+    #[arg(long, env, default_value_t = 8)]
+    pub warm_tiles_concurrency: usize,
 }
 
 #[derive(Args, Debug, Derivative)]
@@ -179,6 +244,44 @@ pub struct ImportRailjsonArgs {
     pub generate: bool,
 }
 
+#[derive(Args, Debug)]
+#[command(
+    about,
+    long_about = "Generate the map layer tiles of infras and store them in the redis cache"
+)]
+pub struct WarmTilesArgs {
+    /// List of infra ids (if not provided, all infras are warmed up)
+    pub infra_ids: Vec<u64>,
+    #[command(flatten)]
+    pub tile_warming_config: TileWarmingConfig,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    about,
+    long_about = "Export the map layers of an infra as an MBTiles archive"
+)]
+pub struct ExportMbtilesArgs {
+    /// Infra id
+    pub infra_id: u64,
+    /// Path of the MBTiles file to create
+    pub path: PathBuf,
+    /// First exported zoom level
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(0..=20))]
+    pub min_zoom: u64,
+    /// Last exported zoom level (included)
+    #[arg(long, default_value_t = 14, value_parser = clap::value_parser!(u64).range(0..=20))]
+    pub max_zoom: u64,
+    /// Only export the tiles intersecting this bounding box (by default the whole infra is exported)
+    #[arg(
+        long,
+        num_args = 4,
+        value_names = ["MIN_LON", "MIN_LAT", "MAX_LON", "MAX_LAT"],
+        allow_negative_numbers = true
+    )]
+    pub bbox: Option<Vec<f64>>,
+}
+
 #[derive(Args, Debug)]
 #[command(about, long_about = "Add a set of electrical profiles")]
 pub struct ImportProfileSetArgs {
@@ -218,9 +321,32 @@ pub struct ListProfileSetArgs {
     pub quiet: bool,
 }
 
-#[derive(Args, Debug)]
-#[command(about, long_about = "Import a rolling stock given a json file")]
+#[derive(ValueEnum, Debug, Derivative, Clone, Copy, PartialEq)]
+#[derivative(Default)]
+pub enum RollingStockFormat {
+    /// A JSON rolling stock
+    #[derivative(Default)]
+    Json,
+    /// A railML 3 document, whose vehicles are each imported as a rolling stock
+    Railml,
+}
+
+#[derive(Args, Debug, Derivative)]
+#[derivative(Default)]
+#[command(
+    about,
+    long_about = "Import a rolling stock given a JSON or railML file"
+)]
 pub struct ImportRollingStockArgs {
+    /// The format of the rolling stock files
+    #[arg(long, value_enum, default_value_t = RollingStockFormat::Json)]
+    pub format: RollingStockFormat,
+    /// A CSV table of tractive efforts replacing the effort curves of the rolling stocks
+    #[arg(long)]
+    pub effort_table: Option<PathBuf>,
+    /// Validate the rolling stocks and print them as JSON without saving them
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
     /// Rolling stock file path
     pub rolling_stock_path: Vec<PathBuf>,
 }
@@ -68,14 +68,14 @@ pub enum PropertyElectrificationValue {
 pub struct OperationalPointOnPath {
     /// Id of the operational point
     #[schema(inline)]
-    id: Identifier,
+    pub id: Identifier,
     /// The part along the path
-    part: OperationalPointPart,
+    pub part: OperationalPointPart,
     /// Extensions associated to the operational point
     #[serde(default)]
-    extensions: OperationalPointExtensions,
+    pub extensions: OperationalPointExtensions,
     /// Distance from the beginning of the path in mm
-    position: u64,
+    pub position: u64,
 }
 
 impl<'a> AsCoreRequest<Json<PathPropertiesResponse>> for PathPropertiesRequest<'a> {
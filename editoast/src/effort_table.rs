@@ -0,0 +1,164 @@
+//! Import of tractive effort curves from a CSV table
+//!
+//! The first column gives speeds in km/h, followed by a column per traction mode giving the max
+//! effort in kN at each speed:
+//!
+//! ```text
+//! speed;25000V;thermal
+//! 0;300;250,5
+//! 10;295;
+//! 20;280;240
+//! ```
+//!
+//! Columns are separated by `,` or `;`, in which case `,` can also be the decimal separator.
+//! A mode gives no effort at the speeds of its empty cells. The `thermal` mode is the only one
+//! which isn't electric, and the first mode is the default one.
+
+use std::collections::BTreeMap;
+
+use editoast_schemas::rolling_stock::EffortCurve;
+use editoast_schemas::rolling_stock::EffortCurves;
+use editoast_schemas::rolling_stock::ModeEffortCurves;
+use thiserror::Error;
+
+const THERMAL_MODE: &str = "thermal";
+
+#[derive(Debug, Error)]
+pub enum EffortTableError {
+    #[error("Invalid effort table: {0}")]
+    InvalidFile(#[from] csv::Error),
+    #[error("The effort table has no traction mode column")]
+    NoMode,
+    #[error("Line {line}, column '{column}': invalid number '{value}'")]
+    InvalidNumber {
+        line: u64,
+        column: String,
+        value: String,
+    },
+    #[error("Line {line}: missing speed")]
+    MissingSpeed { line: u64 },
+    #[error("Column '{column}': {message}")]
+    InvalidCurve {
+        column: String,
+        message: &'static str,
+    },
+}
+
+/// Parse an effort table into the effort curves of a rolling stock, in m/s and N
+pub fn parse_effort_table(content: &str) -> Result<EffortCurves, EffortTableError> {
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = if header.contains(';') { b';' } else { b',' };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let modes: Vec<String> = reader.headers()?.iter().skip(1).map(String::from).collect();
+    if modes.is_empty() {
+        return Err(EffortTableError::NoMode);
+    }
+
+    let mut curves: Vec<(Vec<f64>, Vec<f64>)> = vec![Default::default(); modes.len()];
+    for record in reader.records() {
+        let record = record?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+        let parse = |column: &str, value: &str| -> Result<Option<f64>, EffortTableError> {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            let number = if delimiter == b';' {
+                value.replace(',', ".")
+            } else {
+                value.to_string()
+            };
+            number
+                .parse()
+                .map(Some)
+                .map_err(|_| EffortTableError::InvalidNumber {
+                    line,
+                    column: column.to_string(),
+                    value: value.to_string(),
+                })
+        };
+
+        let speed = parse("speed", record.get(0).unwrap_or_default())?
+            .ok_or(EffortTableError::MissingSpeed { line })?;
+        for (index, mode) in modes.iter().enumerate() {
+            if let Some(effort) = parse(mode, record.get(index + 1).unwrap_or_default())? {
+                let (speeds, max_efforts) = &mut curves[index];
+                speeds.push(speed / 3.6);
+                max_efforts.push(effort * 1000.);
+            }
+        }
+    }
+
+    let default_mode = modes[0].clone();
+    let modes = modes
+        .into_iter()
+        .zip(curves)
+        .map(|(mode, (speeds, max_efforts))| {
+            let curve = EffortCurve::new(speeds, max_efforts).map_err(|message| {
+                EffortTableError::InvalidCurve {
+                    column: mode.clone(),
+                    message,
+                }
+            })?;
+            let is_electric = mode != THERMAL_MODE;
+            Ok((mode, ModeEffortCurves::new(curve, is_electric)))
+        })
+        .collect::<Result<BTreeMap<_, _>, EffortTableError>>()?;
+    Ok(EffortCurves::new(modes, default_mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::parse_effort_table;
+    use super::EffortTableError;
+
+    #[test]
+    fn parse_table_with_decimal_commas() {
+        let table = "speed;25000V;thermal\n0;300;250,5\n36;295;\n72;280;240\n";
+        let effort_curves = serde_json::to_value(parse_effort_table(table).unwrap()).unwrap();
+
+        assert_eq!(effort_curves["default_mode"], json!("25000V"));
+        assert_eq!(
+            effort_curves["modes"]["thermal"]["default_curve"],
+            json!({ "speeds": [0.0, 20.0], "max_efforts": [250500.0, 240000.0] })
+        );
+        assert_eq!(
+            effort_curves["modes"]["25000V"]["default_curve"]["speeds"],
+            json!([0.0, 10.0, 20.0])
+        );
+        assert_eq!(effort_curves["modes"]["25000V"]["is_electric"], json!(true));
+        assert_eq!(
+            effort_curves["modes"]["thermal"]["is_electric"],
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn invalid_number_is_located() {
+        let table = "speed,1500V\n0,300\n10,high\n";
+        let error = parse_effort_table(table).unwrap_err();
+        let EffortTableError::InvalidNumber { line, column, .. } = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!((line, column.as_str()), (3, "1500V"));
+    }
+
+    #[test]
+    fn invalid_curve_is_located() {
+        let table = "speed,1500V,thermal\n0,300,200\n10,290,\n";
+        let error = parse_effort_table(table).unwrap_err();
+        assert!(
+            matches!(&error, EffortTableError::InvalidCurve { column, .. } if column == "thermal")
+        );
+    }
+}
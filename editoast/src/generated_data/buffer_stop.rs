@@ -1,20 +1,14 @@
 use async_trait::async_trait;
-use diesel::delete;
-use diesel::query_dsl::methods::FilterDsl;
 use diesel::sql_query;
-use diesel::sql_types::Array;
 use diesel::sql_types::BigInt;
-use diesel::sql_types::Text;
 use diesel_async::RunQueryDsl;
 
 use super::utils::InvolvedObjects;
 use super::GeneratedData;
-use crate::diesel::ExpressionMethods;
 use crate::error::Result;
 use crate::infra_cache::operation::CacheOperation;
 use crate::infra_cache::InfraCache;
 use crate::modelsv2::DbConnection;
-use crate::tables::infra_layer_buffer_stop::dsl;
 use editoast_schemas::primitives::ObjectType;
 
 pub struct BufferStopLayer;
@@ -39,28 +33,13 @@ impl GeneratedData for BufferStopLayer {
         operations: &[CacheOperation],
         infra_cache: &InfraCache,
     ) -> Result<()> {
-        let involved_objects =
-            InvolvedObjects::from_operations(operations, infra_cache, ObjectType::BufferStop);
-
-        // Delete elements
-        if !involved_objects.deleted.is_empty() {
-            delete(
-                dsl::infra_layer_buffer_stop
-                    .filter(dsl::infra_id.eq(infra))
-                    .filter(dsl::obj_id.eq_any(involved_objects.deleted)),
+        InvolvedObjects::from_operations(operations, infra_cache, ObjectType::BufferStop)
+            .regenerate_layer(
+                conn,
+                infra,
+                Self::table_name(),
+                include_str!("sql/insert_update_buffer_stop_layer.sql"),
             )
-            .execute(conn)
-            .await?;
-        }
-
-        // Update elements
-        if !involved_objects.updated.is_empty() {
-            sql_query(include_str!("sql/insert_update_buffer_stop_layer.sql"))
-                .bind::<BigInt, _>(infra)
-                .bind::<Array<Text>, _>(involved_objects.updated.into_iter().collect::<Vec<_>>())
-                .execute(conn)
-                .await?;
-        }
-        Ok(())
+            .await
     }
 }
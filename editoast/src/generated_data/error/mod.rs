@@ -12,7 +12,6 @@ pub mod track_sections;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::pin::Pin;
 
 use async_trait::async_trait;
 use diesel::prelude::*;
@@ -20,18 +19,21 @@ use diesel::sql_query;
 use diesel::sql_types::Array;
 use diesel::sql_types::BigInt;
 use diesel::sql_types::Json;
+use diesel::sql_types::Jsonb;
 use diesel::sql_types::Text;
 use diesel_async::RunQueryDsl;
-use futures_util::Future;
 use itertools::Itertools;
 use serde_json::to_value;
 use sha1::Digest;
 use sha1::Sha1;
 use tracing::warn;
 
+use super::utils::InvolvedObjects;
 use super::GeneratedData;
 use crate::error::Result;
 use crate::generated_data::infra_error::InfraError;
+use crate::generated_data::infra_error::InfraErrorType;
+use crate::generated_data::infra_error::InfraErrorTypeLabel;
 use crate::infra_cache::operation::CacheOperation;
 use crate::infra_cache::Graph;
 use crate::infra_cache::InfraCache;
@@ -136,11 +138,42 @@ async fn generate_errors<Ctx: Default>(
     object_err_generators: &'static ObjectErrorGenerators<Ctx>,
     global_err_generators: &'static GlobalErrorGenerators<Ctx>,
 ) -> Vec<InfraError> {
+    // Generate object errors
+    let (mut errors, mut context) = generate_object_errors(
+        infra_cache.get_objects_by_type(object_type).values(),
+        infra_cache,
+        graph,
+        object_err_generators,
+    );
+
+    // Generate global errors
+    for f in global_err_generators.iter() {
+        let new_errors = match f {
+            GlobalErrorGenerator::NoContext(check_function) => (check_function)(infra_cache, graph),
+            GlobalErrorGenerator::WithContext(check_function) => {
+                let (new_errors, new_ctx) = (check_function)(infra_cache, graph, context);
+                context = new_ctx;
+                new_errors
+            }
+        };
+        // Add errors to the list
+        errors.extend(new_errors);
+    }
+    errors
+}
+
+/// Generate the errors of a list of objects given static object error generators.
+/// Returns the errors and the context filled by the generators.
+fn generate_object_errors<'a, Ctx: Default>(
+    objects: impl Iterator<Item = &'a ObjectCache>,
+    infra_cache: &InfraCache,
+    graph: &Graph<'_>,
+    object_err_generators: &'static ObjectErrorGenerators<Ctx>,
+) -> (Vec<InfraError>, Ctx) {
     let mut errors = Vec::new();
     let mut context = Ctx::default();
 
-    // Generate object errors
-    for el in infra_cache.get_objects_by_type(object_type).values() {
+    for el in objects {
         let mut found_error = false;
         let mut current_priority = 0;
         for f in object_err_generators.iter() {
@@ -166,105 +199,317 @@ async fn generate_errors<Ctx: Default>(
             current_priority = f.get_priority();
         }
     }
+    (errors, context)
+}
 
-    // Generate global errors
-    for f in global_err_generators.iter() {
-        let new_errors = match f {
-            GlobalErrorGenerator::NoContext(check_function) => (check_function)(infra_cache, graph),
-            GlobalErrorGenerator::WithContext(check_function) => {
-                let (new_errors, new_ctx) = (check_function)(infra_cache, graph, context);
-                context = new_ctx;
-                new_errors
-            }
-        };
-        // Add errors to the list
-        errors.extend(new_errors);
+/// Object types whose errors only depend on the object itself and the track sections it references.
+/// Their errors can be generated object by object.
+const OBJECT_SCOPED_TYPES: [ObjectType; 5] = [
+    ObjectType::TrackSection,
+    ObjectType::Signal,
+    ObjectType::SwitchType,
+    ObjectType::Detector,
+    ObjectType::OperationalPoint,
+];
+
+/// Object types whose errors involve several objects, with the object types their errors depend on.
+/// Their errors must be generated all at once.
+const INFRA_SCOPED_TYPES: [(ObjectType, &[ObjectType]); 5] = [
+    (
+        ObjectType::SpeedSection,
+        &[ObjectType::SpeedSection, ObjectType::TrackSection],
+    ),
+    (
+        ObjectType::Route,
+        &[
+            ObjectType::Route,
+            ObjectType::TrackSection,
+            ObjectType::Detector,
+            ObjectType::BufferStop,
+            ObjectType::Switch,
+            ObjectType::SwitchType,
+        ],
+    ),
+    (
+        ObjectType::BufferStop,
+        &[
+            ObjectType::BufferStop,
+            ObjectType::TrackSection,
+            ObjectType::Switch,
+            ObjectType::SwitchType,
+        ],
+    ),
+    (
+        ObjectType::Switch,
+        &[
+            ObjectType::Switch,
+            ObjectType::SwitchType,
+            ObjectType::TrackSection,
+        ],
+    ),
+    (
+        ObjectType::Electrification,
+        &[ObjectType::Electrification, ObjectType::TrackSection],
+    ),
+];
+
+/// Get the object error generators of an object type listed in [OBJECT_SCOPED_TYPES]
+fn get_object_scoped_generators(obj_type: ObjectType) -> &'static ObjectErrorGenerators<NoContext> {
+    match obj_type {
+        ObjectType::TrackSection => &track_sections::OBJECT_GENERATORS,
+        ObjectType::Signal => &signals::OBJECT_GENERATORS,
+        ObjectType::SwitchType => &switch_types::OBJECT_GENERATORS,
+        ObjectType::Detector => &detectors::OBJECT_GENERATORS,
+        ObjectType::OperationalPoint => &operational_points::OBJECT_GENERATORS,
+        _ => unreachable!("{obj_type:?} errors can't be generated object by object"),
+    }
+}
+
+/// Generate all the errors of an object type listed in [INFRA_SCOPED_TYPES]
+async fn generate_infra_scoped_errors(
+    obj_type: ObjectType,
+    infra_cache: &InfraCache,
+    graph: &Graph<'_>,
+) -> Vec<InfraError> {
+    match obj_type {
+        ObjectType::SpeedSection => {
+            generate_errors(
+                obj_type,
+                infra_cache,
+                graph,
+                &speed_sections::OBJECT_GENERATORS,
+                &speed_sections::GLOBAL_GENERATORS,
+            )
+            .await
+        }
+        ObjectType::Route => {
+            generate_errors(
+                obj_type,
+                infra_cache,
+                graph,
+                &routes::OBJECT_GENERATORS,
+                &routes::GLOBAL_GENERATORS,
+            )
+            .await
+        }
+        ObjectType::BufferStop => {
+            generate_errors(
+                obj_type,
+                infra_cache,
+                graph,
+                &buffer_stops::OBJECT_GENERATORS,
+                &buffer_stops::GLOBAL_GENERATORS,
+            )
+            .await
+        }
+        ObjectType::Switch => {
+            generate_errors(
+                obj_type,
+                infra_cache,
+                graph,
+                &switches::OBJECT_GENERATORS,
+                &[],
+            )
+            .await
+        }
+        ObjectType::Electrification => {
+            generate_errors(
+                obj_type,
+                infra_cache,
+                graph,
+                &electrifications::OBJECT_GENERATORS,
+                &electrifications::GLOBAL_GENERATORS,
+            )
+            .await
+        }
+        _ => unreachable!("{obj_type:?} errors aren't generated for the whole infra"),
     }
-    errors
 }
 
 pub async fn generate_infra_errors(infra_cache: &InfraCache) -> Vec<InfraError> {
     // Create a graph for topological errors
     let graph = Graph::load(infra_cache);
     // Generate the errors
-    let futures: Vec<Pin<Box<dyn Future<Output = _> + Send>>> = vec![
-        Box::pin(generate_errors(
-            ObjectType::TrackSection,
-            infra_cache,
-            &graph,
-            &track_sections::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::Signal,
-            infra_cache,
-            &graph,
-            &signals::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::SpeedSection,
-            infra_cache,
-            &graph,
-            &speed_sections::OBJECT_GENERATORS,
-            &speed_sections::GLOBAL_GENERATORS,
-        )),
-        Box::pin(generate_errors(
-            ObjectType::Route,
-            infra_cache,
-            &graph,
-            &routes::OBJECT_GENERATORS,
-            &routes::GLOBAL_GENERATORS,
-        )),
-        Box::pin(generate_errors(
-            ObjectType::SwitchType,
-            infra_cache,
-            &graph,
-            &switch_types::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::Detector,
-            infra_cache,
-            &graph,
-            &detectors::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::BufferStop,
-            infra_cache,
-            &graph,
-            &buffer_stops::OBJECT_GENERATORS,
-            &buffer_stops::GLOBAL_GENERATORS,
-        )),
-        Box::pin(generate_errors(
-            ObjectType::OperationalPoint,
-            infra_cache,
-            &graph,
-            &operational_points::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::Switch,
-            infra_cache,
-            &graph,
-            &switches::OBJECT_GENERATORS,
-            &[],
-        )),
-        Box::pin(generate_errors(
-            ObjectType::Electrification,
-            infra_cache,
-            &graph,
-            &electrifications::OBJECT_GENERATORS,
-            &electrifications::GLOBAL_GENERATORS,
-        )),
-    ];
+    let mut errors = vec![];
+    for obj_type in OBJECT_SCOPED_TYPES {
+        errors.extend(
+            generate_errors(
+                obj_type,
+                infra_cache,
+                &graph,
+                get_object_scoped_generators(obj_type),
+                &[],
+            )
+            .await,
+        );
+    }
+    for (obj_type, _) in INFRA_SCOPED_TYPES {
+        errors.extend(generate_infra_scoped_errors(obj_type, infra_cache, &graph).await);
+    }
+    errors
+}
 
-    futures::future::join_all(futures)
-        .await
-        .into_iter()
-        .flatten()
-        .collect()
+/// Generate the errors of a list of objects, checked against an infra cache
+///
+/// The objects don't need to be part of the infra cache.
+/// Only object error generators are used: errors of global generators aren't generated.
+pub fn generate_objects_errors<'a>(
+    objects: impl IntoIterator<Item = &'a ObjectCache>,
+    infra_cache: &InfraCache,
+) -> Vec<InfraError> {
+    let graph = Graph::load(infra_cache);
+    let mut errors = vec![];
+    for object in objects {
+        let object_type = object.get_type();
+        let object = std::iter::once(object);
+        let object_errors = match object_type {
+            ObjectType::SpeedSection => {
+                generate_object_errors(
+                    object,
+                    infra_cache,
+                    &graph,
+                    &speed_sections::OBJECT_GENERATORS,
+                )
+                .0
+            }
+            ObjectType::Route => {
+                generate_object_errors(object, infra_cache, &graph, &routes::OBJECT_GENERATORS).0
+            }
+            ObjectType::BufferStop => {
+                generate_object_errors(
+                    object,
+                    infra_cache,
+                    &graph,
+                    &buffer_stops::OBJECT_GENERATORS,
+                )
+                .0
+            }
+            ObjectType::Switch => {
+                generate_object_errors(object, infra_cache, &graph, &switches::OBJECT_GENERATORS).0
+            }
+            ObjectType::Electrification => {
+                generate_object_errors(
+                    object,
+                    infra_cache,
+                    &graph,
+                    &electrifications::OBJECT_GENERATORS,
+                )
+                .0
+            }
+            ObjectType::NeutralSection => vec![],
+            _ => {
+                generate_object_errors(
+                    object,
+                    infra_cache,
+                    &graph,
+                    get_object_scoped_generators(object_type),
+                )
+                .0
+            }
+        };
+        errors.extend(object_errors);
+    }
+    errors
+}
+
+/// Get the object type whose error generators produce a given error
+///
+/// Some global generators report errors on track sections.
+fn get_generator_type(error: &InfraError) -> ObjectType {
+    match error.sub_type {
+        InfraErrorType::MissingBufferStop { .. } => ObjectType::BufferStop,
+        InfraErrorType::MissingRoute => ObjectType::Route,
+        _ => error.obj_type,
+    }
+}
+
+/// Get the types of the errors produced by the generators of an object type, but reported on
+/// objects of another type
+fn get_foreign_error_types(generator_type: ObjectType) -> &'static [InfraErrorTypeLabel] {
+    match generator_type {
+        ObjectType::BufferStop => &[InfraErrorTypeLabel::MissingBufferStop],
+        ObjectType::Route => &[InfraErrorTypeLabel::MissingRoute],
+        _ => &[],
+    }
+}
+
+/// Errors that must be generated again given a list of operations
+#[derive(Debug, Default)]
+struct ErrorScope<'a> {
+    /// Objects whose errors must be generated again, for the types listed in [OBJECT_SCOPED_TYPES]
+    objects: HashMap<ObjectType, HashSet<&'a String>>,
+    /// Object types whose errors must all be generated again, listed in [INFRA_SCOPED_TYPES]
+    types: HashSet<ObjectType>,
+}
+
+impl<'a> ErrorScope<'a> {
+    fn from_operations(operations: &'a [CacheOperation], infra_cache: &'a InfraCache) -> Self {
+        let objects = OBJECT_SCOPED_TYPES
+            .into_iter()
+            .map(|obj_type| {
+                let involved_objects =
+                    InvolvedObjects::from_operations(operations, infra_cache, obj_type);
+                let ids: HashSet<_> = involved_objects
+                    .updated
+                    .union(&involved_objects.deleted)
+                    .copied()
+                    .collect();
+                (obj_type, ids)
+            })
+            .filter(|(_, ids)| !ids.is_empty())
+            .collect();
+
+        let edited_types: HashSet<_> = operations
+            .iter()
+            .map(|operation| match operation {
+                CacheOperation::Create(object_cache) | CacheOperation::Update(object_cache) => {
+                    object_cache.get_type()
+                }
+                CacheOperation::Delete(obj_ref) => obj_ref.obj_type,
+            })
+            .collect();
+        let types = INFRA_SCOPED_TYPES
+            .into_iter()
+            .filter(|(_, dependencies)| dependencies.iter().any(|t| edited_types.contains(t)))
+            .map(|(obj_type, _)| obj_type)
+            .collect();
+
+        Self { objects, types }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.objects.is_empty() && self.types.is_empty()
+    }
+
+    /// Check whether an error belongs to the scope
+    fn contains(&self, error: &InfraError) -> bool {
+        let generator_type = get_generator_type(error);
+        self.types.contains(&generator_type)
+            || self
+                .objects
+                .get(&generator_type)
+                .is_some_and(|ids| ids.contains(&error.obj_id))
+    }
+
+    /// Generate the errors of the scope
+    async fn generate_errors(&self, infra_cache: &InfraCache) -> Vec<InfraError> {
+        let graph = Graph::load(infra_cache);
+        let mut errors = vec![];
+        for (obj_type, ids) in self.objects.iter() {
+            let objects = infra_cache.get_objects_by_type(*obj_type);
+            let (object_errors, _) = generate_object_errors::<NoContext>(
+                ids.iter().filter_map(|id| objects.get(*id)),
+                infra_cache,
+                &graph,
+                get_object_scoped_generators(*obj_type),
+            );
+            errors.extend(object_errors);
+        }
+        for obj_type in self.types.iter() {
+            errors.extend(generate_infra_scoped_errors(*obj_type, infra_cache, &graph).await);
+        }
+        errors
+    }
 }
 
 /// Get sql query that insert errors given an object type
@@ -312,6 +557,50 @@ async fn retrieve_current_errors_hash(
         .await?)
 }
 
+#[derive(QueryableByName)]
+struct ErrorRow {
+    #[diesel(sql_type = Text)]
+    info_hash: ErrorHash,
+    #[diesel(sql_type = Jsonb)]
+    information: serde_json::Value,
+}
+
+/// Retrieve the current errors of a scope and their hash for a given infra
+async fn retrieve_current_errors(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    scope: &ErrorScope<'_>,
+) -> Result<Vec<(ErrorHash, InfraError)>> {
+    // Errors are first filtered in DB by object type, object id and error type, then by scope
+    let obj_types: Vec<_> = scope.types.iter().map(ObjectType::to_string).collect();
+    let obj_ids: Vec<_> = scope.objects.values().flatten().collect();
+    let error_types: Vec<_> = scope
+        .types
+        .iter()
+        .flat_map(|obj_type| get_foreign_error_types(*obj_type))
+        .map(AsRef::<str>::as_ref)
+        .collect();
+    let errors: Vec<ErrorRow> = sql_query(include_str!("sql/retrieve_scoped_errors.sql"))
+        .bind::<BigInt, _>(infra_id)
+        .bind::<Array<Text>, _>(&obj_types)
+        .bind::<Array<Text>, _>(&obj_ids)
+        .bind::<Array<Text>, _>(&error_types)
+        .load(conn)
+        .await?;
+    let mut scoped_errors = vec![];
+    for ErrorRow {
+        info_hash,
+        information,
+    } in errors
+    {
+        let error: InfraError = serde_json::from_value(information)?;
+        if scope.contains(&error) {
+            scoped_errors.push((info_hash, error));
+        }
+    }
+    Ok(scoped_errors)
+}
+
 /// Remove a list of errors given an infra and a list of error hashes
 async fn remove_errors_from_hashes(
     conn: &mut DbConnection,
@@ -363,6 +652,19 @@ async fn update_errors(
     conn: &mut DbConnection,
     infra_id: i64,
     errors: Vec<InfraError>,
+) -> Result<()> {
+    let current_errors_hash = retrieve_current_errors_hash(conn, infra_id).await?;
+    let current_errors_hash = current_errors_hash.into_iter().collect::<HashSet<_>>();
+    replace_errors(conn, infra_id, errors, current_errors_hash).await
+}
+
+/// Replace a set of errors in DB by new ones, given the hashes of the errors to replace.
+/// Errors present in both sets are left untouched.
+async fn replace_errors(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    errors: Vec<InfraError>,
+    current_errors_hash: HashSet<ErrorHash>,
 ) -> Result<()> {
     let new_errors_with_hash: Vec<ErrorWithHash> = errors.into_iter().map_into().collect();
     let new_errors_hash = new_errors_with_hash
@@ -370,9 +672,6 @@ async fn update_errors(
         .map(|e| e.hash.clone())
         .collect::<HashSet<_>>();
 
-    let current_errors_hash = retrieve_current_errors_hash(conn, infra_id).await?;
-    let current_errors_hash = current_errors_hash.into_iter().collect::<HashSet<_>>();
-
     // Filter errors that must be removed
     let to_remove = current_errors_hash
         .difference(&new_errors_hash)
@@ -419,11 +718,24 @@ impl GeneratedData for ErrorLayer {
     async fn update(
         conn: &mut DbConnection,
         infra_id: i64,
-        _operations: &[CacheOperation],
+        operations: &[CacheOperation],
         infra_cache: &InfraCache,
     ) -> Result<()> {
-        // Generate already act like an update
-        Self::generate(conn, infra_id, infra_cache).await
+        let scope = ErrorScope::from_operations(operations, infra_cache);
+        if scope.is_empty() {
+            return Ok(());
+        }
+
+        // Compute the errors of the involved objects
+        let infra_errors = scope.generate_errors(infra_cache).await;
+
+        // Replace the previous errors of the involved objects
+        let current_errors_hash = retrieve_current_errors(conn, infra_id, &scope)
+            .await?
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        replace_errors(conn, infra_id, infra_errors, current_errors_hash).await
     }
 }
 
@@ -442,9 +754,16 @@ mod test {
     use super::switch_types;
     use super::switches;
     use super::track_sections;
+    use super::ErrorScope;
     use super::Graph;
+    use super::InfraError;
+    use crate::infra_cache::operation::CacheOperation;
     use crate::infra_cache::tests::create_buffer_stop_cache;
+    use crate::infra_cache::tests::create_detector_cache;
     use crate::infra_cache::tests::create_small_infra_cache;
+    use crate::infra_cache::tests::create_track_section_cache;
+    use crate::infra_cache::ObjectCache;
+    use editoast_schemas::infra::Endpoint;
     use editoast_schemas::primitives::ObjectType;
 
     #[rstest]
@@ -563,4 +882,53 @@ mod test {
         .await;
         assert_eq!(1, errors.len());
     }
+
+    #[test]
+    fn error_scope_of_detector_creation() {
+        let infra_cache = create_small_infra_cache();
+        let detector = create_detector_cache("D2", "A", 250.);
+        let operations = vec![CacheOperation::Create(ObjectCache::Detector(
+            detector.clone(),
+        ))];
+
+        let scope = ErrorScope::from_operations(&operations, &infra_cache);
+
+        assert_eq!(scope.objects.len(), 1);
+        assert_eq!(scope.objects[&ObjectType::Detector].len(), 1);
+        assert_eq!(scope.types, [ObjectType::Route].into());
+        let track = create_track_section_cache("A", 500.);
+        assert!(scope.contains(&InfraError::new_out_of_range(
+            &detector,
+            "position",
+            250.,
+            [0., 100.]
+        )));
+        assert!(scope.contains(&InfraError::new_missing_route(&"A")));
+        assert!(!scope.contains(&InfraError::new_missing_buffer_stop(
+            &track,
+            Endpoint::Begin
+        )));
+    }
+
+    #[test]
+    fn error_scope_of_track_section_update() {
+        let infra_cache = create_small_infra_cache();
+        let operations = vec![CacheOperation::Update(ObjectCache::TrackSection(
+            create_track_section_cache("B", 420.),
+        ))];
+
+        let scope = ErrorScope::from_operations(&operations, &infra_cache);
+
+        let track = String::from("B");
+        let detector = String::from("D1");
+        assert_eq!(
+            scope.objects[&ObjectType::TrackSection],
+            [&track].into_iter().collect()
+        );
+        assert_eq!(
+            scope.objects[&ObjectType::Detector],
+            [&detector].into_iter().collect()
+        );
+        assert_eq!(scope.types.len(), 5);
+    }
 }
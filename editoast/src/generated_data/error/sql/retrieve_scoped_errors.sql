@@ -0,0 +1,9 @@
+SELECT info_hash,
+    information
+FROM infra_layer_error
+WHERE infra_id = $1
+    AND (
+        information->>'obj_type' = ANY($2)
+        OR information->>'obj_id' = ANY($3)
+        OR information->>'error_type' = ANY($4)
+    )
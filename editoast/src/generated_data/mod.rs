@@ -13,6 +13,7 @@ mod speed_section;
 pub mod sprite_config;
 mod switch;
 mod track_section;
+mod work_schedule;
 
 use async_trait::async_trait;
 use buffer_stop::BufferStopLayer;
@@ -22,6 +23,7 @@ use diesel::sql_types::BigInt;
 use diesel_async::RunQueryDsl;
 use electrification::ElectrificationLayer;
 pub use error::generate_infra_errors;
+pub use error::generate_objects_errors;
 pub use error::infra_error;
 use error::ErrorLayer;
 use neutral_section::NeutralSectionLayer;
@@ -34,6 +36,7 @@ use std::sync::Arc;
 use switch::SwitchLayer;
 use tracing::debug;
 use track_section::TrackSectionLayer;
+pub use work_schedule::WorkScheduleLayer;
 
 use crate::error::Result;
 use crate::infra_cache::operation::CacheOperation;
@@ -112,6 +115,7 @@ pub async fn refresh_all(
         PSLSignLayer::refresh_pool(db_pool.clone(), infra, infra_cache),
         NeutralSectionLayer::refresh_pool(db_pool.clone(), infra, infra_cache),
         NeutralSignLayer::refresh_pool(db_pool.clone(), infra, infra_cache),
+        WorkScheduleLayer::refresh_pool(db_pool.clone(), infra, infra_cache),
     )?;
     debug!("⚙️ Infra {infra}: object layers is generated");
     // The error layer depends on the other layers and must be executed at the end.
@@ -134,6 +138,7 @@ pub async fn clear_all(conn: &mut DbConnection, infra: i64) -> Result<()> {
     ErrorLayer::clear(conn, infra).await?;
     NeutralSectionLayer::clear(conn, infra).await?;
     NeutralSignLayer::clear(conn, infra).await?;
+    WorkScheduleLayer::clear(conn, infra).await?;
     Ok(())
 }
 
@@ -156,20 +161,68 @@ pub async fn update_all(
     ErrorLayer::update(conn, infra, operations, infra_cache).await?;
     NeutralSectionLayer::update(conn, infra, operations, infra_cache).await?;
     NeutralSignLayer::update(conn, infra, operations, infra_cache).await?;
+    WorkScheduleLayer::update(conn, infra, operations, infra_cache).await?;
     Ok(())
 }
 
 #[cfg(test)]
 pub mod tests {
+    use chrono::Utc;
+    use diesel::sql_query;
+    use diesel::sql_types::BigInt;
+    use diesel::sql_types::Text;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel::QueryableByName;
+    use diesel_async::RunQueryDsl;
+    use json_patch::Patch;
+    use json_patch::PatchOperation;
+    use json_patch::ReplaceOperation;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use rand::SeedableRng;
     use rstest::rstest;
+    use serde_json::json;
+    use std::collections::HashSet;
     use std::ops::DerefMut;
 
+    use super::BufferStopLayer;
+    use super::DetectorLayer;
+    use super::ElectrificationLayer;
+    use super::ErrorLayer;
+    use super::GeneratedData;
+    use super::NeutralSectionLayer;
+    use super::NeutralSignLayer;
+    use super::OperationalPointLayer;
+    use super::PSLSignLayer;
+    use super::SignalLayer;
+    use super::SpeedSectionLayer;
+    use super::SwitchLayer;
+    use super::TrackSectionLayer;
+    use super::WorkScheduleLayer;
     use crate::fixtures::tests::db_pool;
+    use crate::fixtures::tests::small_infra;
     use crate::generated_data::clear_all;
     use crate::generated_data::refresh_all;
     use crate::generated_data::update_all;
+    use crate::infra_cache::operation::CacheOperation;
+    use crate::infra_cache::operation::DeleteOperation;
+    use crate::infra_cache::operation::Operation;
+    use crate::infra_cache::operation::UpdateOperation;
+    use crate::infra_cache::InfraCache;
+    use crate::infra_cache::ObjectCache;
     use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::prelude::*;
+    use crate::modelsv2::work_schedules::WorkSchedule;
+    use crate::modelsv2::work_schedules::WorkScheduleGroup;
+    use crate::modelsv2::work_schedules::WorkScheduleType;
+    use crate::modelsv2::DbConnection;
     use crate::modelsv2::DbConnectionPoolV2;
+    use crate::tables::infra_layer_work_schedule;
+    use editoast_schemas::infra::TrackRange;
+    use editoast_schemas::primitives::ObjectRef;
+    use editoast_schemas::primitives::ObjectType;
 
     #[rstest] // Slow test
     async fn refresh_all_test() {
@@ -202,4 +255,199 @@ pub mod tests {
             .await
             .is_ok());
     }
+
+    #[derive(QueryableByName, Debug, PartialEq)]
+    struct GeneratedRow {
+        #[diesel(sql_type = Text)]
+        data: String,
+    }
+
+    /// Retrieve the content of the generated tables of an infra, without the row ids.
+    /// The geometry of the errors is ignored since it isn't refreshed when an error is unchanged.
+    async fn get_generated_data(
+        conn: &mut DbConnection,
+        infra_id: i64,
+    ) -> Vec<(&'static str, Vec<GeneratedRow>)> {
+        let layers = [
+            TrackSectionLayer::table_name(),
+            SpeedSectionLayer::table_name(),
+            SignalLayer::table_name(),
+            SwitchLayer::table_name(),
+            BufferStopLayer::table_name(),
+            ElectrificationLayer::table_name(),
+            DetectorLayer::table_name(),
+            OperationalPointLayer::table_name(),
+            PSLSignLayer::table_name(),
+            NeutralSectionLayer::table_name(),
+            NeutralSignLayer::table_name(),
+            WorkScheduleLayer::table_name(),
+        ];
+        let mut tables = vec![];
+        for table in layers {
+            let query = format!(
+                "SELECT (to_jsonb(t) - 'id')::text AS data FROM {table} AS t WHERE infra_id = $1 ORDER BY data"
+            );
+            let rows = sql_query(query)
+                .bind::<BigInt, _>(infra_id)
+                .load(conn)
+                .await
+                .unwrap();
+            tables.push((table, rows));
+        }
+        let query = format!(
+            "SELECT (to_jsonb(t) - 'id' - 'geographic')::text AS data FROM {} AS t WHERE infra_id = $1 ORDER BY data",
+            ErrorLayer::table_name()
+        );
+        let rows = sql_query(query)
+            .bind::<BigInt, _>(infra_id)
+            .load(conn)
+            .await
+            .unwrap();
+        tables.push((ErrorLayer::table_name(), rows));
+        tables
+    }
+
+    /// Pick a random update or deletion of an object which isn't in `edited`
+    fn random_operation(
+        rng: &mut StdRng,
+        infra_cache: &InfraCache,
+        edited: &HashSet<ObjectRef>,
+    ) -> Operation {
+        let obj_types = [
+            ObjectType::TrackSection,
+            ObjectType::Signal,
+            ObjectType::Detector,
+            ObjectType::BufferStop,
+            ObjectType::Switch,
+            ObjectType::SpeedSection,
+            ObjectType::NeutralSection,
+            ObjectType::OperationalPoint,
+            ObjectType::Electrification,
+            ObjectType::Route,
+        ];
+        loop {
+            let obj_type = *obj_types.choose(rng).unwrap();
+            let mut obj_ids: Vec<_> = infra_cache
+                .get_objects_by_type(obj_type)
+                .keys()
+                .filter(|obj_id| !edited.contains(&ObjectRef::new(obj_type, *obj_id)))
+                .collect();
+            obj_ids.sort();
+            let Some(obj_id) = obj_ids.choose(rng).map(|obj_id| obj_id.to_string()) else {
+                continue;
+            };
+            let path = match obj_type {
+                ObjectType::TrackSection => Some("/length"),
+                ObjectType::Signal | ObjectType::Detector | ObjectType::BufferStop => {
+                    Some("/position")
+                }
+                _ => None,
+            };
+            return match path {
+                Some(path) if rng.gen_bool(0.7) => Operation::Update(UpdateOperation {
+                    obj_id,
+                    obj_type,
+                    railjson_patch: Patch(vec![PatchOperation::Replace(ReplaceOperation {
+                        path: path.parse().unwrap(),
+                        value: json!(rng.gen_range(1.0..3000.0)),
+                    })]),
+                }),
+                _ => Operation::Delete(DeleteOperation { obj_id, obj_type }),
+            };
+        }
+    }
+
+    #[rstest]
+    #[case::seed_1(1)]
+    #[case::seed_2(2)]
+    #[case::seed_3(3)]
+    async fn incremental_update_matches_full_refresh(#[case] seed: u64) {
+        let db_pool = db_pool();
+        let small_infra = small_infra(db_pool.clone()).await;
+        let infra_id = small_infra.id();
+        let conn = &mut db_pool.get().await.unwrap();
+        let mut infra_cache = InfraCache::load(conn, &small_infra.model).await.unwrap();
+        refresh_all(db_pool.clone(), infra_id, &infra_cache)
+            .await
+            .unwrap();
+
+        // Apply random edits, updating the generated data incrementally
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..10 {
+            let mut edited = HashSet::new();
+            let mut cache_operations = vec![];
+            for _ in 0..rng.gen_range(1..=3) {
+                let operation = random_operation(&mut rng, &infra_cache, &edited);
+                edited.insert(operation.get_ref());
+                let cache_operation = match operation.apply(infra_id, conn).await.unwrap() {
+                    Some(railjson) => CacheOperation::Update(ObjectCache::from(railjson)),
+                    None => CacheOperation::Delete(operation.get_ref()),
+                };
+                infra_cache
+                    .apply_operations(std::slice::from_ref(&cache_operation))
+                    .unwrap();
+                cache_operations.push(cache_operation);
+            }
+            update_all(conn, infra_id, &cache_operations, &infra_cache)
+                .await
+                .unwrap();
+        }
+        let incremental_data = get_generated_data(conn, infra_id).await;
+
+        // Generate everything again from scratch
+        refresh_all(db_pool.clone(), infra_id, &infra_cache)
+            .await
+            .unwrap();
+        let full_data = get_generated_data(conn, infra_id).await;
+
+        assert_eq!(incremental_data, full_data);
+    }
+
+    #[rstest]
+    async fn work_schedules_are_projected_on_infra_tracks() {
+        let db_pool = db_pool();
+        let small_infra = small_infra(db_pool.clone()).await;
+        let infra_id = small_infra.id();
+        let conn = &mut db_pool.get().await.unwrap();
+        let infra_cache = InfraCache::load(conn, &small_infra.model).await.unwrap();
+        refresh_all(db_pool.clone(), infra_id, &infra_cache)
+            .await
+            .unwrap();
+
+        let group = WorkScheduleGroup::changeset()
+            .name(format!("work schedule layer test {infra_id}"))
+            .creation_date(Utc::now().naive_utc())
+            .create(conn)
+            .await
+            .unwrap();
+        let work_schedules: Vec<WorkSchedule> = WorkSchedule::create_batch(
+            conn,
+            ["TA0", "unknown_track"].map(|track| {
+                WorkSchedule::changeset()
+                    .start_date_time(Utc::now().naive_utc())
+                    .end_date_time(Utc::now().naive_utc())
+                    .track_ranges(vec![TrackRange::new(track, 0.0, 100.0)])
+                    .obj_id(track.to_string())
+                    .work_schedule_type(WorkScheduleType::Track)
+                    .work_schedule_group_id(group.id)
+            }),
+        )
+        .await
+        .unwrap();
+        let work_schedule_ids: Vec<_> = work_schedules.iter().map(|ws| ws.id).collect();
+        WorkScheduleLayer::insert(conn, &[infra_id], &work_schedule_ids)
+            .await
+            .unwrap();
+
+        let projected: Vec<i64> = infra_layer_work_schedule::table
+            .filter(infra_layer_work_schedule::infra_id.eq(infra_id))
+            .filter(infra_layer_work_schedule::work_schedule_id.eq_any(&work_schedule_ids))
+            .select(infra_layer_work_schedule::work_schedule_id)
+            .load(conn)
+            .await
+            .unwrap();
+        assert_eq!(projected, vec![work_schedules[0].id]);
+
+        group.delete(conn).await.unwrap();
+    }
 }
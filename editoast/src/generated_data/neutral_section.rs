@@ -1,13 +1,21 @@
 use async_trait::async_trait;
+use diesel::delete;
+use diesel::query_dsl::methods::FilterDsl;
 use diesel::sql_query;
+use diesel::sql_types::Array;
 use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
 use diesel_async::RunQueryDsl;
 
+use super::utils::InvolvedObjects;
 use super::GeneratedData;
+use crate::diesel::ExpressionMethods;
 use crate::error::Result;
 use crate::infra_cache::operation::CacheOperation;
 use crate::infra_cache::InfraCache;
 use crate::modelsv2::DbConnection;
+use crate::tables::infra_layer_neutral_section::dsl;
+use editoast_schemas::primitives::ObjectType;
 
 pub struct NeutralSectionLayer;
 
@@ -26,12 +34,39 @@ impl GeneratedData for NeutralSectionLayer {
     }
 
     async fn update(
-        _conn: &mut DbConnection,
-        _infra: i64,
-        _operations: &[CacheOperation],
-        _infra_cache: &InfraCache,
+        conn: &mut DbConnection,
+        infra: i64,
+        operations: &[CacheOperation],
+        infra_cache: &InfraCache,
     ) -> Result<()> {
-        // TODO: we don't manage the update of the neutral_section layer for the moment
+        let involved_objects =
+            InvolvedObjects::from_operations(operations, infra_cache, ObjectType::NeutralSection);
+
+        // Delete elements
+        if !involved_objects.is_empty() {
+            // We must delete both updated and deleted neutral sections because we can only insert them and not update
+            let objs = involved_objects
+                .deleted
+                .iter()
+                .chain(involved_objects.updated.iter());
+
+            delete(
+                dsl::infra_layer_neutral_section
+                    .filter(dsl::infra_id.eq(infra))
+                    .filter(dsl::obj_id.eq_any(objs)),
+            )
+            .execute(conn)
+            .await?;
+        }
+
+        // Insert elements
+        if !involved_objects.updated.is_empty() {
+            sql_query(include_str!("sql/insert_neutral_section_layer.sql"))
+                .bind::<BigInt, _>(infra)
+                .bind::<Array<Text>, _>(involved_objects.updated.into_iter().collect::<Vec<_>>())
+                .execute(conn)
+                .await?;
+        }
         Ok(())
     }
 }
@@ -67,3 +67,98 @@ impl GeneratedData for NeutralSignLayer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use diesel::sql_types::Double;
+    use diesel::QueryableByName;
+    use editoast_schemas::infra::NeutralSection;
+    use editoast_schemas::infra::TrackSection;
+    use geos::geojson::Geometry;
+    use geos::geojson::Value::LineString;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::generated_data::track_section::TrackSectionLayer;
+    use crate::infra_cache::ObjectCache;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::fixtures::create_infra_object;
+    use crate::modelsv2::DbConnectionPoolV2;
+
+    #[derive(QueryableByName)]
+    struct SignAngle {
+        #[diesel(sql_type = Double)]
+        angle_geo: f64,
+    }
+
+    fn sign(sign_type: &str, position: f64, direction: &str) -> serde_json::Value {
+        json!({
+            "track": "A",
+            "position": position,
+            "side": "LEFT",
+            "direction": direction,
+            "type": sign_type,
+            "value": "",
+            "kp": "",
+        })
+    }
+
+    #[rstest]
+    async fn exe_sign_is_oriented_by_its_own_direction() {
+        let db_pool = DbConnectionPoolV2::for_tests();
+        let conn = &mut db_pool.get_ok();
+        let infra = create_empty_infra(conn.deref_mut()).await;
+        // The track goes east, so signs facing its start to stop direction have an angle of 90°
+        let track_section = TrackSection {
+            id: "A".into(),
+            length: 1000.,
+            geo: Geometry::new(LineString(vec![vec![0., 0.], vec![0.01, 0.]])),
+            ..Default::default()
+        };
+        create_infra_object(conn.deref_mut(), infra.id, track_section).await;
+        TrackSectionLayer::generate(conn.deref_mut(), infra.id, &Default::default())
+            .await
+            .unwrap();
+        let neutral_section: NeutralSection = serde_json::from_value(json!({
+            "id": "neutral_section",
+            "announcement_track_ranges": [],
+            "track_ranges": [],
+            "lower_pantograph": false,
+            "extensions": {
+                "neutral_sncf": {
+                    "announcement": [sign("ANNOUNCEMENT", 200., "START_TO_STOP")],
+                    "exe": sign("EXE", 500., "STOP_TO_START"),
+                    "end": [],
+                    "rev": [],
+                },
+            },
+        }))
+        .unwrap();
+        create_infra_object(conn.deref_mut(), infra.id, neutral_section.clone()).await;
+
+        let operations = [CacheOperation::Create(ObjectCache::NeutralSection(
+            neutral_section,
+        ))];
+        NeutralSignLayer::update(conn.deref_mut(), infra.id, &operations, &Default::default())
+            .await
+            .unwrap();
+
+        let angle = |sign_type: &'static str| {
+            sql_query(
+                "SELECT angle_geo FROM infra_layer_neutral_sign WHERE infra_id = $1 AND data->>'type' = $2",
+            )
+            .bind::<BigInt, _>(infra.id)
+            .bind::<Text, _>(sign_type)
+        };
+        let announcement: SignAngle = angle("ANNOUNCEMENT")
+            .get_result(conn.deref_mut())
+            .await
+            .unwrap();
+        let exe: SignAngle = angle("EXE").get_result(conn.deref_mut()).await.unwrap();
+        assert!((announcement.angle_geo - 90.).abs() < 1e-6);
+        assert!((exe.angle_geo - 270.).abs() < 1e-6);
+    }
+}
@@ -1,8 +1,6 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use diesel::delete;
-use diesel::query_dsl::methods::FilterDsl;
 use diesel::sql_query;
 use diesel::sql_types::Array;
 use diesel::sql_types::BigInt;
@@ -11,14 +9,12 @@ use diesel::sql_types::Text;
 
 use super::utils::InvolvedObjects;
 use super::GeneratedData;
-use crate::diesel::ExpressionMethods;
 use crate::error::Result;
 use crate::generated_data::sprite_config::SpriteConfig;
 use crate::generated_data::sprite_config::SpriteConfigs;
 use crate::infra_cache::operation::CacheOperation;
 use crate::infra_cache::InfraCache;
 use crate::modelsv2::DbConnection;
-use crate::tables::infra_layer_signal::dsl;
 use editoast_schemas::infra::LogicalSignal;
 use editoast_schemas::primitives::ObjectType;
 
@@ -112,38 +108,24 @@ impl GeneratedData for SignalLayer {
         operations: &[CacheOperation],
         infra_cache: &InfraCache,
     ) -> Result<()> {
-        use diesel_async::RunQueryDsl;
-
         let involved_objects =
             InvolvedObjects::from_operations(operations, infra_cache, ObjectType::Signal);
 
-        // Delete elements
-        if !involved_objects.deleted.is_empty() {
-            delete(
-                dsl::infra_layer_signal
-                    .filter(dsl::infra_id.eq(infra))
-                    .filter(dsl::obj_id.eq_any(involved_objects.deleted)),
-            )
-            .execute(conn)
-            .await?;
-        }
-
-        // Update elements
-        if !involved_objects.updated.is_empty() {
-            let updated_signals = involved_objects.updated.into_iter().collect::<Vec<_>>();
-            sql_query(include_str!("sql/insert_update_signal_layer.sql"))
-                .bind::<BigInt, _>(infra)
-                .bind::<Array<Text>, _>(&updated_signals)
-                .execute(conn)
-                .await?;
-            generate_signaling_system_and_sprite(
+        involved_objects
+            .regenerate_layer(
                 conn,
                 infra,
-                infra_cache,
-                updated_signals.into_iter(),
+                Self::table_name(),
+                include_str!("sql/insert_update_signal_layer.sql"),
             )
             .await?;
-        }
+        generate_signaling_system_and_sprite(
+            conn,
+            infra,
+            infra_cache,
+            involved_objects.updated.into_iter(),
+        )
+        .await?;
         Ok(())
     }
 }
@@ -64,7 +64,7 @@ WITH signs AS (
             data->'extensions'->'neutral_sncf'->'exe'->'position'
         )::float AS position,
         (
-            data->'extensions'->'neutral_sncf'->'announcement'->>'direction'
+            data->'extensions'->'neutral_sncf'->'exe'->>'direction'
         ) AS direction,
         data->'extensions'->'neutral_sncf'->'exe'->>'track' AS track_id,
         data->'extensions'->'neutral_sncf'->'exe' AS data
@@ -1,8 +1,16 @@
 use std::collections::HashSet;
 
+use diesel::sql_query;
+use diesel::sql_types::Array;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
+use diesel_async::RunQueryDsl;
+
+use crate::error::Result;
 use crate::infra_cache::operation::CacheOperation;
 use crate::infra_cache::InfraCache;
 use crate::infra_cache::ObjectCache;
+use crate::modelsv2::DbConnection;
 use editoast_schemas::primitives::OSRDIdentified;
 use editoast_schemas::primitives::OSRDObject;
 use editoast_schemas::primitives::ObjectType;
@@ -46,6 +54,15 @@ impl<'a> InvolvedObjects<'a> {
                 CacheOperation::Delete(obj_ref) if obj_ref.obj_type == obj_type => {
                     res.deleted.insert(&obj_ref.obj_id);
                 }
+                CacheOperation::Delete(obj_ref) if obj_ref.obj_type == ObjectType::TrackSection => {
+                    // Objects linked to a deleted track section are kept in the cache and must be regenerated
+                    infra_cache
+                        .get_track_refs_type(&obj_ref.obj_id, obj_type)
+                        .iter()
+                        .for_each(|obj_ref| {
+                            res.updated.insert(&obj_ref.obj_id);
+                        });
+                }
                 _ => (),
             }
         }
@@ -56,6 +73,44 @@ impl<'a> InvolvedObjects<'a> {
     pub fn is_empty(&self) -> bool {
         self.updated.is_empty() && self.deleted.is_empty()
     }
+
+    /// Delete the rows of the involved objects from a layer, then insert the updated ones with `insert_query`
+    ///
+    /// Updated objects are deleted too, since they can't always be inserted again: for instance an
+    /// object whose track section was deleted stays in the infra, but can't be located anymore.
+    /// `insert_query` is bound to the infra id and to the ids of the updated objects.
+    pub async fn regenerate_layer(
+        &self,
+        conn: &mut DbConnection,
+        infra: i64,
+        table_name: &str,
+        insert_query: &str,
+    ) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let objs = self
+            .deleted
+            .iter()
+            .chain(self.updated.iter())
+            .collect::<Vec<_>>();
+        sql_query(format!(
+            "DELETE FROM {table_name} WHERE infra_id = $1 AND obj_id = ANY($2)"
+        ))
+        .bind::<BigInt, _>(infra)
+        .bind::<Array<Text>, _>(objs)
+        .execute(conn)
+        .await?;
+
+        if !self.updated.is_empty() {
+            sql_query(insert_query)
+                .bind::<BigInt, _>(infra)
+                .bind::<Array<Text>, _>(self.updated.iter().collect::<Vec<_>>())
+                .execute(conn)
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -85,6 +140,21 @@ mod test {
         assert_eq!(involved_objects.deleted, HashSet::from([&track]));
     }
 
+    #[test]
+    fn detectors_when_track_section_deleted() {
+        let infra_cache = create_small_infra_cache();
+        let track = String::from("B");
+        let operations = vec![CacheOperation::Delete(ObjectRef::new(
+            ObjectType::TrackSection,
+            &track,
+        ))];
+        let involved_objects =
+            InvolvedObjects::from_operations(&operations, &infra_cache, ObjectType::Detector);
+        assert_eq!(involved_objects.deleted.len(), 0);
+        let detector = String::from("D1");
+        assert_eq!(involved_objects.updated, HashSet::from([&detector]));
+    }
+
     #[test]
     fn detectors_when_track_section_update() {
         let infra_cache = create_small_infra_cache();
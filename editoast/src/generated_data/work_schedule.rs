@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use diesel::delete;
+use diesel::query_dsl::methods::FilterDsl;
+use diesel::sql_query;
+use diesel::sql_types::Array;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
+use diesel::QueryableByName;
+use diesel_async::RunQueryDsl;
+
+use super::utils::InvolvedObjects;
+use super::GeneratedData;
+use crate::diesel::ExpressionMethods;
+use crate::error::Result;
+use crate::infra_cache::operation::CacheOperation;
+use crate::infra_cache::InfraCache;
+use crate::modelsv2::DbConnection;
+use crate::tables::infra_layer_work_schedule::dsl;
+use editoast_schemas::primitives::ObjectType;
+
+/// Projection of the work schedules on the track sections of an infra
+///
+/// Work schedules don't belong to an infra: they are projected on every infra holding the
+/// track sections they refer to.
+pub struct WorkScheduleLayer;
+
+#[derive(QueryableByName)]
+struct WorkScheduleId {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+}
+
+impl WorkScheduleLayer {
+    /// Project some work schedules on the track sections of some infras
+    ///
+    /// The work schedules are expected to be missing from the layer of these infras.
+    pub async fn insert(
+        conn: &mut DbConnection,
+        infra_ids: &[i64],
+        work_schedule_ids: &[i64],
+    ) -> Result<()> {
+        if infra_ids.is_empty() || work_schedule_ids.is_empty() {
+            return Ok(());
+        }
+        sql_query(include_str!("sql/insert_work_schedule_layer.sql"))
+            .bind::<Array<BigInt>, _>(infra_ids)
+            .bind::<Array<BigInt>, _>(work_schedule_ids)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GeneratedData for WorkScheduleLayer {
+    fn table_name() -> &'static str {
+        "infra_layer_work_schedule"
+    }
+
+    async fn generate(conn: &mut DbConnection, infra: i64, _cache: &InfraCache) -> Result<()> {
+        sql_query(include_str!("sql/generate_work_schedule_layer.sql"))
+            .bind::<BigInt, _>(infra)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(
+        conn: &mut DbConnection,
+        infra: i64,
+        operations: &[CacheOperation],
+        infra_cache: &InfraCache,
+    ) -> Result<()> {
+        let involved_objects =
+            InvolvedObjects::from_operations(operations, infra_cache, ObjectType::TrackSection);
+        if involved_objects.is_empty() {
+            return Ok(());
+        }
+
+        // Work schedules lying on an edited track section must be projected again
+        let track_ids: Vec<_> = involved_objects
+            .updated
+            .into_iter()
+            .chain(involved_objects.deleted)
+            .collect();
+        let work_schedule_ids: Vec<_> = sql_query(
+            "SELECT DISTINCT work_schedule.id FROM work_schedule,
+                jsonb_array_elements(work_schedule.track_ranges) AS track_range
+                WHERE track_range->>'track' = ANY($1)",
+        )
+        .bind::<Array<Text>, _>(track_ids)
+        .load::<WorkScheduleId>(conn)
+        .await?
+        .into_iter()
+        .map(|work_schedule| work_schedule.id)
+        .collect();
+        if work_schedule_ids.is_empty() {
+            return Ok(());
+        }
+
+        delete(
+            dsl::infra_layer_work_schedule
+                .filter(dsl::infra_id.eq(infra))
+                .filter(dsl::work_schedule_id.eq_any(&work_schedule_ids)),
+        )
+        .execute(conn)
+        .await?;
+        Self::insert(conn, &[infra], &work_schedule_ids).await
+    }
+}
@@ -0,0 +1,417 @@
+//! Import of train schedules from a [GTFS](https://gtfs.org/schedule/reference/) feed
+//!
+//! Only the files needed to build train schedules are read: `agency.txt`, `stops.txt`,
+//! `routes.txt`, `trips.txt`, `stop_times.txt`, `calendar.txt` and `calendar_dates.txt`.
+
+pub mod stop_matching;
+pub mod timetable;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::Read;
+use std::io::Seek;
+
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::Weekday;
+use chrono_tz::Tz;
+use serde::de::DeserializeOwned;
+use serde::de::Error as SerdeError;
+use serde::Deserialize;
+use serde::Deserializer;
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+#[derive(Debug, Error)]
+pub enum GtfsError {
+    #[error("Invalid GTFS archive: {0}")]
+    InvalidArchive(#[from] ZipError),
+    #[error("Missing GTFS file '{0}'")]
+    MissingFile(&'static str),
+    #[error("Invalid GTFS file '{file}': {source}")]
+    InvalidFile {
+        file: &'static str,
+        source: csv::Error,
+    },
+    #[error("Invalid agency timezone '{0}'")]
+    InvalidTimezone(String),
+}
+
+/// Lists the stops and trips of a feed which could not be imported
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub unmatched_stops: Vec<String>,
+    pub unmatched_trips: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn is_empty(&self) -> bool {
+        self.unmatched_stops.is_empty() && self.unmatched_trips.is_empty()
+    }
+}
+
+impl Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.unmatched_stops.is_empty() {
+            writeln!(f, "Unmatched stops:")?;
+            for message in &self.unmatched_stops {
+                writeln!(f, "  - {message}")?;
+            }
+        }
+        if !self.unmatched_trips.is_empty() {
+            writeln!(f, "Unmatched trips:")?;
+            for message in &self.unmatched_trips {
+                writeln!(f, "  - {message}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Agency {
+    agency_timezone: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Stop {
+    pub stop_id: String,
+    #[serde(default)]
+    pub stop_code: Option<String>,
+    #[serde(default)]
+    pub stop_name: Option<String>,
+    #[serde(default)]
+    pub parent_station: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    pub route_id: String,
+    #[serde(default)]
+    pub route_short_name: Option<String>,
+    pub route_type: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Trip {
+    pub route_id: String,
+    pub service_id: String,
+    pub trip_id: String,
+    #[serde(default)]
+    pub trip_short_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StopTime {
+    pub trip_id: String,
+    #[serde(default)]
+    pub arrival_time: Option<GtfsTime>,
+    #[serde(default)]
+    pub departure_time: Option<GtfsTime>,
+    pub stop_id: String,
+    pub stop_sequence: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Calendar {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    #[serde(deserialize_with = "deserialize_date")]
+    start_date: NaiveDate,
+    #[serde(deserialize_with = "deserialize_date")]
+    end_date: NaiveDate,
+}
+
+impl Calendar {
+    fn is_active(&self, date: NaiveDate) -> bool {
+        let day = match date.weekday() {
+            Weekday::Mon => self.monday,
+            Weekday::Tue => self.tuesday,
+            Weekday::Wed => self.wednesday,
+            Weekday::Thu => self.thursday,
+            Weekday::Fri => self.friday,
+            Weekday::Sat => self.saturday,
+            Weekday::Sun => self.sunday,
+        };
+        day == 1 && (self.start_date..=self.end_date).contains(&date)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CalendarDate {
+    service_id: String,
+    #[serde(deserialize_with = "deserialize_date")]
+    date: NaiveDate,
+    /// `1` when the service is added on the date, `2` when it is removed
+    exception_type: u8,
+}
+
+/// A time of the service day, given as `H:MM:SS`
+///
+/// It is measured from "noon minus 12h" of the service day, and can exceed 24 hours for trips
+/// running after midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GtfsTime(u32);
+
+impl GtfsTime {
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(self.0 as i64)
+    }
+}
+
+impl<'de> Deserialize<'de> for GtfsTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let time = String::deserialize(deserializer)?;
+        let fields: Vec<_> = time
+            .trim()
+            .split(':')
+            .map(|field| field.parse::<u32>().ok())
+            .collect();
+        match fields[..] {
+            [Some(hours), Some(minutes), Some(seconds)] if minutes < 60 && seconds < 60 => {
+                Ok(GtfsTime(hours * 3600 + minutes * 60 + seconds))
+            }
+            _ => Err(SerdeError::custom(format!("invalid time '{time}'"))),
+        }
+    }
+}
+
+fn deserialize_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date = String::deserialize(deserializer)?;
+    NaiveDate::parse_from_str(&date, "%Y%m%d").map_err(SerdeError::custom)
+}
+
+/// The content of a GTFS feed
+#[derive(Debug)]
+pub struct Feed {
+    /// The timezone of the agencies, in which the times of the feed are given
+    pub timezone: Tz,
+    pub stops: HashMap<String, Stop>,
+    pub routes: HashMap<String, Route>,
+    pub trips: Vec<Trip>,
+    /// The stop times of each trip, sorted by stop sequence
+    pub stop_times: HashMap<String, Vec<StopTime>>,
+    calendars: HashMap<String, Calendar>,
+    calendar_dates: HashMap<(String, NaiveDate), u8>,
+}
+
+impl Feed {
+    /// Read a zipped GTFS feed
+    pub fn from_zip<R: Read + Seek>(reader: R) -> Result<Self, GtfsError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let agencies: Vec<Agency> = read_file(&mut archive, "agency.txt", true)?;
+        let timezone = agencies
+            .first()
+            .map(|agency| agency.agency_timezone.as_str())
+            .unwrap_or_default();
+        let timezone = timezone
+            .parse()
+            .map_err(|_| GtfsError::InvalidTimezone(timezone.to_string()))?;
+
+        let mut stop_times: HashMap<String, Vec<StopTime>> = HashMap::new();
+        for stop_time in read_file::<StopTime, _>(&mut archive, "stop_times.txt", true)? {
+            stop_times
+                .entry(stop_time.trip_id.clone())
+                .or_default()
+                .push(stop_time);
+        }
+        stop_times
+            .values_mut()
+            .for_each(|stop_times| stop_times.sort_by_key(|stop_time| stop_time.stop_sequence));
+
+        Ok(Self {
+            timezone,
+            stops: read_file::<Stop, _>(&mut archive, "stops.txt", true)?
+                .into_iter()
+                .map(|stop| (stop.stop_id.clone(), stop))
+                .collect(),
+            routes: read_file::<Route, _>(&mut archive, "routes.txt", true)?
+                .into_iter()
+                .map(|route| (route.route_id.clone(), route))
+                .collect(),
+            trips: read_file(&mut archive, "trips.txt", true)?,
+            stop_times,
+            calendars: read_file::<Calendar, _>(&mut archive, "calendar.txt", false)?
+                .into_iter()
+                .map(|calendar| (calendar.service_id.clone(), calendar))
+                .collect(),
+            calendar_dates: read_file::<CalendarDate, _>(
+                &mut archive,
+                "calendar_dates.txt",
+                false,
+            )?
+            .into_iter()
+            .map(|date| ((date.service_id, date.date), date.exception_type))
+            .collect(),
+        })
+    }
+
+    /// Whether a service runs on a date, the exceptions of `calendar_dates.txt` taking precedence
+    pub fn is_service_active(&self, service_id: &str, date: NaiveDate) -> bool {
+        match self.calendar_dates.get(&(service_id.to_string(), date)) {
+            Some(exception_type) => *exception_type == 1,
+            None => self
+                .calendars
+                .get(service_id)
+                .is_some_and(|calendar| calendar.is_active(date)),
+        }
+    }
+}
+
+fn read_file<T: DeserializeOwned, R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    file: &'static str,
+    required: bool,
+) -> Result<Vec<T>, GtfsError> {
+    let content = match archive.by_name(file) {
+        Ok(content) => content,
+        Err(ZipError::FileNotFound) if !required => return Ok(vec![]),
+        Err(ZipError::FileNotFound) => return Err(GtfsError::MissingFile(file)),
+        Err(error) => return Err(error.into()),
+    };
+    csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(|source| GtfsError::InvalidFile { file, source })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+    use std::io::Write;
+
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::Feed;
+    use super::GtfsError;
+    use super::GtfsTime;
+
+    /// Zip a GTFS feed made of the given files
+    pub fn zip_feed(files: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let mut archive = writer.finish().unwrap();
+        archive.set_position(0);
+        archive
+    }
+
+    pub fn sample_feed() -> Cursor<Vec<u8>> {
+        zip_feed(&[
+            (
+                "agency.txt",
+                "agency_id,agency_name,agency_url,agency_timezone\n\
+                 sncf,SNCF,https://www.sncf.com,Europe/Paris\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,parent_station\n\
+                 StopArea:OCE87000001,Station A,\n\
+                 StopPoint:OCETrain-87000001,Station A,StopArea:OCE87000001\n\
+                 StopPoint:B,Station B,\n\
+                 StopPoint:C,Station C,\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_type\n\
+                 ter,TER,2\n\
+                 bus,1,3\n",
+            ),
+            (
+                "trips.txt",
+                "route_id,service_id,trip_id,trip_short_name\n\
+                 ter,weekdays,trip_1,861001\n\
+                 ter,sundays,trip_2,861002\n\
+                 bus,weekdays,trip_3,\n",
+            ),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 trip_1,08:30:00,08:32:00,StopPoint:B,2\n\
+                 trip_1,08:00:00,08:00:00,StopPoint:OCETrain-87000001,1\n\
+                 trip_1,09:00:00,09:00:00,StopPoint:C,3\n\
+                 trip_2,23:50:00,23:50:00,StopPoint:OCETrain-87000001,1\n\
+                 trip_2,24:20:00,24:20:00,StopPoint:C,2\n\
+                 trip_3,08:00:00,08:00:00,StopPoint:B,1\n\
+                 trip_3,08:10:00,08:10:00,StopPoint:C,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 weekdays,1,1,1,1,1,0,0,20240101,20241231\n\
+                 sundays,0,0,0,0,0,0,1,20240101,20241231\n",
+            ),
+            (
+                "calendar_dates.txt",
+                "service_id,date,exception_type\n\
+                 weekdays,20240501,2\n\
+                 sundays,20240501,1\n",
+            ),
+        ])
+    }
+
+    #[test]
+    fn read_feed() {
+        let feed = Feed::from_zip(sample_feed()).unwrap();
+
+        assert_eq!(feed.timezone, chrono_tz::Europe::Paris);
+        assert_eq!(feed.stops.len(), 4);
+        assert_eq!(feed.trips.len(), 3);
+        let stop_sequences: Vec<_> = feed.stop_times["trip_1"]
+            .iter()
+            .map(|stop_time| stop_time.stop_sequence)
+            .collect();
+        assert_eq!(stop_sequences, vec![1, 2, 3]);
+        assert_eq!(
+            feed.stop_times["trip_2"][1].arrival_time,
+            Some(GtfsTime(24 * 3600 + 20 * 60))
+        );
+    }
+
+    #[test]
+    fn read_feed_missing_file() {
+        let feed = zip_feed(&[("stops.txt", "stop_id\n")]);
+
+        assert!(matches!(
+            Feed::from_zip(feed),
+            Err(GtfsError::MissingFile("agency.txt"))
+        ));
+    }
+
+    #[test]
+    fn service_activity() {
+        let feed = Feed::from_zip(sample_feed()).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        let holiday = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+
+        assert!(feed.is_service_active("weekdays", monday));
+        assert!(!feed.is_service_active("sundays", monday));
+        assert!(!feed.is_service_active("weekdays", holiday));
+        assert!(feed.is_service_active("sundays", holiday));
+        assert!(!feed.is_service_active("unknown", monday));
+    }
+}
@@ -0,0 +1,221 @@
+//! Matching of GTFS stops to the operational points of an infra
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use editoast_schemas::train_schedule::PathItemLocation;
+
+use super::Feed;
+use super::ImportReport;
+use super::Stop;
+use crate::error::Result;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::OperationalPointModel;
+
+/// Extract the UIC code of a stop
+///
+/// A numeric stop code is taken as a UIC code. Otherwise, the code is looked for in the stop code
+/// or id as a 7 or 8 digits number, such as in `StopPoint:OCETrain TER-87391003`.
+fn stop_uic(stop: &Stop) -> Option<u32> {
+    if let Some(uic) = stop.stop_code.as_ref().and_then(|code| code.parse().ok()) {
+        return Some(uic);
+    }
+    stop.stop_code
+        .iter()
+        .chain(std::iter::once(&stop.stop_id))
+        .find_map(|value| {
+            value
+                .split(|c: char| !c.is_ascii_digit())
+                .rfind(|digits| (7..=8).contains(&digits.len()))
+                .and_then(|digits| digits.parse().ok())
+        })
+}
+
+/// Match the given stops to the operational points of an infra
+///
+/// A stop is matched by its UIC code, or else by its name when a single operational point bears
+/// it. When the stop itself can't be matched, its parent station is tried. Unmatched stops are
+/// added to the report.
+pub async fn match_stops(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    feed: &Feed,
+    stop_ids: &HashSet<&str>,
+    report: &mut ImportReport,
+) -> Result<HashMap<String, PathItemLocation>> {
+    // The stops to try for each stop id, the stop itself first then its parent station
+    let candidates: HashMap<_, Vec<_>> = stop_ids
+        .iter()
+        .map(|stop_id| {
+            let stop = feed.stops.get(*stop_id);
+            let parent_station = stop
+                .and_then(|stop| stop.parent_station.as_ref())
+                .and_then(|parent_station| feed.stops.get(parent_station));
+            (*stop_id, stop.into_iter().chain(parent_station).collect())
+        })
+        .collect();
+    let uics: Vec<_> = candidates
+        .values()
+        .flatten()
+        .filter_map(|stop| stop_uic(stop))
+        .map(i64::from)
+        .collect();
+    let names: Vec<_> = candidates
+        .values()
+        .flatten()
+        .filter_map(|stop| stop.stop_name.clone())
+        .collect();
+
+    let known_uics: HashSet<_> = OperationalPointModel::retrieve_from_uic(conn, infra_id, &uics)
+        .await?
+        .into_iter()
+        .filter_map(|op| {
+            op.extensions
+                .identifier
+                .as_ref()
+                .map(|identifier| identifier.uic)
+        })
+        .collect();
+    let mut ops_by_name: HashMap<String, Vec<String>> = HashMap::new();
+    for op in OperationalPointModel::retrieve_from_names(conn, infra_id, &names).await? {
+        if let Some(identifier) = &op.extensions.identifier {
+            ops_by_name
+                .entry(identifier.name.to_lowercase())
+                .or_default()
+                .push(op.obj_id.clone());
+        }
+    }
+
+    let mut locations = HashMap::new();
+    for (stop_id, stops) in candidates {
+        let from_uic = stops.iter().find_map(|stop| {
+            let uic = stop_uic(stop)?;
+            known_uics
+                .contains(&i64::from(uic))
+                .then_some(PathItemLocation::OperationalPointUic {
+                    uic,
+                    secondary_code: None,
+                })
+        });
+        let from_name = || {
+            stops.iter().find_map(|stop| {
+                match ops_by_name
+                    .get(&stop.stop_name.as_ref()?.to_lowercase())?
+                    .as_slice()
+                {
+                    [obj_id] => Some(PathItemLocation::OperationalPointId {
+                        operational_point: obj_id.clone().into(),
+                    }),
+                    _ => None,
+                }
+            })
+        };
+        match from_uic.or_else(from_name) {
+            Some(location) => {
+                locations.insert(stop_id.to_string(), location);
+            }
+            None => {
+                let name = stops
+                    .first()
+                    .and_then(|stop| stop.stop_name.as_deref())
+                    .unwrap_or("unknown stop");
+                report.unmatched_stops.push(format!(
+                    "Stop '{stop_id}' ({name}): no operational point with its UIC code or name"
+                ));
+            }
+        }
+    }
+    report.unmatched_stops.sort();
+    Ok(locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use editoast_schemas::train_schedule::PathItemLocation;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::match_stops;
+    use super::stop_uic;
+    use crate::fixtures::tests::db_pool;
+    use crate::fixtures::tests::small_infra;
+    use crate::gtfs::tests::zip_feed;
+    use crate::gtfs::Feed;
+    use crate::gtfs::ImportReport;
+    use crate::gtfs::Stop;
+    use crate::modelsv2::DbConnectionPool;
+
+    #[rstest]
+    #[case::numeric_stop_code("StopPoint:1", Some("8"), Some(8))]
+    #[case::stop_id("StopPoint:OCETrain TER-87391003", None, Some(87391003))]
+    #[case::stop_code("1", Some("OCE8739100"), Some(8739100))]
+    #[case::no_uic("StopPoint:1234", None, None)]
+    fn extract_stop_uic(
+        #[case] stop_id: &str,
+        #[case] stop_code: Option<&str>,
+        #[case] expected: Option<u32>,
+    ) {
+        let stop = Stop {
+            stop_id: stop_id.to_string(),
+            stop_code: stop_code.map(String::from),
+            stop_name: None,
+            parent_station: None,
+        };
+
+        assert_eq!(stop_uic(&stop), expected);
+    }
+
+    #[rstest]
+    async fn match_stops_of_small_infra(db_pool: Arc<DbConnectionPool>) {
+        let small_infra = small_infra(db_pool.clone()).await;
+        let conn = &mut db_pool.get().await.unwrap();
+        let feed = Feed::from_zip(zip_feed(&[
+            (
+                "agency.txt",
+                "agency_name,agency_timezone\nSNCF,Europe/Paris\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_code,stop_name,parent_station\n\
+                 west,2,,\n\
+                 west_platform,,,west\n\
+                 north,,North_station,\n\
+                 nowhere,42,Nowhere,\n",
+            ),
+            ("routes.txt", "route_id,route_type\n"),
+            ("trips.txt", "route_id,service_id,trip_id\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n",
+            ),
+        ]))
+        .unwrap();
+        let stop_ids = HashSet::from(["west_platform", "north", "nowhere"]);
+        let mut report = ImportReport::default();
+
+        let locations = match_stops(conn, small_infra.id, &feed, &stop_ids, &mut report)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            locations["west_platform"],
+            PathItemLocation::OperationalPointUic {
+                uic: 2,
+                secondary_code: None
+            }
+        );
+        assert_eq!(
+            locations["north"],
+            PathItemLocation::OperationalPointId {
+                operational_point: "North_station".into()
+            }
+        );
+        assert_eq!(
+            report.unmatched_stops,
+            vec!["Stop 'nowhere' (Nowhere): no operational point with its UIC code or name"]
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Conversion of the trips of a GTFS feed to train schedules
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use chrono_tz::Tz;
+use editoast_schemas::primitives::NonBlankString;
+use editoast_schemas::primitives::PositiveDuration;
+use editoast_schemas::train_schedule::PathItem;
+use editoast_schemas::train_schedule::PathItemLocation;
+use editoast_schemas::train_schedule::ScheduleItem;
+use editoast_schemas::train_schedule::TrainScheduleBase;
+use serde::Deserialize;
+
+use super::Feed;
+use super::ImportReport;
+use super::Route;
+use super::Trip;
+
+/// The rolling stock used for the trips of each [GTFS route type](https://gtfs.org/schedule/reference/#routestxt)
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RollingStockMapping {
+    /// The name of the rolling stock used for each route type
+    #[serde(default)]
+    pub route_types: HashMap<u16, String>,
+    /// The name of the rolling stock used for the route types missing from `route_types`
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+impl RollingStockMapping {
+    fn rolling_stock_name(&self, route_type: u16) -> Option<&String> {
+        self.route_types.get(&route_type).or(self.default.as_ref())
+    }
+}
+
+/// A trip running on the imported service date, along with its rolling stock
+pub struct SelectedTrip<'a> {
+    trip: &'a Trip,
+    route: &'a Route,
+    rolling_stock_name: String,
+}
+
+/// Select the trips running on a date whose route type is given a rolling stock
+pub fn select_trips<'a>(
+    feed: &'a Feed,
+    date: NaiveDate,
+    rolling_stocks: &RollingStockMapping,
+    report: &mut ImportReport,
+) -> Vec<SelectedTrip<'a>> {
+    feed.trips
+        .iter()
+        .filter(|trip| feed.is_service_active(&trip.service_id, date))
+        .filter_map(|trip| {
+            let Some(route) = feed.routes.get(&trip.route_id) else {
+                report.unmatched_trips.push(format!(
+                    "Trip '{}': unknown route '{}'",
+                    trip.trip_id, trip.route_id
+                ));
+                return None;
+            };
+            let Some(rolling_stock_name) = rolling_stocks.rolling_stock_name(route.route_type)
+            else {
+                report.unmatched_trips.push(format!(
+                    "Trip '{}': no rolling stock for route type {}",
+                    trip.trip_id, route.route_type
+                ));
+                return None;
+            };
+            Some(SelectedTrip {
+                trip,
+                route,
+                rolling_stock_name: rolling_stock_name.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The ids of the stops served by some trips
+pub fn served_stops<'a>(feed: &'a Feed, trips: &[SelectedTrip]) -> HashSet<&'a str> {
+    trips
+        .iter()
+        .filter_map(|selected| feed.stop_times.get(&selected.trip.trip_id))
+        .flatten()
+        .map(|stop_time| stop_time.stop_id.as_str())
+        .collect()
+}
+
+/// The instant from which the times of a service day are measured: "noon minus 12h"
+fn service_start(timezone: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let noon = date.and_time(NaiveTime::from_hms_opt(12, 0, 0).expect("noon is a valid time"));
+    let noon = timezone
+        .from_local_datetime(&noon)
+        .earliest()
+        .map(|noon| noon.with_timezone(&Utc))
+        .unwrap_or_else(|| noon.and_utc());
+    noon - Duration::hours(12)
+}
+
+/// Build the train schedules of the selected trips
+///
+/// Stops missing from `stop_locations` are left out of the path of the trips, and trips with less
+/// than two matched stops are skipped. Both are added to the report.
+pub fn build_train_schedules(
+    feed: &Feed,
+    date: NaiveDate,
+    trips: &[SelectedTrip],
+    stop_locations: &HashMap<String, PathItemLocation>,
+    report: &mut ImportReport,
+) -> Vec<TrainScheduleBase> {
+    let service_start = service_start(feed.timezone, date);
+    trips
+        .iter()
+        .filter_map(|selected| {
+            build_train_schedule(feed, selected, service_start, stop_locations, report)
+        })
+        .collect()
+}
+
+fn build_train_schedule(
+    feed: &Feed,
+    selected: &SelectedTrip,
+    service_start: DateTime<Utc>,
+    stop_locations: &HashMap<String, PathItemLocation>,
+    report: &mut ImportReport,
+) -> Option<TrainScheduleBase> {
+    let trip_id = &selected.trip.trip_id;
+    let stop_times = feed
+        .stop_times
+        .get(trip_id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut start_time = None;
+    let mut path = vec![];
+    let mut schedule = vec![];
+    let mut unmatched_stops = vec![];
+    for stop_time in stop_times {
+        let Some(location) = stop_locations.get(&stop_time.stop_id) else {
+            unmatched_stops.push(stop_time.stop_id.as_str());
+            continue;
+        };
+        let path_item_id =
+            NonBlankString(format!("{}_{}", stop_time.stop_sequence, stop_time.stop_id));
+        path.push(PathItem {
+            id: path_item_id.clone(),
+            deleted: false,
+            location: location.clone(),
+        });
+        let arrival = stop_time
+            .arrival_time
+            .map(|time| service_start + time.duration());
+        let departure = stop_time
+            .departure_time
+            .map(|time| service_start + time.duration());
+
+        let Some(start_time) = start_time else {
+            start_time = departure.or(arrival);
+            if start_time.is_none() {
+                report.unmatched_trips.push(format!(
+                    "Trip '{trip_id}': no departure time at its first matched stop, the trip is skipped"
+                ));
+                return None;
+            }
+            continue;
+        };
+
+        // Every stop time of a trip is a stop
+        let dwell_time = arrival
+            .zip(departure)
+            .map(|(arrival, departure)| departure - arrival)
+            .unwrap_or_else(Duration::zero);
+        schedule.push(ScheduleItem {
+            at: path_item_id,
+            arrival: arrival
+                .or(departure)
+                .and_then(|arrival| PositiveDuration::try_from(arrival - start_time).ok()),
+            stop_for: PositiveDuration::try_from(dwell_time).ok(),
+            on_stop_signal: false,
+            locked: false,
+        });
+    }
+
+    let Some(start_time) = start_time.filter(|_| path.len() >= 2) else {
+        report.unmatched_trips.push(format!(
+            "Trip '{trip_id}': less than two matched stops, the trip is skipped"
+        ));
+        return None;
+    };
+    if !unmatched_stops.is_empty() {
+        report.unmatched_trips.push(format!(
+            "Trip '{trip_id}': unmatched stops left out of the path: {}",
+            unmatched_stops.join(", ")
+        ));
+    }
+    Some(TrainScheduleBase {
+        train_name: selected
+            .trip
+            .trip_short_name
+            .clone()
+            .unwrap_or_else(|| trip_id.clone()),
+        labels: selected.route.route_short_name.iter().cloned().collect(),
+        rolling_stock_name: selected.rolling_stock_name.clone(),
+        start_time,
+        path,
+        schedule,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::collections::HashSet;
+
+    use chrono::NaiveDate;
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use editoast_schemas::train_schedule::PathItemLocation;
+    use pretty_assertions::assert_eq;
+
+    use super::build_train_schedules;
+    use super::select_trips;
+    use super::served_stops;
+    use super::RollingStockMapping;
+    use crate::gtfs::tests::sample_feed;
+    use crate::gtfs::Feed;
+    use crate::gtfs::ImportReport;
+
+    fn rolling_stocks() -> RollingStockMapping {
+        RollingStockMapping {
+            route_types: HashMap::from([(2, "fast_rolling_stock".to_string())]),
+            default: None,
+        }
+    }
+
+    fn stop_locations() -> HashMap<String, PathItemLocation> {
+        HashMap::from([
+            (
+                "StopPoint:OCETrain-87000001".to_string(),
+                PathItemLocation::OperationalPointUic {
+                    uic: 87000001,
+                    secondary_code: None,
+                },
+            ),
+            (
+                "StopPoint:C".to_string(),
+                PathItemLocation::OperationalPointId {
+                    operational_point: "C".into(),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn select_trips_running_on_date() {
+        let feed = Feed::from_zip(sample_feed()).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        let mut report = ImportReport::default();
+
+        let trips = select_trips(&feed, monday, &rolling_stocks(), &mut report);
+
+        let trip_ids: Vec<_> = trips
+            .iter()
+            .map(|selected| &selected.trip.trip_id)
+            .collect();
+        assert_eq!(trip_ids, vec!["trip_1"]);
+        assert_eq!(
+            served_stops(&feed, &trips),
+            HashSet::from(["StopPoint:OCETrain-87000001", "StopPoint:B", "StopPoint:C"])
+        );
+        assert_eq!(
+            report.unmatched_trips,
+            vec!["Trip 'trip_3': no rolling stock for route type 3"]
+        );
+    }
+
+    #[test]
+    fn build_train_schedules_with_unmatched_stop() {
+        let feed = Feed::from_zip(sample_feed()).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        let mut report = ImportReport::default();
+        let trips = select_trips(&feed, monday, &rolling_stocks(), &mut report);
+
+        let train_schedules =
+            build_train_schedules(&feed, monday, &trips, &stop_locations(), &mut report);
+
+        assert_eq!(train_schedules.len(), 1);
+        let train_schedule = &train_schedules[0];
+        assert_eq!(train_schedule.train_name, "861001");
+        assert_eq!(train_schedule.labels, vec!["TER".to_string()]);
+        assert_eq!(train_schedule.rolling_stock_name, "fast_rolling_stock");
+        // 08:00 in Paris during summer time
+        assert_eq!(
+            train_schedule.start_time,
+            Utc.with_ymd_and_hms(2024, 4, 29, 6, 0, 0).unwrap()
+        );
+        assert_eq!(train_schedule.path.len(), 2);
+        assert_eq!(train_schedule.schedule.len(), 1);
+        assert_eq!(
+            train_schedule.schedule[0]
+                .arrival
+                .as_ref()
+                .map(|arrival| arrival.num_minutes()),
+            Some(60)
+        );
+        assert_eq!(
+            train_schedule.schedule[0]
+                .stop_for
+                .as_ref()
+                .map(|stop_for| stop_for.num_seconds()),
+            Some(0)
+        );
+        assert_eq!(
+            report.unmatched_trips,
+            vec![
+                "Trip 'trip_3': no rolling stock for route type 3",
+                "Trip 'trip_1': unmatched stops left out of the path: StopPoint:B",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_train_schedules_after_midnight() {
+        let feed = Feed::from_zip(sample_feed()).unwrap();
+        let holiday = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let mut report = ImportReport::default();
+        let trips = select_trips(&feed, holiday, &rolling_stocks(), &mut report);
+
+        let train_schedules =
+            build_train_schedules(&feed, holiday, &trips, &stop_locations(), &mut report);
+
+        assert!(report.is_empty(), "{report}");
+        assert_eq!(train_schedules.len(), 1);
+        assert_eq!(train_schedules[0].train_name, "861002");
+        assert_eq!(
+            train_schedules[0].schedule[0]
+                .arrival
+                .as_ref()
+                .map(|arrival| arrival.num_minutes()),
+            Some(30)
+        );
+    }
+}
@@ -156,7 +156,7 @@ impl OSRDObject for ObjectCache {
 }
 
 impl ObjectCache {
-    fn get_track_referenced_id(&self) -> Vec<&String> {
+    pub fn get_track_referenced_id(&self) -> Vec<&String> {
         match self {
             ObjectCache::TrackSection(track) => track.get_track_referenced_id(),
             ObjectCache::Signal(signal) => signal.get_track_referenced_id(),
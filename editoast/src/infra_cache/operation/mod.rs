@@ -9,7 +9,9 @@ use editoast_schemas::primitives::OSRDObject as _;
 use json_patch::Patch;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::json;
 use thiserror::Error;
+use update::DataObject;
 pub use update::UpdateOperation;
 use utoipa::ToSchema;
 
@@ -137,6 +139,51 @@ impl CacheOperation {
 }
 
 impl Operation {
+    /// Returns a reference to the object targeted by the operation
+    pub fn get_ref(&self) -> ObjectRef {
+        match self {
+            Operation::Create(railjson_object) => railjson_object.get_ref(),
+            Operation::Update(UpdateOperation {
+                obj_type, obj_id, ..
+            }) => ObjectRef::new(*obj_type, obj_id),
+            Operation::Delete(delete_operation) => delete_operation.clone().into(),
+        }
+    }
+
+    /// Build the operation reverting this one
+    ///
+    /// Since it reads the current content of the targeted object, it must be called before the
+    /// operation is applied.
+    pub async fn inverse(&self, infra_id: i64, conn: &mut DbConnection) -> Result<Operation> {
+        match self {
+            Operation::Create(railjson_object) => {
+                Ok(Operation::Delete(railjson_object.get_ref().into()))
+            }
+            Operation::Update(update) => {
+                let DataObject { data } =
+                    DataObject::load(infra_id, &update.obj_type, &update.obj_id, conn).await?;
+                let mut patched_data = data.clone();
+                json_patch::patch(&mut patched_data, &update.railjson_patch).map_err(|err| {
+                    OperationError::InvalidPatch {
+                        error: err.to_string(),
+                    }
+                })?;
+                Ok(Operation::Update(UpdateOperation {
+                    obj_id: update.obj_id.clone(),
+                    obj_type: update.obj_type,
+                    railjson_patch: json_patch::diff(&patched_data, &data),
+                }))
+            }
+            Operation::Delete(DeleteOperation { obj_id, obj_type }) => {
+                let DataObject { data } =
+                    DataObject::load(infra_id, obj_type, obj_id, conn).await?;
+                let railjson_object =
+                    serde_json::from_value(json!({ "obj_type": obj_type, "railjson": data }))?;
+                Ok(Operation::Create(Box::new(railjson_object)))
+            }
+        }
+    }
+
     pub async fn apply(
         &self,
         infra_id: i64,
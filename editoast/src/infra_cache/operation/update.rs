@@ -33,26 +33,7 @@ pub struct UpdateOperation {
 impl UpdateOperation {
     pub async fn apply(&self, infra_id: i64, conn: &mut DbConnection) -> Result<InfraObject> {
         // Load object
-
-        let mut obj: DataObject = match sql_query(format!(
-            "SELECT data FROM {} WHERE infra_id = $1 AND obj_id = $2",
-            get_table(&self.obj_type)
-        ))
-        .bind::<BigInt, _>(infra_id)
-        .bind::<Text, _>(&self.obj_id)
-        .get_result(conn)
-        .await
-        {
-            Ok(obj) => obj,
-            Err(DieselError::NotFound) => {
-                return Err(OperationError::ObjectNotFound {
-                    obj_id: self.obj_id.clone(),
-                    infra_id,
-                }
-                .into())
-            }
-            Err(err) => return Err(err.into()),
-        };
+        let mut obj = DataObject::load(infra_id, &self.obj_type, &self.obj_id, conn).await?;
 
         // Apply and check patch
         let railjson_obj = obj.patch_and_check(self)?;
@@ -80,12 +61,38 @@ impl UpdateOperation {
 }
 
 #[derive(QueryableByName)]
-struct DataObject {
+pub(super) struct DataObject {
     #[diesel(sql_type = Jsonb)]
-    data: Value,
+    pub data: Value,
 }
 
 impl DataObject {
+    /// Load the railjson data of an object
+    pub async fn load(
+        infra_id: i64,
+        obj_type: &ObjectType,
+        obj_id: &str,
+        conn: &mut DbConnection,
+    ) -> Result<Self> {
+        match sql_query(format!(
+            "SELECT data FROM {} WHERE infra_id = $1 AND obj_id = $2",
+            get_table(obj_type)
+        ))
+        .bind::<BigInt, _>(infra_id)
+        .bind::<Text, _>(obj_id)
+        .get_result(conn)
+        .await
+        {
+            Ok(obj) => Ok(obj),
+            Err(DieselError::NotFound) => Err(OperationError::ObjectNotFound {
+                obj_id: obj_id.to_owned(),
+                infra_id,
+            }
+            .into()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// This function will patch the data object given an update operation.
     /// It will also check that the id of the id of the object is untouched and that the resulted data is valid.
     pub fn patch_and_check(&mut self, update: &UpdateOperation) -> Result<InfraObject> {
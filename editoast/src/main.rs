@@ -3,13 +3,16 @@ extern crate diesel;
 
 mod client;
 mod core;
+mod effort_table;
 mod error;
 mod fixtures;
 mod generated_data;
+mod gtfs;
 mod infra_cache;
 mod map;
 mod models;
 mod modelsv2;
+mod railml;
 mod redis_utils;
 mod tables;
 mod views;
@@ -30,13 +33,16 @@ use clap::Parser;
 use client::PostgresConfig;
 use client::{
     ClearArgs, Client, Color, Commands, DeleteProfileSetArgs, ElectricalProfilesCommands,
-    ExportTimetableArgs, GenerateArgs, ImportProfileSetArgs, ImportRailjsonArgs,
-    ImportRollingStockArgs, ImportTimetableArgs, InfraCloneArgs, InfraCommands, ListProfileSetArgs,
-    MakeMigrationArgs, RedisConfig, RefreshArgs, RunserverArgs, SearchCommands, TimetablesCommands,
+    ExportMbtilesArgs, ExportTimetableArgs, GenerateArgs, ImportGtfsArgs, ImportProfileSetArgs,
+    ImportRailjsonArgs, ImportRollingStockArgs, ImportTimetableArgs, InfraCloneArgs, InfraCommands,
+    ListProfileSetArgs, MakeMigrationArgs, RedisConfig, RefreshArgs, RollingStockFormat,
+    RunserverArgs, SearchCommands, TimetableFormat, TimetablesCommands, WarmTilesArgs,
 };
 use editoast_schemas::infra::ElectricalProfileSetData;
+use editoast_schemas::primitives::BoundingBox;
 use editoast_schemas::rolling_stock::RollingStock;
 use editoast_schemas::train_schedule::TrainScheduleBase;
+use modelsv2::RollingStockRevision;
 use modelsv2::{
     timetable::Timetable, timetable::TimetableWithTrains, train_schedule::TrainSchedule,
     train_schedule::TrainScheduleChangeset,
@@ -62,7 +68,7 @@ use opentelemetry_sdk::Resource;
 pub use redis_utils::{RedisClient, RedisConnection};
 use std::error::Error;
 use std::fs::File;
-use std::io::{BufReader, IsTerminal};
+use std::io::{self, BufReader, IsTerminal, Write};
 use std::process::exit;
 use std::sync::Arc;
 use std::{env, fs};
@@ -71,6 +77,8 @@ use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt as _, util::SubscriberInitExt as _, Layer as _};
 use validator::ValidationErrorsKind;
 use views::infra::InfraApiError;
+use views::layers::mbtiles;
+use views::layers::tile_warming;
 use views::search::{SearchConfig, SearchConfigFinder, SearchConfigStore};
 
 /// The mode editoast is running in
@@ -225,9 +233,14 @@ async fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
                 generate_infra(args, db_pool.pool_v1(), redis_config).await
             }
             InfraCommands::ImportRailjson(args) => import_railjson(args, db_pool.pool_v1()).await,
+            InfraCommands::WarmTiles(args) => warm_tiles(args, db_pool, redis_config).await,
+            InfraCommands::ExportMbtiles(args) => export_mbtiles(args, db_pool).await,
         },
         Commands::Timetables(subcommand) => match subcommand {
             TimetablesCommands::Import(args) => trains_import(args, db_pool.pool_v1()).await,
+            TimetablesCommands::ImportGtfs(args) => {
+                trains_import_gtfs(args, db_pool.pool_v1()).await
+            }
             TimetablesCommands::Export(args) => trains_export(args, db_pool.pool_v1()).await,
         },
     }
@@ -256,8 +269,16 @@ async fn trains_export(
         .map(|ts| Into::<TrainScheduleResult>::into(ts).train_schedule)
         .collect();
 
-    let file = File::create(args.path.clone())?;
-    serde_json::to_writer_pretty(file, &train_schedules)?;
+    let mut file = File::create(args.path.clone())?;
+    match args.format {
+        TimetableFormat::Json => serde_json::to_writer_pretty(file, &train_schedules)?,
+        TimetableFormat::Railml => {
+            let (document, report) =
+                railml::timetable::export_timetable(args.id, &train_schedules)?;
+            file.write_all(document.as_bytes())?;
+            print_mapping_report(&report);
+        }
+    }
 
     println!(
         "✅ Train schedules exported to {0}",
@@ -283,22 +304,82 @@ async fn trains_import(
     };
 
     let conn = &mut db_pool.get().await?;
-    let timetable = match args.id {
-        Some(timetable) => match Timetable::retrieve(conn, timetable).await? {
-            Some(timetable) => timetable,
-            None => {
-                let error = CliError::new(1, format!("❌ Timetable not found, id: {0}", timetable));
-                return Err(Box::new(error));
+    let timetable = retrieve_or_create_timetable(conn, args.id).await?;
+
+    let train_schedules: Vec<TrainScheduleBase> = match args.format {
+        TimetableFormat::Json => serde_json::from_reader(BufReader::new(train_file))?,
+        TimetableFormat::Railml => {
+            let document = io::read_to_string(train_file)?;
+            let (train_schedules, report) = railml::timetable::import_timetable(&document)?;
+            print_mapping_report(&report);
+            train_schedules
+        }
+    };
+    let changesets: Vec<TrainScheduleChangeset> = train_schedules
+        .into_iter()
+        .map(|train_schedule| {
+            TrainScheduleForm {
+                timetable_id: Some(timetable.id),
+                train_schedule,
             }
-        },
-        None => {
-            let changeset = Timetable::changeset();
-            changeset.create(conn).await?
+            .into()
+        })
+        .collect();
+    let inserted: Vec<_> = TrainSchedule::create_batch(conn, changesets).await?;
+
+    println!(
+        "✅ {} train schedules created for timetable with id {}",
+        inserted.len(),
+        timetable.id
+    );
+
+    Ok(())
+}
+
+async fn trains_import_gtfs(
+    args: ImportGtfsArgs,
+    db_pool: Arc<DbConnectionPool>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let feed = match File::open(args.path.clone()) {
+        Ok(file) => gtfs::Feed::from_zip(BufReader::new(file))?,
+        Err(e) => {
+            let error = CliError::new(
+                1,
+                format!("❌ Could not open file {:?} ({:?})", args.path, e),
+            );
+            return Err(Box::new(error));
         }
     };
+    let rolling_stocks: gtfs::timetable::RollingStockMapping =
+        serde_json::from_reader(BufReader::new(File::open(&args.rolling_stock_mapping)?))?;
+
+    let conn = &mut db_pool.get().await?;
+    if Infra::retrieve(conn, args.infra_id).await?.is_none() {
+        let error = CliError::new(
+            1,
+            format!("❌ Infrastructure not found, ID: {}", args.infra_id),
+        );
+        return Err(Box::new(error));
+    }
+    let timetable = retrieve_or_create_timetable(conn, args.id).await?;
+
+    let mut report = gtfs::ImportReport::default();
+    let trips = gtfs::timetable::select_trips(&feed, args.date, &rolling_stocks, &mut report);
+    let stop_ids = gtfs::timetable::served_stops(&feed, &trips);
+    let stop_locations =
+        gtfs::stop_matching::match_stops(conn, args.infra_id, &feed, &stop_ids, &mut report)
+            .await?;
+    let train_schedules = gtfs::timetable::build_train_schedules(
+        &feed,
+        args.date,
+        &trips,
+        &stop_locations,
+        &mut report,
+    );
+    if !report.is_empty() {
+        println!("⚠️ Some stops and trips could not be imported:\n{report}");
+    }
 
-    let train_schedules: Vec<TrainScheduleBase> =
-        serde_json::from_reader(BufReader::new(train_file))?;
     let changesets: Vec<TrainScheduleChangeset> = train_schedules
         .into_iter()
         .map(|train_schedule| {
@@ -320,6 +401,33 @@ async fn trains_import(
     Ok(())
 }
 
+/// Retrieve the given timetable, or create a new one if no id is given
+async fn retrieve_or_create_timetable(
+    conn: &mut DbConnection,
+    timetable_id: Option<i64>,
+) -> Result<Timetable, Box<dyn Error + Send + Sync>> {
+    match timetable_id {
+        Some(timetable_id) => match Timetable::retrieve(conn, timetable_id).await? {
+            Some(timetable) => Ok(timetable),
+            None => {
+                let error =
+                    CliError::new(1, format!("❌ Timetable not found, id: {0}", timetable_id));
+                Err(Box::new(error))
+            }
+        },
+        None => {
+            let changeset = Timetable::changeset();
+            Ok(changeset.create(conn).await?)
+        }
+    }
+}
+
+fn print_mapping_report(report: &railml::MappingReport) {
+    if !report.is_empty() {
+        println!("⚠️ Some elements could not be mapped:\n{report}");
+    }
+}
+
 fn log_received_request(req: &ServiceRequest) {
     let request_line = if req.query_string().is_empty() {
         format!("{} {} {:?}", req.method(), req.path(), req.version())
@@ -529,30 +637,32 @@ async fn import_rolling_stock(
     args: ImportRollingStockArgs,
     db_pool: Arc<DbConnectionPool>,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let effort_curves = match &args.effort_table {
+        Some(effort_table_path) => Some(effort_table::parse_effort_table(&fs::read_to_string(
+            effort_table_path,
+        )?)?),
+        None => None,
+    };
     for rolling_stock_path in args.rolling_stock_path {
         let rolling_stock_file = File::open(rolling_stock_path)?;
-        let rolling_stock_form: RollingStock =
-            serde_json::from_reader(BufReader::new(rolling_stock_file))?;
-        let rolling_stock: Changeset<RollingStockModel> = rolling_stock_form.into();
-        match rolling_stock.validate_imported_rolling_stock() {
-            Ok(()) => {
-                println!(
-                    "🍞 Importing rolling stock {}",
-                    rolling_stock
-                        .name
-                        .as_ref()
-                        .map(|n| n.bold())
-                        .unwrap_or("rolling stock witout name".bold())
-                );
-                let conn = &mut db_pool.get().await?;
-                let rolling_stock = rolling_stock.locked(false).version(0).create(conn).await?;
-                println!(
-                    "✅ Rolling stock {}[{}] saved!",
-                    &rolling_stock.name.bold(),
-                    &rolling_stock.id
-                );
+        let rolling_stock_forms: Vec<RollingStock> = match args.format {
+            RollingStockFormat::Json => {
+                vec![serde_json::from_reader(BufReader::new(rolling_stock_file))?]
+            }
+            RollingStockFormat::Railml => {
+                let document = io::read_to_string(rolling_stock_file)?;
+                let (rolling_stocks, report) =
+                    railml::rolling_stock::import_rolling_stocks(&document)?;
+                print_mapping_report(&report);
+                rolling_stocks
             }
-            Err(e) => {
+        };
+        for mut rolling_stock_form in rolling_stock_forms {
+            if let Some(effort_curves) = &effort_curves {
+                rolling_stock_form.effort_curves = effort_curves.clone();
+            }
+            let rolling_stock: Changeset<RollingStockModel> = rolling_stock_form.clone().into();
+            if let Err(e) = rolling_stock.validate_imported_rolling_stock() {
                 let mut error_message = "❌ Rolling stock was not created!".to_string();
                 if let Some(ValidationErrorsKind::Field(field_errors)) = e.errors().get("__all__") {
                     for error in field_errors {
@@ -570,7 +680,28 @@ async fn import_rolling_stock(
                 }
                 return Err(Box::new(CliError::new(2, error_message)));
             }
-        };
+
+            if args.dry_run {
+                println!("{}", serde_json::to_string_pretty(&rolling_stock_form)?);
+                continue;
+            }
+            println!(
+                "🍞 Importing rolling stock {}",
+                rolling_stock
+                    .name
+                    .as_ref()
+                    .map(|n| n.bold())
+                    .unwrap_or("rolling stock witout name".bold())
+            );
+            let conn = &mut db_pool.get().await?;
+            let rolling_stock = rolling_stock.locked(false).version(0).create(conn).await?;
+            RollingStockRevision::record(conn, &rolling_stock).await?;
+            println!(
+                "✅ Rolling stock {}[{}] saved!",
+                &rolling_stock.name.bold(),
+                &rolling_stock.id
+            );
+        }
     }
     Ok(())
 }
@@ -735,6 +866,137 @@ async fn clear_infra(
     Ok(())
 }
 
+/// Run the warm-tiles subcommand
+/// This command generates and caches the map layer tiles of the given infras (if no infra given then of all of them)
+async fn warm_tiles(
+    args: WarmTilesArgs,
+    db_pool: DbConnectionPoolV2,
+    redis_config: RedisConfig,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut infras = vec![];
+    {
+        let mut conn = db_pool.get().await?;
+        let infra_list = if args.infra_ids.is_empty() {
+            // Retrieve all available infra
+            Infra::all(&mut conn).await
+        } else {
+            // Retrieve given infras
+            batch_retrieve_infras(&mut conn, &args.infra_ids).await?
+        };
+        for infra in infra_list {
+            let infra_cache = InfraCache::load(&mut conn, &infra).await?;
+            infras.push((infra, tile_warming::infra_bbox(&infra_cache)));
+        }
+    }
+
+    let redis = RedisClient::new(redis_config)?;
+    let map_layers = MapLayers::parse();
+    for (infra, bbox) in infras {
+        println!(
+            "🍞 Infra {}[{}] tiles are warming up:",
+            infra.name.clone().bold(),
+            infra.id
+        );
+        let generated = tile_warming::warm_tiles(
+            &db_pool,
+            &redis,
+            &map_layers,
+            infra.id,
+            &bbox,
+            &args.tile_warming_config,
+            |processed, total| {
+                if processed % 1000 == 0 || processed == total {
+                    println!("⏳ {processed}/{total} tiles processed");
+                }
+            },
+        )
+        .await?;
+        println!(
+            "✅ Infra {}[{}] warmed up! {generated} tiles generated",
+            infra.name.bold(),
+            infra.id
+        );
+    }
+    Ok(())
+}
+
+/// Run the export-mbtiles subcommand
+/// This command renders the map layers of an infra in an MBTiles archive
+async fn export_mbtiles(
+    args: ExportMbtilesArgs,
+    db_pool: DbConnectionPoolV2,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if args.min_zoom > args.max_zoom {
+        let error = CliError::new(
+            1,
+            format!(
+                "❌ Invalid zoom range: {} is greater than {}",
+                args.min_zoom, args.max_zoom
+            ),
+        );
+        return Err(Box::new(error));
+    }
+    if args.path.exists() {
+        let error = CliError::new(
+            1,
+            format!(
+                "❌ File already exists, Path: {}",
+                args.path.to_string_lossy()
+            ),
+        );
+        return Err(Box::new(error));
+    }
+
+    let mut conn = db_pool.get().await?;
+    let infra = Infra::retrieve(&mut conn, args.infra_id as i64)
+        .await?
+        .ok_or_else(|| {
+            CliError::new(
+                1,
+                format!("❌ Infrastructure not found, ID: {}", args.infra_id),
+            )
+        })?;
+    let bbox = match args.bbox.as_deref() {
+        Some(&[min_lon, min_lat, max_lon, max_lat]) => {
+            BoundingBox((min_lon, min_lat), (max_lon, max_lat))
+        }
+        _ => tile_warming::infra_bbox(&InfraCache::load(&mut conn, &infra).await?),
+    };
+    if !bbox.is_valid() {
+        let error = CliError::new(1, "❌ Nothing to export, the bounding box is empty");
+        return Err(Box::new(error));
+    }
+
+    println!(
+        "🍞 Exporting the layers of infra {}[{}]",
+        infra.name.clone().bold(),
+        infra.id
+    );
+    let stored_tiles = mbtiles::export_mbtiles(
+        &mut conn,
+        &MapLayers::parse(),
+        infra.id,
+        &infra.name,
+        &bbox,
+        args.min_zoom,
+        args.max_zoom,
+        &args.path,
+        |processed, total| {
+            if processed % 1000 == 0 || processed == total {
+                println!("⏳ {processed}/{total} tiles processed");
+            }
+        },
+    )
+    .await?;
+    println!(
+        "✅ Infra {}[{}] exported in {}! {stored_tiles} tiles stored",
+        infra.name.bold(),
+        infra.id,
+        args.path.to_string_lossy()
+    );
+    Ok(())
+}
+
 /// Prints the OpenApi to stdout
 fn generate_openapi() {
     let openapi = OpenApiRoot::build_openapi();
@@ -894,7 +1156,10 @@ mod tests {
     use tempfile::NamedTempFile;
 
     #[rstest]
-    async fn import_export_timetable_schedule_v2(db_pool: Arc<DbConnectionPool>) {
+    async fn import_export_timetable_schedule_v2(
+        db_pool: Arc<DbConnectionPool>,
+        #[values(TimetableFormat::Json, TimetableFormat::Railml)] format: TimetableFormat,
+    ) {
         let conn = &mut db_pool.get().await.unwrap();
 
         let changeset = Timetable::changeset();
@@ -908,6 +1173,7 @@ mod tests {
         let args = ImportTimetableArgs {
             path: file.path().into(),
             id: Some(timetable.id),
+            ..Default::default()
         };
         let result = trains_import(args, db_pool.clone()).await;
         assert!(result.is_ok(), "{:?}", result);
@@ -917,6 +1183,7 @@ mod tests {
         let args = ExportTimetableArgs {
             path: export_file.path().into(),
             id: timetable.id,
+            format,
         };
         let export_result = trains_export(args, db_pool.clone()).await;
         assert!(export_result.is_ok(), "{:?}", export_result);
@@ -925,6 +1192,7 @@ mod tests {
         let reimport_args = ImportTimetableArgs {
             path: export_file.path().into(),
             id: Some(timetable.id),
+            format,
         };
         let reimport_result = trains_import(reimport_args, db_pool.clone()).await;
         assert!(reimport_result.is_ok(), "{:?}", reimport_result);
@@ -932,11 +1200,46 @@ mod tests {
         Timetable::delete_static(conn, timetable.id).await.unwrap();
     }
 
+    #[rstest]
+    #[case::min_zoom("--min-zoom")]
+    #[case::max_zoom("--max-zoom")]
+    fn export_mbtiles_zoom_out_of_range(#[case] zoom_arg: &str) {
+        let result = Client::try_parse_from([
+            "editoast",
+            "infra",
+            "export-mbtiles",
+            "1",
+            "infra.mbtiles",
+            zoom_arg,
+            "21",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    async fn export_mbtiles_inverted_zoom_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("infra.mbtiles");
+        let args = ExportMbtilesArgs {
+            infra_id: 1,
+            path: path.clone(),
+            min_zoom: 14,
+            max_zoom: 5,
+            bbox: None,
+        };
+
+        let result = export_mbtiles(args, DbConnectionPoolV2::for_tests()).await;
+
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
     #[rstest]
     async fn import_rolling_stock_ko_file_not_found(db_pool: Arc<DbConnectionPool>) {
         // GIVEN
         let args = ImportRollingStockArgs {
             rolling_stock_path: vec!["non/existing/railjson/file/location".into()],
+            ..Default::default()
         };
 
         // WHEN
@@ -959,6 +1262,7 @@ mod tests {
         let file = generate_temp_file(&non_electric_rs);
         let args = ImportRollingStockArgs {
             rolling_stock_path: vec![file.path().into()],
+            ..Default::default()
         };
 
         // WHEN
@@ -989,6 +1293,7 @@ mod tests {
         let file = generate_temp_file(&non_electric_rs);
         let args = ImportRollingStockArgs {
             rolling_stock_path: vec![file.path().into()],
+            ..Default::default()
         };
 
         // WHEN
@@ -1012,6 +1317,32 @@ mod tests {
         assert!(raise_pantograph_time.is_some());
     }
 
+    #[rstest]
+    async fn import_rolling_stock_dry_run(db_pool: Arc<DbConnectionPool>) {
+        // GIVEN
+        let rolling_stock_name = "fast_rolling_stock_import_rolling_stock_dry_run";
+        let rolling_stock = get_fast_rolling_stock_schema(rolling_stock_name);
+
+        let file = generate_temp_file(&rolling_stock);
+        let args = ImportRollingStockArgs {
+            rolling_stock_path: vec![file.path().into()],
+            dry_run: true,
+            ..Default::default()
+        };
+
+        // WHEN
+        let result = import_rolling_stock(args, db_pool.clone()).await;
+
+        // THEN
+        assert!(result.is_ok(), "dry run should succeed");
+        let mut conn = db_pool.get().await.unwrap();
+        use crate::modelsv2::Exists;
+        let exists = RollingStockModel::exists(&mut conn, rolling_stock_name.to_string())
+            .await
+            .unwrap();
+        assert!(!exists, "dry run should not save the rolling stock");
+    }
+
     #[rstest]
     async fn import_electric_rs_without_startup_and_panto_values(db_pool: Arc<DbConnectionPool>) {
         // GIVEN
@@ -1023,6 +1354,7 @@ mod tests {
         let file = generate_temp_file(&electric_rs);
         let args = ImportRollingStockArgs {
             rolling_stock_path: vec![file.path().into()],
+            ..Default::default()
         };
 
         // WHEN
@@ -1050,6 +1382,7 @@ mod tests {
         let file = generate_temp_file(&electric_rolling_stock);
         let args = ImportRollingStockArgs {
             rolling_stock_path: vec![file.path().into()],
+            ..Default::default()
         };
 
         // WHEN
@@ -0,0 +1,159 @@
+use editoast_schemas::primitives::BoundingBox;
+use editoast_schemas::primitives::ObjectType;
+
+use crate::infra_cache::operation::Operation;
+use crate::infra_cache::InfraCache;
+use crate::infra_cache::ObjectCache;
+
+/// Geographic area of an infra impacted by a batch of edition operations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InvalidationZone {
+    pub geo: BoundingBox,
+}
+
+impl InvalidationZone {
+    /// Computes the zone covered by the objects targeted by the operations,
+    /// as they are currently stored in the infra cache.
+    ///
+    /// Objects are located using the bounding box of the track sections they reference.
+    /// To cover both the former and the new location of the edited objects, the zone
+    /// must be computed before and after applying the operations and then merged.
+    pub fn compute(infra_cache: &InfraCache, operations: &[Operation]) -> Self {
+        let mut zone = Self::default();
+        for operation in operations {
+            let obj_ref = operation.get_ref();
+            if obj_ref.obj_type == ObjectType::SwitchType {
+                // Switch types have no location, but the switches using them may be impacted
+                infra_cache
+                    .switches()
+                    .values()
+                    .map(ObjectCache::unwrap_switch)
+                    .filter(|switch| switch.switch_type == obj_ref.obj_id)
+                    .flat_map(|switch| switch.ports.values())
+                    .for_each(|port| zone.add_track(infra_cache, &port.track.0));
+                continue;
+            }
+            let Some(object) = infra_cache
+                .get_objects_by_type(obj_ref.obj_type)
+                .get(&obj_ref.obj_id)
+            else {
+                continue;
+            };
+            match object {
+                ObjectCache::TrackSection(track) => {
+                    zone.geo.union(&track.bbox_geo);
+                }
+                object => object
+                    .get_track_referenced_id()
+                    .into_iter()
+                    .for_each(|track_id| zone.add_track(infra_cache, track_id)),
+            }
+        }
+        zone
+    }
+
+    fn add_track(&mut self, infra_cache: &InfraCache, track_id: &str) {
+        if let Ok(track) = infra_cache.get_track_section(track_id) {
+            self.geo.union(&track.bbox_geo);
+        }
+    }
+
+    /// Extends the zone so that it also covers `other`
+    pub fn union(&mut self, other: &Self) -> &mut Self {
+        self.geo.union(&other.geo);
+        self
+    }
+
+    /// Returns `true` if the zone doesn't cover any area
+    pub fn is_empty(&self) -> bool {
+        !self.geo.is_valid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use editoast_schemas::infra::InfraObject;
+    use editoast_schemas::infra::Signal;
+    use editoast_schemas::primitives::BoundingBox;
+    use editoast_schemas::primitives::ObjectType;
+
+    use super::InvalidationZone;
+    use crate::infra_cache::object_cache::TrackSectionCache;
+    use crate::infra_cache::operation::DeleteOperation;
+    use crate::infra_cache::operation::Operation;
+    use crate::infra_cache::tests::create_signal_cache;
+    use crate::infra_cache::tests::create_track_section_cache;
+    use crate::infra_cache::InfraCache;
+
+    fn infra_cache_with_tracks() -> InfraCache {
+        let mut infra_cache = InfraCache::default();
+        infra_cache
+            .add(TrackSectionCache {
+                bbox_geo: BoundingBox((0., 0.), (1., 1.)),
+                ..create_track_section_cache("A", 500.)
+            })
+            .unwrap();
+        infra_cache
+            .add(TrackSectionCache {
+                bbox_geo: BoundingBox((5., 5.), (6., 6.)),
+                ..create_track_section_cache("B", 500.)
+            })
+            .unwrap();
+        infra_cache
+            .add(create_signal_cache("S1", "B", 100.))
+            .unwrap();
+        infra_cache
+    }
+
+    #[test]
+    fn zone_of_deleted_track_section() {
+        let infra_cache = infra_cache_with_tracks();
+        let operations = [Operation::Delete(DeleteOperation {
+            obj_id: "A".into(),
+            obj_type: ObjectType::TrackSection,
+        })];
+        let zone = InvalidationZone::compute(&infra_cache, &operations);
+        assert_eq!(zone.geo, BoundingBox((0., 0.), (1., 1.)));
+    }
+
+    #[test]
+    fn zone_of_object_referencing_a_track() {
+        let infra_cache = infra_cache_with_tracks();
+        let operations = [Operation::Delete(DeleteOperation {
+            obj_id: "S1".into(),
+            obj_type: ObjectType::Signal,
+        })];
+        let zone = InvalidationZone::compute(&infra_cache, &operations);
+        assert_eq!(zone.geo, BoundingBox((5., 5.), (6., 6.)));
+    }
+
+    #[test]
+    fn zone_of_unknown_objects_is_empty() {
+        let infra_cache = infra_cache_with_tracks();
+        let operations = [Operation::Create(Box::new(InfraObject::Signal {
+            railjson: Signal {
+                id: "S2".into(),
+                track: "A".into(),
+                ..Default::default()
+            },
+        }))];
+        let zone = InvalidationZone::compute(&infra_cache, &operations);
+        assert!(zone.is_empty());
+    }
+
+    #[test]
+    fn union_of_zones() {
+        let infra_cache = infra_cache_with_tracks();
+        let before = [Operation::Delete(DeleteOperation {
+            obj_id: "A".into(),
+            obj_type: ObjectType::TrackSection,
+        })];
+        let after = [Operation::Delete(DeleteOperation {
+            obj_id: "S1".into(),
+            obj_type: ObjectType::Signal,
+        })];
+        let mut zone = InvalidationZone::compute(&infra_cache, &before);
+        zone.union(&InvalidationZone::compute(&infra_cache, &after));
+        assert_eq!(zone.geo, BoundingBox((0., 0.), (6., 6.)));
+    }
+}
@@ -1,13 +1,78 @@
+use std::f64::consts::PI;
+
+use editoast_schemas::primitives::BoundingBox;
+
 use crate::client::get_app_version;
 
+/// Maximum latitude (in degrees) covered by the web mercator projection
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
 /// Web mercator coordinates
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Tile {
     pub x: u64,
     pub y: u64,
     pub z: u64,
 }
 
+impl Tile {
+    /// Returns the tile containing the given WGS84 coordinates at a zoom level
+    pub fn from_coordinates(lon: f64, lat: f64, z: u64) -> Self {
+        let n = (1_u64 << z) as f64;
+        let lat = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        let x = ((lon + 180.) / 360. * n).floor();
+        let y = ((1. - lat.tan().asinh() / PI) / 2. * n).floor();
+        Tile {
+            x: x.clamp(0., n - 1.) as u64,
+            y: y.clamp(0., n - 1.) as u64,
+            z,
+        }
+    }
+}
+
+/// Returns, for each zoom level up to `max_zoom` (included), the top left and
+/// bottom right tiles of the area covered by the bounding box
+fn bbox_tile_corners(bbox: &BoundingBox, max_zoom: u64) -> Vec<(Tile, Tile)> {
+    if !bbox.is_valid() {
+        return vec![];
+    }
+    let BoundingBox((min_lon, min_lat), (max_lon, max_lat)) = *bbox;
+    (0..=max_zoom)
+        .map(|z| {
+            (
+                Tile::from_coordinates(min_lon, max_lat, z),
+                Tile::from_coordinates(max_lon, min_lat, z),
+            )
+        })
+        .collect()
+}
+
+/// Counts the tiles intersecting a bounding box, from zoom 0 up to `max_zoom` (included)
+pub fn count_tiles_in_bbox(bbox: &BoundingBox, max_zoom: u64) -> u64 {
+    bbox_tile_corners(bbox, max_zoom)
+        .into_iter()
+        .map(|(top_left, bottom_right)| {
+            (bottom_right.x - top_left.x + 1) * (bottom_right.y - top_left.y + 1)
+        })
+        .sum()
+}
+
+/// Returns the tiles intersecting a bounding box, from zoom 0 up to `max_zoom` (included)
+pub fn get_tiles_in_bbox(bbox: &BoundingBox, max_zoom: u64) -> Vec<Tile> {
+    bbox_tile_corners(bbox, max_zoom)
+        .into_iter()
+        .flat_map(|(top_left, bottom_right)| {
+            (top_left.x..=bottom_right.x).flat_map(move |x| {
+                (top_left.y..=bottom_right.y).map(move |y| Tile {
+                    x,
+                    y,
+                    z: top_left.z,
+                })
+            })
+        })
+        .collect()
+}
+
 pub fn get_layer_cache_prefix(layer_name: &str, infra_id: i64) -> String {
     let version = get_app_version().unwrap_or("default".into());
     format!("editoast.{version}.layer.{layer_name}.infra_{infra_id}")
@@ -32,8 +97,12 @@ pub fn get_cache_tile_key(view_prefix: &str, tile: &Tile) -> String {
 #[cfg(test)]
 mod tests {
 
+    use editoast_schemas::primitives::BoundingBox;
+
+    use super::count_tiles_in_bbox;
     use super::get_cache_tile_key;
     use super::get_layer_cache_prefix;
+    use super::get_tiles_in_bbox;
     use super::get_view_cache_prefix;
     use super::Tile;
 
@@ -63,4 +132,43 @@ mod tests {
             "editoast.default.layer.track_sections.infra_1.tile/3/1/2"
         );
     }
+
+    #[test]
+    fn test_tile_from_coordinates() {
+        assert_eq!(Tile::from_coordinates(0., 0., 0), Tile { x: 0, y: 0, z: 0 });
+        assert_eq!(
+            Tile::from_coordinates(-0.38, 49.5, 1),
+            Tile { x: 0, y: 0, z: 1 }
+        );
+        assert_eq!(
+            Tile::from_coordinates(2.35, 48.85, 10),
+            Tile {
+                x: 518,
+                y: 352,
+                z: 10
+            }
+        );
+        // Coordinates outside of the projection are clamped
+        assert_eq!(
+            Tile::from_coordinates(180., -90., 2),
+            Tile { x: 3, y: 3, z: 2 }
+        );
+    }
+
+    #[test]
+    fn test_get_tiles_in_bbox() {
+        let bbox = BoundingBox((-0.4, 49.4), (-0.3, 49.5));
+        let tiles = get_tiles_in_bbox(&bbox, 12);
+        assert_eq!(tiles.len() as u64, count_tiles_in_bbox(&bbox, 12));
+        assert!(tiles.contains(&Tile { x: 0, y: 0, z: 0 }));
+        assert!(tiles.contains(&Tile::from_coordinates(-0.35, 49.45, 12)));
+        assert!(!tiles.contains(&Tile::from_coordinates(-0.1, 49.45, 12)));
+        assert_eq!(tiles.iter().filter(|tile| tile.z == 5).count(), 1);
+    }
+
+    #[test]
+    fn test_get_tiles_in_invalid_bbox() {
+        assert!(get_tiles_in_bbox(&BoundingBox::default(), 18).is_empty());
+        assert_eq!(count_tiles_in_bbox(&BoundingBox::default(), 18), 0);
+    }
 }
@@ -21,6 +21,16 @@ pub struct View {
     pub cache_duration: u64,
     #[serde(rename = "where", default)]
     pub where_expr: Vec<String>,
+    /// Allows to only keep the features intersecting a time window given when querying the view
+    #[serde(default)]
+    pub time_window: Option<TimeWindowFields>,
+}
+
+/// Expressions giving the time interval of the features of a view
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TimeWindowFields {
+    pub start_field: String,
+    pub end_field: String,
 }
 
 /// Layer description
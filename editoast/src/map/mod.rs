@@ -1,18 +1,26 @@
+mod invalidation_zone;
 mod layer_cache;
 mod layers;
 
+pub use invalidation_zone::InvalidationZone;
 pub use layers::Layer;
 pub use layers::MapLayers;
 pub use layers::View;
 use redis::AsyncCommands;
 
+use self::layer_cache::count_tiles_in_bbox;
 pub use self::layer_cache::get_cache_tile_key;
 pub use self::layer_cache::get_layer_cache_prefix;
+pub use self::layer_cache::get_tiles_in_bbox;
 pub use self::layer_cache::get_view_cache_prefix;
 pub use self::layer_cache::Tile;
+use crate::client::MapLayersConfig;
 use crate::error::Result;
 use crate::RedisConnection;
 
+/// Maximum number of keys deleted by a single redis command when invalidating tiles
+const TILE_INVALIDATION_BATCH_SIZE: usize = 1000;
+
 /// Invalidates layer cache for a specific infra and view if provided
 ///
 /// # Arguments
@@ -55,3 +63,77 @@ pub async fn invalidate_all(
     }
     Ok(())
 }
+
+/// Invalidates the cached tiles of every view of a layer for a specific infra
+///
+/// # Arguments
+///
+/// * `redis` - Connection to the redis
+/// * `infra_id` - Infra on which the tiles must be invalidated
+/// * `layer_name` - Name of the layer to invalidate
+/// * `layer` - Layer description, giving the views to invalidate
+/// * `tiles` - Tiles to invalidate
+///
+/// Returns the number of deleted keys
+async fn invalidate_layer_tiles(
+    redis: &mut RedisConnection,
+    infra_id: i64,
+    layer_name: &str,
+    layer: &Layer,
+    tiles: &[Tile],
+) -> Result<u64> {
+    let keys: Vec<String> = layer
+        .views
+        .keys()
+        .flat_map(|view_name| {
+            let view_prefix = get_view_cache_prefix(layer_name, infra_id, view_name);
+            tiles
+                .iter()
+                .map(move |tile| get_cache_tile_key(&view_prefix, tile))
+        })
+        .collect();
+    let mut number_of_deleted_keys = 0;
+    // Avoid sending huge commands that would block redis
+    for keys in keys.chunks(TILE_INVALIDATION_BATCH_SIZE) {
+        let deleted: u64 = redis.del(keys).await?;
+        number_of_deleted_keys += deleted;
+    }
+    Ok(number_of_deleted_keys)
+}
+
+/// Invalidates the cached tiles of all map layers of a specific infra intersecting a zone
+///
+/// If the zone spans more tiles than `max_tiles`, whole layers are invalidated instead.
+///
+/// # Arguments
+///
+/// * `redis` - Connection to the redis
+/// * `map_layers` - Layers to invalidate
+/// * `infra_id` - Infra on which layers must be invalidated
+/// * `zone` - Zone impacted by an infra edition
+/// * `map_layers_config` - Gives the maximum zoom level and number of tiles to invalidate
+pub async fn invalidate_zone(
+    redis: &mut RedisConnection,
+    map_layers: &MapLayers,
+    infra_id: i64,
+    zone: &InvalidationZone,
+    map_layers_config: &MapLayersConfig,
+) -> Result<()> {
+    if zone.is_empty() {
+        return Ok(());
+    }
+    let max_zoom = map_layers_config.max_zoom;
+    if count_tiles_in_bbox(&zone.geo, max_zoom) > map_layers_config.max_tiles {
+        return invalidate_all(
+            redis,
+            &map_layers.layers.keys().cloned().collect(),
+            infra_id,
+        )
+        .await;
+    }
+    let tiles = get_tiles_in_bbox(&zone.geo, max_zoom);
+    for (layer_name, layer) in map_layers.layers.iter() {
+        invalidate_layer_tiles(redis, infra_id, layer_name, layer, &tiles).await?;
+    }
+    Ok(())
+}
@@ -36,6 +36,8 @@ use crate::modelsv2::railjson::persist_railjson;
 use crate::modelsv2::Create;
 use crate::modelsv2::DbConnection;
 use crate::modelsv2::DbConnectionPool;
+use crate::modelsv2::InfraEdit;
+use crate::modelsv2::InfraSnapshot;
 use crate::tables::infra::dsl;
 use editoast_schemas::infra::RailJson;
 use editoast_schemas::infra::RAILJSON_VERSION;
@@ -210,6 +212,80 @@ impl Infra {
         })).await
     }
 
+    /// Tag the current state of the infra as a named snapshot
+    pub async fn take_snapshot(
+        &self,
+        conn: &mut DbConnection,
+        name: String,
+    ) -> Result<InfraSnapshot> {
+        conn.build_transaction()
+            .run(|conn| {
+                Box::pin(async {
+                    let snapshot = InfraSnapshot::changeset()
+                        .infra_id(self.id)
+                        .name(name)
+                        .infra_version(self.version.clone())
+                        .create(conn)
+                        .await?;
+                    snapshot.copy_objects_from(conn, self.id).await?;
+                    Ok(snapshot)
+                })
+            })
+            .await
+    }
+
+    /// Replace the railjson objects of the infra by the ones of a snapshot
+    ///
+    /// The version of the infra is bumped and its edition history is cleared, since the recorded
+    /// edits can't be reverted anymore. The generated data is outdated and must be refreshed.
+    pub async fn restore_snapshot(
+        &mut self,
+        conn: &mut DbConnection,
+        snapshot: &InfraSnapshot,
+    ) -> Result<()> {
+        conn.build_transaction()
+            .run(|conn| {
+                Box::pin(async {
+                    for object in ObjectType::iter() {
+                        sql_query(format!(
+                            "DELETE FROM {} WHERE infra_id = $1",
+                            get_table(&object)
+                        ))
+                        .bind::<BigInt, _>(self.id)
+                        .execute(conn)
+                        .await?;
+                    }
+                    snapshot.copy_objects_to(conn, self.id).await?;
+                    InfraEdit::clear_history(conn, self.id).await?;
+                    self.bump_version(conn).await
+                })
+            })
+            .await
+    }
+
+    /// Create a new infra holding the railjson objects of a snapshot
+    ///
+    /// The generated data of the new infra is not computed.
+    pub async fn create_from_snapshot(
+        conn: &mut DbConnection,
+        snapshot: &InfraSnapshot,
+        name: String,
+    ) -> Result<Infra> {
+        conn.build_transaction()
+            .run(|conn| {
+                Box::pin(async {
+                    let infra = Infra::changeset()
+                        .name(name)
+                        .last_railjson_version()
+                        .create(conn)
+                        .await?;
+                    snapshot.copy_objects_to(conn, infra.id).await?;
+                    Ok(infra)
+                })
+            })
+            .await
+    }
+
     /// Refreshes generated data if not up to date and returns whether they were refreshed.
     /// `force` argument allows us to refresh it in any cases.
     /// This function will update `generated_version` accordingly.
@@ -0,0 +1,106 @@
+use chrono::NaiveDateTime;
+use diesel::delete;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use editoast_derive::ModelV2;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::infra_cache::operation::Operation;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::tables::infra_edit::dsl;
+
+editoast_common::schemas! {
+    InfraEdit,
+}
+
+/// A batch of operations applied to an infra, kept in the edition history of the infra
+#[derive(Debug, Clone, Serialize, Deserialize, ModelV2, ToSchema)]
+#[model(table = crate::tables::infra_edit)]
+pub struct InfraEdit {
+    pub id: i64,
+    pub infra_id: i64,
+    /// The version of the infra once the operations were applied
+    pub infra_version: String,
+    pub creation_date: NaiveDateTime,
+    /// The applied operations
+    #[model(json)]
+    pub operations: Vec<Operation>,
+    /// The operations reverting the applied ones
+    #[model(json)]
+    pub inverse_operations: Vec<Operation>,
+    /// Whether the operations were reverted by an undo
+    pub undone: bool,
+    /// The user who applied the operations, if known
+    pub author: Option<String>,
+}
+
+impl InfraEdit {
+    /// Add a batch of operations to the history of an infra
+    ///
+    /// The undone edits of the infra can't be redone anymore, so they are removed from the history.
+    pub async fn record(
+        conn: &mut DbConnection,
+        infra_id: i64,
+        infra_version: String,
+        operations: Vec<Operation>,
+        inverse_operations: Vec<Operation>,
+        author: Option<String>,
+    ) -> Result<InfraEdit> {
+        delete(
+            dsl::infra_edit
+                .filter(dsl::infra_id.eq(infra_id))
+                .filter(dsl::undone.eq(true)),
+        )
+        .execute(conn)
+        .await?;
+        InfraEdit::changeset()
+            .infra_id(infra_id)
+            .infra_version(infra_version)
+            .operations(operations)
+            .inverse_operations(inverse_operations)
+            .author(author)
+            .create(conn)
+            .await
+    }
+
+    /// Remove all the edits of an infra from its history
+    pub async fn clear_history(conn: &mut DbConnection, infra_id: i64) -> Result<()> {
+        delete(dsl::infra_edit.filter(dsl::infra_id.eq(infra_id)))
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieve the last `count` applied edits of an infra, the latest first
+    pub async fn list_last_applied(
+        conn: &mut DbConnection,
+        infra_id: i64,
+        count: u64,
+    ) -> Result<Vec<InfraEdit>> {
+        let settings = SelectionSettings::new()
+            .filter(move || InfraEdit::INFRA_ID.eq(infra_id))
+            .filter(|| InfraEdit::UNDONE.eq(false))
+            .order_by(|| InfraEdit::ID.desc())
+            .limit(count);
+        InfraEdit::list(conn, settings).await
+    }
+
+    /// Retrieve the first `count` undone edits of an infra, the earliest first
+    pub async fn list_first_undone(
+        conn: &mut DbConnection,
+        infra_id: i64,
+        count: u64,
+    ) -> Result<Vec<InfraEdit>> {
+        let settings = SelectionSettings::new()
+            .filter(move || InfraEdit::INFRA_ID.eq(infra_id))
+            .filter(|| InfraEdit::UNDONE.eq(true))
+            .order_by(|| InfraEdit::ID.asc())
+            .limit(count);
+        InfraEdit::list(conn, settings).await
+    }
+}
@@ -293,6 +293,33 @@ impl OperationalPointModel {
             .map(Self::from_row)
             .collect())
     }
+
+    /// Retrieve the operational points whose name matches one of the given names, ignoring case
+    pub async fn retrieve_from_names(
+        conn: &mut DbConnection,
+        infra_id: i64,
+        names: &[String],
+    ) -> crate::error::Result<Vec<Self>> {
+        use diesel::sql_query;
+        use diesel::sql_types::Array;
+        use diesel::sql_types::BigInt;
+        use diesel::sql_types::Text;
+        use diesel_async::RunQueryDsl;
+        let names: Vec<_> = names.iter().map(|name| name.to_lowercase()).collect();
+        let query = {
+            "SELECT * FROM infra_object_operational_point
+                WHERE infra_id = $1 AND lower(data->'extensions'->'identifier'->>'name') = ANY($2)"
+        }
+        .to_string();
+        Ok(sql_query(query)
+            .bind::<BigInt, _>(infra_id)
+            .bind::<Array<Text>, _>(names)
+            .load(conn)
+            .await?
+            .into_iter()
+            .map(Self::from_row)
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -357,4 +384,16 @@ mod tests_retrieve {
             .expect("Failed to retrieve operational points");
         assert_eq!(res.len(), 2);
     }
+
+    #[rstest::rstest]
+    async fn from_names() {
+        let pg_db_pool = db_pool();
+        let small_infra = small_infra(pg_db_pool.clone()).await;
+        let mut conn = pg_db_pool.get().await.unwrap();
+        let names = vec!["west_station".to_string(), "MID_EAST_STATION".to_string()];
+        let res = OperationalPointModel::retrieve_from_names(&mut conn, small_infra.id, &names)
+            .await
+            .expect("Failed to retrieve operational points");
+        assert_eq!(res.len(), 2);
+    }
 }
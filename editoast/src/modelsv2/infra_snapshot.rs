@@ -0,0 +1,77 @@
+use chrono::NaiveDateTime;
+use diesel::sql_query;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Text;
+use diesel_async::RunQueryDsl;
+use editoast_derive::ModelV2;
+use serde::Deserialize;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::modelsv2::get_table;
+use crate::modelsv2::DbConnection;
+use editoast_schemas::primitives::ObjectType;
+
+editoast_common::schemas! {
+    InfraSnapshot,
+}
+
+/// A named copy of the railjson objects of an infra at a given version
+#[derive(Debug, Clone, Serialize, Deserialize, ModelV2, ToSchema)]
+#[model(table = crate::tables::infra_snapshot)]
+pub struct InfraSnapshot {
+    pub id: i64,
+    pub infra_id: i64,
+    pub name: String,
+    /// The version of the infra when the snapshot was taken
+    pub infra_version: String,
+    pub creation_date: NaiveDateTime,
+}
+
+impl InfraSnapshot {
+    /// Copy the railjson objects of an infra into the snapshot
+    pub(in crate::modelsv2) async fn copy_objects_from(
+        &self,
+        conn: &mut DbConnection,
+        infra_id: i64,
+    ) -> Result<()> {
+        for object in ObjectType::iter() {
+            let model_table = get_table(&object);
+            sql_query(format!(
+                "INSERT INTO infra_snapshot_object(snapshot_id, obj_type, obj_id, data)
+                    SELECT $1, $2, obj_id, data FROM {model_table} WHERE infra_id = $3"
+            ))
+            .bind::<BigInt, _>(self.id)
+            .bind::<Text, _>(object.to_string())
+            .bind::<BigInt, _>(infra_id)
+            .execute(conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Copy the railjson objects of the snapshot into an infra
+    ///
+    /// The infra is expected to hold no object.
+    pub(in crate::modelsv2) async fn copy_objects_to(
+        &self,
+        conn: &mut DbConnection,
+        infra_id: i64,
+    ) -> Result<()> {
+        for object in ObjectType::iter() {
+            let model_table = get_table(&object);
+            sql_query(format!(
+                "INSERT INTO {model_table}(obj_id, data, infra_id)
+                    SELECT obj_id, data, $1 FROM infra_snapshot_object WHERE snapshot_id = $2 AND obj_type = $3"
+            ))
+            .bind::<BigInt, _>(infra_id)
+            .bind::<BigInt, _>(self.id)
+            .bind::<Text, _>(object.to_string())
+            .execute(conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -4,7 +4,9 @@ pub mod electrical_profiles;
 #[cfg(test)]
 pub mod fixtures;
 pub mod infra;
+pub mod infra_edit;
 pub mod infra_objects;
+pub mod infra_snapshot;
 pub mod light_rolling_stock;
 // We allow unused until models is moved to a separate crate
 pub mod pagination;
@@ -15,10 +17,13 @@ pub mod railjson;
 pub mod rolling_stock_image;
 pub mod rolling_stock_livery;
 pub mod rolling_stock_model;
+pub mod rolling_stock_revision;
 pub mod scenario;
 pub mod study;
 pub mod timetable;
+pub mod train_composition;
 pub mod train_schedule;
+pub mod train_schedule_pattern;
 pub mod work_schedules;
 
 pub use prelude::*;
@@ -29,20 +34,27 @@ pub use database::DbConnectionPoolV2;
 pub use documents::Document;
 pub use electrical_profiles::ElectricalProfileSet;
 pub use infra::Infra;
+pub use infra_edit::InfraEdit;
 pub use infra_objects::*;
+pub use infra_snapshot::InfraSnapshot;
 pub use light_rolling_stock::LightRollingStockModel;
 pub use projects::Project;
 pub use projects::Tags;
 pub use rolling_stock_image::RollingStockSeparatedImageModel;
 pub use rolling_stock_model::RollingStockModel;
+pub use rolling_stock_revision::RollingStockRevision;
 pub use scenario::Scenario;
 pub use study::Study;
+pub use train_composition::TrainComposition;
 
 pub use crate::models::PreferredId;
 
 editoast_common::schemas! {
     infra::schemas(),
+    infra_edit::schemas(),
+    infra_snapshot::schemas(),
     rolling_stock_model::schemas(),
+    train_composition::schemas(),
 }
 
 #[cfg(test)]
@@ -29,6 +29,8 @@ use crate::error::Result;
 use crate::modelsv2::prelude::*;
 use crate::modelsv2::rolling_stock_livery::RollingStockLiveryMetadataModel;
 use crate::modelsv2::DbConnection;
+use crate::modelsv2::RollingStockRevision;
+use crate::modelsv2::TrainComposition;
 use crate::views::rolling_stocks::RollingStockWithLiveries;
 
 editoast_common::schemas! {
@@ -95,6 +97,59 @@ impl RollingStockModel {
         })
     }
 
+    /// Retrieve the revision `(rolling_stock_id, version)` if specified, the current version of the
+    /// rolling stock named `name` otherwise
+    pub async fn retrieve_version(
+        conn: &mut DbConnection,
+        name: String,
+        revision: Option<(i64, i64)>,
+    ) -> Result<Option<RollingStockModel>> {
+        match revision {
+            Some((rolling_stock_id, version)) => {
+                RollingStockModel::retrieve_revision(conn, rolling_stock_id, version).await
+            }
+            None => RollingStockModel::retrieve(conn, name).await,
+        }
+    }
+
+    /// Retrieve the rolling stock simulated for a train
+    ///
+    /// When the train is made of a composition, the composition must be made of the rolling stock
+    /// of the train, and the returned rolling stock aggregates all the vehicles of the composition.
+    pub async fn retrieve_for_train(
+        conn: &mut DbConnection,
+        name: String,
+        revision: Option<(i64, i64)>,
+        composition_id: Option<i64>,
+    ) -> Result<Option<RollingStockModel>> {
+        let Some(rolling_stock) = RollingStockModel::retrieve_version(conn, name, revision).await?
+        else {
+            return Ok(None);
+        };
+        let Some(composition_id) = composition_id else {
+            return Ok(Some(rolling_stock));
+        };
+        let composition = TrainComposition::retrieve(conn, composition_id).await?;
+        Ok(composition
+            .filter(|composition| composition.rolling_stock_id == rolling_stock.id)
+            .map(|composition| composition.aggregate(rolling_stock)))
+    }
+
+    /// Retrieve a rolling stock as it was at `version`
+    pub async fn retrieve_revision(
+        conn: &mut DbConnection,
+        rolling_stock_id: i64,
+        version: i64,
+    ) -> Result<Option<RollingStockModel>> {
+        if let Some(revision) =
+            RollingStockRevision::retrieve(conn, (rolling_stock_id, version)).await?
+        {
+            return Ok(Some(revision.rolling_stock));
+        }
+        let rolling_stock = RollingStockModel::retrieve(conn, rolling_stock_id).await?;
+        Ok(rolling_stock.filter(|rolling_stock| rolling_stock.version == version))
+    }
+
     pub fn has_thermal_curves(&self) -> bool {
         self.effort_curves
             .modes
@@ -227,6 +282,7 @@ pub mod tests {
     use crate::modelsv2::fixtures::rolling_stock_with_energy_sources_changeset;
     use crate::modelsv2::prelude::*;
     use crate::modelsv2::DbConnectionPoolV2;
+    use crate::modelsv2::TrainComposition;
     use crate::views::rolling_stocks::map_diesel_error;
     use crate::views::rolling_stocks::RollingStockError;
 
@@ -293,4 +349,43 @@ pub mod tests {
             to_value(error).unwrap()
         );
     }
+
+    #[rstest]
+    async fn retrieve_rolling_stock_for_composition() {
+        let db_pool = DbConnectionPoolV2::for_tests();
+        let conn = &mut db_pool.get_ok();
+        let rolling_stock = create_fast_rolling_stock(conn, "composition_rolling_stock").await;
+        let other_rolling_stock =
+            create_rolling_stock_with_energy_sources(conn, "composition_other_rolling_stock").await;
+        let composition = TrainComposition::changeset()
+            .name("double_composition".to_string())
+            .rolling_stock_id(rolling_stock.id)
+            .unit_count(2)
+            .wagons(vec![])
+            .create(conn)
+            .await
+            .expect("Failed to create train composition");
+
+        let aggregate = RollingStockModel::retrieve_for_train(
+            conn,
+            rolling_stock.name.clone(),
+            None,
+            Some(composition.id),
+        )
+        .await
+        .unwrap()
+        .expect("The composition should be found");
+        assert_eq!(aggregate.mass, rolling_stock.mass * 2.);
+
+        // The composition isn't made of this rolling stock
+        let not_found = RollingStockModel::retrieve_for_train(
+            conn,
+            other_rolling_stock.name.clone(),
+            None,
+            Some(composition.id),
+        )
+        .await
+        .unwrap();
+        assert!(not_found.is_none());
+    }
 }
@@ -0,0 +1,65 @@
+use chrono::DateTime;
+use chrono::Utc;
+use editoast_derive::ModelV2;
+
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::RollingStockModel;
+
+/// An immutable copy of a rolling stock at a given version
+///
+/// A revision is recorded each time a rolling stock is created or updated, so that the train
+/// schedules pinned to a version keep being simulated with the same rolling stock.
+#[derive(Debug, Clone, ModelV2)]
+#[model(table = crate::tables::rolling_stock_revision)]
+#[model(identifier = (rolling_stock_id, version))]
+pub struct RollingStockRevision {
+    pub id: i64,
+    pub rolling_stock_id: i64,
+    pub version: i64,
+    /// The name of the rolling stock at this version
+    pub name: String,
+    pub creation_date: DateTime<Utc>,
+    #[model(json)]
+    pub rolling_stock: RollingStockModel,
+}
+
+impl RollingStockRevision {
+    /// Record the current version of a rolling stock
+    pub async fn record(
+        conn: &mut DbConnection,
+        rolling_stock: &RollingStockModel,
+    ) -> Result<RollingStockRevision> {
+        RollingStockRevision::changeset()
+            .rolling_stock_id(rolling_stock.id)
+            .version(rolling_stock.version)
+            .name(rolling_stock.name.clone())
+            .creation_date(Utc::now())
+            .rolling_stock(rolling_stock.clone())
+            .create(conn)
+            .await
+    }
+
+    /// Record the current version of a rolling stock unless it already was
+    pub async fn record_if_missing(
+        conn: &mut DbConnection,
+        rolling_stock: &RollingStockModel,
+    ) -> Result<()> {
+        if !RollingStockRevision::exists(conn, (rolling_stock.id, rolling_stock.version)).await? {
+            RollingStockRevision::record(conn, rolling_stock).await?;
+        }
+        Ok(())
+    }
+
+    /// List the revisions of a rolling stock, the latest first
+    pub async fn list_by_rolling_stock(
+        conn: &mut DbConnection,
+        rolling_stock_id: i64,
+    ) -> Result<Vec<RollingStockRevision>> {
+        let settings = SelectionSettings::new()
+            .filter(move || RollingStockRevision::ROLLING_STOCK_ID.eq(rolling_stock_id))
+            .order_by(|| RollingStockRevision::VERSION.desc());
+        RollingStockRevision::list(conn, settings).await
+    }
+}
@@ -0,0 +1,180 @@
+use editoast_derive::ModelV2;
+use editoast_schemas::rolling_stock::RollingResistance;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::modelsv2::RollingStockModel;
+
+editoast_common::schemas! {
+    TrainComposition,
+    TrainCompositionWagon,
+}
+
+/// A train made of coupled units of a rolling stock, which may haul wagons
+///
+/// Compositions are simulated as a single rolling stock aggregating the physics of their vehicles.
+/// All the traction units are of the same rolling stock: mixing rolling stocks isn't supported,
+/// since their effort curves and electrification modes can't be aggregated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ModelV2, ToSchema)]
+#[model(table = crate::tables::train_composition)]
+pub struct TrainComposition {
+    pub id: i64,
+    #[model(identifier)]
+    pub name: String,
+    /// The rolling stock of the traction units
+    pub rolling_stock_id: i64,
+    /// The number of coupled traction units
+    pub unit_count: i32,
+    #[model(json)]
+    pub wagons: Vec<TrainCompositionWagon>,
+}
+
+/// Identical unpowered vehicles hauled by the traction units
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TrainCompositionWagon {
+    /// The number of wagons
+    pub count: u32,
+    /// Mass of a wagon in kg
+    pub mass: f64,
+    /// Length of a wagon in m
+    pub length: f64,
+    /// Maximum speed of the wagons in m/s, if they limit the speed of the train
+    #[serde(default)]
+    pub max_speed: Option<f64>,
+    pub inertia_coefficient: f64,
+    /// Rolling resistance of a wagon
+    pub rolling_resistance: RollingResistance,
+}
+
+impl TrainCompositionWagon {
+    /// The first field of the wagon which can't be simulated, if any
+    ///
+    /// Masses, lengths, inertia coefficients and max speeds must be strictly positive, and the
+    /// resistance can't be negative up to the max speed of the train.
+    pub fn invalid_field(&self, train_max_speed: f64) -> Option<&'static str> {
+        let max_speed = self
+            .max_speed
+            .unwrap_or(train_max_speed)
+            .min(train_max_speed);
+        let lowest_resistance_speed = self.rolling_resistance.lowest_resistance_speed(max_speed);
+        if !is_positive(self.mass) {
+            Some("mass")
+        } else if !is_positive(self.length) {
+            Some("length")
+        } else if !is_positive(self.inertia_coefficient) {
+            Some("inertia_coefficient")
+        } else if self
+            .max_speed
+            .is_some_and(|max_speed| !is_positive(max_speed))
+        {
+            Some("max_speed")
+        } else if self
+            .rolling_resistance
+            .resistance_at(lowest_resistance_speed)
+            < 0.
+        {
+            Some("rolling_resistance")
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a value is strictly positive, which excludes NaN
+fn is_positive(value: f64) -> bool {
+    value > 0.
+}
+
+impl TrainComposition {
+    /// Aggregate the traction units and the wagons as a single rolling stock, named after the composition
+    ///
+    /// Efforts, masses, lengths and resistances add up, the inertia coefficient is averaged by mass
+    /// and the max speed is the lowest of the vehicles. The other characteristics are the ones of
+    /// the traction units.
+    pub fn aggregate(&self, mut rolling_stock: RollingStockModel) -> RollingStockModel {
+        let unit_count = self.unit_count as f64;
+        rolling_stock.name = self.name.clone();
+        rolling_stock.effort_curves.scale_efforts(unit_count);
+        rolling_stock.length *= unit_count;
+        rolling_stock.mass *= unit_count;
+        rolling_stock.rolling_resistance = rolling_stock.rolling_resistance.scaled(unit_count);
+
+        let mut weighted_inertia = rolling_stock.inertia_coefficient * rolling_stock.mass;
+        for wagon in &self.wagons {
+            let count = wagon.count as f64;
+            rolling_stock.length += wagon.length * count;
+            rolling_stock.mass += wagon.mass * count;
+            weighted_inertia += wagon.inertia_coefficient * wagon.mass * count;
+            rolling_stock.rolling_resistance =
+                rolling_stock.rolling_resistance + wagon.rolling_resistance.scaled(count);
+            if let Some(max_speed) = wagon.max_speed {
+                rolling_stock.max_speed = rolling_stock.max_speed.min(max_speed);
+            }
+        }
+        rolling_stock.inertia_coefficient = weighted_inertia / rolling_stock.mass;
+        rolling_stock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fast_rolling_stock() -> RollingStockModel {
+        let mut rolling_stock: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/example_rolling_stock_1.json"))
+                .expect("Unable to parse");
+        rolling_stock["id"] = 0.into();
+        rolling_stock["version"] = 0.into();
+        let mut rolling_stock: RollingStockModel =
+            serde_json::from_value(rolling_stock).expect("Unable to parse");
+        rolling_stock.rolling_resistance = RollingResistance::davis(1000., 10., 1.);
+        rolling_stock.inertia_coefficient = 1.1;
+        rolling_stock
+    }
+
+    #[test]
+    fn aggregate_locomotive_and_wagons() {
+        let rolling_stock = fast_rolling_stock();
+        let composition = TrainComposition {
+            id: 0,
+            name: "freight_train".to_string(),
+            rolling_stock_id: rolling_stock.id,
+            unit_count: 2,
+            wagons: vec![TrainCompositionWagon {
+                count: 10,
+                mass: 90_000.,
+                length: 20.,
+                max_speed: Some(30.),
+                inertia_coefficient: 1.0,
+                rolling_resistance: RollingResistance::davis(100., 1., 0.1),
+            }],
+        };
+
+        let aggregate = composition.aggregate(rolling_stock.clone());
+
+        assert_eq!(aggregate.name, "freight_train");
+        assert_eq!(aggregate.length, rolling_stock.length * 2. + 200.);
+        assert_eq!(aggregate.mass, rolling_stock.mass * 2. + 900_000.);
+        assert_eq!(aggregate.max_speed, 30.);
+        assert_eq!(
+            aggregate.rolling_resistance,
+            RollingResistance::davis(3000., 30., 3.)
+        );
+        let traction_mass = rolling_stock.mass * 2.;
+        assert_eq!(
+            aggregate.inertia_coefficient,
+            (1.1 * traction_mass + 900_000.) / (traction_mass + 900_000.)
+        );
+        let mode = &aggregate.effort_curves.modes["thermal"];
+        let initial_mode = &rolling_stock.effort_curves.modes["thermal"];
+        assert_eq!(
+            mode.default_curve().max_efforts()[0],
+            initial_mode.default_curve().max_efforts()[0] * 2.
+        );
+    }
+}
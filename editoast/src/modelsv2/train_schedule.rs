@@ -1,5 +1,7 @@
 use chrono::DateTime;
 use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use editoast_derive::ModelV2;
 use editoast_schemas::train_schedule::Comfort;
 use editoast_schemas::train_schedule::Distribution;
@@ -8,6 +10,12 @@ use editoast_schemas::train_schedule::PathItem;
 use editoast_schemas::train_schedule::PowerRestrictionItem;
 use editoast_schemas::train_schedule::ScheduleItem;
 use editoast_schemas::train_schedule::TrainScheduleOptions;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
 
 #[derive(Debug, Default, Clone, ModelV2)]
 #[model(table = crate::tables::train_schedule_v2)]
@@ -34,4 +42,67 @@ pub struct TrainSchedule {
     pub power_restrictions: Vec<PowerRestrictionItem>,
     #[model(json)]
     pub options: TrainScheduleOptions,
+    /// The pattern the train schedule was generated from
+    pub pattern_id: Option<i64>,
+    /// The version of the rolling stock the train schedule is pinned to, set with `rolling_stock_id`
+    pub rolling_stock_version: Option<i64>,
+    /// The rolling stock the train schedule is pinned to, set with `rolling_stock_version`
+    pub rolling_stock_id: Option<i64>,
+    /// The composition the train is made of, simulated instead of the rolling stock if set
+    pub composition_id: Option<i64>,
+}
+
+/// Criteria selecting train schedules, a train schedule must match all of them
+#[derive(Debug, Default, Clone, Deserialize, ToSchema)]
+pub struct TrainScheduleSelection {
+    /// A label of the train schedules
+    pub label: Option<String>,
+    /// A case insensitive SQL `LIKE` pattern matching the name of the train schedules (e.g. `RER%`)
+    pub train_name_pattern: Option<String>,
+    pub rolling_stock_name: Option<String>,
+    /// The train schedules starting at or after this time
+    pub start_after: Option<DateTime<Utc>>,
+    /// The train schedules starting before this time
+    pub start_before: Option<DateTime<Utc>>,
+}
+
+impl TrainSchedule {
+    /// The id and the version of the rolling stock the train schedule is pinned to, if any
+    pub fn rolling_stock_revision(&self) -> Option<(i64, i64)> {
+        self.rolling_stock_id.zip(self.rolling_stock_version)
+    }
+
+    /// List the train schedules of a timetable matching a selection, sorted by start time
+    pub async fn list_selection(
+        conn: &mut DbConnection,
+        timetable_id: i64,
+        selection: &TrainScheduleSelection,
+    ) -> Result<Vec<TrainSchedule>> {
+        use crate::tables::train_schedule_v2::dsl;
+        let mut query = dsl::train_schedule_v2
+            .filter(dsl::timetable_id.eq(timetable_id))
+            .into_boxed();
+        if let Some(label) = &selection.label {
+            query = query.filter(dsl::labels.contains(vec![Some(label.clone())]));
+        }
+        if let Some(pattern) = &selection.train_name_pattern {
+            query = query.filter(dsl::train_name.ilike(pattern.clone()));
+        }
+        if let Some(rolling_stock_name) = &selection.rolling_stock_name {
+            query = query.filter(dsl::rolling_stock_name.eq(rolling_stock_name.clone()));
+        }
+        if let Some(start_after) = selection.start_after {
+            query = query.filter(dsl::start_time.ge(start_after));
+        }
+        if let Some(start_before) = selection.start_before {
+            query = query.filter(dsl::start_time.lt(start_before));
+        }
+        Ok(query
+            .order_by((dsl::start_time, dsl::id))
+            .load(conn)
+            .await?
+            .into_iter()
+            .map(Self::from_row)
+            .collect())
+    }
 }
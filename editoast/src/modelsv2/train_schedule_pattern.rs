@@ -0,0 +1,45 @@
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use editoast_derive::ModelV2;
+use editoast_schemas::train_schedule::Repetition;
+use editoast_schemas::train_schedule::TrainScheduleBase;
+
+use crate::error::Result;
+use crate::modelsv2::DbConnection;
+
+/// A train schedule template repeated over time
+///
+/// The train schedules generated from the pattern, its instances, are linked to it by their `pattern_id`.
+#[derive(Debug, Clone, ModelV2)]
+#[model(table = crate::tables::train_schedule_pattern)]
+pub struct TrainSchedulePattern {
+    pub id: i64,
+    pub timetable_id: i64,
+    #[model(json)]
+    pub template: TrainScheduleBase,
+    #[model(json)]
+    pub repetition: Repetition,
+}
+
+impl TrainSchedulePattern {
+    /// The ids of the train schedules generated from the pattern
+    pub async fn instance_ids(&self, conn: &mut DbConnection) -> Result<Vec<i64>> {
+        use crate::tables::train_schedule_v2::dsl::*;
+        let ids = train_schedule_v2
+            .filter(pattern_id.eq(self.id))
+            .select(id)
+            .order_by(start_time)
+            .load(conn)
+            .await?;
+        Ok(ids)
+    }
+
+    /// Delete the train schedules generated from the pattern
+    pub async fn delete_instances(&self, conn: &mut DbConnection) -> Result<usize> {
+        use crate::tables::train_schedule_v2::dsl::*;
+        let count = diesel::delete(train_schedule_v2.filter(pattern_id.eq(self.id)))
+            .execute(conn)
+            .await?;
+        Ok(count)
+    }
+}
@@ -1,4 +1,7 @@
 use chrono::NaiveDateTime;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
 use editoast_derive::ModelV2;
 use editoast_schemas::infra::TrackRange;
 use strum::FromRepr;
@@ -7,7 +10,12 @@ use serde::Deserialize;
 use serde::Serialize;
 use utoipa::ToSchema;
 
-#[derive(Debug, Clone, ModelV2)]
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::tables::work_schedule::dsl;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ModelV2, ToSchema)]
 #[model(table = crate::tables::work_schedule_group)]
 pub struct WorkScheduleGroup {
     pub id: i64,
@@ -23,7 +31,7 @@ pub enum WorkScheduleType {
     Track,
 }
 
-#[derive(Debug, Default, Clone, ModelV2)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ModelV2, ToSchema)]
 #[model(table = crate::tables::work_schedule)]
 pub struct WorkSchedule {
     pub id: i64,
@@ -33,6 +41,26 @@ pub struct WorkSchedule {
     pub track_ranges: Vec<TrackRange>,
     pub obj_id: String,
     #[model(to_enum)]
+    #[schema(inline)]
     pub work_schedule_type: WorkScheduleType,
     pub work_schedule_group_id: i64,
 }
+
+impl WorkSchedule {
+    /// List the work schedules intersecting a time window, sorted by start date
+    pub async fn list_in_time_window(
+        conn: &mut DbConnection,
+        start_date_time: NaiveDateTime,
+        end_date_time: NaiveDateTime,
+    ) -> Result<Vec<WorkSchedule>> {
+        Ok(dsl::work_schedule
+            .filter(dsl::start_date_time.lt(end_date_time))
+            .filter(dsl::end_date_time.gt(start_date_time))
+            .order_by((dsl::start_date_time, dsl::id))
+            .load(conn)
+            .await?
+            .into_iter()
+            .map(Self::from_row)
+            .collect())
+    }
+}
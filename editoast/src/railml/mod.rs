@@ -0,0 +1,94 @@
+//! Conversions between OSRD objects and [railML 3](https://www.railml.org) documents
+//!
+//! Only the subset of railML needed to exchange data with other planning tools is supported.
+//! Every element which can't be converted is listed in a [MappingReport].
+
+pub mod rolling_stock;
+pub mod timetable;
+
+use std::fmt::Display;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+const RAILML_NAMESPACE: &str = "https://www.railml.org/schemas/3.2";
+const RAILML_VERSION: &str = "3.2";
+
+#[derive(Debug, Error)]
+pub enum RailMLError {
+    #[error("Invalid railML document: {0}")]
+    InvalidDocument(#[from] quick_xml::DeError),
+    #[error("Invalid railML element {element}: {message}")]
+    InvalidElement { element: String, message: String },
+}
+
+/// Lists the elements which could not be mapped during a conversion
+#[derive(Debug, Default)]
+pub struct MappingReport {
+    pub unmapped: Vec<String>,
+}
+
+impl MappingReport {
+    fn add<T: Into<String>>(&mut self, message: T) {
+        self.unmapped.push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unmapped.is_empty()
+    }
+}
+
+impl Display for MappingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for message in &self.unmapped {
+            writeln!(f, "  - {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialize a railML document, prefixed with its XML declaration
+fn to_xml<T: Serialize>(document: &T) -> Result<String, RailMLError> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 2);
+    document.serialize(serializer)?;
+    Ok(xml)
+}
+
+/// A name given to a railML element
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Name {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@language", default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+}
+
+impl Name {
+    fn new<T: Into<String>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            language: None,
+        }
+    }
+}
+
+/// An identifier of a railML element in a given register (UIC code, trigram...)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Designator {
+    #[serde(rename = "@register")]
+    register: String,
+    #[serde(rename = "@entry")]
+    entry: String,
+}
+
+impl Designator {
+    fn new<T: Into<String>>(register: &str, entry: T) -> Self {
+        Self {
+            register: register.to_string(),
+            entry: entry.into(),
+        }
+    }
+}
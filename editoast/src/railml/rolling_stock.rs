@@ -0,0 +1,455 @@
+//! Import of rolling stocks from a railML 3 `<rollingstock>`
+//!
+//! Each `<vehicle>` is imported as a rolling stock named after its first `<name>`, using:
+//!
+//! - the `speed` (km/h), `length` (m) and `bruttoWeight` (t) attributes of the vehicle,
+//! - the `_loadingGauge`, `_powerClass` and `_signalingSystem` designators,
+//! - a `<tractionMode>` of the `<engine>` per traction mode, whose `<tractiveEffort>` lists
+//!   `<valueLine speed="km/h" effort="kN"/>` points. An `electric` mode is named after its
+//!   `voltage` (e.g. `25000V`), any other mode is the `thermal` one,
+//! - the Davis formula factors of `<trainResistance>`, in kN with the speed in km/h,
+//! - the `meanDeceleration` (m/s²) of `<brakes>`.
+//!
+//! A missing loading gauge or rotating mass factor is given a default, listed in the [MappingReport].
+
+use std::collections::BTreeMap;
+
+use editoast_schemas::rolling_stock::EffortCurve;
+use editoast_schemas::rolling_stock::EffortCurves;
+use editoast_schemas::rolling_stock::Gamma;
+use editoast_schemas::rolling_stock::LoadingGaugeType;
+use editoast_schemas::rolling_stock::ModeEffortCurves;
+use editoast_schemas::rolling_stock::RollingResistance;
+use editoast_schemas::rolling_stock::RollingStock;
+use editoast_schemas::rolling_stock::RollingStockSupportedSignalingSystems;
+use editoast_schemas::rolling_stock::ROLLING_STOCK_RAILJSON_VERSION;
+use serde::Deserialize;
+
+use super::Designator;
+use super::MappingReport;
+use super::Name;
+use super::RailMLError;
+
+const LOADING_GAUGE_REGISTER: &str = "_loadingGauge";
+const POWER_CLASS_REGISTER: &str = "_powerClass";
+const SIGNALING_SYSTEM_REGISTER: &str = "_signalingSystem";
+
+const THERMAL_MODE: &str = "thermal";
+
+const DEFAULT_LOADING_GAUGE: LoadingGaugeType = LoadingGaugeType::G1;
+const DEFAULT_STARTUP_TIME: f64 = 10.;
+const DEFAULT_STARTUP_ACCELERATION: f64 = 0.05;
+const DEFAULT_COMFORT_ACCELERATION: f64 = 0.25;
+const DEFAULT_INERTIA_COEFFICIENT: f64 = 1.05;
+const DEFAULT_ELECTRICAL_POWER_STARTUP_TIME: f64 = 5.;
+const DEFAULT_RAISE_PANTOGRAPH_TIME: f64 = 15.;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename = "railML")]
+struct RailML {
+    #[serde(default)]
+    rollingstock: RollingStockElement,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RollingStockElement {
+    #[serde(default)]
+    vehicles: Vehicles,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Vehicles {
+    #[serde(rename = "vehicle", default)]
+    vehicles: Vec<Vehicle>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vehicle {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@speed", default)]
+    speed: Option<String>,
+    #[serde(rename = "@length", default)]
+    length: Option<String>,
+    #[serde(rename = "@bruttoWeight", default)]
+    brutto_weight: Option<String>,
+    #[serde(rename = "@rotatingMassFactor", default)]
+    rotating_mass_factor: Option<String>,
+    #[serde(rename = "name", default)]
+    names: Vec<Name>,
+    #[serde(rename = "designator", default)]
+    designators: Vec<Designator>,
+    #[serde(default)]
+    engine: Engine,
+    #[serde(rename = "trainResistance", default)]
+    train_resistance: Option<TrainResistance>,
+    #[serde(default)]
+    brakes: Option<Brakes>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Engine {
+    #[serde(rename = "tractionMode", default)]
+    traction_modes: Vec<TractionMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TractionMode {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@mode")]
+    mode: String,
+    #[serde(rename = "@voltage", default)]
+    voltage: Option<String>,
+    #[serde(rename = "@isPrimaryMode", default)]
+    is_primary_mode: bool,
+    #[serde(rename = "tractiveEffort", default)]
+    tractive_effort: TractiveEffort,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TractiveEffort {
+    #[serde(rename = "valueLine", default)]
+    value_lines: Vec<ValueLine>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValueLine {
+    #[serde(rename = "@speed")]
+    speed: String,
+    #[serde(rename = "@effort")]
+    effort: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrainResistance {
+    #[serde(rename = "@constantFactor")]
+    constant_factor: String,
+    #[serde(rename = "@speedDependentFactor")]
+    speed_dependent_factor: String,
+    #[serde(rename = "@squareSpeedDependentFactor")]
+    square_speed_dependent_factor: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Brakes {
+    #[serde(rename = "@meanDeceleration")]
+    mean_deceleration: String,
+}
+
+fn invalid_element<T: Into<String>>(element: &str, message: T) -> RailMLError {
+    RailMLError::InvalidElement {
+        element: element.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Parse the value of an attribute of an element
+fn parse_number(element: &str, attribute: &str, value: &str) -> Result<f64, RailMLError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid_element(element, format!("invalid {attribute} '{value}'")))
+}
+
+/// Parse the value of an attribute which the element must have
+fn parse_required(
+    element: &str,
+    attribute: &str,
+    value: &Option<String>,
+) -> Result<f64, RailMLError> {
+    let value = value
+        .as_ref()
+        .ok_or_else(|| invalid_element(element, format!("missing {attribute}")))?;
+    parse_number(element, attribute, value)
+}
+
+impl Vehicle {
+    fn designator(&self, register: &str) -> Option<&str> {
+        self.designators
+            .iter()
+            .find(|designator| designator.register == register)
+            .map(|designator| designator.entry.as_str())
+    }
+}
+
+/// Convert the traction modes of a vehicle to effort curves
+fn effort_curves(
+    vehicle_element: &str,
+    traction_modes: &[TractionMode],
+    report: &mut MappingReport,
+) -> Result<EffortCurves, RailMLError> {
+    let mut modes = BTreeMap::new();
+    let mut default_mode = None;
+    for traction_mode in traction_modes {
+        let element = format!("{vehicle_element} > tractionMode '{}'", traction_mode.id);
+        let is_electric = traction_mode.mode == "electric";
+        let mode = match (is_electric, &traction_mode.voltage) {
+            (true, Some(voltage)) => {
+                format!("{}V", parse_number(&element, "voltage", voltage)?)
+            }
+            (true, None) => return Err(invalid_element(&element, "missing voltage")),
+            (false, _) => THERMAL_MODE.to_string(),
+        };
+        if modes.contains_key(&mode) {
+            report.add(format!("{element}: duplicated mode '{mode}' is ignored"));
+            continue;
+        }
+
+        let mut speeds = Vec::with_capacity(traction_mode.tractive_effort.value_lines.len());
+        let mut max_efforts = Vec::with_capacity(speeds.capacity());
+        for (index, value_line) in traction_mode.tractive_effort.value_lines.iter().enumerate() {
+            let element = format!("{element} > valueLine {}", index + 1);
+            speeds.push(parse_number(&element, "speed", &value_line.speed)? / 3.6);
+            max_efforts.push(parse_number(&element, "effort", &value_line.effort)? * 1000.);
+        }
+        let curve = EffortCurve::new(speeds, max_efforts)
+            .map_err(|message| invalid_element(&format!("{element} > tractiveEffort"), message))?;
+
+        if traction_mode.is_primary_mode || default_mode.is_none() {
+            default_mode = Some(mode.clone());
+        }
+        modes.insert(mode, ModeEffortCurves::new(curve, is_electric));
+    }
+    let default_mode =
+        default_mode.ok_or_else(|| invalid_element(vehicle_element, "no traction mode"))?;
+    Ok(EffortCurves::new(modes, default_mode))
+}
+
+fn import_vehicle(
+    vehicle: &Vehicle,
+    report: &mut MappingReport,
+) -> Result<RollingStock, RailMLError> {
+    let element = format!("vehicle '{}'", vehicle.id);
+    let name = vehicle
+        .names
+        .first()
+        .ok_or_else(|| invalid_element(&element, "missing name"))?
+        .name
+        .clone();
+
+    let max_speed = parse_required(&element, "speed", &vehicle.speed)? / 3.6;
+    let length = parse_required(&element, "length", &vehicle.length)?;
+    let mass = parse_required(&element, "bruttoWeight", &vehicle.brutto_weight)? * 1000.;
+    let inertia_coefficient = match &vehicle.rotating_mass_factor {
+        Some(factor) => parse_number(&element, "rotatingMassFactor", factor)?,
+        None => {
+            report.add(format!(
+                "{element}: no rotatingMassFactor, the inertia coefficient defaults to {DEFAULT_INERTIA_COEFFICIENT}"
+            ));
+            DEFAULT_INERTIA_COEFFICIENT
+        }
+    };
+
+    let effort_curves = effort_curves(&element, &vehicle.engine.traction_modes, report)?;
+    let is_electric = effort_curves.modes.values().any(|mode| mode.is_electric);
+
+    let train_resistance = vehicle
+        .train_resistance
+        .as_ref()
+        .ok_or_else(|| invalid_element(&element, "missing trainResistance"))?;
+    let resistance_element = format!("{element} > trainResistance");
+    // The factors are given in kN with the speed in km/h
+    let rolling_resistance = RollingResistance::davis(
+        parse_number(
+            &resistance_element,
+            "constantFactor",
+            &train_resistance.constant_factor,
+        )? * 1000.,
+        parse_number(
+            &resistance_element,
+            "speedDependentFactor",
+            &train_resistance.speed_dependent_factor,
+        )? * 1000.
+            * 3.6,
+        parse_number(
+            &resistance_element,
+            "squareSpeedDependentFactor",
+            &train_resistance.square_speed_dependent_factor,
+        )? * 1000.
+            * 3.6
+            * 3.6,
+    );
+
+    let brakes = vehicle
+        .brakes
+        .as_ref()
+        .ok_or_else(|| invalid_element(&element, "missing brakes"))?;
+    let gamma = Gamma::constant(parse_number(
+        &format!("{element} > brakes"),
+        "meanDeceleration",
+        &brakes.mean_deceleration,
+    )?);
+
+    let loading_gauge = match vehicle.designator(LOADING_GAUGE_REGISTER) {
+        Some(loading_gauge) => serde_json::from_value(loading_gauge.into()).map_err(|_| {
+            invalid_element(&element, format!("unknown loading gauge '{loading_gauge}'"))
+        })?,
+        None => {
+            report.add(format!(
+                "{element}: no loading gauge, {DEFAULT_LOADING_GAUGE:?} is used"
+            ));
+            DEFAULT_LOADING_GAUGE
+        }
+    };
+    let supported_signaling_systems = vehicle
+        .designators
+        .iter()
+        .filter(|designator| designator.register == SIGNALING_SYSTEM_REGISTER)
+        .map(|designator| designator.entry.clone())
+        .collect();
+
+    Ok(RollingStock {
+        name,
+        effort_curves,
+        base_power_class: vehicle.designator(POWER_CLASS_REGISTER).map(String::from),
+        length,
+        max_speed,
+        startup_time: DEFAULT_STARTUP_TIME,
+        startup_acceleration: DEFAULT_STARTUP_ACCELERATION,
+        comfort_acceleration: DEFAULT_COMFORT_ACCELERATION,
+        gamma,
+        inertia_coefficient,
+        mass,
+        rolling_resistance,
+        loading_gauge,
+        power_restrictions: Default::default(),
+        energy_sources: vec![],
+        electrical_power_startup_time: is_electric.then_some(DEFAULT_ELECTRICAL_POWER_STARTUP_TIME),
+        raise_pantograph_time: is_electric.then_some(DEFAULT_RAISE_PANTOGRAPH_TIME),
+        supported_signaling_systems: RollingStockSupportedSignalingSystems(
+            supported_signaling_systems,
+        ),
+        railjson_version: ROLLING_STOCK_RAILJSON_VERSION.to_string(),
+        metadata: None,
+    })
+}
+
+/// Import the vehicles of a railML document as rolling stocks
+///
+/// The startup and comfort accelerations, startup time and, for electric rolling stocks, the
+/// power startup and pantograph times have no railML counterpart and are always given defaults.
+pub fn import_rolling_stocks(xml: &str) -> Result<(Vec<RollingStock>, MappingReport), RailMLError> {
+    let document: RailML = quick_xml::de::from_str(xml)?;
+    let mut report = MappingReport::default();
+    let rolling_stocks = document
+        .rollingstock
+        .vehicles
+        .vehicles
+        .iter()
+        .map(|vehicle| import_vehicle(vehicle, &mut report))
+        .collect::<Result<_, _>>()?;
+    Ok((rolling_stocks, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::import_rolling_stocks;
+    use crate::railml::RailMLError;
+
+    fn document(tractive_effort: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<railML xmlns="https://www.railml.org/schemas/3.2" version="3.2">
+  <rollingstock>
+    <vehicles>
+      <vehicle id="vh_1" speed="180" length="400" bruttoWeight="900" rotatingMassFactor="1.05">
+        <name name="imported_rolling_stock"/>
+        <designator register="_loadingGauge" entry="GC"/>
+        <designator register="_powerClass" entry="5"/>
+        <designator register="_signalingSystem" entry="BAL"/>
+        <designator register="_signalingSystem" entry="TVM300"/>
+        <engine>
+          <tractionMode id="tm_thermal" mode="diesel">
+            <tractiveEffort>
+              <valueLine speed="0" effort="200"/>
+              <valueLine speed="180" effort="50"/>
+            </tractiveEffort>
+          </tractionMode>
+          <tractionMode id="tm_25kv" mode="electric" voltage="25000" isPrimaryMode="true">
+            <tractiveEffort>
+              {tractive_effort}
+            </tractiveEffort>
+          </tractionMode>
+        </engine>
+        <trainResistance constantFactor="2" speedDependentFactor="0.036" squareSpeedDependentFactor="0.005"/>
+        <brakes meanDeceleration="0.5"/>
+      </vehicle>
+    </vehicles>
+  </rollingstock>
+</railML>"#
+        )
+    }
+
+    #[test]
+    fn import_vehicle() {
+        let xml =
+            document(r#"<valueLine speed="0" effort="300"/><valueLine speed="36" effort="250"/>"#);
+        let (rolling_stocks, report) = import_rolling_stocks(&xml).unwrap();
+
+        assert_eq!(rolling_stocks.len(), 1);
+        let rolling_stock = serde_json::to_value(&rolling_stocks[0]).unwrap();
+        assert_eq!(rolling_stock["name"], json!("imported_rolling_stock"));
+        assert_eq!(rolling_stock["max_speed"], json!(50.0));
+        assert_eq!(rolling_stock["mass"], json!(900000.0));
+        assert_eq!(rolling_stock["loading_gauge"], json!("GC"));
+        assert_eq!(rolling_stock["base_power_class"], json!("5"));
+        assert_eq!(
+            rolling_stock["supported_signaling_systems"],
+            json!(["BAL", "TVM300"])
+        );
+        assert_eq!(
+            rolling_stock["rolling_resistance"],
+            json!({ "type": "davis", "A": 2000.0, "B": 129.6, "C": 64.8 })
+        );
+        assert_eq!(
+            rolling_stock["gamma"],
+            json!({ "type": "CONST", "value": 0.5 })
+        );
+        let effort_curves = &rolling_stock["effort_curves"];
+        assert_eq!(effort_curves["default_mode"], json!("25000V"));
+        assert_eq!(
+            effort_curves["modes"]["25000V"]["default_curve"],
+            json!({ "speeds": [0.0, 10.0], "max_efforts": [300000.0, 250000.0] })
+        );
+        assert_eq!(
+            effort_curves["modes"]["thermal"]["is_electric"],
+            json!(false)
+        );
+        assert_eq!(rolling_stock["raise_pantograph_time"], json!(15.0));
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn invalid_value_line_is_located() {
+        let xml = document(
+            r#"<valueLine speed="0" effort="300"/><valueLine speed="fast" effort="250"/>"#,
+        );
+        let error = import_rolling_stocks(&xml).unwrap_err();
+        let RailMLError::InvalidElement { element, .. } = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(
+            element,
+            "vehicle 'vh_1' > tractionMode 'tm_25kv' > valueLine 2"
+        );
+    }
+
+    #[test]
+    fn invalid_effort_curve_is_located() {
+        let xml =
+            document(r#"<valueLine speed="36" effort="300"/><valueLine speed="0" effort="250"/>"#);
+        let error = import_rolling_stocks(&xml).unwrap_err();
+        let RailMLError::InvalidElement { element, message } = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(
+            element,
+            "vehicle 'vh_1' > tractionMode 'tm_25kv' > tractiveEffort"
+        );
+        assert_eq!(message, "speeds values must be strictly increasing.");
+    }
+}
@@ -0,0 +1,859 @@
+//! Import and export of train schedules as a railML 3 `<timetable>`
+//!
+//! Each train schedule is exported as an `<operationalTrain>` run by a single `<trainPart>`.
+//! The itinerary of the train part references operational points by their UIC code or trigram.
+//! Times are given relative to the start date of the operating period of the train part.
+
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveTime;
+use chrono::Utc;
+use editoast_schemas::primitives::NonBlankString;
+use editoast_schemas::primitives::PositiveDuration;
+use editoast_schemas::train_schedule::MarginValue;
+use editoast_schemas::train_schedule::PathItem;
+use editoast_schemas::train_schedule::PathItemLocation;
+use editoast_schemas::train_schedule::ScheduleItem;
+use editoast_schemas::train_schedule::TrainScheduleBase;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::to_xml;
+use super::Designator;
+use super::MappingReport;
+use super::Name;
+use super::RailMLError;
+use super::RAILML_NAMESPACE;
+use super::RAILML_VERSION;
+
+const UIC_REGISTER: &str = "UIC";
+const TRIGRAM_REGISTER: &str = "_trigram";
+const SECONDARY_CODE_REGISTER: &str = "_secondaryCode";
+const LABEL_REGISTER: &str = "_label";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "railML")]
+struct RailML {
+    #[serde(rename = "@xmlns", default)]
+    xmlns: String,
+    #[serde(rename = "@version", default)]
+    version: String,
+    #[serde(default)]
+    infrastructure: Infrastructure,
+    #[serde(default)]
+    rollingstock: RollingStock,
+    #[serde(default)]
+    timetable: Timetable,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Infrastructure {
+    #[serde(rename = "functionalInfrastructure", default)]
+    functional_infrastructure: FunctionalInfrastructure,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FunctionalInfrastructure {
+    #[serde(rename = "operationalPoints", default)]
+    operational_points: OperationalPoints,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OperationalPoints {
+    #[serde(rename = "operationalPoint", default)]
+    operational_points: Vec<OperationalPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OperationalPoint {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "designator", default)]
+    designators: Vec<Designator>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RollingStock {
+    #[serde(default)]
+    formations: Formations,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Formations {
+    #[serde(rename = "formation", default)]
+    formations: Vec<Formation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Formation {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "name", default)]
+    names: Vec<Name>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Timetable {
+    #[serde(rename = "@id", default)]
+    id: String,
+    #[serde(rename = "operatingPeriods", default)]
+    operating_periods: OperatingPeriods,
+    #[serde(rename = "operationalTrains", default)]
+    operational_trains: OperationalTrains,
+    #[serde(rename = "trainParts", default)]
+    train_parts: TrainParts,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OperatingPeriods {
+    #[serde(rename = "operatingPeriod", default)]
+    operating_periods: Vec<OperatingPeriod>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OperatingPeriod {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@startDate")]
+    start_date: NaiveDate,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OperationalTrains {
+    #[serde(rename = "operationalTrain", default)]
+    operational_trains: Vec<OperationalTrain>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OperationalTrain {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "name", default)]
+    names: Vec<Name>,
+    #[serde(rename = "designator", default)]
+    designators: Vec<Designator>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrainParts {
+    #[serde(rename = "trainPart", default)]
+    train_parts: Vec<TrainPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TrainPart {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(
+        rename = "@operationalTrainRef",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    operational_train_ref: Option<String>,
+    #[serde(
+        rename = "@operatingPeriodRef",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    operating_period_ref: Option<String>,
+    #[serde(
+        rename = "formationTT",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    formation_tt: Option<FormationTT>,
+    #[serde(default)]
+    itinerary: Itinerary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FormationTT {
+    #[serde(rename = "@formationRef")]
+    formation_ref: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Itinerary {
+    #[serde(rename = "itineraryPoint", default)]
+    itinerary_points: Vec<ItineraryPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ItineraryPoint {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@seq")]
+    seq: u32,
+    #[serde(rename = "@operationalPointRef")]
+    operational_point_ref: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    arrival: Option<Time>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    departure: Option<Time>,
+    #[serde(
+        rename = "stopDescription",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    stop_description: Option<StopDescription>,
+}
+
+/// A time of day, shifted by a number of days from the start date of the operating period
+#[derive(Debug, Serialize, Deserialize)]
+struct Time {
+    #[serde(rename = "@time")]
+    time: NaiveTime,
+    #[serde(rename = "@dayOffset", default)]
+    day_offset: i64,
+}
+
+impl Time {
+    fn new(period_start: DateTime<Utc>, instant: DateTime<Utc>) -> Self {
+        Self {
+            time: instant.time(),
+            day_offset: (instant - period_start).num_days(),
+        }
+    }
+
+    fn instant(&self, period_start: DateTime<Utc>) -> DateTime<Utc> {
+        period_start + Duration::days(self.day_offset) + (self.time - NaiveTime::MIN)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StopDescription {
+    #[serde(
+        rename = "@stopDuration",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    stop_duration: Option<PositiveDuration>,
+    #[serde(rename = "@onStopSignal", default)]
+    on_stop_signal: bool,
+}
+
+/// Export train schedules as a railML document
+///
+/// Path items which aren't located by a UIC code or a trigram can't be referenced in railML and
+/// are left out, along with the data that has no railML counterpart (margins, power restrictions...).
+pub fn export_timetable(
+    timetable_id: i64,
+    train_schedules: &[TrainScheduleBase],
+) -> Result<(String, MappingReport), RailMLError> {
+    let mut report = MappingReport::default();
+    let mut document = RailML {
+        xmlns: RAILML_NAMESPACE.to_string(),
+        version: RAILML_VERSION.to_string(),
+        ..Default::default()
+    };
+    document.timetable.id = format!("tt_{timetable_id}");
+
+    let period_start_date = train_schedules
+        .iter()
+        .map(|train_schedule| train_schedule.start_time.date_naive())
+        .min()
+        .unwrap_or_default();
+    let period_start = period_start_date.and_time(NaiveTime::MIN).and_utc();
+    let period_id = "opp_1".to_string();
+    document
+        .timetable
+        .operating_periods
+        .operating_periods
+        .push(OperatingPeriod {
+            id: period_id.clone(),
+            start_date: period_start_date,
+        });
+
+    let mut operational_point_ids: HashMap<Vec<Designator>, String> = HashMap::new();
+    let mut formation_ids: HashMap<String, String> = HashMap::new();
+    for (index, train_schedule) in train_schedules.iter().enumerate() {
+        let train_name = &train_schedule.train_name;
+        let train_id = format!("ot_{}", index + 1);
+        let train_part_id = format!("tp_{}", index + 1);
+
+        if train_schedule
+            .margins
+            .values
+            .iter()
+            .any(|value| *value != MarginValue::None)
+        {
+            report.add(format!("Train '{train_name}': margins are not exported"));
+        }
+        if !train_schedule.power_restrictions.is_empty() {
+            report.add(format!(
+                "Train '{train_name}': power restrictions are not exported"
+            ));
+        }
+        if train_schedule.speed_limit_tag.is_some() {
+            report.add(format!(
+                "Train '{train_name}': speed limit tag is not exported"
+            ));
+        }
+
+        let formation_count = formation_ids.len();
+        let formation_id = formation_ids
+            .entry(train_schedule.rolling_stock_name.clone())
+            .or_insert_with(|| {
+                let formation_id = format!("fm_{}", formation_count + 1);
+                document.rollingstock.formations.formations.push(Formation {
+                    id: formation_id.clone(),
+                    names: vec![Name::new(&train_schedule.rolling_stock_name)],
+                });
+                formation_id
+            })
+            .clone();
+
+        let schedule: HashMap<_, _> = train_schedule
+            .schedule
+            .iter()
+            .map(|schedule_item| (&schedule_item.at, schedule_item))
+            .collect();
+        let mut itinerary_points = vec![];
+        for (seq, path_item) in train_schedule.path.iter().enumerate() {
+            let Some(designators) = operational_point_designators(&path_item.location) else {
+                report.add(format!(
+                    "Train '{train_name}': path item '{}' is not located by a UIC code or a trigram",
+                    path_item.id.0
+                ));
+                continue;
+            };
+            let operational_point_count = operational_point_ids.len();
+            let operational_point_ref = operational_point_ids
+                .entry(designators)
+                .or_insert_with_key(|designators| {
+                    let id = format!("op_{}", operational_point_count + 1);
+                    document
+                        .infrastructure
+                        .functional_infrastructure
+                        .operational_points
+                        .operational_points
+                        .push(OperationalPoint {
+                            id: id.clone(),
+                            designators: designators.clone(),
+                        });
+                    id
+                })
+                .clone();
+
+            let mut itinerary_point = ItineraryPoint {
+                id: format!("{train_part_id}_{}", seq + 1),
+                seq: seq as u32 + 1,
+                operational_point_ref,
+                arrival: None,
+                departure: None,
+                stop_description: None,
+            };
+            if seq == 0 {
+                itinerary_point.departure =
+                    Some(Time::new(period_start, train_schedule.start_time));
+            } else if let Some(schedule_item) = schedule.get(&path_item.id) {
+                let arrival = schedule_item
+                    .arrival
+                    .as_ref()
+                    .map(|arrival| train_schedule.start_time + **arrival);
+                itinerary_point.arrival = arrival.map(|arrival| Time::new(period_start, arrival));
+                if let Some(stop_for) = &schedule_item.stop_for {
+                    itinerary_point.departure =
+                        arrival.map(|arrival| Time::new(period_start, arrival + **stop_for));
+                    itinerary_point.stop_description = Some(StopDescription {
+                        stop_duration: Some(stop_for.clone()),
+                        on_stop_signal: schedule_item.on_stop_signal,
+                    });
+                }
+            }
+            itinerary_points.push(itinerary_point);
+        }
+
+        document
+            .timetable
+            .operational_trains
+            .operational_trains
+            .push(OperationalTrain {
+                id: train_id.clone(),
+                names: vec![Name::new(train_name)],
+                designators: train_schedule
+                    .labels
+                    .iter()
+                    .map(|label| Designator::new(LABEL_REGISTER, label))
+                    .collect(),
+            });
+        document.timetable.train_parts.train_parts.push(TrainPart {
+            id: train_part_id,
+            operational_train_ref: Some(train_id),
+            operating_period_ref: Some(period_id.clone()),
+            formation_tt: Some(FormationTT {
+                formation_ref: formation_id,
+            }),
+            itinerary: Itinerary { itinerary_points },
+        });
+    }
+
+    Ok((to_xml(&document)?, report))
+}
+
+/// The designators identifying the operational point of a path item, if it can be referenced in railML
+fn operational_point_designators(location: &PathItemLocation) -> Option<Vec<Designator>> {
+    let (designator, secondary_code) = match location {
+        PathItemLocation::OperationalPointUic {
+            uic,
+            secondary_code,
+        } => (
+            Designator::new(UIC_REGISTER, uic.to_string()),
+            secondary_code,
+        ),
+        PathItemLocation::OperationalPointDescription {
+            trigram,
+            secondary_code,
+        } => (
+            Designator::new(TRIGRAM_REGISTER, &trigram.0),
+            secondary_code,
+        ),
+        _ => return None,
+    };
+    let mut designators = vec![designator];
+    if let Some(secondary_code) = secondary_code {
+        designators.push(Designator::new(SECONDARY_CODE_REGISTER, secondary_code));
+    }
+    Some(designators)
+}
+
+/// The railML elements a train part refers to, indexed by id
+struct ImportContext<'a> {
+    locations: HashMap<&'a str, PathItemLocation>,
+    formations: HashMap<&'a str, &'a str>,
+    operational_trains: HashMap<&'a str, &'a OperationalTrain>,
+    period_starts: HashMap<&'a str, DateTime<Utc>>,
+}
+
+/// Import the train schedules of a railML document
+///
+/// Operational points are mapped to path items located by their UIC code, or else by their trigram.
+/// Itinerary points referencing an operational point which can't be mapped are left out, and
+/// train parts lacking a formation, an operating period or a departure time are skipped.
+pub fn import_timetable(xml: &str) -> Result<(Vec<TrainScheduleBase>, MappingReport), RailMLError> {
+    let document: RailML = quick_xml::de::from_str(xml)?;
+    let mut report = MappingReport::default();
+
+    let mut locations = HashMap::new();
+    for operational_point in &document
+        .infrastructure
+        .functional_infrastructure
+        .operational_points
+        .operational_points
+    {
+        match path_item_location(operational_point) {
+            Some(location) => {
+                locations.insert(operational_point.id.as_str(), location);
+            }
+            None => report.add(format!(
+                "Operational point '{}': no valid UIC code nor trigram",
+                operational_point.id
+            )),
+        }
+    }
+    let context = ImportContext {
+        locations,
+        formations: document
+            .rollingstock
+            .formations
+            .formations
+            .iter()
+            .filter_map(|formation| {
+                let name = formation.names.first()?;
+                Some((formation.id.as_str(), name.name.as_str()))
+            })
+            .collect(),
+        operational_trains: document
+            .timetable
+            .operational_trains
+            .operational_trains
+            .iter()
+            .map(|train| (train.id.as_str(), train))
+            .collect(),
+        period_starts: document
+            .timetable
+            .operating_periods
+            .operating_periods
+            .iter()
+            .map(|period| {
+                let start = period.start_date.and_time(NaiveTime::MIN).and_utc();
+                (period.id.as_str(), start)
+            })
+            .collect(),
+    };
+
+    let train_schedules = document
+        .timetable
+        .train_parts
+        .train_parts
+        .iter()
+        .filter_map(|train_part| import_train_part(train_part, &context, &mut report))
+        .collect();
+    Ok((train_schedules, report))
+}
+
+fn path_item_location(operational_point: &OperationalPoint) -> Option<PathItemLocation> {
+    let designator = |register: &str| {
+        operational_point
+            .designators
+            .iter()
+            .find(|designator| designator.register == register)
+            .map(|designator| designator.entry.clone())
+    };
+    let secondary_code = designator(SECONDARY_CODE_REGISTER);
+    if let Some(uic) = designator(UIC_REGISTER).and_then(|uic| uic.parse().ok()) {
+        return Some(PathItemLocation::OperationalPointUic {
+            uic,
+            secondary_code,
+        });
+    }
+    designator(TRIGRAM_REGISTER)
+        .filter(|trigram| !trigram.is_empty())
+        .map(|trigram| PathItemLocation::OperationalPointDescription {
+            trigram: NonBlankString(trigram),
+            secondary_code,
+        })
+}
+
+fn import_train_part(
+    train_part: &TrainPart,
+    context: &ImportContext,
+    report: &mut MappingReport,
+) -> Option<TrainScheduleBase> {
+    let id = &train_part.id;
+    let operational_train = train_part
+        .operational_train_ref
+        .as_ref()
+        .and_then(|train_ref| context.operational_trains.get(train_ref.as_str()));
+    let train_name = operational_train
+        .and_then(|train| train.names.first())
+        .map(|name| name.name.clone())
+        .unwrap_or_else(|| id.clone());
+    let labels = operational_train
+        .map(|train| {
+            train
+                .designators
+                .iter()
+                .filter(|designator| designator.register == LABEL_REGISTER)
+                .map(|designator| designator.entry.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(rolling_stock_name) = train_part
+        .formation_tt
+        .as_ref()
+        .and_then(|formation| context.formations.get(formation.formation_ref.as_str()))
+    else {
+        report.add(format!(
+            "Train part '{id}': no named formation, the train part is skipped"
+        ));
+        return None;
+    };
+    let Some(period_start) = train_part
+        .operating_period_ref
+        .as_ref()
+        .and_then(|period_ref| context.period_starts.get(period_ref.as_str()))
+        .copied()
+    else {
+        report.add(format!(
+            "Train part '{id}': no operating period, the train part is skipped"
+        ));
+        return None;
+    };
+
+    let mut itinerary_points: Vec<_> = train_part.itinerary.itinerary_points.iter().collect();
+    itinerary_points.sort_by_key(|point| point.seq);
+    let mut start_time = None;
+    let mut path = vec![];
+    let mut schedule = vec![];
+    for point in itinerary_points {
+        let Some(location) = context.locations.get(point.operational_point_ref.as_str()) else {
+            report.add(format!(
+                "Train part '{id}': itinerary point '{}' references an unmapped operational point '{}'",
+                point.id, point.operational_point_ref
+            ));
+            continue;
+        };
+        let path_item_id = NonBlankString(point.id.clone());
+        path.push(PathItem {
+            id: path_item_id.clone(),
+            deleted: false,
+            location: location.clone(),
+        });
+        let arrival = point
+            .arrival
+            .as_ref()
+            .map(|time| time.instant(period_start));
+        let departure = point
+            .departure
+            .as_ref()
+            .map(|time| time.instant(period_start));
+        let dwell_time = arrival
+            .zip(departure)
+            .map(|(arrival, departure)| departure - arrival);
+
+        let Some(start_time) = start_time else {
+            start_time = departure.or(arrival);
+            if start_time.is_none() {
+                report.add(format!(
+                    "Train part '{id}': no departure time, the train part is skipped"
+                ));
+                return None;
+            }
+            continue;
+        };
+
+        let arrival = arrival
+            .or(departure)
+            .and_then(|arrival| PositiveDuration::try_from(arrival - start_time).ok());
+        let stop_for = match &point.stop_description {
+            Some(StopDescription {
+                stop_duration: Some(stop_duration),
+                ..
+            }) => Some(stop_duration.clone()),
+            Some(_) => PositiveDuration::try_from(dwell_time.unwrap_or_else(Duration::zero)).ok(),
+            None => dwell_time
+                .filter(|dwell_time| *dwell_time > Duration::zero())
+                .and_then(|dwell_time| PositiveDuration::try_from(dwell_time).ok()),
+        };
+        if arrival.is_none() && stop_for.is_none() {
+            continue;
+        }
+        let on_stop_signal = stop_for.is_some()
+            && point
+                .stop_description
+                .as_ref()
+                .is_some_and(|stop_description| stop_description.on_stop_signal);
+        schedule.push(ScheduleItem {
+            at: path_item_id,
+            arrival,
+            stop_for,
+            on_stop_signal,
+            locked: false,
+        });
+    }
+
+    let Some(start_time) = start_time.filter(|_| path.len() >= 2) else {
+        report.add(format!(
+            "Train part '{id}': less than two mapped itinerary points, the train part is skipped"
+        ));
+        return None;
+    };
+    Some(TrainScheduleBase {
+        train_name,
+        labels,
+        rolling_stock_name: rolling_stock_name.to_string(),
+        start_time,
+        path,
+        schedule,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use editoast_schemas::train_schedule::PathItemLocation;
+    use editoast_schemas::train_schedule::TrainScheduleBase;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::export_timetable;
+    use super::import_timetable;
+
+    fn train_schedule(train_name: &str, start_time: &str) -> TrainScheduleBase {
+        serde_json::from_value(json!({
+            "train_name": train_name,
+            "labels": ["fret"],
+            "rolling_stock_name": "fast_rolling_stock",
+            "start_time": start_time,
+            "path": [
+                { "id": "a", "uic": 87000001, "secondary_code": "BV" },
+                { "id": "b", "trigram": "MID" },
+                { "id": "c", "track": "TA0", "offset": 100 },
+                { "id": "d", "uic": 87000002 }
+            ],
+            "schedule": [
+                { "at": "b", "arrival": "PT600S", "stop_for": "PT120S", "on_stop_signal": true },
+                { "at": "d", "arrival": "PT1800S", "stop_for": "PT0S" }
+            ],
+            "margins": { "boundaries": [], "values": ["5%"] },
+            "constraint_distribution": "MARECO"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let train_schedules = vec![
+            train_schedule("train 1", "2024-01-01T08:00:00Z"),
+            train_schedule("train 2", "2024-01-02T23:50:00Z"),
+        ];
+
+        let (xml, export_report) = export_timetable(1, &train_schedules).unwrap();
+        let (imported, import_report) = import_timetable(&xml).unwrap();
+
+        assert_eq!(export_report.unmapped.len(), 4);
+        assert!(import_report.is_empty(), "{import_report}");
+        assert_eq!(imported.len(), 2);
+        let second_train = &imported[1];
+        assert_eq!(second_train.train_name, "train 2");
+        assert_eq!(second_train.labels, vec!["fret".to_string()]);
+        assert_eq!(second_train.rolling_stock_name, "fast_rolling_stock");
+        assert_eq!(
+            second_train.start_time,
+            Utc.with_ymd_and_hms(2024, 1, 2, 23, 50, 0).unwrap()
+        );
+        let locations: Vec<_> = second_train
+            .path
+            .iter()
+            .map(|path_item| path_item.location.clone())
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                PathItemLocation::OperationalPointUic {
+                    uic: 87000001,
+                    secondary_code: Some("BV".into())
+                },
+                PathItemLocation::OperationalPointDescription {
+                    trigram: "MID".into(),
+                    secondary_code: None
+                },
+                PathItemLocation::OperationalPointUic {
+                    uic: 87000002,
+                    secondary_code: None
+                },
+            ]
+        );
+        let schedule: Vec<_> = second_train
+            .schedule
+            .iter()
+            .map(|schedule_item| {
+                (
+                    schedule_item.at.0.clone(),
+                    schedule_item
+                        .arrival
+                        .as_ref()
+                        .map(|arrival| arrival.num_seconds()),
+                    schedule_item
+                        .stop_for
+                        .as_ref()
+                        .map(|stop_for| stop_for.num_seconds()),
+                    schedule_item.on_stop_signal,
+                )
+            })
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![
+                (
+                    second_train.path[1].id.0.clone(),
+                    Some(600),
+                    Some(120),
+                    true
+                ),
+                (
+                    second_train.path[2].id.0.clone(),
+                    Some(1800),
+                    Some(0),
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_reports_unmapped_elements() {
+        let (_, report) =
+            export_timetable(1, &[train_schedule("train 1", "2024-01-01T08:00:00Z")]).unwrap();
+
+        assert_eq!(
+            report.unmapped,
+            vec![
+                "Train 'train 1': margins are not exported".to_string(),
+                "Train 'train 1': path item 'c' is not located by a UIC code or a trigram"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_reports_unmapped_elements() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <railML xmlns="https://www.railml.org/schemas/3.2" version="3.2">
+              <infrastructure>
+                <functionalInfrastructure>
+                  <operationalPoints>
+                    <operationalPoint id="op_1"><designator register="UIC" entry="87000001"/></operationalPoint>
+                    <operationalPoint id="op_2"><designator register="DB640" entry="FF"/></operationalPoint>
+                    <operationalPoint id="op_3"><designator register="_trigram" entry="END"/></operationalPoint>
+                  </operationalPoints>
+                </functionalInfrastructure>
+              </infrastructure>
+              <rollingstock>
+                <formations>
+                  <formation id="fm_1"><name name="fast_rolling_stock"/></formation>
+                </formations>
+              </rollingstock>
+              <timetable id="tt_1">
+                <operatingPeriods>
+                  <operatingPeriod id="opp_1" startDate="2024-01-01"/>
+                </operatingPeriods>
+                <operationalTrains>
+                  <operationalTrain id="ot_1"><name name="train 1"/></operationalTrain>
+                </operationalTrains>
+                <trainParts>
+                  <trainPart id="tp_1" operationalTrainRef="ot_1" operatingPeriodRef="opp_1">
+                    <formationTT formationRef="fm_1"/>
+                    <itinerary>
+                      <itineraryPoint id="tp_1_3" seq="3" operationalPointRef="op_3">
+                        <arrival time="09:00:00" dayOffset="1"/>
+                      </itineraryPoint>
+                      <itineraryPoint id="tp_1_1" seq="1" operationalPointRef="op_1">
+                        <departure time="08:00:00"/>
+                      </itineraryPoint>
+                      <itineraryPoint id="tp_1_2" seq="2" operationalPointRef="op_2">
+                        <arrival time="08:30:00"/>
+                      </itineraryPoint>
+                    </itinerary>
+                  </trainPart>
+                  <trainPart id="tp_2" operatingPeriodRef="opp_1"/>
+                </trainParts>
+              </timetable>
+            </railML>"#;
+
+        let (train_schedules, report) = import_timetable(xml).unwrap();
+
+        assert_eq!(
+            report.unmapped,
+            vec![
+                "Operational point 'op_2': no valid UIC code nor trigram".to_string(),
+                "Train part 'tp_1': itinerary point 'tp_1_2' references an unmapped operational point 'op_2'".to_string(),
+                "Train part 'tp_2': no named formation, the train part is skipped".to_string(),
+            ]
+        );
+        assert_eq!(train_schedules.len(), 1);
+        let train_schedule = &train_schedules[0];
+        assert_eq!(train_schedule.train_name, "train 1");
+        assert_eq!(
+            train_schedule.start_time,
+            Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap()
+        );
+        assert_eq!(train_schedule.path.len(), 2);
+        assert_eq!(train_schedule.schedule.len(), 1);
+        assert_eq!(
+            train_schedule.schedule[0]
+                .arrival
+                .as_ref()
+                .map(|arrival| arrival.num_hours()),
+            Some(25)
+        );
+        assert!(train_schedule.schedule[0].stop_for.is_none());
+    }
+}
@@ -45,6 +45,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    infra_edit (id) {
+        id -> Int8,
+        infra_id -> Int8,
+        #[max_length = 40]
+        infra_version -> Varchar,
+        creation_date -> Timestamptz,
+        operations -> Jsonb,
+        inverse_operations -> Jsonb,
+        undone -> Bool,
+        #[max_length = 255]
+        author -> Nullable<Varchar>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -212,6 +230,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    infra_layer_work_schedule (id) {
+        id -> Int8,
+        work_schedule_id -> Int8,
+        geographic -> Geometry,
+        infra_id -> Int8,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -355,6 +385,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    infra_snapshot (id) {
+        id -> Int8,
+        infra_id -> Int8,
+        #[max_length = 255]
+        name -> Varchar,
+        #[max_length = 40]
+        infra_version -> Varchar,
+        creation_date -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    infra_snapshot_object (id) {
+        id -> Int8,
+        snapshot_id -> Int8,
+        #[max_length = 32]
+        obj_type -> Varchar,
+        #[max_length = 255]
+        obj_id -> Varchar,
+        data -> Jsonb,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -441,6 +501,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    rolling_stock_revision (id) {
+        id -> Int8,
+        rolling_stock_id -> Int8,
+        version -> Int8,
+        #[max_length = 255]
+        name -> Varchar,
+        creation_date -> Timestamptz,
+        rolling_stock -> Jsonb,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -643,6 +718,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    train_composition (id) {
+        id -> Int8,
+        #[max_length = 255]
+        name -> Varchar,
+        rolling_stock_id -> Int8,
+        unit_count -> Int4,
+        wagons -> Jsonb,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -671,6 +760,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+
+    train_schedule_pattern (id) {
+        id -> Int8,
+        timetable_id -> Int8,
+        template -> Jsonb,
+        repetition -> Jsonb,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use postgis_diesel::sql_types::*;
@@ -694,6 +795,10 @@ diesel::table! {
         speed_limit_tag -> Nullable<Varchar>,
         power_restrictions -> Jsonb,
         options -> Jsonb,
+        pattern_id -> Nullable<Int8>,
+        rolling_stock_version -> Nullable<Int8>,
+        rolling_stock_id -> Nullable<Int8>,
+        composition_id -> Nullable<Int8>,
     }
 }
 
@@ -725,6 +830,7 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(infra_edit -> infra (infra_id));
 diesel::joinable!(infra_layer_buffer_stop -> infra (infra_id));
 diesel::joinable!(infra_layer_detector -> infra (infra_id));
 diesel::joinable!(infra_layer_electrification -> infra (infra_id));
@@ -737,6 +843,8 @@ diesel::joinable!(infra_layer_signal -> infra (infra_id));
 diesel::joinable!(infra_layer_speed_section -> infra (infra_id));
 diesel::joinable!(infra_layer_switch -> infra (infra_id));
 diesel::joinable!(infra_layer_track_section -> infra (infra_id));
+diesel::joinable!(infra_layer_work_schedule -> infra (infra_id));
+diesel::joinable!(infra_layer_work_schedule -> work_schedule (work_schedule_id));
 diesel::joinable!(infra_object_buffer_stop -> infra (infra_id));
 diesel::joinable!(infra_object_detector -> infra (infra_id));
 diesel::joinable!(infra_object_electrification -> infra (infra_id));
@@ -748,10 +856,13 @@ diesel::joinable!(infra_object_signal -> infra (infra_id));
 diesel::joinable!(infra_object_speed_section -> infra (infra_id));
 diesel::joinable!(infra_object_switch -> infra (infra_id));
 diesel::joinable!(infra_object_track_section -> infra (infra_id));
+diesel::joinable!(infra_snapshot -> infra (infra_id));
+diesel::joinable!(infra_snapshot_object -> infra_snapshot (snapshot_id));
 diesel::joinable!(pathfinding -> infra (infra_id));
 diesel::joinable!(project -> document (image_id));
 diesel::joinable!(rolling_stock_livery -> document (compound_image_id));
 diesel::joinable!(rolling_stock_livery -> rolling_stock (rolling_stock_id));
+diesel::joinable!(rolling_stock_revision -> rolling_stock (rolling_stock_id));
 diesel::joinable!(rolling_stock_separate_image -> document (image_id));
 diesel::joinable!(rolling_stock_separate_image -> rolling_stock_livery (livery_id));
 diesel::joinable!(scenario -> electrical_profile_set (electrical_profile_set_id));
@@ -769,16 +880,20 @@ diesel::joinable!(search_study -> study (id));
 diesel::joinable!(simulation_output -> train_schedule (train_schedule_id));
 diesel::joinable!(study -> project (project_id));
 diesel::joinable!(timetable_v2 -> electrical_profile_set (electrical_profile_set_id));
+diesel::joinable!(train_composition -> rolling_stock (rolling_stock_id));
 diesel::joinable!(train_schedule -> pathfinding (path_id));
 diesel::joinable!(train_schedule -> rolling_stock (rolling_stock_id));
 diesel::joinable!(train_schedule -> timetable (timetable_id));
+diesel::joinable!(train_schedule_pattern -> timetable_v2 (timetable_id));
 diesel::joinable!(train_schedule_v2 -> timetable_v2 (timetable_id));
+diesel::joinable!(train_schedule_v2 -> train_schedule_pattern (pattern_id));
 diesel::joinable!(work_schedule -> work_schedule_group (work_schedule_group_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     document,
     electrical_profile_set,
     infra,
+    infra_edit,
     infra_layer_buffer_stop,
     infra_layer_detector,
     infra_layer_electrification,
@@ -791,6 +906,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     infra_layer_speed_section,
     infra_layer_switch,
     infra_layer_track_section,
+    infra_layer_work_schedule,
     infra_object_buffer_stop,
     infra_object_detector,
     infra_object_electrification,
@@ -802,10 +918,13 @@ diesel::allow_tables_to_appear_in_same_query!(
     infra_object_speed_section,
     infra_object_switch,
     infra_object_track_section,
+    infra_snapshot,
+    infra_snapshot_object,
     pathfinding,
     project,
     rolling_stock,
     rolling_stock_livery,
+    rolling_stock_revision,
     rolling_stock_separate_image,
     scenario,
     scenario_v2,
@@ -819,7 +938,9 @@ diesel::allow_tables_to_appear_in_same_query!(
     study,
     timetable,
     timetable_v2,
+    train_composition,
     train_schedule,
+    train_schedule_pattern,
     train_schedule_v2,
     work_schedule,
     work_schedule_group,
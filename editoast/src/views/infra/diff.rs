@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use actix_web::get;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use editoast_schemas::infra::InfraObject;
+use editoast_schemas::primitives::ObjectType;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value as JsonValue;
+use strum::IntoEnumIterator;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::InfraApiError;
+use super::InfraIdParam;
+use crate::error::Result;
+use crate::infra_cache::operation::DeleteOperation;
+use crate::infra_cache::operation::Operation;
+use crate::infra_cache::operation::UpdateOperation;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+
+crate::routes! {
+    get_diff,
+}
+
+editoast_common::schemas! {
+    ObjectsDiff,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct OtherInfraIdParam {
+    /// The infra to compare with
+    other_infra_id: i64,
+}
+
+/// Changes of the objects of a given type between two infras
+///
+/// Each change is an edition operation, so that applying them to the first infra gives the
+/// objects of the second one.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ObjectsDiff {
+    /// Objects only present in the second infra
+    created: Vec<Operation>,
+    /// Objects only present in the first infra
+    deleted: Vec<Operation>,
+    /// Objects present in both infras with a different content
+    modified: Vec<Operation>,
+}
+
+impl ObjectsDiff {
+    fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.deleted.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Compute the changes between the railjson objects of a given type of two infras
+fn diff_objects(
+    obj_type: ObjectType,
+    mut objects: BTreeMap<String, JsonValue>,
+    other_objects: BTreeMap<String, JsonValue>,
+) -> Result<ObjectsDiff> {
+    let mut diff = ObjectsDiff::default();
+    for (obj_id, other_object) in other_objects {
+        match objects.remove(&obj_id) {
+            None => {
+                let railjson = build_infra_object(obj_type, other_object)?;
+                diff.created.push(Operation::Create(Box::new(railjson)));
+            }
+            Some(object) if object != other_object => {
+                diff.modified.push(Operation::Update(UpdateOperation {
+                    obj_id,
+                    obj_type,
+                    railjson_patch: json_patch::diff(&object, &other_object),
+                }));
+            }
+            Some(_) => (),
+        }
+    }
+    diff.deleted = objects
+        .into_keys()
+        .map(|obj_id| Operation::Delete(DeleteOperation { obj_id, obj_type }))
+        .collect();
+    Ok(diff)
+}
+
+/// Build a railjson object given its type and its content
+pub(super) fn build_infra_object(obj_type: ObjectType, railjson: JsonValue) -> Result<InfraObject> {
+    Ok(serde_json::from_value(
+        json!({ "obj_type": obj_type, "railjson": railjson }),
+    )?)
+}
+
+/// Retrieve the railjson objects of a given type of an infra, indexed by id
+pub(super) async fn get_objects(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    obj_type: ObjectType,
+) -> Result<BTreeMap<String, JsonValue>> {
+    Infra::get_railjson(conn, infra_id, &obj_type)
+        .await?
+        .into_iter()
+        .map(|data| {
+            let object: JsonValue = serde_json::from_str(&data.railjson)?;
+            let obj_id = String::deserialize(&object["id"])?;
+            Ok((obj_id, object))
+        })
+        .collect()
+}
+
+/// Compute the changes between two infras
+///
+/// Changes are given by object type, only object types with changes are listed.
+/// Applying the changes to the first infra with the edition endpoint gives the second infra.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, OtherInfraIdParam),
+    responses(
+        (status = 200, description = "The changes by object type", body = HashMap<ObjectType, ObjectsDiff>),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[get("/diff/{other_infra_id}")]
+async fn get_diff(
+    infra_id_param: Path<InfraIdParam>,
+    other_infra_id_param: Path<OtherInfraIdParam>,
+    db_pool: Data<DbConnectionPoolV2>,
+) -> Result<Json<HashMap<ObjectType, ObjectsDiff>>> {
+    let infra_id = infra_id_param.infra_id;
+    let other_infra_id = other_infra_id_param.other_infra_id;
+    let conn = &mut db_pool.get().await?;
+    Infra::retrieve_or_fail(conn.deref_mut(), infra_id, || InfraApiError::NotFound {
+        infra_id,
+    })
+    .await?;
+    Infra::retrieve_or_fail(conn.deref_mut(), other_infra_id, || {
+        InfraApiError::NotFound {
+            infra_id: other_infra_id,
+        }
+    })
+    .await?;
+
+    let mut diffs = HashMap::new();
+    for obj_type in ObjectType::iter() {
+        let objects = get_objects(conn, infra_id, obj_type).await?;
+        let other_objects = get_objects(conn, other_infra_id, obj_type).await?;
+        let diff = diff_objects(obj_type, objects, other_objects)?;
+        if !diff.is_empty() {
+            diffs.insert(obj_type, diff);
+        }
+    }
+    Ok(Json(diffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::infra::TrackSection;
+    use editoast_schemas::primitives::ObjectType;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+    use std::ops::DerefMut;
+
+    use super::diff_objects;
+    use super::ObjectsDiff;
+    use crate::infra_cache::operation::create::apply_create_operation;
+    use crate::infra_cache::operation::DeleteOperation;
+    use crate::infra_cache::operation::Operation;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::views::test_app::TestAppBuilder;
+
+    #[test]
+    fn diff_objects_lists_created_deleted_and_modified_objects() {
+        let detector = |id: &str, track: &str, position: f64| {
+            let detector =
+                json!({ "id": id, "track": track, "position": position, "extensions": {} });
+            (id.to_owned(), detector)
+        };
+        let objects = BTreeMap::from([
+            detector("D1", "T1", 10.0),
+            detector("D2", "T1", 20.0),
+            detector("D3", "T1", 30.0),
+        ]);
+        let other_objects = BTreeMap::from([
+            detector("D1", "T1", 10.0),
+            detector("D3", "T2", 30.0),
+            detector("D4", "T1", 40.0),
+        ]);
+
+        let diff = diff_objects(ObjectType::Detector, objects, other_objects).unwrap();
+
+        let expected_diff: ObjectsDiff = serde_json::from_value(json!({
+            "created": [{
+                "operation_type": "CREATE",
+                "obj_type": "Detector",
+                "railjson": { "id": "D4", "track": "T1", "position": 40.0, "extensions": {} },
+            }],
+            "deleted": [{ "operation_type": "DELETE", "obj_type": "Detector", "obj_id": "D2" }],
+            "modified": [{
+                "operation_type": "UPDATE",
+                "obj_type": "Detector",
+                "obj_id": "D3",
+                "railjson_patch": [{ "op": "replace", "path": "/track", "value": "T2" }],
+            }],
+        }))
+        .unwrap();
+        assert_eq!(diff, expected_diff);
+    }
+
+    #[rstest]
+    async fn diff_between_two_infras() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+        let other_infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+        let track_section = TrackSection {
+            id: "track_section_id".into(),
+            length: 100.,
+            ..Default::default()
+        }
+        .into();
+        apply_create_operation(&track_section, infra.id, db_pool.get_ok().deref_mut())
+            .await
+            .expect("Failed to create track section object");
+
+        let request = TestRequest::get()
+            .uri(format!("/infra/{}/diff/{}", infra.id, other_infra.id).as_str())
+            .to_request();
+        let diff: HashMap<ObjectType, ObjectsDiff> =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        assert_eq!(
+            diff,
+            HashMap::from([(
+                ObjectType::TrackSection,
+                ObjectsDiff {
+                    deleted: vec![Operation::Delete(DeleteOperation {
+                        obj_id: "track_section_id".into(),
+                        obj_type: ObjectType::TrackSection,
+                    })],
+                    ..Default::default()
+                }
+            )])
+        );
+    }
+
+    #[rstest]
+    async fn diff_with_unknown_infra() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::get()
+            .uri(format!("/infra/{}/diff/{}", infra.id, -1).as_str())
+            .to_request();
+
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+}
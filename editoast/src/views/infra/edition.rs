@@ -24,6 +24,7 @@ use tracing::error;
 use tracing::info;
 use uuid::Uuid;
 
+use crate::client::MapLayersConfig;
 use crate::error::Result;
 use crate::generated_data;
 use crate::infra_cache::object_cache::OperationalPointPartCache;
@@ -34,13 +35,16 @@ use crate::infra_cache::operation::UpdateOperation;
 use crate::infra_cache::InfraCache;
 use crate::infra_cache::ObjectCache;
 use crate::map;
+use crate::map::InvalidationZone;
 use crate::map::MapLayers;
 use crate::modelsv2::prelude::*;
 use crate::modelsv2::DbConnection;
 use crate::modelsv2::DbConnectionPool;
 use crate::modelsv2::Infra;
+use crate::modelsv2::InfraEdit;
 use crate::views::infra::InfraApiError;
 use crate::views::infra::InfraIdParam;
+use crate::views::params::RemoteUser;
 use crate::RedisClient;
 use editoast_schemas::infra::InfraObject;
 
@@ -57,8 +61,11 @@ crate::routes! {
 /// The consistency of the patch with the RailJSON schema is checked. If a patch
 /// is erroneous, the whole batch is rejected.
 ///
-/// After editing the object, the generated cartographic layers are invalidated and
-/// regenerated. The edition step fails if the regeneration fails.
+/// After editing the object, the generated cartographic layers are regenerated and
+/// the cached map tiles intersecting the edited objects are invalidated.
+/// The edition step fails if the regeneration fails.
+///
+/// The batch is recorded in the edition history of the infra, so that it can be undone.
 #[utoipa::path(
     tag = "infra",
     params(InfraIdParam),
@@ -68,6 +75,7 @@ crate::routes! {
     )
 )]
 #[post("")]
+#[allow(clippy::too_many_arguments)]
 pub async fn edit<'a>(
     infra: Path<InfraIdParam>,
     operations: Json<Vec<Operation>>,
@@ -75,6 +83,8 @@ pub async fn edit<'a>(
     infra_caches: Data<CHashMap<i64, InfraCache>>,
     redis_client: Data<RedisClient>,
     map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+    RemoteUser(author): RemoteUser,
 ) -> Result<Json<Vec<InfraObject>>> {
     let infra_id = infra.infra_id;
     let mut conn = db_pool.get().await?;
@@ -83,14 +93,18 @@ pub async fn edit<'a>(
         Infra::retrieve_or_fail(&mut conn, infra_id, || InfraApiError::NotFound { infra_id })
             .await?;
     let mut infra_cache = InfraCache::get_or_load_mut(&mut conn, &infra_caches, &infra).await?;
+    let mut invalidation_zone = InvalidationZone::compute(&infra_cache, &operations);
     let operation_results =
-        apply_edit(&mut conn, &mut infra, &operations, &mut infra_cache).await?;
+        apply_edit(&mut conn, &mut infra, &operations, &mut infra_cache, author).await?;
+    invalidation_zone.union(&InvalidationZone::compute(&infra_cache, &operations));
 
     let mut conn = redis_client.get_connection().await?;
-    map::invalidate_all(
+    map::invalidate_zone(
         &mut conn,
-        &map_layers.layers.keys().cloned().collect(),
+        &map_layers,
         infra_id,
+        &invalidation_zone,
+        &map_layers_config,
     )
     .await?;
 
@@ -106,6 +120,7 @@ pub async fn edit<'a>(
     ),
 )]
 #[post("/split_track_section")]
+#[allow(clippy::too_many_arguments)]
 pub async fn split_track_section<'a>(
     infra: Path<i64>,
     payload: Json<TrackOffset>,
@@ -113,6 +128,8 @@ pub async fn split_track_section<'a>(
     infra_caches: Data<CHashMap<i64, InfraCache>>,
     redis_client: Data<RedisClient>,
     map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+    RemoteUser(author): RemoteUser,
 ) -> Result<Json<Vec<String>>> {
     let payload = payload.into_inner();
     let infra_id = infra.into_inner();
@@ -306,12 +323,16 @@ pub async fn split_track_section<'a>(
     }));
 
     // Apply operations
-    apply_edit(conn, &mut infra, &operations, &mut infra_cache).await?;
+    let mut invalidation_zone = InvalidationZone::compute(&infra_cache, &operations);
+    apply_edit(conn, &mut infra, &operations, &mut infra_cache, author).await?;
+    invalidation_zone.union(&InvalidationZone::compute(&infra_cache, &operations));
     let mut conn = redis_client.get_connection().await?;
-    map::invalidate_all(
+    map::invalidate_zone(
         &mut conn,
-        &map_layers.layers.keys().cloned().collect(),
+        &map_layers,
         infra_id,
+        &invalidation_zone,
+        &map_layers_config,
     )
     .await?;
 
@@ -809,63 +830,90 @@ fn get_splitted_patch_operations_for_ranges(
     patch_operations
 }
 
-async fn apply_edit(
+/// Apply a batch of operations to an infra and record it in the edition history of the infra
+pub(super) async fn apply_edit(
     connection: &mut DbConnection,
     infra: &mut Infra,
     operations: &[Operation],
     infra_cache: &mut InfraCache,
+    author: Option<String>,
 ) -> Result<Vec<InfraObject>> {
-    let infra_id = infra.id;
-    // Check if the infra is locked
-    if infra.locked {
-        return Err(EditionError::InfraIsLocked { infra_id }.into());
-    }
-
     // Apply modifications in one transaction
     connection
         .build_transaction()
         .run(|conn| {
             Box::pin(async {
-                let mut railjsons = vec![];
-                let mut cache_operations = vec![];
-                for operation in operations {
-                    let railjson = operation.apply(infra_id, conn).await?;
-                    match (operation, railjson) {
-                        (Operation::Create(_), Some(railjson)) => {
-                            railjsons.push(railjson.clone());
-                            cache_operations
-                                .push(CacheOperation::Create(ObjectCache::from(railjson)));
-                        }
-                        (Operation::Update(_), Some(railjson)) => {
-                            railjsons.push(railjson.clone());
-                            cache_operations
-                                .push(CacheOperation::Update(ObjectCache::from(railjson)));
-                        }
-                        (Operation::Delete(delete_operation), _) => {
-                            cache_operations
-                                .push(CacheOperation::Delete(delete_operation.clone().into()));
-                        }
-                        _ => unreachable!("CREATE and UPDATE always produce a RailJSON"),
-                    }
-                }
+                let (railjsons, inverse_operations) =
+                    apply_operations(conn, infra, operations, infra_cache).await?;
+                InfraEdit::record(
+                    conn,
+                    infra.id,
+                    infra.version.clone(),
+                    operations.to_vec(),
+                    inverse_operations,
+                    author,
+                )
+                .await?;
+                Ok(railjsons)
+            })
+        })
+        .await
+}
 
-                // Bump version
-                infra.bump_version(conn).await?;
-                // Apply operations to infra cache
-                infra_cache.apply_operations(&cache_operations)?;
+/// Apply a batch of operations to an infra, keeping its cache and generated data up to date
+///
+/// Returns the RailJSON of the created and updated objects, and the operations reverting the
+/// whole batch. This function must be called inside a transaction.
+pub(super) async fn apply_operations(
+    conn: &mut DbConnection,
+    infra: &mut Infra,
+    operations: &[Operation],
+    infra_cache: &mut InfraCache,
+) -> Result<(Vec<InfraObject>, Vec<Operation>)> {
+    let infra_id = infra.id;
+    // Check if the infra is locked
+    if infra.locked {
+        return Err(EditionError::InfraIsLocked { infra_id }.into());
+    }
 
-                // Refresh layers if needed
-                generated_data::update_all(conn, infra_id, &cache_operations, infra_cache)
-                    .await
-                    .expect("Update generated data failed");
+    let mut railjsons = vec![];
+    let mut cache_operations = vec![];
+    let mut inverse_operations = vec![];
+    for operation in operations {
+        inverse_operations.push(operation.inverse(infra_id, conn).await?);
+        let railjson = operation.apply(infra_id, conn).await?;
+        match (operation, railjson) {
+            (Operation::Create(_), Some(railjson)) => {
+                railjsons.push(railjson.clone());
+                cache_operations.push(CacheOperation::Create(ObjectCache::from(railjson)));
+            }
+            (Operation::Update(_), Some(railjson)) => {
+                railjsons.push(railjson.clone());
+                cache_operations.push(CacheOperation::Update(ObjectCache::from(railjson)));
+            }
+            (Operation::Delete(delete_operation), _) => {
+                cache_operations.push(CacheOperation::Delete(delete_operation.clone().into()));
+            }
+            _ => unreachable!("CREATE and UPDATE always produce a RailJSON"),
+        }
+    }
+    // The last operation has to be reverted first
+    inverse_operations.reverse();
 
-                // Bump infra generated version to the infra version
-                infra.bump_generated_version(conn).await?;
+    // Bump version
+    infra.bump_version(conn).await?;
+    // Apply operations to infra cache
+    infra_cache.apply_operations(&cache_operations)?;
 
-                Ok(railjsons)
-            })
-        })
+    // Refresh layers if needed
+    generated_data::update_all(conn, infra_id, &cache_operations, infra_cache)
         .await
+        .expect("Update generated data failed");
+
+    // Bump infra generated version to the infra version
+    infra.bump_generated_version(conn).await?;
+
+    Ok((railjsons, inverse_operations))
 }
 
 #[derive(Debug, Clone, Error, EditoastError)]
@@ -890,6 +938,7 @@ pub mod tests {
     use actix_web::test::call_service;
     use actix_web::test::TestRequest;
     use pretty_assertions::assert_eq;
+    use redis::AsyncCommands;
     use rstest::rstest;
 
     use super::*;
@@ -897,6 +946,7 @@ pub mod tests {
     use crate::fixtures::tests::small_infra;
     use crate::generated_data::infra_error::InfraError;
     use crate::generated_data::infra_error::InfraErrorType;
+    use crate::map::Tile;
     use crate::modelsv2::infra::ObjectQueryable;
     use crate::views::infra::errors::query_errors;
     use crate::views::tests::create_test_service;
@@ -1006,6 +1056,56 @@ pub mod tests {
         assert_eq!(errors_without_routes.len() - init_errors.len(), 0);
     }
 
+    #[rstest]
+    async fn edit_track_section_only_invalidates_intersecting_tiles() {
+        // Init
+        let pg_db_pool = db_pool();
+        let small_infra = small_infra(pg_db_pool.clone()).await;
+        let infra_id = small_infra.id();
+        let app = create_test_service().await;
+
+        // Cache a tile containing TA0 and a tile far from it (on the east side of the infra)
+        let mut redis = RedisClient::new(Default::default())
+            .unwrap()
+            .get_connection()
+            .await
+            .unwrap();
+        let view_prefix = map::get_view_cache_prefix("track_sections", infra_id, "geo");
+        let edited_tile_key =
+            map::get_cache_tile_key(&view_prefix, &Tile::from_coordinates(-0.38, 49.5, 14));
+        let unrelated_tile_key =
+            map::get_cache_tile_key(&view_prefix, &Tile::from_coordinates(-0.1, 49.47, 14));
+        for key in [&edited_tile_key, &unrelated_tile_key] {
+            redis
+                .set_ex::<_, _, ()>(key, vec![42_u8], 60)
+                .await
+                .unwrap();
+        }
+
+        // Edit TA0
+        let operations = vec![Operation::Update(UpdateOperation {
+            obj_type: ObjectType::TrackSection,
+            obj_id: "TA0".to_string(),
+            railjson_patch: Patch(vec![PatchOperation::Replace(ReplaceOperation {
+                path: "/length".to_string().parse().unwrap(),
+                value: json!(1950),
+            })]),
+        })];
+        let req = TestRequest::post()
+            .uri(format!("/infra/{infra_id}").as_str())
+            .set_json(operations)
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // Check that only the tile containing TA0 was invalidated
+        let edited_tile_cached: bool = redis.exists(&edited_tile_key).await.unwrap();
+        let unrelated_tile_cached: bool = redis.exists(&unrelated_tile_key).await.unwrap();
+        redis.del::<_, ()>(&unrelated_tile_key).await.unwrap();
+        assert!(!edited_tile_cached);
+        assert!(unrelated_tile_cached);
+    }
+
     #[rstest]
     async fn apply_edit_transaction_should_work() {
         // Init
@@ -1030,10 +1130,15 @@ pub mod tests {
             }),
         ]
         .to_vec();
-        let result: Vec<InfraObject> =
-            apply_edit(conn, &mut small_infra.model, &operations, &mut infra_cache)
-                .await
-                .unwrap();
+        let result: Vec<InfraObject> = apply_edit(
+            conn,
+            &mut small_infra.model,
+            &operations,
+            &mut infra_cache,
+            None,
+        )
+        .await
+        .unwrap();
 
         // Check that the updated track has the new length
         assert_eq!(1234.0, result[0].get_data()["length"]);
@@ -1074,7 +1179,14 @@ pub mod tests {
             }),
         ]
         .to_vec();
-        let result = apply_edit(conn, &mut small_infra.model, &operations, &mut infra_cache).await;
+        let result = apply_edit(
+            conn,
+            &mut small_infra.model,
+            &operations,
+            &mut infra_cache,
+            None,
+        )
+        .await;
 
         // Check that we have an error
         assert!(result.is_err());
@@ -0,0 +1,481 @@
+use actix_web::get;
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use chashmap::CHashMap;
+use editoast_derive::EditoastError;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::edition::apply_operations;
+use super::InfraApiError;
+use super::InfraIdParam;
+use crate::client::MapLayersConfig;
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::infra_cache::operation::Operation;
+use crate::infra_cache::InfraCache;
+use crate::map;
+use crate::map::InvalidationZone;
+use crate::map::MapLayers;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnectionPool;
+use crate::modelsv2::Infra;
+use crate::modelsv2::InfraEdit;
+use crate::views::pagination::PaginatedList as _;
+use crate::views::pagination::PaginationQueryParam;
+use crate::views::pagination::PaginationStats;
+use crate::RedisClient;
+
+crate::routes! {
+    "/history" => {
+        list,
+        undo,
+        redo,
+    },
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "infra:history")]
+enum HistoryError {
+    #[error("At least one edit must be undone or redone")]
+    #[editoast_error(status = 400)]
+    NullCount,
+    #[error("Cannot undo {count} edits of infra '{infra_id}', only {available} can be undone")]
+    #[editoast_error(status = 400)]
+    NotEnoughEditsToUndo {
+        infra_id: i64,
+        count: u64,
+        available: u64,
+    },
+    #[error("Cannot redo {count} edits of infra '{infra_id}', only {available} can be redone")]
+    #[editoast_error(status = 400)]
+    NotEnoughEditsToRedo {
+        infra_id: i64,
+        count: u64,
+        available: u64,
+    },
+}
+
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(test, derive(Deserialize))]
+struct InfraEditListResponse {
+    #[serde(flatten)]
+    stats: PaginationStats,
+    results: Vec<InfraEdit>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQueryParams {
+    /// The number of edits to undo or redo
+    #[serde(default = "default_count")]
+    #[param(minimum = 1, default = 1)]
+    count: u64,
+}
+
+fn default_count() -> u64 {
+    1
+}
+
+impl HistoryQueryParams {
+    /// The number of edits to undo or redo, which can't be null
+    fn validate(self) -> Result<u64> {
+        if self.count == 0 {
+            return Err(HistoryError::NullCount.into());
+        }
+        Ok(self.count)
+    }
+}
+
+/// List the edits applied to an infra, the latest first
+///
+/// Each edit holds the batch of operations applied by an edition request, along with the
+/// operations reverting it.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, PaginationQueryParam),
+    responses(
+        (status = 200, description = "The edits of the infra, paginated", body = inline(InfraEditListResponse)),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[get("")]
+async fn list(
+    infra: Path<InfraIdParam>,
+    pagination_params: Query<PaginationQueryParam>,
+    db_pool: Data<DbConnectionPool>,
+) -> Result<Json<InfraEditListResponse>> {
+    let infra_id = infra.infra_id;
+    let conn = &mut db_pool.get().await?;
+    Infra::retrieve_or_fail(conn, infra_id, || InfraApiError::NotFound { infra_id }).await?;
+
+    let settings = pagination_params
+        .validate(1000)?
+        .warn_page_size(100)
+        .into_selection_settings()
+        .filter(move || InfraEdit::INFRA_ID.eq(infra_id))
+        .order_by(|| InfraEdit::ID.desc());
+    let (results, stats) = InfraEdit::list_paginated(conn, settings).await?;
+    Ok(Json(InfraEditListResponse { stats, results }))
+}
+
+/// Revert the last edits applied to an infra
+///
+/// The reverted edits can be redone as long as no new edit is applied to the infra.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, HistoryQueryParams),
+    responses(
+        (status = 200, description = "The reverted edits, the latest first", body = Vec<InfraEdit>),
+        (status = 400, description = "The count is null or there are not enough edits to undo"),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[post("/undo")]
+async fn undo(
+    infra: Path<InfraIdParam>,
+    params: Query<HistoryQueryParams>,
+    db_pool: Data<DbConnectionPool>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+) -> Result<Json<Vec<InfraEdit>>> {
+    let infra_id = infra.infra_id;
+    let count = params.into_inner().validate()?;
+    let mut conn = db_pool.get().await?;
+    let mut infra =
+        Infra::retrieve_or_fail(&mut conn, infra_id, || InfraApiError::NotFound { infra_id })
+            .await?;
+    let mut infra_cache = InfraCache::get_or_load_mut(&mut conn, &infra_caches, &infra).await?;
+
+    let mut edits = InfraEdit::list_last_applied(&mut conn, infra_id, count).await?;
+    if (edits.len() as u64) < count {
+        return Err(HistoryError::NotEnoughEditsToUndo {
+            infra_id,
+            count,
+            available: edits.len() as u64,
+        }
+        .into());
+    }
+    let operations: Vec<Operation> = edits
+        .iter()
+        .flat_map(|edit| edit.inverse_operations.clone())
+        .collect();
+
+    let mut invalidation_zone = InvalidationZone::compute(&infra_cache, &operations);
+    conn.build_transaction()
+        .run(|conn| {
+            Box::pin(async {
+                apply_operations(conn, &mut infra, &operations, &mut infra_cache).await?;
+                for edit in edits.iter_mut() {
+                    edit.patch().undone(true).apply(conn).await?;
+                }
+                Ok::<_, InternalError>(())
+            })
+        })
+        .await?;
+    invalidation_zone.union(&InvalidationZone::compute(&infra_cache, &operations));
+
+    let mut redis_conn = redis_client.get_connection().await?;
+    map::invalidate_zone(
+        &mut redis_conn,
+        &map_layers,
+        infra_id,
+        &invalidation_zone,
+        &map_layers_config,
+    )
+    .await?;
+
+    Ok(Json(edits))
+}
+
+/// Apply again the last reverted edits of an infra
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, HistoryQueryParams),
+    responses(
+        (status = 200, description = "The applied edits, the earliest first", body = Vec<InfraEdit>),
+        (status = 400, description = "The count is null or there are not enough edits to redo"),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[post("/redo")]
+async fn redo(
+    infra: Path<InfraIdParam>,
+    params: Query<HistoryQueryParams>,
+    db_pool: Data<DbConnectionPool>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+) -> Result<Json<Vec<InfraEdit>>> {
+    let infra_id = infra.infra_id;
+    let count = params.into_inner().validate()?;
+    let mut conn = db_pool.get().await?;
+    let mut infra =
+        Infra::retrieve_or_fail(&mut conn, infra_id, || InfraApiError::NotFound { infra_id })
+            .await?;
+    let mut infra_cache = InfraCache::get_or_load_mut(&mut conn, &infra_caches, &infra).await?;
+
+    let mut edits = InfraEdit::list_first_undone(&mut conn, infra_id, count).await?;
+    if (edits.len() as u64) < count {
+        return Err(HistoryError::NotEnoughEditsToRedo {
+            infra_id,
+            count,
+            available: edits.len() as u64,
+        }
+        .into());
+    }
+    let operations: Vec<Operation> = edits
+        .iter()
+        .flat_map(|edit| edit.operations.clone())
+        .collect();
+
+    let mut invalidation_zone = InvalidationZone::compute(&infra_cache, &operations);
+    conn.build_transaction()
+        .run(|conn| {
+            Box::pin(async {
+                apply_operations(conn, &mut infra, &operations, &mut infra_cache).await?;
+                for edit in edits.iter_mut() {
+                    edit.patch()
+                        .undone(false)
+                        .infra_version(infra.version.clone())
+                        .apply(conn)
+                        .await?;
+                }
+                Ok::<_, InternalError>(())
+            })
+        })
+        .await?;
+    invalidation_zone.union(&InvalidationZone::compute(&infra_cache, &operations));
+
+    let mut redis_conn = redis_client.get_connection().await?;
+    map::invalidate_zone(
+        &mut redis_conn,
+        &map_layers,
+        infra_id,
+        &invalidation_zone,
+        &map_layers_config,
+    )
+    .await?;
+
+    Ok(Json(edits))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_web::http::StatusCode;
+    use actix_web::test::call_and_read_body_json;
+    use actix_web::test::call_service;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::infra::TrackSection;
+    use editoast_schemas::primitives::ObjectType;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::InfraEditListResponse;
+    use crate::fixtures::tests::db_pool;
+    use crate::fixtures::tests::empty_infra;
+    use crate::infra_cache::operation::Operation;
+    use crate::modelsv2::InfraEdit;
+    use crate::views::infra::diff::get_objects;
+    use crate::views::tests::create_test_service;
+
+    fn create_track_section(id: &str, length: f64) -> Operation {
+        let track_section = TrackSection {
+            id: id.into(),
+            length,
+            ..Default::default()
+        };
+        Operation::Create(Box::new(track_section.into()))
+    }
+
+    fn update_track_section_length(id: &str, length: f64) -> Operation {
+        serde_json::from_value(json!({
+            "operation_type": "UPDATE",
+            "obj_type": "TrackSection",
+            "obj_id": id,
+            "railjson_patch": [{ "op": "replace", "path": "/length", "value": length }],
+        }))
+        .unwrap()
+    }
+
+    async fn get_track_section_lengths(infra_id: i64) -> Vec<f64> {
+        let conn = &mut db_pool().get().await.unwrap();
+        get_objects(conn, infra_id, ObjectType::TrackSection)
+            .await
+            .unwrap()
+            .values()
+            .map(|track_section| track_section["length"].as_f64().unwrap())
+            .collect()
+    }
+
+    fn edit_request(infra_id: i64, operations: Vec<Operation>) -> Request {
+        TestRequest::post()
+            .uri(format!("/infra/{infra_id}/").as_str())
+            .set_json(operations)
+            .to_request()
+    }
+
+    fn history_request(infra_id: i64, action: &str, count: u64) -> Request {
+        TestRequest::post()
+            .uri(format!("/infra/{infra_id}/history/{action}?count={count}").as_str())
+            .to_request()
+    }
+
+    #[rstest]
+    async fn edits_are_listed_with_their_inverse() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let operations = vec![create_track_section("T1", 100.)];
+        let response = call_service(&app, edit_request(infra.id, operations.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let update = vec![update_track_section_length("T1", 200.)];
+        let response = call_service(&app, edit_request(infra.id, update.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::get()
+            .uri(format!("/infra/{}/history/", infra.id).as_str())
+            .to_request();
+        let history: InfraEditListResponse = call_and_read_body_json(&app, request).await;
+
+        let edits: Vec<_> = history
+            .results
+            .into_iter()
+            .map(|edit| (edit.operations, edit.inverse_operations, edit.undone))
+            .collect();
+        assert_eq!(
+            edits,
+            vec![
+                (update, vec![update_track_section_length("T1", 100.)], false),
+                (
+                    operations,
+                    vec![serde_json::from_value(json!({
+                        "operation_type": "DELETE",
+                        "obj_type": "TrackSection",
+                        "obj_id": "T1",
+                    }))
+                    .unwrap()],
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn edits_are_listed_with_their_author() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/", infra.id).as_str())
+            .insert_header(("x-remote-user", "provider/user"))
+            .set_json(vec![create_track_section("T1", 100.)])
+            .to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let operations = vec![update_track_section_length("T1", 200.)];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::get()
+            .uri(format!("/infra/{}/history/", infra.id).as_str())
+            .to_request();
+        let history: InfraEditListResponse = call_and_read_body_json(&app, request).await;
+
+        let authors: Vec<_> = history
+            .results
+            .into_iter()
+            .map(|edit| edit.author)
+            .collect();
+        assert_eq!(authors, vec![None, Some("provider/user".to_string())]);
+    }
+
+    #[rstest]
+    async fn undo_and_redo_edits() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        for operation in [
+            create_track_section("T1", 100.),
+            update_track_section_length("T1", 200.),
+            update_track_section_length("T1", 300.),
+        ] {
+            let response = call_service(&app, edit_request(infra.id, vec![operation])).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let undone: Vec<InfraEdit> =
+            call_and_read_body_json(&app, history_request(infra.id, "undo", 2)).await;
+        assert_eq!(undone.len(), 2);
+        assert!(undone.iter().all(|edit| edit.undone));
+        assert_eq!(get_track_section_lengths(infra.id).await, vec![100.]);
+
+        let redone: Vec<InfraEdit> =
+            call_and_read_body_json(&app, history_request(infra.id, "redo", 1)).await;
+        assert_eq!(redone.len(), 1);
+        assert!(!redone[0].undone);
+        assert_eq!(get_track_section_lengths(infra.id).await, vec![200.]);
+
+        let undone: Vec<InfraEdit> =
+            call_and_read_body_json(&app, history_request(infra.id, "undo", 2)).await;
+        assert_eq!(undone.len(), 2);
+        assert!(get_track_section_lengths(infra.id).await.is_empty());
+    }
+
+    #[rstest]
+    #[case::undo("undo")]
+    #[case::redo("redo")]
+    async fn null_count_is_rejected(#[case] action: &str) {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let operations = vec![create_track_section("T1", 100.)];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call_service(&app, history_request(infra.id, "undo", 1)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call_service(&app, history_request(infra.id, action, 0)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(get_track_section_lengths(infra.id).await.is_empty());
+    }
+
+    #[rstest]
+    async fn new_edit_discards_undone_edits() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let operations = vec![create_track_section("T1", 100.)];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call_service(&app, history_request(infra.id, "undo", 1)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let operations = vec![create_track_section("T2", 100.)];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call_service(&app, history_request(infra.id, "redo", 1)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    async fn undo_more_edits_than_applied() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let operations = vec![create_track_section("T1", 100.)];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call_service(&app, history_request(infra.id, "undo", 2)).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(get_track_section_lengths(infra.id).await, vec![100.]);
+    }
+}
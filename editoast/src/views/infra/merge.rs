@@ -0,0 +1,590 @@
+use std::collections::HashSet;
+
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use chashmap::CHashMap;
+use editoast_schemas::primitives::ObjectRef;
+use editoast_schemas::primitives::ObjectType;
+use serde::Deserialize;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::diff::build_infra_object;
+use super::diff::get_objects;
+use super::edition::apply_edit;
+use super::InfraApiError;
+use super::InfraIdParam;
+use crate::client::MapLayersConfig;
+use crate::error::Result;
+use crate::generated_data;
+use crate::generated_data::infra_error::InfraError;
+use crate::generated_data::infra_error::InfraErrorType;
+use crate::infra_cache::operation::CacheOperation;
+use crate::infra_cache::operation::DeleteOperation;
+use crate::infra_cache::operation::Operation;
+use crate::infra_cache::operation::UpdateOperation;
+use crate::infra_cache::InfraCache;
+use crate::infra_cache::ObjectCache;
+use crate::map;
+use crate::map::InvalidationZone;
+use crate::map::MapLayers;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+use crate::views::params::RemoteUser;
+use crate::RedisClient;
+
+crate::routes! {
+    merge,
+}
+
+editoast_common::schemas! {
+    MergeRequest,
+    MergeReport,
+    MergeConflict,
+    MergeConflictType,
+}
+
+/// Infras from which the changes to merge are computed
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct MergeRequest {
+    /// The infra the source infra was cloned from
+    base_infra_id: i64,
+    /// The infra holding the changes to merge
+    source_infra_id: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MergeQueryParams {
+    /// Only compute the merge report, without editing the infra
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Result of a merge
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+struct MergeReport {
+    /// Operations applied to the infra, or that would be applied in dry run mode
+    operations: Vec<Operation>,
+    /// Changes that were not applied
+    conflicts: Vec<MergeConflict>,
+}
+
+/// A change that can't be merged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+struct MergeConflict {
+    obj_type: ObjectType,
+    obj_id: String,
+    #[serde(flatten)]
+    conflict_type: MergeConflictType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "conflict_type", rename_all = "snake_case")]
+enum MergeConflictType {
+    /// The object was modified differently in the source infra and in the merged infra
+    ModifiedOnBothSides,
+    /// The object was deleted in the source infra and modified in the merged infra
+    DeletedInSource,
+    /// The object was modified in the source infra and deleted in the merged infra
+    DeletedInTarget,
+    /// The change would break a reference between the object and another one
+    DanglingReference {
+        /// The other end of the broken reference
+        reference: ObjectRef,
+    },
+}
+
+/// A change to merge with its effect on the infra cache
+struct Change {
+    operation: Operation,
+    cache_operation: CacheOperation,
+}
+
+impl Change {
+    fn get_ref(&self) -> ObjectRef {
+        self.operation.get_ref()
+    }
+}
+
+/// Compute the changes made between the base and the source infra, and split them between the
+/// ones applicable to the target infra and the conflicting ones
+async fn compute_changes(
+    conn: &mut DbConnection,
+    base_infra_id: i64,
+    source_infra_id: i64,
+    target_infra_id: i64,
+) -> Result<(Vec<Change>, Vec<MergeConflict>)> {
+    let mut changes = vec![];
+    let mut conflicts = vec![];
+    for obj_type in ObjectType::iter() {
+        let mut base_objects = get_objects(conn, base_infra_id, obj_type).await?;
+        let mut source_objects = get_objects(conn, source_infra_id, obj_type).await?;
+        let mut target_objects = get_objects(conn, target_infra_id, obj_type).await?;
+
+        let obj_ids: HashSet<_> = base_objects
+            .keys()
+            .chain(source_objects.keys())
+            .cloned()
+            .collect();
+        let mut obj_ids: Vec<_> = obj_ids.into_iter().collect();
+        obj_ids.sort();
+        for obj_id in obj_ids {
+            let base = base_objects.remove(&obj_id);
+            let source = source_objects.remove(&obj_id);
+            let target = target_objects.remove(&obj_id);
+            if base == source || source == target {
+                // Nothing to merge
+                continue;
+            }
+            if base != target {
+                let conflict_type = match (source, target) {
+                    (None, _) => MergeConflictType::DeletedInSource,
+                    (_, None) => MergeConflictType::DeletedInTarget,
+                    _ => MergeConflictType::ModifiedOnBothSides,
+                };
+                conflicts.push(MergeConflict {
+                    obj_type,
+                    obj_id,
+                    conflict_type,
+                });
+                continue;
+            }
+            let change = match (target, source) {
+                (None, Some(source)) => {
+                    let railjson = build_infra_object(obj_type, source)?;
+                    Change {
+                        cache_operation: CacheOperation::Create(ObjectCache::from(
+                            railjson.clone(),
+                        )),
+                        operation: Operation::Create(Box::new(railjson)),
+                    }
+                }
+                (Some(target), Some(source)) => Change {
+                    operation: Operation::Update(UpdateOperation {
+                        obj_id,
+                        obj_type,
+                        railjson_patch: json_patch::diff(&target, &source),
+                    }),
+                    cache_operation: CacheOperation::Update(ObjectCache::from(build_infra_object(
+                        obj_type, source,
+                    )?)),
+                },
+                (Some(_), None) => Change {
+                    operation: Operation::Delete(DeleteOperation {
+                        obj_id: obj_id.clone(),
+                        obj_type,
+                    }),
+                    cache_operation: CacheOperation::Delete(ObjectRef::new(obj_type, obj_id)),
+                },
+                (None, None) => unreachable!("source and target are equal"),
+            };
+            changes.push(change);
+        }
+    }
+    Ok((changes, conflicts))
+}
+
+/// List the references to missing objects, as (referencing object, missing object)
+fn invalid_references(errors: Vec<InfraError>) -> impl Iterator<Item = (ObjectRef, ObjectRef)> {
+    errors.into_iter().filter_map(|error| match error.sub_type {
+        InfraErrorType::InvalidReference { reference } => {
+            Some((ObjectRef::new(error.obj_type, error.obj_id), reference))
+        }
+        _ => None,
+    })
+}
+
+/// Remove the changes which would introduce references to missing objects
+///
+/// A change creating or updating an object referencing a missing object is removed.
+/// Otherwise, the deletion of the referenced object is removed.
+/// The changes referencing an object whose creation is removed are removed as well.
+async fn remove_dangling_changes(
+    infra_cache: &InfraCache,
+    changes: &mut Vec<Change>,
+) -> Result<Vec<MergeConflict>> {
+    let initial_references: HashSet<_> =
+        invalid_references(generated_data::generate_infra_errors(infra_cache).await).collect();
+    let mut merged_cache = infra_cache.clone();
+    let cache_operations: Vec<_> = changes
+        .iter()
+        .map(|change| change.cache_operation.clone())
+        .collect();
+    merged_cache.apply_operations(&cache_operations)?;
+    let dangling_references: Vec<_> =
+        invalid_references(generated_data::generate_infra_errors(&merged_cache).await)
+            .filter(|reference| !initial_references.contains(reference))
+            .collect();
+
+    // The references of the created and updated objects to the created objects, which break when
+    // the creation is removed
+    let created: HashSet<_> = changes
+        .iter()
+        .filter(|change| matches!(change.operation, Operation::Create(_)))
+        .map(Change::get_ref)
+        .collect();
+    merged_cache.apply_operations(
+        &created
+            .iter()
+            .cloned()
+            .map(CacheOperation::Delete)
+            .collect::<Vec<_>>(),
+    )?;
+    let edited_objects = cache_operations
+        .iter()
+        .filter_map(|operation| match operation {
+            CacheOperation::Create(object) | CacheOperation::Update(object) => Some(object),
+            CacheOperation::Delete(_) => None,
+        });
+    let dependencies: Vec<_> = invalid_references(generated_data::generate_objects_errors(
+        edited_objects,
+        &merged_cache,
+    ))
+    .filter(|(_, referenced)| created.contains(referenced))
+    .collect();
+
+    let mut conflicts = vec![];
+    let mut removed_creations = vec![];
+    for (referencing, referenced) in dangling_references {
+        let (obj_ref, reference) =
+            if let Some(index) = changes.iter().position(|c| c.get_ref() == referencing) {
+                changes.remove(index);
+                (referencing, referenced)
+            } else if let Some(index) = changes.iter().position(|c| c.get_ref() == referenced) {
+                changes.remove(index);
+                (referenced, referencing)
+            } else {
+                continue;
+            };
+        if created.contains(&obj_ref) {
+            removed_creations.push(obj_ref.clone());
+        }
+        conflicts.push(MergeConflict {
+            obj_type: obj_ref.obj_type,
+            obj_id: obj_ref.obj_id,
+            conflict_type: MergeConflictType::DanglingReference { reference },
+        });
+    }
+    while let Some(missing) = removed_creations.pop() {
+        for (referencing, _) in dependencies
+            .iter()
+            .filter(|(_, referenced)| referenced == &missing)
+        {
+            let Some(index) = changes.iter().position(|c| &c.get_ref() == referencing) else {
+                continue;
+            };
+            changes.remove(index);
+            if created.contains(referencing) {
+                removed_creations.push(referencing.clone());
+            }
+            conflicts.push(MergeConflict {
+                obj_type: referencing.obj_type,
+                obj_id: referencing.obj_id.clone(),
+                conflict_type: MergeConflictType::DanglingReference {
+                    reference: missing.clone(),
+                },
+            });
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Merge the changes made on an infra into another one
+///
+/// The changes are the differences between a base infra and a source infra, usually a clone of
+/// the base infra. They are applied to the merged infra unless they conflict with changes made on
+/// the merged infra itself, or would leave references to missing objects. Conflicting changes are
+/// not applied and are listed in the report.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, MergeQueryParams),
+    request_body = MergeRequest,
+    responses(
+        (status = 200, body = MergeReport, description = "The applied operations and the conflicts"),
+        (status = 404, description = "Infra ID not found"),
+    )
+)]
+#[post("/merge")]
+#[allow(clippy::too_many_arguments)]
+async fn merge(
+    infra: Path<InfraIdParam>,
+    Query(MergeQueryParams { dry_run }): Query<MergeQueryParams>,
+    request: Json<MergeRequest>,
+    db_pool: Data<DbConnectionPoolV2>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+    RemoteUser(author): RemoteUser,
+) -> Result<Json<MergeReport>> {
+    let infra_id = infra.infra_id;
+    let MergeRequest {
+        base_infra_id,
+        source_infra_id,
+    } = request.into_inner();
+    let mut conn = db_pool.get().await?;
+    let mut infra =
+        Infra::retrieve_or_fail(&mut conn, infra_id, || InfraApiError::NotFound { infra_id })
+            .await?;
+    for other_infra_id in [base_infra_id, source_infra_id] {
+        Infra::retrieve_or_fail(&mut conn, other_infra_id, || InfraApiError::NotFound {
+            infra_id: other_infra_id,
+        })
+        .await?;
+    }
+
+    let (mut changes, mut conflicts) =
+        compute_changes(&mut conn, base_infra_id, source_infra_id, infra_id).await?;
+    let infra_cache = InfraCache::get_or_load(&mut conn, &infra_caches, &infra)
+        .await?
+        .clone();
+    conflicts.extend(remove_dangling_changes(&infra_cache, &mut changes).await?);
+    let operations: Vec<_> = changes.into_iter().map(|change| change.operation).collect();
+
+    if !dry_run && !operations.is_empty() {
+        let mut infra_cache = InfraCache::get_or_load_mut(&mut conn, &infra_caches, &infra).await?;
+        let mut invalidation_zone = InvalidationZone::compute(&infra_cache, &operations);
+        apply_edit(&mut conn, &mut infra, &operations, &mut infra_cache, author).await?;
+        invalidation_zone.union(&InvalidationZone::compute(&infra_cache, &operations));
+
+        let mut conn = redis_client.get_connection().await?;
+        map::invalidate_zone(
+            &mut conn,
+            &map_layers,
+            infra_id,
+            &invalidation_zone,
+            &map_layers_config,
+        )
+        .await?;
+    }
+
+    Ok(Json(MergeReport {
+        operations,
+        conflicts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::call_and_read_body_json;
+    use actix_web::test::call_service;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::infra::Detector;
+    use editoast_schemas::infra::InfraObject;
+    use editoast_schemas::infra::Route;
+    use editoast_schemas::infra::TrackSection;
+    use editoast_schemas::infra::Waypoint;
+    use editoast_schemas::primitives::ObjectRef;
+    use editoast_schemas::primitives::ObjectType;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::MergeConflict;
+    use super::MergeConflictType;
+    use super::MergeReport;
+    use crate::fixtures::tests::db_pool;
+    use crate::fixtures::tests::empty_infra;
+    use crate::fixtures::tests::TestFixture;
+    use crate::infra_cache::operation::create::apply_create_operation;
+    use crate::modelsv2::Infra;
+    use crate::views::tests::create_test_service;
+
+    fn track_section(id: &str, length: f64) -> InfraObject {
+        TrackSection {
+            id: id.into(),
+            length,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    fn detector(id: &str, track: &str) -> InfraObject {
+        Detector {
+            id: id.into(),
+            track: track.into(),
+            position: 10.,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    fn route(id: &str, entry_detector: &str, exit_detector: &str) -> InfraObject {
+        Route {
+            id: id.into(),
+            entry_point: Waypoint::new_detector(entry_detector),
+            exit_point: Waypoint::new_detector(exit_detector),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    async fn create_objects(infra: &TestFixture<Infra>, objects: &[InfraObject]) {
+        let conn = &mut db_pool().get().await.unwrap();
+        for object in objects {
+            apply_create_operation(object, infra.id, conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[rstest]
+    async fn merge_dry_run_reports_conflicts() {
+        let app = create_test_service().await;
+        let base_infra = empty_infra(db_pool()).await;
+        let source_infra = empty_infra(db_pool()).await;
+        let target_infra = empty_infra(db_pool()).await;
+        let base_objects = [
+            track_section("T1", 100.),
+            track_section("T2", 100.),
+            track_section("T3", 100.),
+        ];
+        create_objects(&base_infra, &base_objects).await;
+        // The source adds a detector on T1, modifies T2 and adds a detector on T3
+        create_objects(
+            &source_infra,
+            &[
+                track_section("T1", 100.),
+                track_section("T2", 200.),
+                track_section("T3", 100.),
+                detector("D1", "T1"),
+                detector("D3", "T3"),
+            ],
+        )
+        .await;
+        // The target modifies T2 too and deletes T3
+        create_objects(
+            &target_infra,
+            &[track_section("T1", 100.), track_section("T2", 300.)],
+        )
+        .await;
+
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/merge?dry_run=true", target_infra.id).as_str())
+            .set_json(json!({
+                "base_infra_id": base_infra.id,
+                "source_infra_id": source_infra.id,
+            }))
+            .to_request();
+        let report: MergeReport = call_and_read_body_json(&app, request).await;
+
+        let operations: Vec<_> = report.operations.iter().map(|op| op.get_ref()).collect();
+        assert_eq!(operations, vec![ObjectRef::new(ObjectType::Detector, "D1")]);
+        assert_eq!(
+            report.conflicts,
+            vec![
+                MergeConflict {
+                    obj_type: ObjectType::TrackSection,
+                    obj_id: "T2".into(),
+                    conflict_type: MergeConflictType::ModifiedOnBothSides,
+                },
+                MergeConflict {
+                    obj_type: ObjectType::Detector,
+                    obj_id: "D3".into(),
+                    conflict_type: MergeConflictType::DanglingReference {
+                        reference: ObjectRef::new(ObjectType::TrackSection, "T3"),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn merge_removes_changes_depending_on_dangling_ones() {
+        let app = create_test_service().await;
+        let base_infra = empty_infra(db_pool()).await;
+        let source_infra = empty_infra(db_pool()).await;
+        let target_infra = empty_infra(db_pool()).await;
+        create_objects(
+            &base_infra,
+            &[track_section("T1", 100.), track_section("T3", 100.)],
+        )
+        .await;
+        // The source adds a route ending on a detector of T3
+        create_objects(
+            &source_infra,
+            &[
+                track_section("T1", 100.),
+                track_section("T3", 100.),
+                detector("D1", "T1"),
+                detector("D3", "T3"),
+                route("R1", "D1", "D3"),
+            ],
+        )
+        .await;
+        // The target deletes T3
+        create_objects(&target_infra, &[track_section("T1", 100.)]).await;
+
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/merge?dry_run=true", target_infra.id).as_str())
+            .set_json(json!({
+                "base_infra_id": base_infra.id,
+                "source_infra_id": source_infra.id,
+            }))
+            .to_request();
+        let report: MergeReport = call_and_read_body_json(&app, request).await;
+
+        let operations: Vec<_> = report.operations.iter().map(|op| op.get_ref()).collect();
+        assert_eq!(operations, vec![ObjectRef::new(ObjectType::Detector, "D1")]);
+        assert_eq!(
+            report.conflicts,
+            vec![
+                MergeConflict {
+                    obj_type: ObjectType::Detector,
+                    obj_id: "D3".into(),
+                    conflict_type: MergeConflictType::DanglingReference {
+                        reference: ObjectRef::new(ObjectType::TrackSection, "T3"),
+                    },
+                },
+                MergeConflict {
+                    obj_type: ObjectType::Route,
+                    obj_id: "R1".into(),
+                    conflict_type: MergeConflictType::DanglingReference {
+                        reference: ObjectRef::new(ObjectType::Detector, "D3"),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn merge_applies_changes() {
+        let app = create_test_service().await;
+        let base_infra = empty_infra(db_pool()).await;
+        let source_infra = empty_infra(db_pool()).await;
+        let target_infra = empty_infra(db_pool()).await;
+        create_objects(&base_infra, &[track_section("T1", 100.)]).await;
+        create_objects(
+            &source_infra,
+            &[track_section("T1", 100.), detector("D1", "T1")],
+        )
+        .await;
+        create_objects(&target_infra, &[track_section("T1", 100.)]).await;
+
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/merge", target_infra.id).as_str())
+            .set_json(json!({
+                "base_infra_id": base_infra.id,
+                "source_infra_id": source_infra.id,
+            }))
+            .to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::get()
+            .uri(format!("/infra/{}/diff/{}", source_infra.id, target_infra.id).as_str())
+            .to_request();
+        let diff: serde_json::Value = call_and_read_body_json(&app, request).await;
+        assert_eq!(diff, json!({}));
+    }
+}
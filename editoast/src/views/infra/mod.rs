@@ -1,12 +1,16 @@
 mod attached;
 mod auto_fixes;
+mod diff;
 mod edition;
 mod errors;
+mod history;
 mod lines;
+mod merge;
 mod objects;
 mod pathfinding;
 mod railjson;
 mod routes;
+mod snapshots;
 
 use actix_web::delete;
 use actix_web::get;
@@ -26,11 +30,13 @@ use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::Arc;
 use thiserror::Error;
+use tracing::error;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 
 use super::pagination::PaginationStats;
 use super::params::List;
+use crate::client::MapLayersConfig;
 use crate::core::infra_loading::InfraLoadRequest;
 use crate::core::infra_state::InfraStateRequest;
 use crate::core::infra_state::InfraStateResponse;
@@ -45,6 +51,8 @@ use crate::modelsv2::prelude::*;
 use crate::modelsv2::DbConnectionPool;
 use crate::modelsv2::DbConnectionPoolV2;
 use crate::modelsv2::Infra;
+use crate::views::layers::tile_warming::infra_bbox;
+use crate::views::layers::tile_warming::warm_tiles as warm_infra_tiles;
 use crate::views::pagination::PaginatedList as _;
 use crate::views::pagination::PaginationQueryParam;
 use crate::RedisClient;
@@ -62,11 +70,15 @@ crate::routes! {
                 objects::routes(),
                 routes::routes(),
                 lines::routes(),
+                merge::routes(),
                 auto_fixes::routes(),
+                diff::routes(),
                 pathfinding::routes(),
                 attached::routes(),
                 edition::routes(),
                 errors::routes(),
+                history::routes(),
+                snapshots::routes(),
             ),
             get,
             load,
@@ -83,7 +95,10 @@ crate::routes! {
 }
 
 editoast_common::schemas! {
+    diff::schemas(),
+    merge::schemas(),
     pathfinding::schemas(),
+    snapshots::schemas(),
     InfraState,
     InfraWithState,
 }
@@ -107,6 +122,9 @@ struct RefreshQueryParams {
     #[serde(default)]
     #[param(value_type = Vec<u64>)]
     infras: List<i64>,
+    /// Whether the map layer tiles of the refreshed infras should be generated and cached in the background
+    #[serde(default)]
+    warm_tiles: bool,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -126,17 +144,19 @@ struct RefreshResponse {
 )]
 #[post("/refresh")]
 async fn refresh(
-    db_pool: Data<DbConnectionPool>,
+    db_pool: Data<DbConnectionPoolV2>,
     redis_client: Data<RedisClient>,
     Query(query_params): Query<RefreshQueryParams>,
     infra_caches: Data<CHashMap<i64, InfraCache>>,
     map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
 ) -> Result<Json<RefreshResponse>> {
     let mut conn = db_pool.get().await?;
     // Use a transaction to give scope to infra list lock
     let RefreshQueryParams {
         force,
         infras: List(infras),
+        warm_tiles,
     } = query_params;
 
     let infras_list = if infras.is_empty() {
@@ -151,13 +171,17 @@ async fn refresh(
     };
 
     // Refresh each infras
-    let db_pool = db_pool.into_inner();
     let mut infra_refreshed = vec![];
+    let mut infra_bboxes = vec![];
 
     for mut infra in infras_list {
         let infra_cache = InfraCache::get_or_load(&mut conn, &infra_caches, &infra).await?;
-        if infra.refresh(db_pool.clone(), force, &infra_cache).await? {
+        if infra
+            .refresh(db_pool.pool_v1(), force, &infra_cache)
+            .await?
+        {
             infra_refreshed.push(infra.id);
+            infra_bboxes.push((infra.id, infra_bbox(&infra_cache)));
         }
     }
 
@@ -171,6 +195,30 @@ async fn refresh(
         .await?;
     }
 
+    if warm_tiles {
+        // Tiles are generated in the background since it may take a while on large infras
+        let redis_client = redis_client.into_inner();
+        let map_layers = map_layers.into_inner();
+        let db_pool = db_pool.into_inner();
+        tokio::spawn(async move {
+            for (infra_id, bbox) in infra_bboxes {
+                if let Err(error) = warm_infra_tiles(
+                    &db_pool,
+                    &redis_client,
+                    &map_layers,
+                    infra_id,
+                    &bbox,
+                    &map_layers_config.tile_warming_config,
+                    |_, _| {},
+                )
+                .await
+                {
+                    error!("Failed to warm up the map tiles of infra {infra_id}: {error}");
+                }
+            }
+        });
+    }
+
     Ok(Json(RefreshResponse { infra_refreshed }))
 }
 
@@ -0,0 +1,421 @@
+use actix_web::delete;
+use actix_web::get;
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use actix_web::HttpResponse;
+use chashmap::CHashMap;
+use editoast_derive::EditoastError;
+use serde::Deserialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::InfraApiError;
+use super::InfraIdParam;
+use crate::error::Result;
+use crate::infra_cache::InfraCache;
+use crate::map;
+use crate::map::MapLayers;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPool;
+use crate::modelsv2::Infra;
+use crate::modelsv2::InfraSnapshot;
+use crate::RedisClient;
+
+crate::routes! {
+    "/snapshots" => {
+        list,
+        create,
+        "/{snapshot_id}" => {
+            delete,
+            restore,
+            clone,
+        },
+    },
+}
+
+editoast_common::schemas! {
+    InfraSnapshotForm,
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "infra:snapshots")]
+enum SnapshotError {
+    #[error("Snapshot '{snapshot_id}' of infra '{infra_id}' could not be found")]
+    #[editoast_error(status = 404)]
+    NotFound { infra_id: i64, snapshot_id: i64 },
+    #[error("Infra '{infra_id}' already has a snapshot named '{name}'")]
+    #[editoast_error(status = 400)]
+    NameAlreadyUsed { infra_id: i64, name: String },
+    #[error("Infra {infra_id} is locked")]
+    #[editoast_error(status = 400)]
+    InfraIsLocked { infra_id: i64 },
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct SnapshotIdParam {
+    /// An existing snapshot ID
+    snapshot_id: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct InfraSnapshotForm {
+    /// The name of the snapshot, unique among the snapshots of the infra
+    name: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CloneSnapshotQuery {
+    /// The name of the new infra
+    name: String,
+}
+
+async fn retrieve_snapshot(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    snapshot_id: i64,
+) -> Result<InfraSnapshot> {
+    let not_found = || SnapshotError::NotFound {
+        infra_id,
+        snapshot_id,
+    };
+    let snapshot = InfraSnapshot::retrieve_or_fail(conn, snapshot_id, not_found).await?;
+    if snapshot.infra_id != infra_id {
+        return Err(not_found().into());
+    }
+    Ok(snapshot)
+}
+
+/// List the snapshots of an infra, the oldest first
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam),
+    responses(
+        (status = 200, description = "The snapshots of the infra", body = Vec<InfraSnapshot>),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[get("")]
+async fn list(
+    infra: Path<InfraIdParam>,
+    db_pool: Data<DbConnectionPool>,
+) -> Result<Json<Vec<InfraSnapshot>>> {
+    let infra_id = infra.infra_id;
+    let conn = &mut db_pool.get().await?;
+    Infra::retrieve_or_fail(conn, infra_id, || InfraApiError::NotFound { infra_id }).await?;
+
+    let settings = SelectionSettings::new()
+        .filter(move || InfraSnapshot::INFRA_ID.eq(infra_id))
+        .order_by(|| InfraSnapshot::ID.asc());
+    Ok(Json(InfraSnapshot::list(conn, settings).await?))
+}
+
+/// Tag the current state of an infra as a named snapshot
+///
+/// The snapshot holds a copy of the railjson objects of the infra, which can be restored later.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam),
+    request_body = InfraSnapshotForm,
+    responses(
+        (status = 201, description = "The created snapshot", body = InfraSnapshot),
+        (status = 400, description = "The name is already used by another snapshot of the infra"),
+        (status = 404, description = "Infra ID not found"),
+    ),
+)]
+#[post("")]
+async fn create(
+    infra: Path<InfraIdParam>,
+    form: Json<InfraSnapshotForm>,
+    db_pool: Data<DbConnectionPool>,
+) -> Result<HttpResponse> {
+    let infra_id = infra.infra_id;
+    let InfraSnapshotForm { name } = form.into_inner();
+    let conn = &mut db_pool.get().await?;
+    let infra =
+        Infra::retrieve_or_fail(conn, infra_id, || InfraApiError::NotFound { infra_id }).await?;
+
+    let settings = SelectionSettings::new()
+        .filter(move || InfraSnapshot::INFRA_ID.eq(infra_id))
+        .filter({
+            let name = name.clone();
+            move || InfraSnapshot::NAME.eq(name.clone())
+        });
+    if InfraSnapshot::count(conn, settings).await? > 0 {
+        return Err(SnapshotError::NameAlreadyUsed { infra_id, name }.into());
+    }
+
+    let snapshot = infra.take_snapshot(conn, name).await?;
+    Ok(HttpResponse::Created().json(snapshot))
+}
+
+/// Delete a snapshot of an infra
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, SnapshotIdParam),
+    responses(
+        (status = 204, description = "The snapshot was deleted"),
+        (status = 404, description = "Infra or snapshot ID not found"),
+    ),
+)]
+#[delete("")]
+async fn delete(
+    infra: Path<InfraIdParam>,
+    snapshot: Path<SnapshotIdParam>,
+    db_pool: Data<DbConnectionPool>,
+) -> Result<HttpResponse> {
+    let conn = &mut db_pool.get().await?;
+    let snapshot = retrieve_snapshot(conn, infra.infra_id, snapshot.snapshot_id).await?;
+    snapshot.delete(conn).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Restore an infra to the state saved in one of its snapshots
+///
+/// The objects of the infra are replaced by the ones of the snapshot, then the generated data
+/// is refreshed. The version of the infra is bumped and its edition history is cleared.
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, SnapshotIdParam),
+    responses(
+        (status = 200, description = "The restored infra", body = Infra),
+        (status = 400, description = "The infra is locked"),
+        (status = 404, description = "Infra or snapshot ID not found"),
+    ),
+)]
+#[post("/restore")]
+async fn restore(
+    infra: Path<InfraIdParam>,
+    snapshot: Path<SnapshotIdParam>,
+    db_pool: Data<DbConnectionPool>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+) -> Result<Json<Infra>> {
+    let infra_id = infra.infra_id;
+    let mut conn = db_pool.get().await?;
+    let mut infra =
+        Infra::retrieve_or_fail(&mut conn, infra_id, || InfraApiError::NotFound { infra_id })
+            .await?;
+    if infra.locked {
+        return Err(SnapshotError::InfraIsLocked { infra_id }.into());
+    }
+    let snapshot = retrieve_snapshot(&mut conn, infra_id, snapshot.snapshot_id).await?;
+
+    infra.restore_snapshot(&mut conn, &snapshot).await?;
+
+    // The cached infra doesn't match the restored objects anymore
+    infra_caches.remove(&infra_id);
+    let infra_cache = InfraCache::get_or_load(&mut conn, &infra_caches, &infra).await?;
+    infra
+        .refresh(db_pool.into_inner(), true, &infra_cache)
+        .await?;
+
+    let mut redis_conn = redis_client.get_connection().await?;
+    map::invalidate_all(
+        &mut redis_conn,
+        &map_layers.layers.keys().cloned().collect(),
+        infra_id,
+    )
+    .await?;
+
+    Ok(Json(infra))
+}
+
+/// Create a new infra from a snapshot
+#[utoipa::path(
+    tag = "infra",
+    params(InfraIdParam, SnapshotIdParam, CloneSnapshotQuery),
+    responses(
+        (status = 200, description = "The new infra ID", body = u64),
+        (status = 404, description = "Infra or snapshot ID not found"),
+    ),
+)]
+#[post("/clone")]
+async fn clone(
+    infra: Path<InfraIdParam>,
+    snapshot: Path<SnapshotIdParam>,
+    Query(CloneSnapshotQuery { name }): Query<CloneSnapshotQuery>,
+    db_pool: Data<DbConnectionPool>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+) -> Result<Json<i64>> {
+    let mut conn = db_pool.get().await?;
+    let snapshot = retrieve_snapshot(&mut conn, infra.infra_id, snapshot.snapshot_id).await?;
+
+    let mut new_infra = Infra::create_from_snapshot(&mut conn, &snapshot, name).await?;
+    let infra_cache = InfraCache::get_or_load(&mut conn, &infra_caches, &new_infra).await?;
+    new_infra
+        .refresh(db_pool.into_inner(), true, &infra_cache)
+        .await?;
+
+    Ok(Json(new_infra.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_web::http::StatusCode;
+    use actix_web::test::call_and_read_body_json;
+    use actix_web::test::call_service;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::infra::TrackSection;
+    use editoast_schemas::primitives::ObjectType;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    use crate::fixtures::tests::db_pool;
+    use crate::fixtures::tests::empty_infra;
+    use crate::infra_cache::operation::Operation;
+    use crate::modelsv2::prelude::*;
+    use crate::modelsv2::Infra;
+    use crate::modelsv2::InfraEdit;
+    use crate::modelsv2::InfraSnapshot;
+    use crate::views::infra::diff::get_objects;
+    use crate::views::tests::create_test_service;
+
+    fn create_track_section(id: &str, length: f64) -> Operation {
+        let track_section = TrackSection {
+            id: id.into(),
+            length,
+            ..Default::default()
+        };
+        Operation::Create(Box::new(track_section.into()))
+    }
+
+    fn edit_request(infra_id: i64, operations: Vec<Operation>) -> Request {
+        TestRequest::post()
+            .uri(format!("/infra/{infra_id}/").as_str())
+            .set_json(operations)
+            .to_request()
+    }
+
+    fn create_snapshot_request(infra_id: i64, name: &str) -> Request {
+        TestRequest::post()
+            .uri(format!("/infra/{infra_id}/snapshots").as_str())
+            .set_json(json!({ "name": name }))
+            .to_request()
+    }
+
+    async fn get_track_section_lengths(infra_id: i64) -> BTreeMap<String, f64> {
+        let conn = &mut db_pool().get().await.unwrap();
+        get_objects(conn, infra_id, ObjectType::TrackSection)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(obj_id, track_section)| (obj_id, track_section["length"].as_f64().unwrap()))
+            .collect()
+    }
+
+    #[rstest]
+    async fn restore_snapshot() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let response = call_service(
+            &app,
+            edit_request(infra.id, vec![create_track_section("T1", 100.)]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let snapshot: InfraSnapshot =
+            call_and_read_body_json(&app, create_snapshot_request(infra.id, "initial")).await;
+        let operations = vec![
+            serde_json::from_value(json!({
+                "operation_type": "UPDATE",
+                "obj_type": "TrackSection",
+                "obj_id": "T1",
+                "railjson_patch": [{ "op": "replace", "path": "/length", "value": 200. }],
+            }))
+            .unwrap(),
+            create_track_section("T2", 100.),
+        ];
+        let response = call_service(&app, edit_request(infra.id, operations)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/snapshots/{}/restore", infra.id, snapshot.id).as_str())
+            .to_request();
+        let restored_infra: Infra = call_and_read_body_json(&app, request).await;
+
+        assert_eq!(
+            get_track_section_lengths(infra.id).await,
+            BTreeMap::from([("T1".to_owned(), 100.)])
+        );
+        assert_eq!(
+            restored_infra.generated_version,
+            Some(restored_infra.version)
+        );
+        let conn = &mut db_pool().get().await.unwrap();
+        let edits = InfraEdit::list_last_applied(conn, infra.id, 10)
+            .await
+            .unwrap();
+        assert!(edits.is_empty());
+    }
+
+    #[rstest]
+    async fn clone_snapshot() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let response = call_service(
+            &app,
+            edit_request(infra.id, vec![create_track_section("T1", 100.)]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let snapshot: InfraSnapshot =
+            call_and_read_body_json(&app, create_snapshot_request(infra.id, "initial")).await;
+
+        let request = TestRequest::post()
+            .uri(
+                format!(
+                    "/infra/{}/snapshots/{}/clone?name=from_snapshot",
+                    infra.id, snapshot.id
+                )
+                .as_str(),
+            )
+            .to_request();
+        let new_infra_id: i64 = call_and_read_body_json(&app, request).await;
+
+        assert_eq!(
+            get_track_section_lengths(new_infra_id).await,
+            get_track_section_lengths(infra.id).await
+        );
+        let conn = &mut db_pool().get().await.unwrap();
+        assert!(Infra::delete_static(conn, new_infra_id).await.unwrap());
+    }
+
+    #[rstest]
+    async fn snapshot_name_already_used() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let response = call_service(&app, create_snapshot_request(infra.id, "initial")).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = call_service(&app, create_snapshot_request(infra.id, "initial")).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    async fn restore_snapshot_of_another_infra() {
+        let app = create_test_service().await;
+        let infra = empty_infra(db_pool()).await;
+        let other_infra = empty_infra(db_pool()).await;
+        let snapshot: InfraSnapshot =
+            call_and_read_body_json(&app, create_snapshot_request(other_infra.id, "initial")).await;
+
+        let request = TestRequest::post()
+            .uri(format!("/infra/{}/snapshots/{}/restore", infra.id, snapshot.id).as_str())
+            .to_request();
+        let response = call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,270 @@
+use std::io::Write as _;
+use std::path::Path;
+
+use editoast_schemas::primitives::BoundingBox;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use mvt::Tile as MvtTile;
+use rusqlite::params;
+use serde_json::json;
+
+use super::get_view_records;
+use super::mvt_utils::add_layer_to_mvt_tile;
+use crate::error::Result;
+use crate::map::get_tiles_in_bbox;
+use crate::map::MapLayers;
+use crate::map::Tile;
+use crate::modelsv2::DbConnection;
+
+/// Writes tiles in an MBTiles archive
+///
+/// See the [MBTiles specification](https://github.com/mapbox/mbtiles-spec/blob/master/1.3/spec.md)
+struct MbtilesWriter {
+    connection: rusqlite::Connection,
+}
+
+impl MbtilesWriter {
+    /// Creates the archive and its tables, the file must not contain an archive already
+    fn create(path: &Path) -> Result<Self> {
+        let connection = rusqlite::Connection::open(path)?;
+        connection.execute_batch(
+            "
+            CREATE TABLE metadata (name TEXT, value TEXT);
+            CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+            CREATE UNIQUE INDEX tile_index ON tiles (zoom_level, tile_column, tile_row);
+            BEGIN;
+            ",
+        )?;
+        Ok(Self { connection })
+    }
+
+    fn insert_metadata<T: AsRef<str>>(&self, name: &str, value: T) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO metadata (name, value) VALUES (?1, ?2)",
+            params![name, value.as_ref()],
+        )?;
+        Ok(())
+    }
+
+    /// Inserts an encoded MVT tile, compressing it with gzip as expected by the specification
+    fn insert_tile(&self, tile: &Tile, mvt_bytes: &[u8]) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(mvt_bytes).unwrap();
+        let tile_data = encoder.finish().unwrap();
+        // MBTiles uses the TMS scheme, where rows are numbered from the south
+        let tile_row = (1_u64 << tile.z) - 1 - tile.y;
+        self.connection
+            .prepare_cached(
+                "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![tile.z, tile.x, tile_row, tile_data])?;
+        Ok(())
+    }
+
+    /// Commits all the inserted data
+    fn finish(self) -> Result<()> {
+        self.connection.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+}
+
+/// Exports every layer view of an infra in an MBTiles archive
+///
+/// Each view is stored in the vector tiles as a layer named `<layer>.<view>`.
+/// Tiles without any feature are not stored.
+///
+/// # Arguments
+///
+/// * `conn` - Connection to the database
+/// * `map_layers` - Layers and views to export
+/// * `infra_id` - Infra to export
+/// * `infra_name` - Name given to the archive
+/// * `bbox` - Area to export
+/// * `min_zoom` - First zoom level to export
+/// * `max_zoom` - Last zoom level to export (included)
+/// * `path` - Path of the created archive
+/// * `on_progress` - Called with the number of processed tiles and the total number of tiles
+///
+/// Returns the number of stored tiles
+#[allow(clippy::too_many_arguments)]
+pub async fn export_mbtiles<F: FnMut(u64, u64)>(
+    conn: &mut DbConnection,
+    map_layers: &MapLayers,
+    infra_id: i64,
+    infra_name: &str,
+    bbox: &BoundingBox,
+    min_zoom: u64,
+    max_zoom: u64,
+    path: &Path,
+    mut on_progress: F,
+) -> Result<u64> {
+    let mut layers: Vec<_> = map_layers.layers.iter().collect();
+    layers.sort_by_key(|(layer_slug, _)| *layer_slug);
+    let views: Vec<_> = layers
+        .into_iter()
+        .flat_map(|(layer_slug, layer)| {
+            let mut views: Vec<_> = layer.views.iter().collect();
+            views.sort_by_key(|(view_slug, _)| *view_slug);
+            views
+                .into_iter()
+                .map(move |(view_slug, view)| (format!("{layer_slug}.{view_slug}"), layer, view))
+        })
+        .collect();
+
+    let writer = MbtilesWriter::create(path)?;
+    let BoundingBox((min_lon, min_lat), (max_lon, max_lat)) = *bbox;
+    let mut attributions: Vec<_> = map_layers
+        .layers
+        .values()
+        .filter_map(|layer| layer.attribution.as_deref())
+        .filter(|attribution| !attribution.is_empty())
+        .collect();
+    attributions.sort();
+    attributions.dedup();
+    let vector_layers: Vec<_> = views
+        .iter()
+        .map(|(name, layer, _)| {
+            json!({
+                "id": name,
+                "description": layer.attribution.clone().unwrap_or_default(),
+                "minzoom": min_zoom,
+                "maxzoom": max_zoom,
+                "fields": {},
+            })
+        })
+        .collect();
+    writer.insert_metadata("name", infra_name)?;
+    writer.insert_metadata("format", "pbf")?;
+    writer.insert_metadata("type", "overlay")?;
+    writer.insert_metadata("description", format!("Layers of the infra {infra_id}"))?;
+    writer.insert_metadata("bounds", format!("{min_lon},{min_lat},{max_lon},{max_lat}"))?;
+    writer.insert_metadata(
+        "center",
+        format!(
+            "{},{},{min_zoom}",
+            (min_lon + max_lon) / 2.,
+            (min_lat + max_lat) / 2.
+        ),
+    )?;
+    writer.insert_metadata("minzoom", min_zoom.to_string())?;
+    writer.insert_metadata("maxzoom", max_zoom.to_string())?;
+    writer.insert_metadata("attribution", attributions.join(", "))?;
+    writer.insert_metadata(
+        "json",
+        json!({ "vector_layers": vector_layers }).to_string(),
+    )?;
+
+    let tiles: Vec<_> = get_tiles_in_bbox(bbox, max_zoom)
+        .into_iter()
+        .filter(|tile| tile.z >= min_zoom)
+        .collect();
+    let total = tiles.len() as u64;
+    let mut stored = 0;
+    for (processed, tile) in tiles.iter().enumerate() {
+        let mut mvt_tile = MvtTile::new(4096);
+        for (name, layer, view) in views.iter() {
+            let records =
+                get_view_records(conn, layer, view, infra_id, tile, &Default::default()).await?;
+            add_layer_to_mvt_tile(&mut mvt_tile, name, records);
+        }
+        if mvt_tile.num_layers() > 0 {
+            writer.insert_tile(tile, &mvt_tile.to_bytes().unwrap())?;
+            stored += 1;
+        }
+        on_progress(processed as u64 + 1, total);
+    }
+    writer.finish()?;
+    Ok(stored)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+    use std::ops::DerefMut;
+
+    use editoast_schemas::primitives::BoundingBox;
+    use flate2::read::GzDecoder;
+    use rstest::rstest;
+
+    use super::export_mbtiles;
+    use super::MbtilesWriter;
+    use crate::map::MapLayers;
+    use crate::map::Tile;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::DbConnectionPoolV2;
+
+    #[test]
+    fn mbtiles_writer_stores_gzipped_tiles_in_tms_scheme() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.mbtiles");
+        let writer = MbtilesWriter::create(&path).unwrap();
+        writer.insert_metadata("name", "test").unwrap();
+        writer
+            .insert_tile(&Tile { x: 1, y: 0, z: 2 }, &[1, 2, 3])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let name: String = connection
+            .query_row(
+                "SELECT value FROM metadata WHERE name = 'name'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let (column, row, data): (u64, u64, Vec<u8>) = connection
+            .query_row(
+                "SELECT tile_column, tile_row, tile_data FROM tiles WHERE zoom_level = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        let mut mvt_bytes = vec![];
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut mvt_bytes)
+            .unwrap();
+
+        assert_eq!(name, "test");
+        assert_eq!((column, row), (1, 3));
+        assert_eq!(mvt_bytes, vec![1, 2, 3]);
+    }
+
+    #[rstest]
+    async fn export_empty_infra_only_stores_metadata() {
+        let db_pool = DbConnectionPoolV2::for_tests();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("infra.mbtiles");
+
+        let mut processed_tiles = 0;
+        let stored = export_mbtiles(
+            db_pool.get_ok().deref_mut(),
+            &MapLayers::parse(),
+            infra.id,
+            &infra.name,
+            &BoundingBox((-0.4, 49.46), (-0.09, 49.52)),
+            5,
+            6,
+            &path,
+            |processed, _| processed_tiles = processed,
+        )
+        .await
+        .unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let tile_count: u64 = connection
+            .query_row("SELECT COUNT(*) FROM tiles", [], |row| row.get(0))
+            .unwrap();
+        let min_zoom: String = connection
+            .query_row(
+                "SELECT value FROM metadata WHERE name = 'minzoom'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, 0);
+        assert_eq!(tile_count, 0);
+        assert_eq!(min_zoom, "5");
+        assert!(processed_tiles > 0);
+    }
+}
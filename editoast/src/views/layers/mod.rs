@@ -1,4 +1,6 @@
+pub mod mbtiles;
 mod mvt_utils;
+pub mod tile_warming;
 
 use std::collections::HashMap;
 
@@ -8,8 +10,11 @@ use actix_web::web::Json;
 use actix_web::web::Path;
 use actix_web::web::Query;
 use actix_web::HttpResponse;
+use chrono::NaiveDateTime;
 use diesel::sql_query;
 use diesel::sql_types::Integer;
+use diesel::sql_types::Nullable;
+use diesel::sql_types::Timestamptz;
 use diesel_async::RunQueryDsl;
 use editoast_derive::EditoastError;
 use mvt_utils::create_and_fill_mvt_tile;
@@ -30,6 +35,8 @@ use crate::map::get_view_cache_prefix;
 use crate::map::Layer;
 use crate::map::MapLayers;
 use crate::map::Tile;
+use crate::map::View;
+use crate::modelsv2::DbConnection;
 use crate::modelsv2::DbConnectionPoolV2;
 use crate::RedisClient;
 
@@ -84,6 +91,41 @@ struct InfraQueryParam {
     infra: i64,
 }
 
+/// Time window restricting the features of the views supporting it
+///
+/// Tiles restricted to a time window aren't cached.
+#[derive(Deserialize, Debug, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TimeWindowQueryParam {
+    /// Only keep the features ending after this date
+    start_date_time: Option<NaiveDateTime>,
+    /// Only keep the features starting before this date
+    end_date_time: Option<NaiveDateTime>,
+}
+
+impl TimeWindowQueryParam {
+    fn is_empty(&self) -> bool {
+        self.start_date_time.is_none() && self.end_date_time.is_none()
+    }
+
+    /// Whether the features of a view must be restricted to this time window
+    fn applies_to(&self, view: &View) -> bool {
+        view.time_window.is_some() && !self.is_empty()
+    }
+
+    fn to_query_string(&self) -> String {
+        [
+            ("start_date_time", self.start_date_time),
+            ("end_date_time", self.end_date_time),
+        ]
+        .into_iter()
+        .filter_map(|(name, date)| {
+            Some(format!("&{name}={}", date?.format("%Y-%m-%dT%H:%M:%S%.f")))
+        })
+        .collect()
+    }
+}
+
 #[derive(Deserialize, IntoParams)]
 #[allow(unused)]
 struct LayerViewParams {
@@ -113,7 +155,7 @@ struct ViewMetadata {
 /// Returns layer view metadata to query tiles
 #[utoipa::path(
     tag = "layers",
-    params(InfraQueryParam, LayerViewParams),
+    params(InfraQueryParam, TimeWindowQueryParam, LayerViewParams),
     responses(
         (status = 200, body = inline(ViewMetadata), description = "Successful Response"),
     )
@@ -122,6 +164,7 @@ struct ViewMetadata {
 async fn layer_view(
     path: Path<(String, String)>,
     params: Query<InfraQueryParam>,
+    time_window: Query<TimeWindowQueryParam>,
     map_layers: Data<MapLayers>,
     map_layers_config: Data<MapLayersConfig>,
 ) -> Result<Json<ViewMetadata>> {
@@ -132,17 +175,21 @@ async fn layer_view(
         None => return Err(LayersError::new_layer_not_found(layer_slug, &map_layers).into()),
     };
 
-    if !layer.views.contains_key(&view_slug) {
-        return Err(LayersError::new_view_not_found(view_slug, layer).into());
-    }
+    let view = match layer.views.get(&view_slug) {
+        Some(view) => view,
+        None => return Err(LayersError::new_view_not_found(view_slug, layer).into()),
+    };
 
     let mut root_url = get_root_url()?;
     if !root_url.path().ends_with('/') {
         root_url.path_segments_mut().unwrap().push(""); // Add a trailing slash
     }
     let root_url = root_url.to_string();
-    let tiles_url_pattern =
+    let mut tiles_url_pattern =
         format!("{root_url}layers/tile/{layer_slug}/{view_slug}/{{z}}/{{x}}/{{y}}/?infra={infra}");
+    if time_window.applies_to(view) {
+        tiles_url_pattern.push_str(&time_window.to_query_string());
+    }
 
     Ok(Json(ViewMetadata {
         data_type: "vector".to_owned(),
@@ -169,7 +216,7 @@ struct TileParams {
 /// Mvt tile from the cache if possible, otherwise gets data from the database and caches it in redis
 #[utoipa::path(
     tag = "layers",
-    params(InfraQueryParam, TileParams),
+    params(InfraQueryParam, TimeWindowQueryParam, TileParams),
     responses(
         (status = 200, body = Vec<u8>, description = "Successful Response"),
     )
@@ -178,6 +225,7 @@ struct TileParams {
 async fn cache_and_get_mvt_tile(
     path: Path<(String, String, u64, u64, u64)>,
     params: Query<InfraQueryParam>,
+    time_window: Query<TimeWindowQueryParam>,
     map_layers: Data<MapLayers>,
     db_pool: Data<DbConnectionPoolV2>,
     redis_client: Data<RedisClient>,
@@ -192,6 +240,22 @@ async fn cache_and_get_mvt_tile(
         Some(view) => view,
         None => return Err(LayersError::new_view_not_found(view_slug, layer).into()),
     };
+    if time_window.applies_to(view) {
+        let mut conn = db_pool.get().await?;
+        let mvt_bytes = generate_mvt_tile(
+            &mut conn,
+            &layer_slug,
+            layer,
+            view,
+            infra,
+            &Tile { x, y, z },
+            &time_window,
+        )
+        .await?;
+        return Ok(HttpResponse::Ok()
+            .content_type("application/x-protobuf")
+            .body(mvt_bytes));
+    }
     let cache_key = get_cache_tile_key(
         &get_view_cache_prefix(&layer_slug, infra, &view_slug),
         &Tile { x, y, z },
@@ -208,19 +272,17 @@ async fn cache_and_get_mvt_tile(
             .body(value));
     }
 
-    let geo_json_query = get_geo_json_sql_query(&layer.table_name, view);
     let mut conn = db_pool.get().await?;
-    let records = sql_query(geo_json_query)
-        .bind::<Integer, _>(z as i32)
-        .bind::<Integer, _>(x as i32)
-        .bind::<Integer, _>(y as i32)
-        .bind::<Integer, _>(infra as i32)
-        .get_results::<GeoJsonAndData>(&mut conn)
-        .await?;
-
-    let mvt_bytes: Vec<u8> = create_and_fill_mvt_tile(layer_slug, records)
-        .to_bytes()
-        .unwrap();
+    let mvt_bytes = generate_mvt_tile(
+        &mut conn,
+        &layer_slug,
+        layer,
+        view,
+        infra,
+        &Tile { x, y, z },
+        &Default::default(),
+    )
+    .await?;
     redis
         .set_ex(&cache_key, mvt_bytes.clone(), view.cache_duration)
         .await
@@ -230,6 +292,58 @@ async fn cache_and_get_mvt_tile(
         .body(mvt_bytes))
 }
 
+/// Queries the features of a layer view intersecting a tile and encodes them as a MVT tile
+///
+/// # Arguments
+///
+/// * `conn` - Connection to the database
+/// * `layer_slug` - Name of the layer
+/// * `layer` - Layer description
+/// * `view` - View of the layer to query
+/// * `infra` - Infra from which the features are taken
+/// * `tile` - Tile to generate
+/// * `time_window` - Time window restricting the features, if the view supports it
+async fn generate_mvt_tile(
+    conn: &mut DbConnection,
+    layer_slug: &str,
+    layer: &Layer,
+    view: &View,
+    infra: i64,
+    tile: &Tile,
+    time_window: &TimeWindowQueryParam,
+) -> Result<Vec<u8>> {
+    let records = get_view_records(conn, layer, view, infra, tile, time_window).await?;
+    Ok(create_and_fill_mvt_tile(layer_slug, records)
+        .to_bytes()
+        .unwrap())
+}
+
+/// Queries the features of a layer view intersecting a tile
+async fn get_view_records(
+    conn: &mut DbConnection,
+    layer: &Layer,
+    view: &View,
+    infra: i64,
+    tile: &Tile,
+    time_window: &TimeWindowQueryParam,
+) -> Result<Vec<GeoJsonAndData>> {
+    let with_time_window = time_window.applies_to(view);
+    let geo_json_query = get_geo_json_sql_query(&layer.table_name, view, with_time_window);
+    let mut query = sql_query(geo_json_query)
+        .into_boxed()
+        .bind::<Integer, _>(tile.z as i32)
+        .bind::<Integer, _>(tile.x as i32)
+        .bind::<Integer, _>(tile.y as i32)
+        .bind::<Integer, _>(infra as i32);
+    if with_time_window {
+        query = query
+            .bind::<Nullable<Timestamptz>, _>(time_window.start_date_time)
+            .bind::<Nullable<Timestamptz>, _>(time_window.end_date_time);
+    }
+    let records = query.get_results::<GeoJsonAndData>(conn).await?;
+    Ok(records)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
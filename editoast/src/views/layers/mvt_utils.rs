@@ -122,9 +122,25 @@ pub fn create_and_fill_mvt_tile<T: AsRef<str>>(
     records: Vec<GeoJsonAndData>,
 ) -> MvtTile {
     let mut tile = MvtTile::new(4096);
-    // Return if no records as a tile without layers can be created but is not really useful
+    add_layer_to_mvt_tile(&mut tile, layer_name, records);
+    tile
+}
+
+/// Adds a layer filled with records to a MVT tile
+///
+/// # Arguments
+///
+/// * `tile` - Tile on which the layer must be added
+/// * `layer_name` - Name of the layer, must be unique in the tile
+/// * `records` - Records to add as features to the layer
+pub fn add_layer_to_mvt_tile<T: AsRef<str>>(
+    tile: &mut MvtTile,
+    layer_name: T,
+    records: Vec<GeoJsonAndData>,
+) {
+    // Skip if no records as a layer without features can be created but is not really useful
     if records.is_empty() {
-        return tile;
+        return;
     }
     let mut mvt_layer = tile.create_layer(layer_name.as_ref());
     for record in records.into_iter() {
@@ -133,7 +149,6 @@ pub fn create_and_fill_mvt_tile<T: AsRef<str>>(
         mvt_layer = feature.into_layer();
     }
     tile.add_layer(mvt_layer).unwrap();
-    tile
 }
 
 /// Creates an SQL query to get geo json data
@@ -142,7 +157,17 @@ pub fn create_and_fill_mvt_tile<T: AsRef<str>>(
 ///
 /// * `table_name` - Table containing the data
 /// * `view` - View containing info to get the data
-pub fn get_geo_json_sql_query(table_name: &str, view: &View) -> String {
+/// * `with_time_window` - Whether the features must intersect the time window given by the
+///   nullable parameters `$5` and `$6`, if the view supports it
+pub fn get_geo_json_sql_query(table_name: &str, view: &View, with_time_window: bool) -> String {
+    let time_window_condition = match &view.time_window {
+        Some(time_window) if with_time_window => format!(
+            "AND ($5::timestamptz IS NULL OR {end_field} > $5) AND ($6::timestamptz IS NULL OR {start_field} < $6)",
+            start_field = time_window.start_field,
+            end_field = time_window.end_field,
+        ),
+        _ => String::new(),
+    };
     format!(
         "
         WITH bbox AS (
@@ -163,7 +188,7 @@ pub fn get_geo_json_sql_query(table_name: &str, view: &View) -> String {
         FROM matches
         INNER JOIN {table_name} layer on matches.id = layer.id
         {joins}
-        WHERE geo_json is not NULL {where_condition}
+        WHERE geo_json is not NULL {where_condition} {time_window_condition}
         ",
         on_field = view.on_field,
         data_expr = view.data_expr,
@@ -244,11 +269,25 @@ mod tests {
             let query = get_geo_json_sql_query(
                 &track_sections.table_name,
                 track_sections.views.get("geo").unwrap(),
+                false,
             );
             assert_eq!(expected_queries[i].trim(), query.trim());
         }
     }
 
+    #[test]
+    fn test_query_creation_with_time_window() {
+        let map_layers = MapLayers::parse();
+        let work_schedules = map_layers.layers.get("work_schedules").unwrap();
+        let view = work_schedules.views.get("geo").unwrap();
+        let condition = "AND ($5::timestamptz IS NULL OR work_schedule.end_date_time > $5) \
+            AND ($6::timestamptz IS NULL OR work_schedule.start_date_time < $6)";
+        assert!(get_geo_json_sql_query(&work_schedules.table_name, view, true).contains(condition));
+        assert!(
+            !get_geo_json_sql_query(&work_schedules.table_name, view, false).contains(condition)
+        );
+    }
+
     #[test]
     fn test_create_and_fill_tile() {
         let records = vec![GeoJsonAndData {
@@ -0,0 +1,220 @@
+use editoast_schemas::primitives::BoundingBox;
+use futures::stream;
+use futures::StreamExt as _;
+use redis::AsyncCommands;
+
+use super::generate_mvt_tile;
+use crate::client::TileWarmingConfig;
+use crate::error::Result;
+use crate::infra_cache::InfraCache;
+use crate::infra_cache::ObjectCache;
+use crate::map::get_cache_tile_key;
+use crate::map::get_tiles_in_bbox;
+use crate::map::get_view_cache_prefix;
+use crate::map::Layer;
+use crate::map::MapLayers;
+use crate::map::Tile;
+use crate::map::View;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::RedisClient;
+
+/// Returns the geographic bounding box of an infra, computed from its track sections
+pub fn infra_bbox(infra_cache: &InfraCache) -> BoundingBox {
+    let mut bbox = BoundingBox::default();
+    infra_cache
+        .track_sections()
+        .values()
+        .map(ObjectCache::unwrap_track_section)
+        .for_each(|track| {
+            bbox.union(&track.bbox_geo);
+        });
+    bbox
+}
+
+/// Generates the tiles of every layer view over a bounding box and stores them in the redis cache
+///
+/// Tiles are cached using the same keys as the `/layers/tile` endpoint. Tiles already cached are
+/// left untouched.
+///
+/// # Arguments
+///
+/// * `db_pool` - Pool used to query the layers
+/// * `redis_client` - Client of the redis cache to fill
+/// * `map_layers` - Layers and views to warm up
+/// * `infra_id` - Infra from which the layers are generated
+/// * `bbox` - Area to warm up
+/// * `config` - Zoom range and concurrency of the warm up
+/// * `on_progress` - Called with the number of processed tiles and the total number of tiles
+///
+/// Returns the number of generated tiles
+pub async fn warm_tiles<F: FnMut(u64, u64)>(
+    db_pool: &DbConnectionPoolV2,
+    redis_client: &RedisClient,
+    map_layers: &MapLayers,
+    infra_id: i64,
+    bbox: &BoundingBox,
+    config: &TileWarmingConfig,
+    mut on_progress: F,
+) -> Result<u64> {
+    let tiles: Vec<_> = get_tiles_in_bbox(bbox, config.warm_tiles_max_zoom)
+        .into_iter()
+        .filter(|tile| tile.z >= config.warm_tiles_min_zoom)
+        .collect();
+    let view_count: usize = map_layers
+        .layers
+        .values()
+        .map(|layer| layer.views.len())
+        .sum();
+    let total = (view_count * tiles.len()) as u64;
+
+    let mut processed = 0;
+    let mut generated = 0;
+    for (layer_slug, layer) in map_layers.layers.iter() {
+        for (view_slug, view) in layer.views.iter() {
+            let mut warmed_tiles = stream::iter(tiles.iter().copied())
+                .map(|tile| {
+                    warm_tile(
+                        db_pool,
+                        redis_client,
+                        infra_id,
+                        layer_slug,
+                        layer,
+                        view_slug,
+                        view,
+                        tile,
+                    )
+                })
+                .buffer_unordered(config.warm_tiles_concurrency.max(1));
+            while let Some(warmed) = warmed_tiles.next().await {
+                if warmed? {
+                    generated += 1;
+                }
+                processed += 1;
+                on_progress(processed, total);
+            }
+        }
+    }
+    Ok(generated)
+}
+
+/// Generates and caches a single tile if it isn't already cached
+///
+/// Returns `true` if the tile was generated
+#[allow(clippy::too_many_arguments)]
+async fn warm_tile(
+    db_pool: &DbConnectionPoolV2,
+    redis_client: &RedisClient,
+    infra_id: i64,
+    layer_slug: &str,
+    layer: &Layer,
+    view_slug: &str,
+    view: &View,
+    tile: Tile,
+) -> Result<bool> {
+    let cache_key = get_cache_tile_key(
+        &get_view_cache_prefix(layer_slug, infra_id, view_slug),
+        &tile,
+    );
+    let mut redis = redis_client.get_connection().await?;
+    let cached: bool = redis.exists(&cache_key).await?;
+    if cached {
+        return Ok(false);
+    }
+
+    let mut conn = db_pool.get().await?;
+    let mvt_bytes = generate_mvt_tile(
+        &mut conn,
+        layer_slug,
+        layer,
+        view,
+        infra_id,
+        &tile,
+        &Default::default(),
+    )
+    .await?;
+    redis
+        .set_ex::<_, _, ()>(&cache_key, mvt_bytes, view.cache_duration)
+        .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use editoast_schemas::primitives::BoundingBox;
+    use redis::AsyncCommands;
+    use rstest::rstest;
+
+    use super::warm_tiles;
+    use crate::client::TileWarmingConfig;
+    use crate::map;
+    use crate::map::MapLayers;
+    use crate::map::Tile;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::DbConnectionPoolV2;
+    use crate::RedisClient;
+
+    #[rstest]
+    async fn warm_tiles_fills_the_cache_once() {
+        let db_pool = DbConnectionPoolV2::for_tests();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+        let redis_client = RedisClient::new(Default::default()).unwrap();
+        let map_layers = MapLayers::parse();
+        let bbox = BoundingBox((-0.4, 49.46), (-0.09, 49.52));
+        let config = TileWarmingConfig {
+            warm_tiles_min_zoom: 10,
+            warm_tiles_max_zoom: 10,
+            warm_tiles_concurrency: 4,
+        };
+        let view_count: usize = map_layers.layers.values().map(|l| l.views.len()).sum();
+        let tile_count = map::get_tiles_in_bbox(&bbox, 10)
+            .into_iter()
+            .filter(|tile| tile.z == 10)
+            .count();
+        let expected_tiles = (view_count * tile_count) as u64;
+
+        let mut last_progress = (0, 0);
+        let generated = warm_tiles(
+            &db_pool,
+            &redis_client,
+            &map_layers,
+            infra.id,
+            &bbox,
+            &config,
+            |processed, total| last_progress = (processed, total),
+        )
+        .await
+        .unwrap();
+        let warmed_again = warm_tiles(
+            &db_pool,
+            &redis_client,
+            &map_layers,
+            infra.id,
+            &bbox,
+            &config,
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        let mut redis = redis_client.get_connection().await.unwrap();
+        let tile_key = map::get_cache_tile_key(
+            &map::get_view_cache_prefix("track_sections", infra.id, "geo"),
+            &Tile::from_coordinates(-0.38, 49.5, 10),
+        );
+        let tile_cached: bool = redis.exists(&tile_key).await.unwrap();
+        map::invalidate_all(
+            &mut redis,
+            &map_layers.layers.keys().cloned().collect(),
+            infra.id,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(generated, expected_tiles);
+        assert_eq!(last_progress, (expected_tiles, expected_tiles));
+        assert_eq!(warmed_again, 0);
+        assert!(tile_cached);
+    }
+}
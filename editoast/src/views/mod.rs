@@ -1,7 +1,7 @@
 mod documents;
 pub mod electrical_profiles;
 pub mod infra;
-mod layers;
+pub mod layers;
 pub mod light_rolling_stocks;
 mod openapi;
 pub mod operational_studies;
@@ -17,6 +17,7 @@ pub mod sprites;
 pub mod stdcm;
 pub mod study;
 pub mod timetable;
+pub mod train_compositions;
 pub mod train_schedule;
 pub mod v2;
 pub mod work_schedules;
@@ -53,7 +54,7 @@ use crate::RedisClient;
 
 crate::routes! {
     (health, version, core_version),
-    (rolling_stocks::routes(), light_rolling_stocks::routes()),
+    (rolling_stocks::routes(), light_rolling_stocks::routes(), train_compositions::routes()),
     (pathfinding::routes(), stdcm::routes(), train_schedule::routes()),
     (projects::routes(),timetable::routes(), work_schedules::routes()),
     documents::routes(),
@@ -90,6 +91,7 @@ editoast_common::schemas! {
     search::schemas(),
     single_simulation::schemas(),
     timetable::schemas(),
+    train_compositions::schemas(),
     train_schedule::schemas(),
     v2::schemas(),
     work_schedules::schemas(),
@@ -1,5 +1,10 @@
+use std::future::ready;
+use std::future::Ready;
 use std::str::FromStr;
 
+use actix_web::dev::Payload;
+use actix_web::FromRequest;
+use actix_web::HttpRequest;
 use serde::de::Error;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -28,3 +33,23 @@ where
         Ok(List(res))
     }
 }
+
+/// The user who sent the request, as forwarded by the gateway in the `x-remote-user` header
+///
+/// Requests which didn't go through an authenticating gateway have no user.
+#[derive(Debug, Default, Clone)]
+pub struct RemoteUser(pub Option<String>);
+
+impl FromRequest for RemoteUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user = req
+            .headers()
+            .get("x-remote-user")
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        ready(Ok(RemoteUser(user)))
+    }
+}
@@ -0,0 +1,318 @@
+//! Physical sanity checks of a rolling stock
+//!
+//! The deserialization of a rolling stock only checks the shape of its fields. These checks look
+//! for data which is well formed but physically implausible.
+
+use actix_web::get;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use editoast_schemas::rolling_stock::EffortCurve;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::retrieve_existing_rolling_stock;
+use super::rolling_stock_form::RollingStockForm;
+use super::RollingStockIdParam;
+use super::RollingStockKey;
+use crate::error::Result;
+use crate::modelsv2::DbConnectionPoolV2;
+
+crate::routes! {
+    check,
+}
+
+editoast_common::schemas! {
+    RollingStockCheckReport,
+    RollingStockIssue,
+    RollingStockIssueCode,
+}
+
+/// Standard gravity in m/s²
+const GRAVITY: f64 = 9.81;
+/// The adhesion coefficient between wheels and rails on dry rails, which bounds the acceleration
+const MAX_ADHESION_COEFFICIENT: f64 = 0.3;
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct RollingStockCheckReport {
+    /// The issues making the simulations of the rolling stock wrong
+    pub errors: Vec<RollingStockIssue>,
+    /// The issues which may not be intended
+    pub warnings: Vec<RollingStockIssue>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RollingStockIssue {
+    /// The field of the rolling stock the issue is about (e.g. `effort_curves.modes.thermal`)
+    pub field: String,
+    pub code: RollingStockIssueCode,
+    pub message: String,
+}
+
+impl RollingStockIssue {
+    fn new(field: impl Into<String>, code: RollingStockIssueCode, message: String) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RollingStockIssueCode {
+    IncreasingEffort,
+    MaxSpeedNotReached,
+    AboveAdhesionLimit,
+    NegativeResistance,
+    MissingPowerRestrictionCurve,
+    MissingElectricalTime,
+}
+
+/// Convert a speed in m/s to km/h, for messages
+fn km_h(speed: f64) -> f64 {
+    (speed * 3.6 * 10.).round() / 10.
+}
+
+fn check_effort_curve(
+    field: String,
+    curve: &EffortCurve,
+    max_speed: f64,
+    report: &mut RollingStockCheckReport,
+) {
+    let speeds = curve.speeds();
+    let max_efforts = curve.max_efforts();
+    if let Some(index) = max_efforts.windows(2).position(|pair| pair[1] > pair[0]) {
+        report.warnings.push(RollingStockIssue::new(
+            field.clone(),
+            RollingStockIssueCode::IncreasingEffort,
+            format!(
+                "The effort increases with the speed from {} km/h",
+                km_h(speeds[index])
+            ),
+        ));
+    }
+    let last_speed = speeds.last().copied().unwrap_or_default();
+    if last_speed < max_speed {
+        report.warnings.push(RollingStockIssue::new(
+            field,
+            RollingStockIssueCode::MaxSpeedNotReached,
+            format!(
+                "The curve stops at {} km/h, below the max speed of {} km/h",
+                km_h(last_speed),
+                km_h(max_speed)
+            ),
+        ));
+    }
+}
+
+/// Check a rolling stock is physically plausible
+pub fn check_rolling_stock(rolling_stock: &RollingStockForm) -> RollingStockCheckReport {
+    let mut report = RollingStockCheckReport::default();
+    let max_speed = rolling_stock.max_speed;
+
+    for (mode, mode_curves) in &rolling_stock.effort_curves.modes {
+        let field = format!("effort_curves.modes.{mode}");
+        check_effort_curve(
+            format!("{field}.default_curve"),
+            mode_curves.default_curve(),
+            max_speed,
+            &mut report,
+        );
+        for (index, conditional_curve) in mode_curves.conditional_curves().iter().enumerate() {
+            check_effort_curve(
+                format!("{field}.curves[{index}]"),
+                conditional_curve.curve(),
+                max_speed,
+                &mut report,
+            );
+        }
+
+        // Power restrictions only limit the electrical power
+        if !mode_curves.is_electric {
+            continue;
+        }
+        let mut power_restriction_codes: Vec<_> = rolling_stock.power_restrictions.keys().collect();
+        power_restriction_codes.sort();
+        for code in power_restriction_codes {
+            let has_curve = mode_curves
+                .conditional_curves()
+                .iter()
+                .any(|curve| curve.cond().power_restriction_code() == Some(code.as_str()));
+            if !has_curve {
+                report.warnings.push(RollingStockIssue::new(
+                    field.clone(),
+                    RollingStockIssueCode::MissingPowerRestrictionCurve,
+                    format!("No curve for the power restriction code '{code}'"),
+                ));
+            }
+        }
+    }
+
+    let adhesion_limit = MAX_ADHESION_COEFFICIENT * GRAVITY;
+    if rolling_stock.comfort_acceleration > adhesion_limit {
+        report.errors.push(RollingStockIssue::new(
+            "comfort_acceleration",
+            RollingStockIssueCode::AboveAdhesionLimit,
+            format!(
+                "The comfort acceleration of {} m/s² is above the adhesion limit of {adhesion_limit:.2} m/s²",
+                rolling_stock.comfort_acceleration
+            ),
+        ));
+    }
+
+    let resistance = &rolling_stock.rolling_resistance;
+    let lowest_resistance_speed = resistance.lowest_resistance_speed(max_speed);
+    if resistance.resistance_at(lowest_resistance_speed) < 0. {
+        report.errors.push(RollingStockIssue::new(
+            "rolling_resistance",
+            RollingStockIssueCode::NegativeResistance,
+            format!(
+                "The resistance is negative at {} km/h",
+                km_h(lowest_resistance_speed)
+            ),
+        ));
+    }
+
+    if rolling_stock.effort_curves.is_electric() {
+        let electrical_times = [
+            (
+                "electrical_power_startup_time",
+                rolling_stock.electrical_power_startup_time,
+            ),
+            ("raise_pantograph_time", rolling_stock.raise_pantograph_time),
+        ];
+        for (field, time) in electrical_times {
+            if time.is_none() {
+                report.errors.push(RollingStockIssue::new(
+                    field,
+                    RollingStockIssueCode::MissingElectricalTime,
+                    format!("An electric rolling stock needs a {field}"),
+                ));
+            }
+        }
+    }
+
+    report
+}
+
+/// Check a rolling stock is physically plausible
+///
+/// Looks for effort curves increasing with the speed or stopping below the max speed, a comfort
+/// acceleration above the adhesion limit, a negative resistance, missing power restriction curves
+/// and missing electrical times.
+#[utoipa::path(
+    tag = "rolling_stock",
+    params(RollingStockIdParam),
+    responses(
+        (status = 200, body = RollingStockCheckReport, description = "The errors and warnings of the rolling stock"),
+        (status = 404, description = "The rolling stock was not found"),
+    )
+)]
+#[get("/check")]
+async fn check(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<i64>,
+) -> Result<Json<RollingStockCheckReport>> {
+    let conn = &mut db_pool.get().await?;
+    let rolling_stock_id = path.into_inner();
+
+    let rolling_stock =
+        retrieve_existing_rolling_stock(conn, RollingStockKey::Id(rolling_stock_id)).await?;
+    Ok(Json(check_rolling_stock(&rolling_stock.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use actix_http::StatusCode;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::rolling_stock::RollingResistance;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_fast_rolling_stock;
+    use crate::modelsv2::fixtures::fast_rolling_stock_form;
+    use crate::views::test_app::TestAppBuilder;
+
+    fn codes(issues: &[RollingStockIssue]) -> Vec<(&str, RollingStockIssueCode)> {
+        issues
+            .iter()
+            .map(|issue| (issue.field.as_str(), issue.code))
+            .collect()
+    }
+
+    #[test]
+    fn check_fast_rolling_stock() {
+        let report = check_rolling_stock(&fast_rolling_stock_form("check_fast_rolling_stock"));
+
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            codes(&report.warnings),
+            vec![
+                (
+                    "effort_curves.modes.25000V",
+                    RollingStockIssueCode::MissingPowerRestrictionCurve
+                ),
+                (
+                    "effort_curves.modes.thermal.default_curve",
+                    RollingStockIssueCode::MaxSpeedNotReached
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_implausible_rolling_stock() {
+        let mut rolling_stock = fast_rolling_stock_form("check_implausible_rolling_stock");
+        rolling_stock.comfort_acceleration = 5.0;
+        rolling_stock.rolling_resistance = RollingResistance::davis(1000., -200., 1.);
+        rolling_stock.raise_pantograph_time = None;
+
+        let report = check_rolling_stock(&rolling_stock);
+
+        assert_eq!(
+            codes(&report.errors),
+            vec![
+                (
+                    "comfort_acceleration",
+                    RollingStockIssueCode::AboveAdhesionLimit
+                ),
+                (
+                    "rolling_resistance",
+                    RollingStockIssueCode::NegativeResistance
+                ),
+                (
+                    "raise_pantograph_time",
+                    RollingStockIssueCode::MissingElectricalTime
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn check_rolling_stock_endpoint() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(db_pool.get_ok().deref_mut(), "check_fast_rolling_stock")
+                .await;
+
+        let request = TestRequest::get()
+            .uri(format!("/rolling_stock/{}/check", rolling_stock.id).as_str())
+            .to_request();
+        let report: RollingStockCheckReport =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert!(report.errors.is_empty());
+        assert_eq!(report.warnings.len(), 2);
+
+        let request = TestRequest::get()
+            .uri("/rolling_stock/0/check")
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,425 @@
+//! Comparison of rolling stocks running on the same path
+//!
+//! Each rolling stock is simulated on the path as a train without schedule nor margins, so that
+//! their running times and speeds only depend on their physics.
+
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use editoast_schemas::train_schedule::Comfort;
+use editoast_schemas::train_schedule::PathItem;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::retrieve_existing_rolling_stock;
+use super::RollingStockError;
+use super::RollingStockKey;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::core::v2::simulation::ReportTrain;
+use crate::core::v2::simulation::SimulationResponse;
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::ElectricalProfileSet;
+use crate::modelsv2::Infra;
+use crate::views::electrical_profiles::ElectricalProfilesError;
+use crate::views::infra::InfraApiError;
+use crate::views::v2::train_schedule::detached_train_simulation;
+use crate::CoreClient;
+use crate::RedisClient;
+
+crate::routes! {
+    compare,
+}
+
+/// Each rolling stock is simulated in turn, so their number is limited to answer in a timely manner
+const MAX_COMPARED_ROLLING_STOCKS: usize = 10;
+
+editoast_common::schemas! {
+    RollingStockComparisonForm,
+    RollingStockComparison,
+    RollingStockComparisonResult,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct RollingStockComparisonForm {
+    infra_id: i64,
+    /// The rolling stocks to compare, at most 10
+    rolling_stock_ids: Vec<i64>,
+    /// The path followed by every rolling stock
+    path: Vec<PathItem>,
+    #[serde(default)]
+    comfort: Comfort,
+    /// Train category for speed limits
+    #[serde(default)]
+    speed_limit_tag: Option<String>,
+    /// The electrical profile set used by the simulations, if any
+    #[serde(default)]
+    electrical_profile_set_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RollingStockComparison {
+    rolling_stock_id: i64,
+    rolling_stock_name: String,
+    result: RollingStockComparisonResult,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum RollingStockComparisonResult {
+    /// The run of the rolling stock on the path
+    Success {
+        /// Running time in ms
+        running_time: u64,
+        /// Length of the path in mm
+        length: u64,
+        /// Total energy consumption
+        energy_consumption: f64,
+        /// Energy consumption per km of path
+        energy_consumption_per_km: f64,
+        /// Highest speed reached in m/s
+        max_speed: f64,
+        /// Mean speed in m/s
+        mean_speed: f64,
+        /// Positions along the path in mm
+        positions: Vec<u64>,
+        /// Times in ms at each position
+        times: Vec<u64>,
+        /// Speeds in m/s at each position
+        speeds: Vec<f64>,
+    },
+    /// No path could be found for the rolling stock
+    PathfindingFailed {
+        pathfinding_result: PathfindingResult,
+    },
+    /// An error has occured during the simulation
+    SimulationFailed { core_error: InternalError },
+}
+
+impl From<ReportTrain> for RollingStockComparisonResult {
+    fn from(report: ReportTrain) -> Self {
+        let length = report.positions.last().copied().unwrap_or_default();
+        let running_time = report.times.last().copied().unwrap_or_default();
+        let max_speed = report.speeds.iter().copied().fold(0., f64::max);
+        // mm per ms is m/s
+        let mean_speed = if running_time > 0 {
+            length as f64 / running_time as f64
+        } else {
+            0.
+        };
+        let energy_consumption_per_km = if length > 0 {
+            report.energy_consumption / (length as f64 / 1_000_000.)
+        } else {
+            0.
+        };
+        Self::Success {
+            running_time,
+            length,
+            energy_consumption: report.energy_consumption,
+            energy_consumption_per_km,
+            max_speed,
+            mean_speed,
+            positions: report.positions,
+            times: report.times,
+            speeds: report.speeds,
+        }
+    }
+}
+
+impl From<SimulationResponse> for RollingStockComparisonResult {
+    fn from(simulation: SimulationResponse) -> Self {
+        match simulation {
+            SimulationResponse::Success { final_output, .. } => final_output.report_train.into(),
+            SimulationResponse::PathfindingFailed { pathfinding_result } => {
+                Self::PathfindingFailed { pathfinding_result }
+            }
+            SimulationResponse::SimulationFailed { core_error } => {
+                Self::SimulationFailed { core_error }
+            }
+        }
+    }
+}
+
+/// Compare rolling stocks running on the same path
+///
+/// Each rolling stock is simulated on the path, in the order of the request. The speeds along the
+/// path are given to overlay the runs of the rolling stocks.
+#[utoipa::path(
+    tag = "rolling_stock",
+    request_body = RollingStockComparisonForm,
+    responses(
+        (status = 200, body = Vec<RollingStockComparison>, description = "The run of each rolling stock on the path"),
+        (status = 400, description = "Too many rolling stocks are compared"),
+        (status = 404, description = "The infra, a rolling stock or the electrical profile set was not found"),
+    )
+)]
+#[post("/compare")]
+async fn compare(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    core_client: Data<CoreClient>,
+    data: Json<RollingStockComparisonForm>,
+) -> Result<Json<Vec<RollingStockComparison>>> {
+    let RollingStockComparisonForm {
+        infra_id,
+        rolling_stock_ids,
+        path,
+        comfort,
+        speed_limit_tag,
+        electrical_profile_set_id,
+    } = data.into_inner();
+    if rolling_stock_ids.len() > MAX_COMPARED_ROLLING_STOCKS {
+        return Err(RollingStockError::TooManyComparedRollingStocks {
+            count: rolling_stock_ids.len(),
+            max_count: MAX_COMPARED_ROLLING_STOCKS,
+        }
+        .into());
+    }
+    let redis_client = redis_client.into_inner();
+    let core_client = core_client.into_inner();
+    let conn = &mut db_pool.get().await?;
+
+    let infra =
+        Infra::retrieve_or_fail(conn, infra_id, || InfraApiError::NotFound { infra_id }).await?;
+    if let Some(electrical_profile_set_id) = electrical_profile_set_id {
+        if !ElectricalProfileSet::exists(conn, electrical_profile_set_id).await? {
+            return Err(ElectricalProfilesError::NotFound {
+                electrical_profile_set_id,
+            }
+            .into());
+        }
+    }
+    let mut rolling_stocks = Vec::with_capacity(rolling_stock_ids.len());
+    for rolling_stock_id in rolling_stock_ids {
+        rolling_stocks.push(
+            retrieve_existing_rolling_stock(conn, RollingStockKey::Id(rolling_stock_id)).await?,
+        );
+    }
+
+    let mut comparisons = Vec::with_capacity(rolling_stocks.len());
+    for rolling_stock in rolling_stocks {
+        let train_schedule = TrainSchedule {
+            rolling_stock_name: rolling_stock.name.clone(),
+            rolling_stock_id: Some(rolling_stock.id),
+            rolling_stock_version: Some(rolling_stock.version),
+            path: path.clone(),
+            comfort,
+            speed_limit_tag: speed_limit_tag.clone(),
+            ..Default::default()
+        };
+        let simulation = detached_train_simulation(
+            conn,
+            redis_client.clone(),
+            core_client.clone(),
+            &train_schedule,
+            &infra,
+            electrical_profile_set_id,
+        )
+        .await?;
+        comparisons.push(RollingStockComparison {
+            rolling_stock_id: rolling_stock.id,
+            rolling_stock_name: rolling_stock.name,
+            result: simulation.into(),
+        });
+    }
+    Ok(Json(comparisons))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use actix_http::StatusCode;
+    use actix_web::test::TestRequest;
+    use editoast_schemas::infra::TrackSection;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::mocking::MockingClient;
+    use crate::modelsv2::fixtures::create_electrical_profile_set;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::fixtures::create_fast_rolling_stock;
+    use crate::modelsv2::fixtures::create_infra_object;
+    use crate::views::test_app::TestAppBuilder;
+
+    fn mocked_core() -> MockingClient {
+        let mut core = MockingClient::new();
+        core.stub("/v2/pathfinding/blocks")
+            .method(reqwest::Method::POST)
+            .response(StatusCode::OK)
+            .body(
+                json!({
+                    "status": "success",
+                    "blocks": [],
+                    "routes": [],
+                    "track_section_ranges": [
+                        { "track_section": "A", "begin": 0, "end": 2_000_000, "direction": "START_TO_STOP" }
+                    ],
+                    "length": 2_000_000,
+                    "path_items_positions": [0, 2_000_000],
+                })
+                .to_string(),
+            )
+            .finish();
+        let report_train = json!({
+            "positions": [0, 1_000_000, 2_000_000],
+            "times": [0, 40_000, 80_000],
+            "speeds": [0., 30., 20.],
+            "energy_consumption": 400.,
+            "scheduled_points_honored": true,
+        });
+        let mut final_output = report_train.clone();
+        final_output["signal_sightings"] = json!([]);
+        final_output["zone_updates"] = json!([]);
+        final_output["spacing_requirements"] = json!([]);
+        final_output["routing_requirements"] = json!([]);
+        core.stub("/v2/standalone_simulation")
+            .method(reqwest::Method::POST)
+            .response(StatusCode::OK)
+            .body(
+                json!({
+                    "status": "success",
+                    "base": report_train,
+                    "provisional": report_train,
+                    "final_output": final_output,
+                    "mrsp": { "positions": [], "speeds": [] },
+                    "electrical_profiles": { "boundaries": [], "values": [] },
+                })
+                .to_string(),
+            )
+            .finish();
+        core
+    }
+
+    #[test]
+    fn run_indicators() {
+        let report = ReportTrain {
+            positions: vec![0, 1_000_000, 3_000_000],
+            times: vec![0, 50_000, 100_000],
+            speeds: vec![0., 40., 25.],
+            energy_consumption: 600.,
+            scheduled_points_honored: true,
+        };
+
+        let RollingStockComparisonResult::Success {
+            running_time,
+            length,
+            energy_consumption_per_km,
+            max_speed,
+            mean_speed,
+            ..
+        } = report.into()
+        else {
+            panic!("The run should be successful");
+        };
+        assert_eq!(running_time, 100_000);
+        assert_eq!(length, 3_000_000);
+        assert_eq!(energy_consumption_per_km, 200.);
+        assert_eq!(max_speed, 40.);
+        assert_eq!(mean_speed, 30.);
+    }
+
+    #[rstest]
+    async fn compare_unknown_rolling_stock() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri("/rolling_stock/compare")
+            .set_json(json!({
+                "infra_id": infra.id,
+                "rolling_stock_ids": [0],
+                "path": [],
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    async fn compare_too_many_rolling_stocks() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri("/rolling_stock/compare")
+            .set_json(json!({
+                "infra_id": infra.id,
+                "rolling_stock_ids": vec![0; MAX_COMPARED_ROLLING_STOCKS + 1],
+                "path": [],
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    async fn compare_rolling_stocks() {
+        let db_pool = DbConnectionPoolV2::for_tests();
+        let app = TestAppBuilder::new()
+            .db_pool(db_pool.clone())
+            .core_client(mocked_core().into())
+            .build();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+        let track_section = TrackSection {
+            id: "A".into(),
+            length: 2_000.,
+            ..Default::default()
+        };
+        create_infra_object(db_pool.get_ok().deref_mut(), infra.id, track_section).await;
+        let electrical_profile_set =
+            create_electrical_profile_set(db_pool.get_ok().deref_mut()).await;
+        let rolling_stock =
+            create_fast_rolling_stock(db_pool.get_ok().deref_mut(), "compare_rolling_stocks_rs")
+                .await;
+
+        let request = TestRequest::post()
+            .uri("/rolling_stock/compare")
+            .set_json(json!({
+                "infra_id": infra.id,
+                "rolling_stock_ids": [rolling_stock.id],
+                "path": [
+                    { "id": "a", "track": "A", "offset": 0 },
+                    { "id": "b", "track": "A", "offset": 2_000_000 },
+                ],
+                "electrical_profile_set_id": electrical_profile_set.id,
+            }))
+            .to_request();
+        let comparisons: Vec<serde_json::Value> =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        assert_eq!(comparisons.len(), 1);
+        assert_eq!(comparisons[0]["rolling_stock_id"], json!(rolling_stock.id));
+        let result = &comparisons[0]["result"];
+        assert_eq!(result["status"], json!("success"));
+        assert_eq!(result["running_time"], json!(80_000));
+        assert_eq!(result["length"], json!(2_000_000));
+        assert_eq!(result["max_speed"], json!(30.));
+        assert_eq!(result["energy_consumption_per_km"], json!(200.));
+    }
+
+    #[rstest]
+    async fn compare_with_unknown_electrical_profile_set() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let infra = create_empty_infra(db_pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri("/rolling_stock/compare")
+            .set_json(json!({
+                "infra_id": infra.id,
+                "rolling_stock_ids": [],
+                "path": [],
+                "electrical_profile_set_id": 0,
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -1,4 +1,7 @@
+pub mod check;
+pub mod compare;
 pub mod light_rolling_stock;
+pub mod revisions;
 pub mod rolling_stock_form;
 
 use std::io::BufReader;
@@ -17,6 +20,8 @@ use actix_web::web::Json;
 use actix_web::web::Path;
 use actix_web::web::Query;
 use actix_web::HttpResponse;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
 use editoast_derive::EditoastError;
 use editoast_schemas::rolling_stock::RollingStockLivery;
 use editoast_schemas::rolling_stock::RollingStockLiveryMetadata;
@@ -44,6 +49,7 @@ use crate::modelsv2::DbConnectionPool;
 use crate::modelsv2::DbConnectionPoolV2;
 use crate::modelsv2::Document;
 use crate::modelsv2::RollingStockModel;
+use crate::modelsv2::RollingStockRevision;
 use crate::modelsv2::RollingStockSeparatedImageModel;
 
 crate::routes! {
@@ -55,6 +61,7 @@ crate::routes! {
         "/name/{rolling_stock_name}" => {
             get_by_name,
         },
+        compare::routes(),
         "/{rolling_stock_id}" => {
             get,
             update,
@@ -65,6 +72,8 @@ crate::routes! {
             "/livery" => {
                 create_livery,
             },
+            check::routes(),
+            revisions::routes(),
         }
     }
 }
@@ -77,7 +86,10 @@ editoast_common::schemas! {
     RollingStockError,
     RollingStockKey,
     RollingStockWithLiveries,
+    check::schemas(),
+    compare::schemas(),
     light_rolling_stock::schemas(),
+    revisions::schemas(),
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -122,6 +134,12 @@ pub enum RollingStockError {
     #[error("Base power class is an empty string")]
     #[editoast_error(status = 400)]
     BasePowerClassEmpty,
+    #[error("Version {version} of rolling stock '{rolling_stock_id}' could not be found")]
+    #[editoast_error(status = 404)]
+    RevisionNotFound { rolling_stock_id: i64, version: i64 },
+    #[error("Cannot compare {count} rolling stocks, at most {max_count} can be compared")]
+    #[editoast_error(status = 400)]
+    TooManyComparedRollingStocks { count: usize, max_count: usize },
 }
 
 pub fn map_diesel_error(e: InternalError, name: impl AsRef<str>) -> InternalError {
@@ -234,17 +252,28 @@ async fn create(
     let rolling_stock_name = rolling_stock_form.name.clone();
     let rolling_stock_changeset: Changeset<RollingStockModel> = rolling_stock_form.into();
 
-    let rolling_stock = rolling_stock_changeset
-        .locked(query_params.locked)
-        .version(0)
-        .create(conn)
-        .await
-        .map_err(|e| map_diesel_error(e, rolling_stock_name))?;
+    let rolling_stock = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let rolling_stock = rolling_stock_changeset
+                    .locked(query_params.locked)
+                    .version(0)
+                    .create(conn)
+                    .await
+                    .map_err(|e| map_diesel_error(e, rolling_stock_name))?;
+                RollingStockRevision::record(conn, &rolling_stock).await?;
+                Ok(rolling_stock)
+            }
+            .scope_boxed()
+        })
+        .await?;
 
     Ok(Json(rolling_stock))
 }
 
 /// Patch a rolling stock
+///
+/// A new revision of the rolling stock is recorded if it changed.
 #[utoipa::path(tag = "rolling_stock",
     params(RollingStockIdParam),
     request_body = RollingStockForm,
@@ -273,21 +302,33 @@ async fn update(
         .await?;
     assert_rolling_stock_unlocked(&previous_rolling_stock)?;
 
-    let mut new_rolling_stock = Into::<Changeset<RollingStockModel>>::into(rolling_stock_form)
-        .update(conn, rolling_stock_id)
-        .await
-        .map_err(|e| map_diesel_error(e, name.clone()))?
-        .ok_or(RollingStockError::KeyNotFound {
-            rolling_stock_key: RollingStockKey::Id(rolling_stock_id),
-        })?;
-
-    if new_rolling_stock != previous_rolling_stock {
-        new_rolling_stock.version += 1;
-        new_rolling_stock
-            .save(conn)
-            .await
-            .map_err(|err| map_diesel_error(err, name))?;
-    }
+    let new_rolling_stock = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let mut new_rolling_stock =
+                    Into::<Changeset<RollingStockModel>>::into(rolling_stock_form)
+                        .update(conn, rolling_stock_id)
+                        .await
+                        .map_err(|e| map_diesel_error(e, name.clone()))?
+                        .ok_or(RollingStockError::KeyNotFound {
+                            rolling_stock_key: RollingStockKey::Id(rolling_stock_id),
+                        })?;
+
+                if new_rolling_stock != previous_rolling_stock {
+                    // The previous version has no revision if the rolling stock wasn't created by the API
+                    RollingStockRevision::record_if_missing(conn, &previous_rolling_stock).await?;
+                    new_rolling_stock.version += 1;
+                    new_rolling_stock
+                        .save(conn)
+                        .await
+                        .map_err(|err| map_diesel_error(err, name))?;
+                    RollingStockRevision::record(conn, &new_rolling_stock).await?;
+                }
+                Ok(new_rolling_stock)
+            }
+            .scope_boxed()
+        })
+        .await?;
 
     Ok(Json(new_rolling_stock.with_liveries(conn).await?))
 }
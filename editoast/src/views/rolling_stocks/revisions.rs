@@ -0,0 +1,304 @@
+//! Revisions of a rolling stock, recorded each time it's created or updated
+
+use actix_web::get;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::retrieve_existing_rolling_stock;
+use super::RollingStockError;
+use super::RollingStockIdParam;
+use super::RollingStockKey;
+use crate::error::Result;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::RollingStockModel;
+use crate::modelsv2::RollingStockRevision;
+
+crate::routes! {
+    "/revisions" => {
+        list,
+        "/compare" => {
+            compare,
+        },
+        "/{version}" => {
+            get,
+        },
+    },
+}
+
+editoast_common::schemas! {
+    RollingStockRevisionInfo,
+    RollingStockFieldDiff,
+}
+
+/// The fields which don't describe the rolling stock itself, left out of the comparisons
+const BOOKKEEPING_FIELDS: [&str; 3] = ["id", "locked", "version"];
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct RollingStockRevisionParam {
+    rolling_stock_id: i64,
+    /// A version of the rolling stock
+    version: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct CompareQueryParams {
+    /// The version compared from
+    from: i64,
+    /// The version compared to
+    to: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct RollingStockRevisionInfo {
+    version: i64,
+    /// The name of the rolling stock at this version
+    name: String,
+    creation_date: DateTime<Utc>,
+}
+
+impl From<RollingStockRevision> for RollingStockRevisionInfo {
+    fn from(revision: RollingStockRevision) -> Self {
+        Self {
+            version: revision.version,
+            name: revision.name,
+            creation_date: revision.creation_date,
+        }
+    }
+}
+
+/// A field with a different value in two rolling stocks
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RollingStockFieldDiff {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// The top-level fields whose value differs between two rolling stocks, sorted by name
+pub fn rolling_stock_diff(
+    from: &RollingStockModel,
+    to: &RollingStockModel,
+) -> Vec<RollingStockFieldDiff> {
+    let Value::Object(from) = serde_json::to_value(from).expect("rolling stock is serializable")
+    else {
+        unreachable!("rolling stock is serialized as an object");
+    };
+    let Value::Object(mut to) = serde_json::to_value(to).expect("rolling stock is serializable")
+    else {
+        unreachable!("rolling stock is serialized as an object");
+    };
+    let mut diff: Vec<_> = from
+        .into_iter()
+        .filter(|(field, _)| !BOOKKEEPING_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, from)| {
+            let to = to.remove(&field).unwrap_or_default();
+            (from != to).then_some(RollingStockFieldDiff { field, from, to })
+        })
+        .collect();
+    diff.sort_by(|a, b| a.field.cmp(&b.field));
+    diff
+}
+
+/// Retrieve a rolling stock as it was at a given version
+async fn retrieve_revision(
+    conn: &mut DbConnection,
+    rolling_stock_id: i64,
+    version: i64,
+) -> Result<RollingStockModel> {
+    RollingStockModel::retrieve_revision(conn, rolling_stock_id, version)
+        .await?
+        .ok_or_else(|| {
+            RollingStockError::RevisionNotFound {
+                rolling_stock_id,
+                version,
+            }
+            .into()
+        })
+}
+
+/// List the revisions of a rolling stock, the latest first
+#[utoipa::path(
+    tag = "rolling_stock",
+    params(RollingStockIdParam),
+    responses(
+        (status = 200, body = Vec<RollingStockRevisionInfo>, description = "The revisions of the rolling stock"),
+        (status = 404, description = "The rolling stock was not found"),
+    )
+)]
+#[get("")]
+async fn list(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<i64>,
+) -> Result<Json<Vec<RollingStockRevisionInfo>>> {
+    let conn = &mut db_pool.get().await?;
+    let rolling_stock_id = path.into_inner();
+
+    retrieve_existing_rolling_stock(conn, RollingStockKey::Id(rolling_stock_id)).await?;
+    let revisions = RollingStockRevision::list_by_rolling_stock(conn, rolling_stock_id).await?;
+    Ok(Json(revisions.into_iter().map(Into::into).collect()))
+}
+
+/// Get a rolling stock as it was at a given version
+#[utoipa::path(
+    tag = "rolling_stock",
+    params(RollingStockRevisionParam),
+    responses(
+        (status = 200, body = RollingStock, description = "The rolling stock at this version"),
+        (status = 404, description = "The rolling stock or the version was not found"),
+    )
+)]
+#[get("")]
+async fn get(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<RollingStockRevisionParam>,
+) -> Result<Json<RollingStockModel>> {
+    let conn = &mut db_pool.get().await?;
+    let rolling_stock = retrieve_revision(conn, path.rolling_stock_id, path.version).await?;
+    Ok(Json(rolling_stock))
+}
+
+/// Compare two versions of a rolling stock
+#[utoipa::path(
+    tag = "rolling_stock",
+    params(RollingStockIdParam, CompareQueryParams),
+    responses(
+        (status = 200, body = Vec<RollingStockFieldDiff>, description = "The fields which changed between the two versions"),
+        (status = 404, description = "The rolling stock or one of the versions was not found"),
+    )
+)]
+#[get("")]
+async fn compare(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<i64>,
+    query: Query<CompareQueryParams>,
+) -> Result<Json<Vec<RollingStockFieldDiff>>> {
+    let conn = &mut db_pool.get().await?;
+    let rolling_stock_id = path.into_inner();
+
+    let from = retrieve_revision(conn, rolling_stock_id, query.from).await?;
+    let to = retrieve_revision(conn, rolling_stock_id, query.to).await?;
+    Ok(Json(rolling_stock_diff(&from, &to)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use actix_http::StatusCode;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_fast_rolling_stock;
+    use crate::views::rolling_stocks::rolling_stock_form::RollingStockForm;
+    use crate::views::test_app::TestAppBuilder;
+
+    #[rstest]
+    async fn update_records_revisions() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(db_pool.get_ok().deref_mut(), "revisions_fast_rolling_stock")
+                .await;
+
+        let mut rolling_stock_form: RollingStockForm = rolling_stock.clone().into();
+        rolling_stock_form.name = "revisions_renamed_fast_rolling_stock".to_string();
+        rolling_stock_form.max_speed += 10.0;
+        let request = TestRequest::patch()
+            .uri(format!("/rolling_stock/{}", rolling_stock.id).as_str())
+            .set_json(&rolling_stock_form)
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::OK);
+
+        let request = TestRequest::get()
+            .uri(format!("/rolling_stock/{}/revisions", rolling_stock.id).as_str())
+            .to_request();
+        let revisions: Vec<RollingStockRevisionInfo> =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        let versions: Vec<_> = revisions
+            .iter()
+            .map(|revision| (revision.version, revision.name.as_str()))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                (1, "revisions_renamed_fast_rolling_stock"),
+                (0, "revisions_fast_rolling_stock")
+            ]
+        );
+
+        // The previous version stays reachable once the rolling stock is renamed
+        let previous = RollingStockModel::retrieve_version(
+            db_pool.get_ok().deref_mut(),
+            "revisions_renamed_fast_rolling_stock".to_string(),
+            Some((rolling_stock.id, 0)),
+        )
+        .await
+        .unwrap()
+        .expect("the previous version should have a revision");
+        assert_eq!(previous.max_speed, rolling_stock.max_speed);
+
+        let request = TestRequest::get()
+            .uri(
+                format!(
+                    "/rolling_stock/{}/revisions/compare?from=0&to=1",
+                    rolling_stock.id
+                )
+                .as_str(),
+            )
+            .to_request();
+        let diff: Vec<RollingStockFieldDiff> =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(
+            diff,
+            vec![
+                RollingStockFieldDiff {
+                    field: "max_speed".to_string(),
+                    from: json!(rolling_stock.max_speed),
+                    to: json!(rolling_stock.max_speed + 10.0),
+                },
+                RollingStockFieldDiff {
+                    field: "name".to_string(),
+                    from: json!("revisions_fast_rolling_stock"),
+                    to: json!("revisions_renamed_fast_rolling_stock"),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn get_unexisting_revision() {
+        let app = TestAppBuilder::default_app();
+        let db_pool = app.db_pool();
+        let rolling_stock = create_fast_rolling_stock(
+            db_pool.get_ok().deref_mut(),
+            "unexisting_revision_fast_rolling_stock",
+        )
+        .await;
+
+        let request = TestRequest::get()
+            .uri(format!("/rolling_stock/{}/revisions/{}", rolling_stock.id, 3).as_str())
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+
+        // The current version is available without a revision
+        let request = TestRequest::get()
+            .uri(format!("/rolling_stock/{}/revisions/{}", rolling_stock.id, 0).as_str())
+            .to_request();
+        let current: RollingStockModel =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(current, rolling_stock);
+    }
+}
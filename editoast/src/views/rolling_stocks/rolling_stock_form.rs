@@ -90,8 +90,6 @@ fn validate_rolling_stock_form(
     )
 }
 
-// Used in some tests where we import a rolling stock as a fixture
-#[cfg(test)]
 impl From<RollingStockModel> for RollingStockForm {
     fn from(value: RollingStockModel) -> Self {
         RollingStockForm {
@@ -0,0 +1,606 @@
+use actix_web::delete;
+use actix_web::get;
+use actix_web::post;
+use actix_web::put;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use actix_web::HttpResponse;
+use editoast_derive::EditoastError;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::train_composition::TrainCompositionChangeset;
+use crate::modelsv2::train_composition::TrainCompositionWagon;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::RollingStockModel;
+use crate::modelsv2::TrainComposition;
+
+crate::routes! {
+    "/train_composition" => {
+        create,
+        "/{train_composition_id}" => {
+            get,
+            update,
+            delete,
+        },
+    }
+}
+
+editoast_common::schemas! {
+    TrainCompositionForm,
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "train_composition")]
+pub enum TrainCompositionError {
+    #[error("Name '{name}' already used")]
+    #[editoast_error(status = 400)]
+    NameAlreadyUsed { name: String },
+    #[error("Train composition '{train_composition_id}' could not be found")]
+    #[editoast_error(status = 404)]
+    NotFound { train_composition_id: i64 },
+    #[error("Rolling stock '{rolling_stock_id}' could not be found")]
+    #[editoast_error(status = 404)]
+    RollingStockNotFound { rolling_stock_id: i64 },
+    #[error("A train composition needs at least one traction unit")]
+    #[editoast_error(status = 400)]
+    NoTractionUnit,
+    #[error("The {field} of wagon {wagon_index} is invalid")]
+    #[editoast_error(status = 400)]
+    InvalidWagon { wagon_index: usize, field: String },
+    #[error("Train composition '{train_composition_id}' is used")]
+    #[editoast_error(status = 409)]
+    IsUsed {
+        train_composition_id: i64,
+        train_ids: Vec<i64>,
+    },
+}
+
+fn map_diesel_error(e: InternalError, name: impl AsRef<str>) -> InternalError {
+    if e.message
+        .contains(r#"duplicate key value violates unique constraint "train_composition_name_key""#)
+    {
+        TrainCompositionError::NameAlreadyUsed {
+            name: name.as_ref().to_string(),
+        }
+        .into()
+    } else {
+        e
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct TrainCompositionForm {
+    name: String,
+    /// The rolling stock of the traction units
+    rolling_stock_id: i64,
+    /// The number of coupled traction units
+    unit_count: i32,
+    #[serde(default)]
+    wagons: Vec<TrainCompositionWagon>,
+}
+
+impl TrainCompositionForm {
+    /// Checks that the composition has traction units of an existing rolling stock, and wagons
+    /// which can be simulated
+    async fn validate(&self, conn: &mut DbConnection) -> Result<()> {
+        if self.unit_count < 1 {
+            return Err(TrainCompositionError::NoTractionUnit.into());
+        }
+        let rolling_stock_id = self.rolling_stock_id;
+        let rolling_stock = RollingStockModel::retrieve_or_fail(conn, rolling_stock_id, || {
+            TrainCompositionError::RollingStockNotFound { rolling_stock_id }
+        })
+        .await?;
+        for (wagon_index, wagon) in self.wagons.iter().enumerate() {
+            if let Some(field) = wagon.invalid_field(rolling_stock.max_speed) {
+                return Err(TrainCompositionError::InvalidWagon {
+                    wagon_index,
+                    field: field.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<TrainCompositionForm> for TrainCompositionChangeset {
+    fn from(form: TrainCompositionForm) -> Self {
+        TrainComposition::changeset()
+            .name(form.name)
+            .rolling_stock_id(form.rolling_stock_id)
+            .unit_count(form.unit_count)
+            .wagons(form.wagons)
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct TrainCompositionIdParam {
+    /// A train composition ID
+    train_composition_id: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct DeleteTrainCompositionQueryParams {
+    /// Delete the train composition even if train schedules are made of it
+    #[serde(default)]
+    force: bool,
+}
+
+/// The ids of the train schedules made of a train composition
+async fn train_composition_usage(
+    conn: &mut DbConnection,
+    train_composition_id: i64,
+) -> Result<Vec<i64>> {
+    let settings = SelectionSettings::new()
+        .filter(move || TrainSchedule::COMPOSITION_ID.eq(Some(train_composition_id)));
+    let train_schedules = TrainSchedule::list(conn, settings).await?;
+    Ok(train_schedules
+        .into_iter()
+        .map(|train_schedule| train_schedule.id)
+        .collect())
+}
+
+/// Create a train composition
+#[utoipa::path(
+    tag = "train_composition",
+    request_body = TrainCompositionForm,
+    responses(
+        (status = 200, body = TrainComposition, description = "The created train composition"),
+        (status = 400, description = "The name is already used, there is no traction unit or a wagon is invalid"),
+        (status = 404, description = "The rolling stock was not found"),
+    )
+)]
+#[post("")]
+async fn create(
+    db_pool: Data<DbConnectionPoolV2>,
+    data: Json<TrainCompositionForm>,
+) -> Result<Json<TrainComposition>> {
+    let form = data.into_inner();
+    let conn = &mut db_pool.get().await?;
+    form.validate(conn).await?;
+
+    let name = form.name.clone();
+    let train_composition = TrainCompositionChangeset::from(form)
+        .create(conn)
+        .await
+        .map_err(|e| map_diesel_error(e, name))?;
+    Ok(Json(train_composition))
+}
+
+/// Retrieve a train composition
+#[utoipa::path(
+    tag = "train_composition",
+    params(TrainCompositionIdParam),
+    responses(
+        (status = 200, body = TrainComposition, description = "The train composition"),
+        (status = 404, description = "The train composition was not found"),
+    )
+)]
+#[get("")]
+async fn get(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<TrainCompositionIdParam>,
+) -> Result<Json<TrainComposition>> {
+    let train_composition_id = path.train_composition_id;
+    let conn = &mut db_pool.get().await?;
+    let train_composition = TrainComposition::retrieve_or_fail(conn, train_composition_id, || {
+        TrainCompositionError::NotFound {
+            train_composition_id,
+        }
+    })
+    .await?;
+    Ok(Json(train_composition))
+}
+
+/// Update a train composition
+///
+/// The train schedules made of this composition are simulated with its new vehicles. Its rolling
+/// stock can't be changed while train schedules are made of it.
+#[utoipa::path(
+    tag = "train_composition",
+    params(TrainCompositionIdParam),
+    request_body = TrainCompositionForm,
+    responses(
+        (status = 200, body = TrainComposition, description = "The updated train composition"),
+        (status = 400, description = "The name is already used, there is no traction unit or a wagon is invalid"),
+        (status = 404, description = "The train composition or the rolling stock was not found"),
+        (status = 409, description = "The rolling stock of a used train composition was changed"),
+    )
+)]
+#[put("")]
+async fn update(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<TrainCompositionIdParam>,
+    data: Json<TrainCompositionForm>,
+) -> Result<Json<TrainComposition>> {
+    let train_composition_id = path.train_composition_id;
+    let form = data.into_inner();
+    let conn = &mut db_pool.get().await?;
+    form.validate(conn).await?;
+
+    let previous = TrainComposition::retrieve_or_fail(conn, train_composition_id, || {
+        TrainCompositionError::NotFound {
+            train_composition_id,
+        }
+    })
+    .await?;
+    if previous.rolling_stock_id != form.rolling_stock_id {
+        let train_ids = train_composition_usage(conn, train_composition_id).await?;
+        if !train_ids.is_empty() {
+            return Err(TrainCompositionError::IsUsed {
+                train_composition_id,
+                train_ids,
+            }
+            .into());
+        }
+    }
+
+    let name = form.name.clone();
+    let train_composition = TrainCompositionChangeset::from(form)
+        .update_or_fail(conn, train_composition_id, || {
+            TrainCompositionError::NotFound {
+                train_composition_id,
+            }
+        })
+        .await
+        .map_err(|e| map_diesel_error(e, name))?;
+    Ok(Json(train_composition))
+}
+
+/// Delete a train composition
+///
+/// The train schedules made of this composition can't be simulated anymore, so the deletion has to
+/// be forced while train schedules are made of it.
+#[utoipa::path(
+    tag = "train_composition",
+    params(TrainCompositionIdParam, DeleteTrainCompositionQueryParams),
+    responses(
+        (status = 204, description = "The train composition was deleted"),
+        (status = 404, description = "The train composition was not found"),
+        (status = 409, description = "The train composition is used"),
+    )
+)]
+#[delete("")]
+async fn delete(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<TrainCompositionIdParam>,
+    params: Query<DeleteTrainCompositionQueryParams>,
+) -> Result<HttpResponse> {
+    let train_composition_id = path.train_composition_id;
+    let conn = &mut db_pool.get().await?;
+    if !params.force {
+        let train_ids = train_composition_usage(conn, train_composition_id).await?;
+        if !train_ids.is_empty() {
+            return Err(TrainCompositionError::IsUsed {
+                train_composition_id,
+                train_ids,
+            }
+            .into());
+        }
+    }
+    TrainComposition::delete_static_or_fail(conn, train_composition_id, || {
+        TrainCompositionError::NotFound {
+            train_composition_id,
+        }
+    })
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_fast_rolling_stock;
+    use crate::modelsv2::fixtures::create_timetable;
+    use crate::modelsv2::fixtures::simple_train_schedule_form;
+    use crate::views::test_app::TestAppBuilder;
+
+    fn wagon() -> serde_json::Value {
+        json!({
+            "count": 10,
+            "mass": 90000.0,
+            "length": 20.0,
+            "inertia_coefficient": 1.0,
+            "rolling_resistance": { "type": "davis", "A": 100.0, "B": 1.0, "C": 0.1 }
+        })
+    }
+
+    fn wagon_with(values: serde_json::Value) -> serde_json::Value {
+        let mut wagon = wagon();
+        for (key, value) in values.as_object().expect("values should be an object") {
+            wagon[key] = value.clone();
+        }
+        wagon
+    }
+
+    async fn create_train_composition(
+        conn: &mut DbConnection,
+        name: &str,
+        rolling_stock_id: i64,
+    ) -> TrainComposition {
+        TrainComposition::changeset()
+            .name(name.to_string())
+            .rolling_stock_id(rolling_stock_id)
+            .unit_count(1)
+            .wagons(vec![])
+            .create(conn)
+            .await
+            .expect("Failed to create train composition")
+    }
+
+    async fn create_train_schedule_made_of(
+        conn: &mut DbConnection,
+        train_composition_id: i64,
+    ) -> TrainSchedule {
+        let timetable = create_timetable(conn).await;
+        let train_schedule: Changeset<TrainSchedule> =
+            simple_train_schedule_form(timetable.id).into();
+        train_schedule
+            .composition_id(Some(train_composition_id))
+            .create(conn)
+            .await
+            .expect("Failed to create train schedule")
+    }
+
+    #[rstest]
+    async fn train_composition_create_get_delete() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(pool.get_ok().deref_mut(), "train_composition_rs").await;
+
+        let request = TestRequest::post()
+            .uri("/train_composition")
+            .set_json(json!({
+                "name": "train_composition_create_get_delete",
+                "rolling_stock_id": rolling_stock.id,
+                "unit_count": 2,
+                "wagons": [{
+                    "count": 10,
+                    "mass": 90000.0,
+                    "length": 20.0,
+                    "inertia_coefficient": 1.0,
+                    "rolling_resistance": { "type": "davis", "A": 100.0, "B": 1.0, "C": 0.1 }
+                }]
+            }))
+            .to_request();
+        let created: TrainComposition =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(created.unit_count, 2);
+        assert_eq!(created.wagons.len(), 1);
+
+        let request = TestRequest::get()
+            .uri(format!("/train_composition/{}", created.id).as_str())
+            .to_request();
+        let retrieved: TrainComposition =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(retrieved, created);
+
+        let request = TestRequest::delete()
+            .uri(format!("/train_composition/{}", created.id).as_str())
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NO_CONTENT);
+        let exists = TrainComposition::exists(pool.get_ok().deref_mut(), created.id)
+            .await
+            .expect("Failed to check the train composition");
+        assert!(!exists);
+    }
+
+    #[rstest]
+    async fn train_composition_create_without_traction_unit() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(pool.get_ok().deref_mut(), "train_composition_no_unit_rs")
+                .await;
+
+        let request = TestRequest::post()
+            .uri("/train_composition")
+            .set_json(json!({
+                "name": "train_composition_without_traction_unit",
+                "rolling_stock_id": rolling_stock.id,
+                "unit_count": 0,
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    #[case::null_mass(json!({ "mass": 0.0 }), "mass")]
+    #[case::negative_length(json!({ "length": -20.0 }), "length")]
+    #[case::null_inertia_coefficient(json!({ "inertia_coefficient": 0.0 }), "inertia_coefficient")]
+    #[case::null_max_speed(json!({ "max_speed": 0.0 }), "max_speed")]
+    #[case::negative_resistance(
+        json!({ "rolling_resistance": { "type": "davis", "A": -100.0, "B": 0.0, "C": 0.0 } }),
+        "rolling_resistance"
+    )]
+    async fn train_composition_create_invalid_wagon(
+        #[case] invalid_values: serde_json::Value,
+        #[case] field: &str,
+    ) {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock = create_fast_rolling_stock(
+            pool.get_ok().deref_mut(),
+            &format!("train_composition_invalid_{field}_rs"),
+        )
+        .await;
+
+        let request = TestRequest::post()
+            .uri("/train_composition")
+            .set_json(json!({
+                "name": format!("train_composition_invalid_{field}"),
+                "rolling_stock_id": rolling_stock.id,
+                "unit_count": 1,
+                "wagons": [wagon(), wagon_with(invalid_values)],
+            }))
+            .to_request();
+        let error: InternalError = app
+            .fetch(request)
+            .assert_status(StatusCode::BAD_REQUEST)
+            .json_into();
+        assert_eq!(error.context["wagon_index"], json!(1));
+        assert_eq!(error.context["field"], json!(field));
+    }
+
+    #[rstest]
+    async fn train_composition_create_rolling_stock_not_found() {
+        let app = TestAppBuilder::default_app();
+
+        let request = TestRequest::post()
+            .uri("/train_composition")
+            .set_json(json!({
+                "name": "train_composition_rolling_stock_not_found",
+                "rolling_stock_id": 0,
+                "unit_count": 1,
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    async fn train_composition_update() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(pool.get_ok().deref_mut(), "train_composition_update_rs")
+                .await;
+        let train_composition = create_train_composition(
+            pool.get_ok().deref_mut(),
+            "train_composition_update",
+            rolling_stock.id,
+        )
+        .await;
+        create_train_schedule_made_of(pool.get_ok().deref_mut(), train_composition.id).await;
+
+        let request = TestRequest::put()
+            .uri(format!("/train_composition/{}", train_composition.id).as_str())
+            .set_json(json!({
+                "name": "train_composition_updated",
+                "rolling_stock_id": rolling_stock.id,
+                "unit_count": 3,
+            }))
+            .to_request();
+        let updated: TrainComposition =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(updated.id, train_composition.id);
+        assert_eq!(updated.name, "train_composition_updated");
+        assert_eq!(updated.unit_count, 3);
+    }
+
+    #[rstest]
+    async fn train_composition_update_invalid_wagon() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock = create_fast_rolling_stock(
+            pool.get_ok().deref_mut(),
+            "train_composition_update_invalid_wagon_rs",
+        )
+        .await;
+        let train_composition = create_train_composition(
+            pool.get_ok().deref_mut(),
+            "train_composition_update_invalid_wagon",
+            rolling_stock.id,
+        )
+        .await;
+
+        let request = TestRequest::put()
+            .uri(format!("/train_composition/{}", train_composition.id).as_str())
+            .set_json(json!({
+                "name": "train_composition_update_invalid_wagon",
+                "rolling_stock_id": rolling_stock.id,
+                "unit_count": 1,
+                "wagons": [wagon_with(json!({ "mass": -90000.0 }))],
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+        let not_updated =
+            TrainComposition::retrieve(pool.get_ok().deref_mut(), train_composition.id)
+                .await
+                .expect("Failed to retrieve the train composition")
+                .expect("The train composition should exist");
+        assert_eq!(not_updated, train_composition);
+    }
+
+    #[rstest]
+    async fn train_composition_update_rolling_stock_of_used_composition() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock =
+            create_fast_rolling_stock(pool.get_ok().deref_mut(), "train_composition_used_rs").await;
+        let other_rolling_stock =
+            create_fast_rolling_stock(pool.get_ok().deref_mut(), "train_composition_other_rs")
+                .await;
+        let train_composition = create_train_composition(
+            pool.get_ok().deref_mut(),
+            "train_composition_update_used",
+            rolling_stock.id,
+        )
+        .await;
+        create_train_schedule_made_of(pool.get_ok().deref_mut(), train_composition.id).await;
+
+        let request = TestRequest::put()
+            .uri(format!("/train_composition/{}", train_composition.id).as_str())
+            .set_json(json!({
+                "name": "train_composition_update_used",
+                "rolling_stock_id": other_rolling_stock.id,
+                "unit_count": 1,
+            }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::CONFLICT);
+    }
+
+    #[rstest]
+    async fn train_composition_delete_used() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let rolling_stock = create_fast_rolling_stock(
+            pool.get_ok().deref_mut(),
+            "train_composition_delete_used_rs",
+        )
+        .await;
+        let train_composition = create_train_composition(
+            pool.get_ok().deref_mut(),
+            "train_composition_delete_used",
+            rolling_stock.id,
+        )
+        .await;
+        let train_schedule =
+            create_train_schedule_made_of(pool.get_ok().deref_mut(), train_composition.id).await;
+
+        let request = TestRequest::delete()
+            .uri(format!("/train_composition/{}", train_composition.id).as_str())
+            .to_request();
+        let error: InternalError = app
+            .fetch(request)
+            .assert_status(StatusCode::CONFLICT)
+            .json_into();
+        assert_eq!(error.context["train_ids"], json!([train_schedule.id]));
+
+        let request = TestRequest::delete()
+            .uri(format!("/train_composition/{}?force=true", train_composition.id).as_str())
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NO_CONTENT);
+    }
+}
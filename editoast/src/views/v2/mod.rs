@@ -2,9 +2,11 @@ pub mod path;
 pub mod scenario;
 pub mod timetable;
 pub mod train_schedule;
+pub mod train_schedule_pattern;
 
 crate::routes! {
             train_schedule::routes(),
+            train_schedule_pattern::routes(),
             timetable::routes(),
             path::routes(),
             scenario::routes(),
@@ -12,6 +14,7 @@ crate::routes! {
 
 editoast_common::schemas! {
     train_schedule::schemas(),
+    train_schedule_pattern::schemas(),
     timetable::schemas(),
     path::schemas(),
     scenario::schemas(),
@@ -1,6 +1,6 @@
 pub mod pathfinding;
 pub mod projection;
-mod properties;
+pub mod properties;
 
 pub use pathfinding::pathfinding_from_train;
 
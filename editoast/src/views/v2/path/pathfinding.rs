@@ -169,7 +169,13 @@ pub async fn pathfinding_from_train(
 ) -> Result<PathfindingResult> {
     // Retrieve rolling stock
     let rolling_stock_name = train_schedule.rolling_stock_name.clone();
-    let Some(rolling_stock) = RollingStockModel::retrieve(conn, rolling_stock_name.clone()).await?
+    let Some(rolling_stock) = RollingStockModel::retrieve_for_train(
+        conn,
+        rolling_stock_name.clone(),
+        train_schedule.rolling_stock_revision(),
+        train_schedule.composition_id,
+    )
+    .await?
     else {
         return Ok(PathfindingResult::RollingStockNotFound { rolling_stock_name });
     };
@@ -355,6 +361,49 @@ pub async fn extract_location_from_path_items(
     Ok(Ok(result))
 }
 
+/// The indexes of the path items which can't be located on an infra
+pub async fn unresolved_path_items(
+    conn: &mut DbConnection,
+    infra_id: i64,
+    path_items: &[PathItemLocation],
+) -> Result<Vec<usize>> {
+    let mut unresolved = vec![];
+    // The extraction stops at the first path item it can't locate, so it's resumed after it
+    let mut start = 0;
+    while start < path_items.len() {
+        match extract_location_from_path_items(conn, infra_id, &path_items[start..]).await? {
+            Ok(_) => break,
+            Err(error) => {
+                unresolved.push(start + error.index);
+                start += error.index + 1;
+            }
+        }
+    }
+
+    // Track offsets are always extracted, the existence of their track is checked separately
+    let track_offsets: Vec<_> = path_items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, path_item)| match path_item {
+            PathItemLocation::TrackOffset(track_offset) => Some((index, track_offset)),
+            _ => None,
+        })
+        .collect();
+    let ids = track_offsets
+        .iter()
+        .map(|(_, track_offset)| (infra_id, track_offset.track.0.clone()));
+    let (tracks, _): (Vec<_>, _) = TrackSectionModel::retrieve_batch(conn, ids).await?;
+    let tracks: Vec<_> = tracks.into_iter().map(|track| track.obj_id).collect();
+    unresolved.extend(
+        track_offsets
+            .into_iter()
+            .filter(|(_, track_offset)| !tracks.contains(&track_offset.track.0))
+            .map(|(index, _)| index),
+    );
+    unresolved.sort_unstable();
+    Ok(unresolved)
+}
+
 async fn check_tracks_from_path_items(
     conn: &mut DbConnection,
     infra_id: i64,
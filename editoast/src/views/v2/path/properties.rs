@@ -9,6 +9,7 @@ use actix_web::post;
 use actix_web::web::Data;
 use actix_web::web::Json;
 use actix_web::web::Path;
+use editoast_derive::EditoastError;
 use enumset::EnumSet;
 use enumset::EnumSetType;
 use itertools::Itertools;
@@ -18,6 +19,7 @@ use serde_qs::actix::QsQuery;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hash;
 use std::hash::Hasher;
+use thiserror::Error;
 use tracing::info;
 use utoipa::ToSchema;
 
@@ -32,6 +34,7 @@ use crate::core::AsCoreRequest;
 use crate::core::CoreClient;
 use crate::error::Result;
 use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
 use crate::views::v2::path::retrieve_infra_version;
 use crate::RedisClient;
 use crate::RedisConnection;
@@ -51,6 +54,14 @@ editoast_common::schemas! {
     OperationalPointExtensions,
 }
 
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "path_properties")]
+enum PathPropertiesError {
+    #[error("The computed path properties have no geometry")]
+    #[editoast_error(status = 500)]
+    MissingGeometry,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Hash)]
 pub struct PathPropertiesInput {
     /// List of track sections
@@ -186,28 +197,12 @@ pub async fn post(
 
     // 3) Compute missing properties
     if !missing_props.is_empty() {
-        let request = PathPropertiesRequest {
-            track_section_ranges: &path_properties_input.track_section_ranges,
-            infra: infra_id,
-            expected_version: infra_version.clone(),
-        };
-        let computed_path_properties = request.fetch(&core_client).await?;
-
-        path_properties = PathProperties {
-            slopes: Some(computed_path_properties.slopes),
-            curves: Some(computed_path_properties.curves),
-            electrifications: Some(computed_path_properties.electrifications),
-            geometry: Some(computed_path_properties.geometry),
-            operational_points: Some(computed_path_properties.operational_points),
-        };
-
-        // Cache new properties
-        cache_path_properties(
+        path_properties = compute_path_properties(
             &mut redis_conn,
+            &core_client,
             infra_id,
             &infra_version,
             &path_properties_input,
-            &path_properties,
         )
         .await?;
     } else {
@@ -220,6 +215,97 @@ pub async fn post(
     Ok(Json(filtered_path_properties))
 }
 
+/// Retrieve the operational points along a path, using the cache when possible
+pub async fn operational_points_on_path(
+    redis_conn: &mut RedisConnection,
+    core_client: &CoreClient,
+    infra: &Infra,
+    track_section_ranges: Vec<TrackRange>,
+) -> Result<Vec<OperationalPointOnPath>> {
+    let path_properties_input = PathPropertiesInput {
+        track_section_ranges,
+    };
+    let path_properties =
+        retrieve_path_properties(redis_conn, infra.id, &infra.version, &path_properties_input)
+            .await?;
+    if let Some(operational_points) = path_properties.operational_points {
+        return Ok(operational_points);
+    }
+    let path_properties = compute_path_properties(
+        redis_conn,
+        core_client,
+        infra.id,
+        &infra.version,
+        &path_properties_input,
+    )
+    .await?;
+    Ok(path_properties.operational_points.unwrap_or_default())
+}
+
+/// Retrieves the geometry of a path, from the cache or by computing its properties with core.
+pub async fn path_geometry(
+    redis_conn: &mut RedisConnection,
+    core_client: &CoreClient,
+    infra: &Infra,
+    track_section_ranges: Vec<TrackRange>,
+) -> Result<GeoJsonLineString> {
+    let path_properties_input = PathPropertiesInput {
+        track_section_ranges,
+    };
+    let path_properties =
+        retrieve_path_properties(redis_conn, infra.id, &infra.version, &path_properties_input)
+            .await?;
+    if let Some(geometry) = path_properties.geometry {
+        return Ok(geometry);
+    }
+    let path_properties = compute_path_properties(
+        redis_conn,
+        core_client,
+        infra.id,
+        &infra.version,
+        &path_properties_input,
+    )
+    .await?;
+    path_properties
+        .geometry
+        .ok_or_else(|| PathPropertiesError::MissingGeometry.into())
+}
+
+/// Computes all the properties of a path with core and caches them.
+async fn compute_path_properties(
+    redis_conn: &mut RedisConnection,
+    core_client: &CoreClient,
+    infra: i64,
+    infra_version: &String,
+    path_properties_input: &PathPropertiesInput,
+) -> Result<PathProperties> {
+    let request = PathPropertiesRequest {
+        track_section_ranges: &path_properties_input.track_section_ranges,
+        infra,
+        expected_version: infra_version.clone(),
+    };
+    let computed_path_properties = request.fetch(core_client).await?;
+
+    let path_properties = PathProperties {
+        slopes: Some(computed_path_properties.slopes),
+        curves: Some(computed_path_properties.curves),
+        electrifications: Some(computed_path_properties.electrifications),
+        geometry: Some(computed_path_properties.geometry),
+        operational_points: Some(computed_path_properties.operational_points),
+    };
+
+    // Cache new properties
+    cache_path_properties(
+        redis_conn,
+        infra,
+        infra_version,
+        path_properties_input,
+        &path_properties,
+    )
+    .await?;
+    Ok(path_properties)
+}
+
 /// Retrieves path properties from cache.
 async fn retrieve_path_properties(
     redis_conn: &mut RedisConnection,
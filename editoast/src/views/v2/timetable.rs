@@ -1,4 +1,10 @@
+mod batch_update;
+pub mod conflict_explanation;
+mod conflicts_cache;
+mod duplicate;
+pub mod passing_times;
 pub mod stdcm;
+mod work_schedule_conflicts;
 
 use std::collections::HashMap;
 use std::ops::DerefMut as _;
@@ -12,6 +18,7 @@ use actix_web::web::Json;
 use actix_web::web::Path;
 use actix_web::web::Query;
 use actix_web::HttpResponse;
+use chrono::Duration;
 use derivative::Derivative;
 use editoast_derive::EditoastError;
 use editoast_schemas::train_schedule::TrainScheduleBase;
@@ -25,6 +32,8 @@ use utoipa::ToSchema;
 use crate::core::v2::conflict_detection::Conflict;
 use crate::core::v2::conflict_detection::ConflictDetectionRequest;
 use crate::core::v2::conflict_detection::TrainRequirements;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::core::v2::pathfinding::PathfindingResultSuccess;
 use crate::core::v2::simulation::SimulationResponse;
 use crate::core::AsCoreRequest;
 use crate::error::Result;
@@ -33,12 +42,19 @@ use crate::modelsv2::timetable::Timetable;
 use crate::modelsv2::timetable::TimetableWithTrains;
 use crate::modelsv2::train_schedule::TrainSchedule;
 use crate::modelsv2::train_schedule::TrainScheduleChangeset;
+use crate::modelsv2::work_schedules::WorkSchedule;
 use crate::modelsv2::DbConnectionPoolV2;
 use crate::modelsv2::Infra;
+use crate::modelsv2::RollingStockModel;
 use crate::views::pagination::PaginatedList;
 use crate::views::pagination::PaginationQueryParam;
 use crate::views::pagination::PaginationStats;
-use crate::views::v2::train_schedule::train_simulation_batch;
+use crate::views::v2::path::pathfinding_from_train;
+use crate::views::v2::timetable::conflicts_cache::trains_to_check;
+use crate::views::v2::timetable::conflicts_cache::ConflictsCache;
+use crate::views::v2::timetable::conflicts_cache::TrainKey;
+use crate::views::v2::timetable::work_schedule_conflicts::TrainOccupancy;
+use crate::views::v2::train_schedule::train_simulation_batch_with_cache_keys;
 use crate::views::v2::train_schedule::TrainScheduleForm;
 use crate::views::v2::train_schedule::TrainScheduleResult;
 use crate::CoreClient;
@@ -54,7 +70,11 @@ crate::routes! {
             get,
             put,
             conflicts,
+            conflict_explanation::routes(),
             train_schedule,
+            batch_update::routes(),
+            duplicate::routes(),
+            passing_times::routes(),
             stdcm::routes(),
         }
     },
@@ -64,6 +84,9 @@ editoast_common::schemas! {
     TimetableForm,
     TimetableResult,
     TimetableDetailedResult,
+    batch_update::schemas(),
+    conflict_explanation::schemas(),
+    duplicate::schemas(),
     stdcm::schemas(),
 }
 
@@ -76,6 +99,18 @@ enum TimetableError {
     #[error("Infra '{infra_id}', could not be found")]
     #[editoast_error(status = 404)]
     InfraNotFound { infra_id: i64 },
+    #[error("The patch of the train schedules doesn't change anything")]
+    #[editoast_error(status = 400)]
+    EmptyPatch,
+    #[error("Rolling stock '{rolling_stock_name}', could not be found")]
+    #[editoast_error(status = 404)]
+    RollingStockNotFound { rolling_stock_name: String },
+    #[error("The margins of the patch don't match the path of train '{train_id}'")]
+    #[editoast_error(status = 400)]
+    InvalidMargins { train_id: i64 },
+    #[error("The time shift of {time_shift} ms is out of range")]
+    #[editoast_error(status = 400)]
+    InvalidTimeShift { time_shift: i64 },
 }
 
 /// Creation form for a Timetable
@@ -305,14 +340,21 @@ async fn train_schedule(
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, IntoParams, ToSchema)]
-pub struct InfraIdQueryParam {
+pub struct ConflictsQueryParams {
     infra_id: i64,
+    /// Only check again the trains which changed since the last computation of the conflicts
+    #[serde(default)]
+    incremental: bool,
 }
 
 /// Retrieve the list of conflict of the timetable (invalid trains are ignored)
+///
+/// Conflicts between trains and work schedules are included.
+/// The conflicts between trains are cached, so that the incremental mode only checks again the
+/// trains which changed since the last computation.
 #[utoipa::path(
     tag = "timetablev2",
-    params(TimetableIdParam, InfraIdQueryParam),
+    params(TimetableIdParam, ConflictsQueryParams),
     responses(
         (status = 200, description = "List of conflict", body = Vec<ConflictV2>),
     ),
@@ -323,14 +365,17 @@ pub async fn conflicts(
     redis_client: Data<RedisClient>,
     core_client: Data<CoreClient>,
     timetable_id: Path<TimetableIdParam>,
-    query: Query<InfraIdQueryParam>,
+    query: Query<ConflictsQueryParams>,
 ) -> Result<Json<Vec<Conflict>>> {
     let db_pool = db_pool.into_inner();
     let conn = &mut db_pool.clone().get().await?;
     let redis_client = redis_client.into_inner();
     let core_client = core_client.into_inner();
     let timetable_id = timetable_id.into_inner().id;
-    let infra_id = query.into_inner().infra_id;
+    let ConflictsQueryParams {
+        infra_id,
+        incremental,
+    } = query.into_inner();
 
     // 1. Retrieve Timetable / Infra / Trains / Simultion
     let timetable = TimetableWithTrains::retrieve_or_fail(conn, timetable_id, || {
@@ -345,7 +390,7 @@ pub async fn conflicts(
 
     let (trains, _): (Vec<_>, _) = TrainSchedule::retrieve_batch(conn, timetable.train_ids).await?;
 
-    let simulations = train_simulation_batch(
+    let simulations = train_simulation_batch_with_cache_keys(
         db_pool.clone(),
         redis_client.clone(),
         core_client.clone(),
@@ -354,12 +399,40 @@ pub async fn conflicts(
     )
     .await?;
 
-    // 2. Build core request
+    // 2. Retrieve the work schedules happening during the timetable
+    let time_window = trains
+        .iter()
+        .zip(&simulations)
+        .filter_map(|(train, (sim, _))| match sim {
+            SimulationResponse::Success { final_output, .. } => {
+                let run_time = final_output.report_train.times.last().copied()?;
+                Some((
+                    train.start_time,
+                    train.start_time + Duration::milliseconds(run_time as i64),
+                ))
+            }
+            _ => None,
+        })
+        .reduce(|(start_a, end_a), (start_b, end_b)| (start_a.min(start_b), end_a.max(end_b)));
+    let work_schedules = match time_window {
+        Some((start_time, end_time)) => {
+            WorkSchedule::list_in_time_window(conn, start_time.naive_utc(), end_time.naive_utc())
+                .await?
+        }
+        None => vec![],
+    };
+
+    // 3. Build core request and check the trains against the work schedules
+    let mut redis_conn = redis_client.get_connection().await?;
+    let mut train_lengths = HashMap::new();
+    let mut work_schedule_conflicts = vec![];
     let mut trains_requirements = HashMap::with_capacity(trains.len());
-    for (train, sim) in trains.into_iter().zip(simulations) {
-        let final_output = match sim {
-            SimulationResponse::Success { final_output, .. } => final_output,
-            _ => continue,
+    let mut train_keys = HashMap::with_capacity(trains.len());
+    for (train, (sim, simulation_key)) in trains.into_iter().zip(simulations) {
+        let (SimulationResponse::Success { final_output, .. }, Some(simulation_key)) =
+            (sim, simulation_key)
+        else {
+            continue;
         };
         trains_requirements.insert(
             train.id,
@@ -369,15 +442,113 @@ pub async fn conflicts(
                 routing_requirements: final_output.routing_requirements,
             },
         );
+        train_keys.insert(
+            train.id,
+            TrainKey {
+                simulation: simulation_key,
+                start_time: train.start_time,
+            },
+        );
+        if !work_schedules.is_empty() {
+            let rolling_stock_key = (
+                train.rolling_stock_name.clone(),
+                train.rolling_stock_revision(),
+                train.composition_id,
+            );
+            let length = match train_lengths.get(&rolling_stock_key) {
+                Some(length) => *length,
+                None => {
+                    let Some(rolling_stock) = RollingStockModel::retrieve_for_train(
+                        conn,
+                        train.rolling_stock_name.clone(),
+                        train.rolling_stock_revision(),
+                        train.composition_id,
+                    )
+                    .await?
+                    else {
+                        continue;
+                    };
+                    let length = (rolling_stock.length * 1000.).round() as u64;
+                    train_lengths.insert(rolling_stock_key, length);
+                    length
+                }
+            };
+            let PathfindingResult::Success(PathfindingResultSuccess {
+                track_section_ranges,
+                ..
+            }) = pathfinding_from_train(
+                conn,
+                &mut redis_conn,
+                core_client.clone(),
+                &infra,
+                train.clone(),
+            )
+            .await?
+            else {
+                continue;
+            };
+            let occupancy = TrainOccupancy {
+                train_id: train.id,
+                start_time: train.start_time,
+                track_section_ranges: &track_section_ranges,
+                report_train: &final_output.report_train,
+                length,
+            };
+            work_schedule_conflicts.extend(
+                work_schedules
+                    .iter()
+                    .filter_map(|work_schedule| occupancy.work_schedule_conflict(work_schedule)),
+            );
+        }
     }
-    let conflict_detection_request = ConflictDetectionRequest {
-        trains_requirements,
+
+    // 4. Call core, in incremental mode only for the trains which changed since the cached conflicts
+    let cache = if incremental {
+        ConflictsCache::get(&mut redis_conn, timetable_id, infra_id).await?
+    } else {
+        None
+    };
+    let train_conflicts = match cache {
+        Some(cache) => {
+            let changed_trains = cache.changed_trains(&train_keys);
+            let mut conflicts = cache.unchanged_conflicts(&train_keys, &changed_trains);
+            if !changed_trains.is_empty() {
+                let checked_trains = trains_to_check(&trains_requirements, &changed_trains);
+                trains_requirements.retain(|id, _| checked_trains.contains(id));
+                let new_conflicts = ConflictDetectionRequest {
+                    trains_requirements,
+                }
+                .fetch(&core_client)
+                .await?
+                .conflicts;
+                conflicts.extend(new_conflicts.into_iter().filter(|conflict| {
+                    conflict
+                        .train_ids
+                        .iter()
+                        .any(|id| changed_trains.contains(id))
+                }));
+            }
+            conflicts
+        }
+        None => {
+            ConflictDetectionRequest {
+                trains_requirements,
+            }
+            .fetch(&core_client)
+            .await?
+            .conflicts
+        }
+    };
+    let cache = ConflictsCache {
+        train_keys,
+        conflicts: train_conflicts,
     };
+    cache.set(&mut redis_conn, timetable_id, infra_id).await?;
 
-    // 3. Call core
-    let conflicts = conflict_detection_request.fetch(&core_client).await?;
+    let mut conflicts = cache.conflicts;
+    conflicts.extend(work_schedule_conflicts);
 
-    Ok(Json(conflicts.conflicts))
+    Ok(Json(conflicts))
 }
 
 #[cfg(test)]
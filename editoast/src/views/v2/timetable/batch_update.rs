@@ -0,0 +1,322 @@
+//! Update of a selection of the train schedules of a timetable at once
+
+use actix_web::patch;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use chrono::Duration;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use editoast_schemas::train_schedule::Margins;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::TimetableError;
+use super::TimetableIdParam;
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::timetable::Timetable;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::train_schedule::TrainScheduleSelection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::RollingStockModel;
+
+crate::routes! {
+    batch_update,
+}
+
+editoast_common::schemas! {
+    TrainScheduleBatchUpdateForm,
+    TrainScheduleSelection,
+    TrainScheduleBatchPatch,
+    TrainScheduleBatchUpdateResult,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct TrainScheduleBatchUpdateForm {
+    /// The train schedules to update, all the train schedules of the timetable if empty
+    #[serde(default)]
+    selection: TrainScheduleSelection,
+    patch: TrainScheduleBatchPatch,
+}
+
+/// The changes applied to each selected train schedule
+#[derive(Debug, Default, Deserialize, ToSchema)]
+struct TrainScheduleBatchPatch {
+    /// Shift of the start time in ms, negative to start earlier
+    time_shift: Option<i64>,
+    /// The new rolling stock, which also removes the composition of the train schedules
+    rolling_stock_name: Option<String>,
+    /// The new speed limit tag, `null` to remove it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    speed_limit_tag: Option<Option<String>>,
+    margins: Option<Margins>,
+}
+
+/// Distinguishes a field set to `null` from a missing field
+fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl TrainScheduleBatchPatch {
+    fn is_empty(&self) -> bool {
+        self.time_shift.is_none()
+            && self.rolling_stock_name.is_none()
+            && self.speed_limit_tag.is_none()
+            && self.margins.is_none()
+    }
+
+    /// The shift of the start time, if it can be represented
+    fn time_shift(&self) -> Result<Option<Duration>> {
+        self.time_shift
+            .map(|time_shift| {
+                Duration::try_milliseconds(time_shift)
+                    .ok_or_else(|| TimetableError::InvalidTimeShift { time_shift }.into())
+            })
+            .transpose()
+    }
+
+    /// Apply the patch to a train schedule, which is detached from its pattern
+    ///
+    /// The margins boundaries must be path items of the train schedule, and the shifted start
+    /// time must stay within the supported dates.
+    fn apply(&self, train_schedule: &mut TrainSchedule) -> Result<()> {
+        if let Some(delta) = self.time_shift()? {
+            train_schedule.start_time = train_schedule
+                .start_time
+                .checked_add_signed(delta)
+                .ok_or_else(|| TimetableError::InvalidTimeShift {
+                    time_shift: delta.num_milliseconds(),
+                })?;
+        }
+        if let Some(rolling_stock_name) = &self.rolling_stock_name {
+            train_schedule.rolling_stock_name = rolling_stock_name.clone();
+            train_schedule.rolling_stock_id = None;
+            train_schedule.rolling_stock_version = None;
+            train_schedule.composition_id = None;
+        }
+        if let Some(speed_limit_tag) = &self.speed_limit_tag {
+            train_schedule.speed_limit_tag = speed_limit_tag.clone();
+        }
+        if let Some(margins) = &self.margins {
+            let valid_boundaries = margins.boundaries.iter().all(|boundary| {
+                train_schedule
+                    .path
+                    .iter()
+                    .any(|path_item| &path_item.id == boundary)
+            });
+            if !valid_boundaries || margins.values.len() != margins.boundaries.len() + 1 {
+                return Err(TimetableError::InvalidMargins {
+                    train_id: train_schedule.id,
+                }
+                .into());
+            }
+            train_schedule.margins = margins.clone();
+        }
+        train_schedule.pattern_id = None;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct TrainScheduleBatchUpdateResult {
+    /// The ids of the updated train schedules
+    train_ids: Vec<i64>,
+}
+
+/// Update the train schedules of a timetable matching a selection
+///
+/// The selected train schedules are all updated, or none of them if an error occurs.
+#[utoipa::path(
+    tag = "timetablev2,train_schedulev2",
+    params(TimetableIdParam),
+    request_body = TrainScheduleBatchUpdateForm,
+    responses(
+        (status = 200, description = "The ids of the updated train schedules", body = TrainScheduleBatchUpdateResult),
+        (status = 400, description = "The patch is empty, its time shift is out of range or its margins don't match the path of a train schedule"),
+        (status = 404, description = "The timetable or the rolling stock was not found"),
+    )
+)]
+#[patch("train_schedule")]
+async fn batch_update(
+    db_pool: Data<DbConnectionPoolV2>,
+    timetable_id: Path<TimetableIdParam>,
+    data: Json<TrainScheduleBatchUpdateForm>,
+) -> Result<Json<TrainScheduleBatchUpdateResult>> {
+    let timetable_id = timetable_id.id;
+    let TrainScheduleBatchUpdateForm { selection, patch } = data.into_inner();
+    if patch.is_empty() {
+        return Err(TimetableError::EmptyPatch.into());
+    }
+    patch.time_shift()?;
+    let conn = &mut db_pool.get().await?;
+
+    Timetable::retrieve_or_fail(conn, timetable_id, || TimetableError::NotFound {
+        timetable_id,
+    })
+    .await?;
+    if let Some(rolling_stock_name) = &patch.rolling_stock_name {
+        if !RollingStockModel::exists(conn, rolling_stock_name.clone()).await? {
+            return Err(TimetableError::RollingStockNotFound {
+                rolling_stock_name: rolling_stock_name.clone(),
+            }
+            .into());
+        }
+    }
+
+    let train_ids = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let train_schedules =
+                    TrainSchedule::list_selection(conn, timetable_id, &selection).await?;
+                let mut train_ids = Vec::with_capacity(train_schedules.len());
+                for mut train_schedule in train_schedules {
+                    patch.apply(&mut train_schedule)?;
+                    train_schedule.save(conn).await?;
+                    train_ids.push(train_schedule.id);
+                }
+                Ok(train_ids)
+            }
+            .scope_boxed()
+        })
+        .await?;
+    Ok(Json(TrainScheduleBatchUpdateResult { train_ids }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut as _;
+
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_simple_train_schedule;
+    use crate::modelsv2::fixtures::create_timetable;
+    use crate::views::test_app::TestAppBuilder;
+
+    #[rstest]
+    async fn shift_selected_train_schedules() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let selected = create_simple_train_schedule(pool.get_ok().deref_mut(), timetable.id).await;
+        let mut other = create_simple_train_schedule(pool.get_ok().deref_mut(), timetable.id).await;
+        other.rolling_stock_name = "other_rolling_stock".to_string();
+        other.save(pool.get_ok().deref_mut()).await.unwrap();
+
+        let request = TestRequest::patch()
+            .uri(&format!("/v2/timetable/{}/train_schedule", timetable.id))
+            .set_json(json!({
+                "selection": { "rolling_stock_name": selected.rolling_stock_name },
+                "patch": { "time_shift": 600_000, "speed_limit_tag": "MA100" },
+            }))
+            .to_request();
+        let result: TrainScheduleBatchUpdateResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+        assert_eq!(result.train_ids, vec![selected.id]);
+
+        let updated = TrainSchedule::retrieve(pool.get_ok().deref_mut(), selected.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            updated.start_time,
+            selected.start_time + Duration::minutes(10)
+        );
+        assert_eq!(updated.speed_limit_tag, Some("MA100".to_string()));
+        let not_updated = TrainSchedule::retrieve(pool.get_ok().deref_mut(), other.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(not_updated.start_time, other.start_time);
+    }
+
+    #[rstest]
+    async fn margins_outside_of_the_path_are_rejected() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let train_schedule =
+            create_simple_train_schedule(pool.get_ok().deref_mut(), timetable.id).await;
+
+        let request = TestRequest::patch()
+            .uri(&format!("/v2/timetable/{}/train_schedule", timetable.id))
+            .set_json(json!({
+                "patch": {
+                    "time_shift": 600_000,
+                    "margins": { "boundaries": ["not_on_the_path"], "values": ["5%", "none"] },
+                },
+            }))
+            .to_request();
+        let response = app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+        let error: InternalError = response.json_into();
+        assert_eq!(error.context["train_id"], json!(train_schedule.id));
+
+        let not_updated = TrainSchedule::retrieve(pool.get_ok().deref_mut(), train_schedule.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(not_updated.start_time, train_schedule.start_time);
+    }
+
+    #[rstest]
+    #[case::unrepresentable(i64::MIN)]
+    #[case::beyond_supported_dates(i64::MAX)]
+    async fn out_of_range_time_shift_is_rejected(#[case] time_shift: i64) {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let train_schedule =
+            create_simple_train_schedule(pool.get_ok().deref_mut(), timetable.id).await;
+
+        let request = TestRequest::patch()
+            .uri(&format!("/v2/timetable/{}/train_schedule", timetable.id))
+            .set_json(json!({ "patch": { "time_shift": time_shift } }))
+            .to_request();
+        let error: InternalError = app
+            .fetch(request)
+            .assert_status(StatusCode::BAD_REQUEST)
+            .json_into();
+        assert_eq!(error.context["time_shift"], json!(time_shift));
+
+        let not_updated = TrainSchedule::retrieve(pool.get_ok().deref_mut(), train_schedule.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(not_updated.start_time, train_schedule.start_time);
+    }
+
+    #[rstest]
+    async fn empty_patch_is_rejected() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::patch()
+            .uri(&format!("/v2/timetable/{}/train_schedule", timetable.id))
+            .set_json(json!({ "patch": {} }))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn null_speed_limit_tag_is_removed() {
+        let patch: TrainScheduleBatchPatch =
+            serde_json::from_value(json!({ "speed_limit_tag": null })).unwrap();
+        assert_eq!(patch.speed_limit_tag, Some(None));
+        let patch: TrainScheduleBatchPatch = serde_json::from_value(json!({})).unwrap();
+        assert!(patch.is_empty());
+    }
+}
@@ -0,0 +1,1124 @@
+//! Explanation of a conflict and candidate resolutions
+//!
+//! The zones and routes involved in a conflict are located on the path of each of its trains.
+//! Resolutions delay one of the trains, either at its departure or with a stop before the
+//! conflict, or reroute it through a detour around the tracks of the conflict. Each resolution is
+//! checked by running the conflict detection again.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::ops::DerefMut as _;
+use std::slice;
+use std::sync::Arc;
+
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use chashmap::CHashMap;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use editoast_common::geometry::GeoJsonLineString;
+use editoast_derive::EditoastError;
+use editoast_schemas::infra::Direction;
+use editoast_schemas::infra::Endpoint;
+use editoast_schemas::infra::TrackEndpoint;
+use editoast_schemas::infra::TrackOffset;
+use editoast_schemas::primitives::Identifier;
+use editoast_schemas::primitives::NonBlankString;
+use editoast_schemas::primitives::PositiveDuration;
+use editoast_schemas::train_schedule::PathItem;
+use editoast_schemas::train_schedule::PathItemLocation;
+use editoast_schemas::train_schedule::ScheduleItem;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::conflicts_cache::requirements_time_window;
+use super::work_schedule_conflicts::TrainOccupancy;
+use super::TimetableIdParam;
+use crate::core::v2::conflict_detection::Conflict;
+use crate::core::v2::conflict_detection::ConflictDetectionRequest;
+use crate::core::v2::conflict_detection::ConflictType;
+use crate::core::v2::conflict_detection::TrainRequirements;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::core::v2::pathfinding::PathfindingResultSuccess;
+use crate::core::v2::pathfinding::TrackRange;
+use crate::core::v2::simulation::ReportTrain;
+use crate::core::v2::simulation::SimulationResponse;
+use crate::core::v2::simulation::ZoneUpdate;
+use crate::core::AsCoreRequest;
+use crate::error::Result;
+use crate::infra_cache::Graph;
+use crate::infra_cache::InfraCache;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::timetable::TimetableWithTrains;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::work_schedules::WorkSchedule;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+use crate::modelsv2::RollingStockModel;
+use crate::views::v2::path::pathfinding_from_train;
+use crate::views::v2::path::properties::path_geometry;
+use crate::views::v2::train_schedule::train_simulation;
+use crate::views::v2::train_schedule::train_simulation_batch;
+use crate::CoreClient;
+use crate::RedisClient;
+use crate::RetrieveBatch;
+
+/// The maximum number of times a resolution is adjusted to solve the conflicts it creates
+const MAX_ITERATIONS: usize = 10;
+
+/// The maximum delay a resolution can add to a train, in hours
+const MAX_DELAY_HOURS: i64 = 24;
+
+/// The maximum number of tracks of a detour
+const MAX_DETOUR_TRACKS: usize = 20;
+
+crate::routes! {
+    explain_conflict,
+}
+
+editoast_common::schemas! {
+    ConflictExplanation,
+    TrainConflictExplanation,
+    ConflictZone,
+    ConflictResolution,
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "conflict_explanation")]
+enum ConflictExplanationError {
+    #[error("Timetable '{timetable_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    TimetableNotFound { timetable_id: i64 },
+    #[error("Infra '{infra_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    InfraNotFound { infra_id: i64 },
+    #[error("Train '{train_id}' of the conflict is not part of the timetable")]
+    #[editoast_error(status = 404)]
+    TrainNotFound { train_id: i64 },
+    #[error("Train '{train_id}' of the conflict could not be simulated")]
+    #[editoast_error(status = 400)]
+    UnsimulatedTrain { train_id: i64 },
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct InfraIdQueryParam {
+    infra_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct ConflictExplanation {
+    /// What each train of the conflict requires during the conflict
+    trains: Vec<TrainConflictExplanation>,
+    /// Candidate resolutions, each of them solves the conflicts of the train it changes
+    resolutions: Vec<ConflictResolution>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct TrainConflictExplanation {
+    train_id: i64,
+    /// The routes required by the train during the conflict
+    routes: Vec<String>,
+    /// The zones required by the train during the conflict, located on its path
+    zones: Vec<ConflictZone>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct ConflictZone {
+    zone: String,
+    /// The track ranges of the zone, on the path of the train
+    track_section_ranges: Vec<TrackRange>,
+    geometry: GeoJsonLineString,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ConflictResolution {
+    /// Delay the departure of a train
+    ShiftDeparture {
+        train_id: i64,
+        /// The new departure time of the train
+        start_time: DateTime<Utc>,
+    },
+    /// Stop a train, or lengthen its stop, at a path item before the conflict
+    AddStop {
+        train_id: i64,
+        /// The path item where the train stops
+        at: String,
+        /// The new duration of the stop
+        #[schema(value_type = String, example = "PT5M")]
+        stop_for: PositiveDuration,
+    },
+    /// Reroute a train through a detour around the tracks of the conflict
+    Reroute {
+        train_id: i64,
+        /// The new path of the train, with via points on the detour
+        path: Vec<PathItem>,
+    },
+}
+
+/// Explain a conflict of the timetable and propose resolutions
+///
+/// A detour is the shortest alternative, in number of tracks, to the part of the path of a train
+/// going through the conflict zones.
+#[utoipa::path(
+    tag = "timetablev2",
+    params(TimetableIdParam, InfraIdQueryParam),
+    request_body = ConflictV2,
+    responses(
+        (status = 200, description = "The explanation of the conflict", body = ConflictExplanation),
+        (status = 404, description = "The timetable, the infra or a train of the conflict was not found"),
+    ),
+)]
+#[post("/conflicts/explanation")]
+async fn explain_conflict(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    core_client: Data<CoreClient>,
+    infra_caches: Data<CHashMap<i64, InfraCache>>,
+    timetable_id: Path<TimetableIdParam>,
+    query: Query<InfraIdQueryParam>,
+    data: Json<Conflict>,
+) -> Result<Json<ConflictExplanation>> {
+    let db_pool = db_pool.into_inner();
+    let conn = &mut db_pool.clone().get().await?;
+    let redis_client = redis_client.into_inner();
+    let core_client = core_client.into_inner();
+    let timetable_id = timetable_id.into_inner().id;
+    let infra_id = query.into_inner().infra_id;
+    let conflict = data.into_inner();
+
+    // 1. Retrieve and simulate the trains of the timetable
+    let timetable = TimetableWithTrains::retrieve_or_fail(conn, timetable_id, || {
+        ConflictExplanationError::TimetableNotFound { timetable_id }
+    })
+    .await?;
+    let infra = Infra::retrieve_or_fail(conn, infra_id, || {
+        ConflictExplanationError::InfraNotFound { infra_id }
+    })
+    .await?;
+    let (trains, _): (Vec<_>, _) = TrainSchedule::retrieve_batch(conn, timetable.train_ids).await?;
+    if let Some(train_id) = conflict
+        .train_ids
+        .iter()
+        .find(|train_id| !trains.iter().any(|train| train.id == **train_id))
+    {
+        return Err(ConflictExplanationError::TrainNotFound {
+            train_id: *train_id,
+        }
+        .into());
+    }
+    let simulations = train_simulation_batch(
+        db_pool.clone(),
+        redis_client.clone(),
+        core_client.clone(),
+        &trains,
+        &infra,
+    )
+    .await?;
+
+    // 2. Retrieve the requirements of the trains, and the path of the trains of the conflict
+    let mut redis_conn = redis_client.get_connection().await?;
+    let mut trains_requirements = HashMap::with_capacity(trains.len());
+    let mut involved_trains = vec![];
+    let mut time_window: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+    for (train, sim) in trains.into_iter().zip(simulations) {
+        let SimulationResponse::Success { final_output, .. } = sim else {
+            continue;
+        };
+        let requirements = TrainRequirements {
+            start_time: train.start_time,
+            spacing_requirements: final_output.spacing_requirements,
+            routing_requirements: final_output.routing_requirements,
+        };
+        let (begin, end) = requirements_time_window(&requirements);
+        time_window = Some(match time_window {
+            Some((start_time, end_time)) => (start_time.min(begin), end_time.max(end)),
+            None => (begin, end),
+        });
+        trains_requirements.insert(train.id, requirements);
+        if !conflict.train_ids.contains(&train.id) {
+            continue;
+        }
+
+        let rolling_stock = RollingStockModel::retrieve_for_train(
+            conn,
+            train.rolling_stock_name.clone(),
+            train.rolling_stock_revision(),
+            train.composition_id,
+        )
+        .await?
+        .ok_or(ConflictExplanationError::UnsimulatedTrain { train_id: train.id })?;
+        let PathfindingResult::Success(PathfindingResultSuccess {
+            track_section_ranges,
+            path_items_positions,
+            ..
+        }) = pathfinding_from_train(
+            conn,
+            &mut redis_conn,
+            core_client.clone(),
+            &infra,
+            train.clone(),
+        )
+        .await?
+        else {
+            return Err(ConflictExplanationError::UnsimulatedTrain { train_id: train.id }.into());
+        };
+        involved_trains.push(InvolvedTrain {
+            train,
+            report_train: final_output.report_train,
+            zone_updates: final_output.zone_updates,
+            track_section_ranges,
+            path_items_positions,
+            length: (rolling_stock.length * 1000.).round() as u64,
+        });
+    }
+    if let Some(train_id) = conflict
+        .train_ids
+        .iter()
+        .find(|train_id| !trains_requirements.contains_key(train_id))
+    {
+        return Err(ConflictExplanationError::UnsimulatedTrain {
+            train_id: *train_id,
+        }
+        .into());
+    }
+
+    // Resolutions may delay the trains up to the maximum delay
+    let work_schedules = match time_window {
+        Some((start_time, end_time)) => {
+            let end_time = end_time + Duration::hours(MAX_DELAY_HOURS);
+            WorkSchedule::list_in_time_window(conn, start_time.naive_utc(), end_time.naive_utc())
+                .await?
+        }
+        None => vec![],
+    };
+    let checker = ConflictChecker {
+        core_client: &core_client,
+        trains_requirements,
+        work_schedules,
+    };
+
+    // 3. Locate the zones and routes of the conflict on the path of each train
+    let mut explanations = vec![];
+    for train in &involved_trains {
+        let (zones, routes) = checker.conflict_zones(train.train.id, &conflict);
+        let mut conflict_zones = vec![];
+        for zone in zones {
+            let track_section_ranges = train.zone_track_ranges(zone);
+            if track_section_ranges.is_empty() {
+                continue;
+            }
+            let geometry = path_geometry(
+                &mut redis_conn,
+                &core_client,
+                &infra,
+                track_section_ranges.clone(),
+            )
+            .await?;
+            conflict_zones.push(ConflictZone {
+                zone: zone.to_string(),
+                track_section_ranges,
+                geometry,
+            });
+        }
+        explanations.push(TrainConflictExplanation {
+            train_id: train.train.id,
+            routes: routes.into_iter().map(str::to_string).collect(),
+            zones: conflict_zones,
+        });
+    }
+
+    // 4. Look for detours around the tracks of the conflict zones
+    let detour_paths: HashMap<_, _> = {
+        let infra_cache = InfraCache::get_or_load(conn, &infra_caches, &infra).await?;
+        let graph = Graph::load(&infra_cache);
+        involved_trains
+            .iter()
+            .zip(&explanations)
+            .filter_map(|(train, explanation)| {
+                let conflict_tracks = explanation
+                    .zones
+                    .iter()
+                    .flat_map(|zone| &zone.track_section_ranges)
+                    .map(|range| &range.track_section)
+                    .collect();
+                let path = train.detour_path(&infra_cache, &graph, &conflict_tracks)?;
+                Some((train.train.id, path))
+            })
+            .collect()
+    };
+
+    // 5. Look for resolutions
+    let mut resolutions = vec![];
+    for train in &involved_trains {
+        if let Some(resolution) = checker.shift_departure(train, &conflict).await? {
+            resolutions.push(resolution);
+        }
+        let resolution = checker
+            .add_stop(
+                db_pool.get().await?.deref_mut(),
+                redis_client.clone(),
+                core_client.clone(),
+                &infra,
+                train,
+                &conflict,
+            )
+            .await?;
+        if let Some(resolution) = resolution {
+            resolutions.push(resolution);
+        }
+        let Some(path) = detour_paths.get(&train.train.id) else {
+            continue;
+        };
+        let resolution = checker
+            .reroute(
+                db_pool.get().await?.deref_mut(),
+                redis_client.clone(),
+                core_client.clone(),
+                &infra,
+                train,
+                path.clone(),
+            )
+            .await?;
+        if let Some(resolution) = resolution {
+            resolutions.push(resolution);
+        }
+    }
+
+    Ok(Json(ConflictExplanation {
+        trains: explanations,
+        resolutions,
+    }))
+}
+
+/// A simulated train of the conflict
+struct InvolvedTrain {
+    train: TrainSchedule,
+    report_train: ReportTrain,
+    zone_updates: Vec<ZoneUpdate>,
+    track_section_ranges: Vec<TrackRange>,
+    path_items_positions: Vec<u64>,
+    /// Length of the train in mm
+    length: u64,
+}
+
+impl InvolvedTrain {
+    fn occupancy<'a>(
+        &'a self,
+        start_time: DateTime<Utc>,
+        report_train: &'a ReportTrain,
+    ) -> TrainOccupancy<'a> {
+        TrainOccupancy {
+            train_id: self.train.id,
+            start_time,
+            track_section_ranges: &self.track_section_ranges,
+            report_train,
+            length: self.length,
+        }
+    }
+
+    /// The track ranges of a zone on the path of the train
+    ///
+    /// The train leaves a zone when its tail passes the end of the zone.
+    fn zone_track_ranges(&self, zone: &str) -> Vec<TrackRange> {
+        let path_length = self
+            .report_train
+            .positions
+            .last()
+            .copied()
+            .unwrap_or_default();
+        let updates = self
+            .zone_updates
+            .iter()
+            .filter(|update| update.zone == zone);
+        let Some(begin) = updates
+            .clone()
+            .filter(|update| update.is_entry)
+            .map(|update| update.position)
+            .min()
+        else {
+            return vec![];
+        };
+        let end = updates
+            .filter(|update| !update.is_entry)
+            .map(|update| update.position.saturating_sub(self.length))
+            .max()
+            .unwrap_or(path_length);
+        path_track_ranges(&self.track_section_ranges, begin, end)
+    }
+
+    /// A copy of the path of the train with via points on a detour around some of its tracks
+    ///
+    /// The detour leaves the path at the end of the track before the avoided tracks and joins it
+    /// back at the start of the track after them. It is only looked for when no path item of the
+    /// train is located on the avoided part of the path.
+    fn detour_path(
+        &self,
+        infra_cache: &InfraCache,
+        graph: &Graph,
+        avoided_tracks: &HashSet<&Identifier>,
+    ) -> Option<Vec<PathItem>> {
+        let ranges = &self.track_section_ranges;
+        let first = ranges
+            .iter()
+            .position(|range| avoided_tracks.contains(&range.track_section))?;
+        let last = ranges
+            .iter()
+            .rposition(|range| avoided_tracks.contains(&range.track_section))?;
+        let before = &ranges[first.checked_sub(1)?];
+        let after = ranges.get(last + 1)?;
+        let leave_position: u64 = ranges[..first].iter().map(TrackRange::length).sum();
+        let join_position: u64 = ranges[..=last].iter().map(TrackRange::length).sum();
+        let path_item_index = self
+            .path_items_positions
+            .iter()
+            .rposition(|position| *position <= leave_position)?;
+        if *self.path_items_positions.get(path_item_index + 1)? < join_position {
+            return None;
+        }
+
+        let from = TrackEndpoint::from_track_and_direction(&before.track_section, before.direction);
+        let to_endpoint = match after.direction {
+            Direction::StartToStop => Endpoint::Begin,
+            Direction::StopToStart => Endpoint::End,
+        };
+        let to = TrackEndpoint::new(&after.track_section, to_endpoint);
+        // The detour doesn't run on the tracks of the path, to be a genuine alternative
+        let path_tracks = ranges.iter().map(|range| &range.track_section).collect();
+        let detour = detour_tracks(graph, &from, &to, &path_tracks)?;
+        if detour.is_empty() {
+            return None;
+        }
+        let via_points = detour
+            .into_iter()
+            .map(|track| {
+                let length = infra_cache.get_track_section(&track).ok()?.length;
+                Some(PathItem {
+                    id: NonBlankString(format!("detour-{}", track.0)),
+                    deleted: false,
+                    location: PathItemLocation::TrackOffset(TrackOffset {
+                        track,
+                        // The middle of the track, in mm
+                        offset: (length * 500.).round() as u64,
+                    }),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut path = self.train.path.clone();
+        path.splice(path_item_index + 1..path_item_index + 1, via_points);
+        Some(path)
+    }
+}
+
+/// A zone required by a train during a time span
+struct ZoneRequirement<'a> {
+    zone: &'a str,
+    /// The route requiring the zone, if required for routing
+    route: Option<&'a str>,
+    begin_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+impl ZoneRequirement<'_> {
+    fn overlaps(&self, begin_time: DateTime<Utc>, end_time: DateTime<Utc>) -> bool {
+        self.begin_time < end_time && begin_time < self.end_time
+    }
+
+    /// Whether the requirement can cause a conflict of the given type
+    fn causes(&self, conflict: &Conflict) -> bool {
+        match conflict.conflict_type {
+            ConflictType::Spacing => self.route.is_none(),
+            ConflictType::Routing => self.route.is_some(),
+            ConflictType::WorkSchedule => true,
+        }
+    }
+}
+
+fn zone_requirements(requirements: &TrainRequirements) -> Vec<ZoneRequirement<'_>> {
+    let start_time = requirements.start_time;
+    let spacing = requirements
+        .spacing_requirements
+        .iter()
+        .map(move |requirement| ZoneRequirement {
+            zone: &requirement.zone,
+            route: None,
+            begin_time: start_time + Duration::milliseconds(requirement.begin_time as i64),
+            end_time: start_time + Duration::milliseconds(requirement.end_time as i64),
+        });
+    let routing = requirements
+        .routing_requirements
+        .iter()
+        .flat_map(move |requirement| {
+            requirement.zones.iter().map(move |zone| ZoneRequirement {
+                zone: &zone.zone,
+                route: Some(&requirement.route),
+                begin_time: start_time + Duration::milliseconds(requirement.begin_time as i64),
+                end_time: start_time + Duration::milliseconds(zone.end_time as i64),
+            })
+        });
+    spacing.chain(routing).collect()
+}
+
+/// Checks the requirements of a train against the other trains of the timetable and the work schedules
+struct ConflictChecker<'a> {
+    core_client: &'a CoreClient,
+    trains_requirements: HashMap<i64, TrainRequirements>,
+    work_schedules: Vec<WorkSchedule>,
+}
+
+impl ConflictChecker<'_> {
+    /// The zones and routes of a train causing a conflict
+    ///
+    /// When the conflict involves other trains, only the zones they also require are kept.
+    fn conflict_zones(&self, train_id: i64, conflict: &Conflict) -> (Vec<&str>, Vec<&str>) {
+        let train_zones: Vec<_> = zone_requirements(&self.trains_requirements[&train_id])
+            .into_iter()
+            .filter(|zone| zone.causes(conflict))
+            .filter(|zone| zone.overlaps(conflict.start_time, conflict.end_time))
+            .collect();
+        let other_zones: Vec<_> = conflict
+            .train_ids
+            .iter()
+            .filter(|id| **id != train_id)
+            .filter_map(|id| self.trains_requirements.get(id))
+            .flat_map(zone_requirements)
+            .filter(|zone| zone.causes(conflict))
+            .filter(|zone| zone.overlaps(conflict.start_time, conflict.end_time))
+            .collect();
+        let train_zones: Vec<_> = train_zones
+            .into_iter()
+            .filter(|zone| {
+                other_zones.is_empty() || other_zones.iter().any(|other| other.zone == zone.zone)
+            })
+            .collect();
+        let zones = train_zones.iter().map(|zone| zone.zone).unique().collect();
+        let routes = train_zones
+            .iter()
+            .filter_map(|zone| zone.route)
+            .unique()
+            .collect();
+        (zones, routes)
+    }
+
+    /// The conflicts of a train with the other trains and the work schedules
+    async fn train_conflicts(
+        &self,
+        requirements: &TrainRequirements,
+        occupancy: &TrainOccupancy<'_>,
+    ) -> Result<Vec<Conflict>> {
+        let train_id = occupancy.train_id;
+        // Only the trains running at the same time can be in conflict with the train
+        let (begin, end) = requirements_time_window(requirements);
+        let mut trains_requirements: HashMap<_, _> = self
+            .trains_requirements
+            .iter()
+            .filter(|(id, other)| {
+                let (other_begin, other_end) = requirements_time_window(other);
+                **id != train_id && other_begin <= end && begin <= other_end
+            })
+            .map(|(id, other)| (*id, other.clone()))
+            .collect();
+        trains_requirements.insert(train_id, requirements.clone());
+        let mut conflicts: Vec<_> = ConflictDetectionRequest {
+            trains_requirements,
+        }
+        .fetch(self.core_client)
+        .await?
+        .conflicts
+        .into_iter()
+        .filter(|conflict| conflict.train_ids.contains(&train_id))
+        .collect();
+        conflicts.extend(
+            self.work_schedules
+                .iter()
+                .filter_map(|work_schedule| occupancy.work_schedule_conflict(work_schedule)),
+        );
+        Ok(conflicts)
+    }
+
+    /// The delay to add to a train so that it requires the zones of its conflicts after the other
+    /// trains and the work schedules
+    fn delay(
+        &self,
+        train_id: i64,
+        requirements: &TrainRequirements,
+        conflicts: &[Conflict],
+    ) -> Duration {
+        let zones = zone_requirements(requirements);
+        let mut delay = Duration::zero();
+        for conflict in conflicts {
+            let train_zones = zones
+                .iter()
+                .filter(|zone| zone.causes(conflict))
+                .filter(|zone| zone.overlaps(conflict.start_time, conflict.end_time));
+            let other_zones: Vec<_> = conflict
+                .train_ids
+                .iter()
+                .filter(|id| **id != train_id)
+                .filter_map(|id| self.trains_requirements.get(id))
+                .flat_map(zone_requirements)
+                .filter(|zone| zone.causes(conflict))
+                .filter(|zone| zone.overlaps(conflict.start_time, conflict.end_time))
+                .collect();
+            for zone in train_zones {
+                for other in other_zones.iter().filter(|other| {
+                    other.zone == zone.zone && other.overlaps(zone.begin_time, zone.end_time)
+                }) {
+                    delay = delay.max(other.end_time - zone.begin_time);
+                }
+            }
+            for work_schedule in self
+                .work_schedules
+                .iter()
+                .filter(|work_schedule| conflict.work_schedule_ids.contains(&work_schedule.id))
+            {
+                delay = delay.max(work_schedule.end_date_time.and_utc() - conflict.start_time);
+            }
+        }
+        delay
+    }
+
+    /// Delay the departure of a train by the smallest amount solving its conflicts
+    async fn shift_departure(
+        &self,
+        train: &InvolvedTrain,
+        conflict: &Conflict,
+    ) -> Result<Option<ConflictResolution>> {
+        let train_id = train.train.id;
+        let requirements = &self.trains_requirements[&train_id];
+        let mut delay = self.delay(train_id, requirements, slice::from_ref(conflict));
+        for _ in 0..MAX_ITERATIONS {
+            if delay <= Duration::zero() || delay > Duration::hours(MAX_DELAY_HOURS) {
+                break;
+            }
+            let start_time = train.train.start_time + delay;
+            let requirements = TrainRequirements {
+                start_time,
+                ..requirements.clone()
+            };
+            let occupancy = train.occupancy(start_time, &train.report_train);
+            let conflicts = self.train_conflicts(&requirements, &occupancy).await?;
+            if conflicts.is_empty() {
+                return Ok(Some(ConflictResolution::ShiftDeparture {
+                    train_id,
+                    start_time,
+                }));
+            }
+            let additional_delay = self.delay(train_id, &requirements, &conflicts);
+            if additional_delay <= Duration::zero() {
+                break;
+            }
+            delay += additional_delay;
+        }
+        Ok(None)
+    }
+
+    /// Stop a train before its conflict, for the shortest duration solving its conflicts
+    ///
+    /// The train stops at the last path item it reaches before requiring the zones of the conflict.
+    async fn add_stop(
+        &self,
+        conn: &mut DbConnection,
+        redis_client: Arc<RedisClient>,
+        core_client: Arc<CoreClient>,
+        infra: &Infra,
+        train: &InvolvedTrain,
+        conflict: &Conflict,
+    ) -> Result<Option<ConflictResolution>> {
+        let train_id = train.train.id;
+        let start_time = train.train.start_time;
+        let requirements = &self.trains_requirements[&train_id];
+        let first_requirement_time = zone_requirements(requirements)
+            .iter()
+            .filter(|zone| zone.causes(conflict))
+            .filter(|zone| zone.overlaps(conflict.start_time, conflict.end_time))
+            .map(|zone| zone.begin_time)
+            .min()
+            .unwrap_or(conflict.start_time);
+        let first_requirement_time = (first_requirement_time - start_time)
+            .num_milliseconds()
+            .max(0) as u64;
+        let position = position_at_time(&train.report_train, first_requirement_time);
+        // The departure of the train is delayed by the other resolution
+        let Some(path_item_index) = train
+            .path_items_positions
+            .iter()
+            .rposition(|path_item_position| *path_item_position < position)
+            .filter(|index| *index > 0)
+        else {
+            return Ok(None);
+        };
+        let at = &train.train.path[path_item_index].id;
+        let current_stop = train
+            .train
+            .schedule
+            .iter()
+            .find(|item| &item.at == at)
+            .and_then(|item| item.stop_for.as_ref())
+            .map(|stop_for| **stop_for)
+            .unwrap_or_else(Duration::zero);
+
+        let mut delay = self.delay(train_id, requirements, slice::from_ref(conflict));
+        for _ in 0..MAX_ITERATIONS {
+            if delay <= Duration::zero() || delay > Duration::hours(MAX_DELAY_HOURS) {
+                break;
+            }
+            let Ok(stop_for) = PositiveDuration::try_from(current_stop + delay) else {
+                break;
+            };
+            let train_schedule = with_stop(&train.train, path_item_index, stop_for.clone());
+            let simulation = train_simulation(
+                conn,
+                redis_client.clone(),
+                core_client.clone(),
+                &train_schedule,
+                infra,
+            )
+            .await?;
+            let SimulationResponse::Success { final_output, .. } = simulation else {
+                break;
+            };
+            let requirements = TrainRequirements {
+                start_time,
+                spacing_requirements: final_output.spacing_requirements,
+                routing_requirements: final_output.routing_requirements,
+            };
+            let occupancy = train.occupancy(start_time, &final_output.report_train);
+            let conflicts = self.train_conflicts(&requirements, &occupancy).await?;
+            if conflicts.is_empty() {
+                return Ok(Some(ConflictResolution::AddStop {
+                    train_id,
+                    at: at.0.clone(),
+                    stop_for,
+                }));
+            }
+            let additional_delay = self.delay(train_id, &requirements, &conflicts);
+            if additional_delay <= Duration::zero() {
+                break;
+            }
+            delay += additional_delay;
+        }
+        Ok(None)
+    }
+
+    /// Reroute a train through a detour, if it solves its conflicts
+    async fn reroute(
+        &self,
+        conn: &mut DbConnection,
+        redis_client: Arc<RedisClient>,
+        core_client: Arc<CoreClient>,
+        infra: &Infra,
+        train: &InvolvedTrain,
+        path: Vec<PathItem>,
+    ) -> Result<Option<ConflictResolution>> {
+        let train_id = train.train.id;
+        let start_time = train.train.start_time;
+        let train_schedule = TrainSchedule {
+            path,
+            ..train.train.clone()
+        };
+        let simulation = train_simulation(
+            conn,
+            redis_client.clone(),
+            core_client.clone(),
+            &train_schedule,
+            infra,
+        )
+        .await?;
+        let SimulationResponse::Success { final_output, .. } = simulation else {
+            return Ok(None);
+        };
+        let mut redis_conn = redis_client.get_connection().await?;
+        let PathfindingResult::Success(PathfindingResultSuccess {
+            track_section_ranges,
+            ..
+        }) = pathfinding_from_train(
+            conn,
+            &mut redis_conn,
+            core_client,
+            infra,
+            train_schedule.clone(),
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+        let requirements = TrainRequirements {
+            start_time,
+            spacing_requirements: final_output.spacing_requirements,
+            routing_requirements: final_output.routing_requirements,
+        };
+        let occupancy = TrainOccupancy {
+            train_id,
+            start_time,
+            track_section_ranges: &track_section_ranges,
+            report_train: &final_output.report_train,
+            length: train.length,
+        };
+        if !self
+            .train_conflicts(&requirements, &occupancy)
+            .await?
+            .is_empty()
+        {
+            return Ok(None);
+        }
+        Ok(Some(ConflictResolution::Reroute {
+            train_id,
+            path: train_schedule.path,
+        }))
+    }
+}
+
+/// A copy of a train schedule stopping at one of its path items
+fn with_stop(
+    train_schedule: &TrainSchedule,
+    path_item_index: usize,
+    stop_for: PositiveDuration,
+) -> TrainSchedule {
+    let mut train_schedule = train_schedule.clone();
+    let at = &train_schedule.path[path_item_index].id;
+    if let Some(item) = train_schedule
+        .schedule
+        .iter_mut()
+        .find(|item| &item.at == at)
+    {
+        item.stop_for = Some(stop_for);
+        return train_schedule;
+    }
+    // Keep the schedule in the order of the path
+    let path_item_indexes: HashMap<_, _> = train_schedule
+        .path
+        .iter()
+        .enumerate()
+        .map(|(index, path_item)| (&path_item.id, index))
+        .collect();
+    let index = train_schedule
+        .schedule
+        .iter()
+        .position(|item| path_item_indexes[&item.at] > path_item_index)
+        .unwrap_or(train_schedule.schedule.len());
+    let item = ScheduleItem {
+        at: at.clone(),
+        stop_for: Some(stop_for),
+        ..Default::default()
+    };
+    train_schedule.schedule.insert(index, item);
+    train_schedule
+}
+
+/// The tracks of the shortest detour between two track endpoints, in number of tracks
+///
+/// The detour starts after the track of `from`, leaving it through this endpoint, and ends before
+/// the track of `to`, entering it through this endpoint. It doesn't run on the avoided tracks.
+fn detour_tracks(
+    graph: &Graph,
+    from: &TrackEndpoint,
+    to: &TrackEndpoint,
+    avoided_tracks: &HashSet<&Identifier>,
+) -> Option<Vec<Identifier>> {
+    // The exit endpoint of each reached track, mapped to the exit endpoint of the track before
+    let mut previous_exits: HashMap<TrackEndpoint, TrackEndpoint> = HashMap::new();
+    let mut queue = VecDeque::from([(from.clone(), 0)]);
+    while let Some((exit, track_count)) = queue.pop_front() {
+        for group in graph.get_neighbour_groups(&exit) {
+            let Some(entry) = graph.get_neighbour(&exit, group) else {
+                continue;
+            };
+            if entry == to {
+                let mut tracks = vec![];
+                let mut current = exit.clone();
+                while current != *from {
+                    let previous = previous_exits.remove(&current)?;
+                    tracks.push(current.track);
+                    current = previous;
+                }
+                tracks.reverse();
+                return Some(tracks);
+            }
+            let direction = match entry.endpoint {
+                Endpoint::Begin => Direction::StartToStop,
+                Endpoint::End => Direction::StopToStart,
+            };
+            let next_exit = TrackEndpoint::from_track_and_direction(&entry.track, direction);
+            if track_count >= MAX_DETOUR_TRACKS
+                || avoided_tracks.contains(&entry.track)
+                || previous_exits.contains_key(&next_exit)
+            {
+                continue;
+            }
+            previous_exits.insert(next_exit.clone(), exit.clone());
+            queue.push_back((next_exit, track_count + 1));
+        }
+    }
+    None
+}
+
+/// The position of the head of a train at a time, in mm
+fn position_at_time(report_train: &ReportTrain, time: u64) -> u64 {
+    let ReportTrain {
+        positions, times, ..
+    } = report_train;
+    let index = times.partition_point(|t| *t < time);
+    if index == 0 {
+        return positions[0];
+    }
+    if index == times.len() {
+        return positions[index - 1];
+    }
+    let (start_time, end_time) = (times[index - 1], times[index]);
+    let (start_position, end_position) = (positions[index - 1], positions[index]);
+    let ratio = (time - start_time) as f64 / (end_time - start_time) as f64;
+    start_position + ((end_position - start_position) as f64 * ratio).round() as u64
+}
+
+/// The track ranges of a path between two of its positions, in mm
+fn path_track_ranges(track_section_ranges: &[TrackRange], begin: u64, end: u64) -> Vec<TrackRange> {
+    let mut track_ranges = vec![];
+    let mut path_offset = 0;
+    for range in track_section_ranges {
+        let range_begin = begin.max(path_offset) - path_offset;
+        let range_end = end
+            .min(path_offset + range.length())
+            .saturating_sub(path_offset);
+        path_offset += range.length();
+        if range_begin >= range_end {
+            continue;
+        }
+        let (track_begin, track_end) = match range.direction {
+            Direction::StartToStop => (range.begin + range_begin, range.begin + range_end),
+            Direction::StopToStart => (range.end - range_end, range.end - range_begin),
+        };
+        track_ranges.push(TrackRange {
+            track_section: range.track_section.clone(),
+            begin: track_begin,
+            end: track_end,
+            direction: range.direction,
+        });
+    }
+    track_ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::core::mocking::MockingClient;
+    use crate::core::v2::simulation::SpacingRequirement;
+    use crate::infra_cache::tests::create_small_infra_cache;
+
+    fn spacing_requirements(start_time: &str, zones: &[(&str, u64, u64)]) -> TrainRequirements {
+        TrainRequirements {
+            start_time: start_time.parse().unwrap(),
+            spacing_requirements: zones
+                .iter()
+                .map(|(zone, begin_time, end_time)| SpacingRequirement {
+                    zone: zone.to_string(),
+                    begin_time: *begin_time,
+                    end_time: *end_time,
+                })
+                .collect(),
+            routing_requirements: vec![],
+        }
+    }
+
+    fn spacing_conflict(start_time: &str, end_time: &str) -> Conflict {
+        Conflict {
+            train_ids: vec![1, 2],
+            work_schedule_ids: vec![],
+            start_time: start_time.parse().unwrap(),
+            end_time: end_time.parse().unwrap(),
+            conflict_type: ConflictType::Spacing,
+        }
+    }
+
+    #[test]
+    fn conflict_zones_and_delay() {
+        let core_client = CoreClient::Mocked(MockingClient::new());
+        // Train 2 enters zone B 30 seconds before train 1 leaves it
+        let checker = ConflictChecker {
+            core_client: &core_client,
+            trains_requirements: HashMap::from([
+                (
+                    1,
+                    spacing_requirements(
+                        "2024-05-01T08:00:00Z",
+                        &[("A", 0, 60_000), ("B", 60_000, 150_000)],
+                    ),
+                ),
+                (
+                    2,
+                    spacing_requirements(
+                        "2024-05-01T08:01:00Z",
+                        &[("A", 0, 60_000), ("B", 60_000, 120_000)],
+                    ),
+                ),
+            ]),
+            work_schedules: vec![],
+        };
+        let conflict = spacing_conflict("2024-05-01T08:02:00Z", "2024-05-01T08:02:30Z");
+
+        let (zones, routes) = checker.conflict_zones(2, &conflict);
+        assert_eq!(zones, vec!["B"]);
+        assert!(routes.is_empty());
+        let delay = checker.delay(2, &checker.trains_requirements[&2], &[conflict]);
+        assert_eq!(delay, Duration::seconds(30));
+    }
+
+    #[rstest]
+    #[case::before_departure(0, 0)]
+    #[case::running(50_000, 500_000)]
+    #[case::stopped(130_000, 1_000_000)]
+    #[case::after_arrival(400_000, 3_000_000)]
+    fn position_of_a_train(#[case] time: u64, #[case] expected: u64) {
+        let report_train = ReportTrain {
+            positions: vec![0, 1_000_000, 1_000_000, 3_000_000],
+            times: vec![0, 100_000, 160_000, 360_000],
+            speeds: vec![10., 10., 0., 10.],
+            energy_consumption: 0.,
+            scheduled_points_honored: true,
+        };
+        assert_eq!(position_at_time(&report_train, time), expected);
+    }
+
+    #[test]
+    fn detour_between_track_endpoints() {
+        let infra_cache = create_small_infra_cache();
+        let graph = Graph::load(&infra_cache);
+        let from = TrackEndpoint::new("A", Endpoint::End);
+        let to = TrackEndpoint::new("C", Endpoint::Begin);
+        let (a, b, c): (Identifier, Identifier, Identifier) = ("A".into(), "B".into(), "C".into());
+
+        let detour = detour_tracks(&graph, &from, &to, &HashSet::from([&a, &c]));
+        assert_eq!(detour, Some(vec![b.clone()]));
+        let detour = detour_tracks(&graph, &from, &to, &HashSet::from([&a, &b, &c]));
+        assert_eq!(detour, None);
+    }
+
+    #[test]
+    fn track_ranges_between_positions() {
+        let path = [
+            TrackRange::new("A", 0, 2_000_000, Direction::StartToStop),
+            TrackRange::new("B", 500_000, 1_500_000, Direction::StopToStart),
+            TrackRange::new("C", 0, 1_000_000, Direction::StartToStop),
+        ];
+        assert_eq!(
+            path_track_ranges(&path, 1_500_000, 2_800_000),
+            vec![
+                TrackRange::new("A", 1_500_000, 2_000_000, Direction::StartToStop),
+                TrackRange::new("B", 700_000, 1_500_000, Direction::StopToStart),
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Cache of the conflicts between the trains of a timetable
+//!
+//! Each train is identified by its key in the simulation cache and its start time. When the
+//! conflicts are computed incrementally, only the conflicts involving trains whose key changed
+//! since the cached computation are checked again.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::client::get_app_version;
+use crate::core::v2::conflict_detection::Conflict;
+use crate::core::v2::conflict_detection::TrainRequirements;
+use crate::error::Result;
+use crate::RedisConnection;
+
+const CACHE_CONFLICTS_EXPIRATION: u64 = 604800; // 1 week
+
+/// Identifies the requirements of a train used to compute its conflicts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainKey {
+    /// The key of the train in the simulation cache
+    pub simulation: String,
+    pub start_time: DateTime<Utc>,
+}
+
+/// The conflicts between the trains of a timetable, as last computed on an infra
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConflictsCache {
+    pub train_keys: HashMap<i64, TrainKey>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictsCache {
+    fn key(timetable_id: i64, infra_id: i64) -> String {
+        let osrd_version = get_app_version().unwrap_or_default();
+        format!("conflicts_{osrd_version}.{timetable_id}.{infra_id}")
+    }
+
+    pub async fn get(
+        redis_conn: &mut RedisConnection,
+        timetable_id: i64,
+        infra_id: i64,
+    ) -> Result<Option<Self>> {
+        redis_conn
+            .json_get_ex(
+                Self::key(timetable_id, infra_id),
+                CACHE_CONFLICTS_EXPIRATION,
+            )
+            .await
+    }
+
+    pub async fn set(
+        &self,
+        redis_conn: &mut RedisConnection,
+        timetable_id: i64,
+        infra_id: i64,
+    ) -> Result<()> {
+        redis_conn
+            .json_set_ex(
+                Self::key(timetable_id, infra_id),
+                self,
+                CACHE_CONFLICTS_EXPIRATION,
+            )
+            .await
+    }
+
+    /// The trains that were added or changed since the cached computation
+    pub fn changed_trains(&self, train_keys: &HashMap<i64, TrainKey>) -> HashSet<i64> {
+        train_keys
+            .iter()
+            .filter(|(id, key)| self.train_keys.get(id) != Some(key))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// The cached conflicts that are still valid, i.e. between trains that didn't change nor were removed
+    pub fn unchanged_conflicts(
+        &self,
+        train_keys: &HashMap<i64, TrainKey>,
+        changed_trains: &HashSet<i64>,
+    ) -> Vec<Conflict> {
+        self.conflicts
+            .iter()
+            .filter(|conflict| {
+                conflict
+                    .train_ids
+                    .iter()
+                    .all(|id| train_keys.contains_key(id) && !changed_trains.contains(id))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// The time window during which a train has requirements on the infrastructure
+pub fn requirements_time_window(
+    requirements: &TrainRequirements,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let begin_times = requirements
+        .spacing_requirements
+        .iter()
+        .map(|requirement| requirement.begin_time)
+        .chain(
+            requirements
+                .routing_requirements
+                .iter()
+                .map(|requirement| requirement.begin_time),
+        );
+    let end_times = requirements
+        .spacing_requirements
+        .iter()
+        .map(|requirement| requirement.end_time)
+        .chain(
+            requirements
+                .routing_requirements
+                .iter()
+                .flat_map(|requirement| &requirement.zones)
+                .map(|zone| zone.end_time),
+        );
+    let begin_time = begin_times.min().unwrap_or_default();
+    let end_time = end_times.max().unwrap_or_default();
+    (
+        requirements.start_time + Duration::milliseconds(begin_time as i64),
+        requirements.start_time + Duration::milliseconds(end_time as i64),
+    )
+}
+
+/// The trains which may be in conflict with the changed trains: the changed trains and the trains
+/// whose requirements overlap theirs in time
+pub fn trains_to_check(
+    trains_requirements: &HashMap<i64, TrainRequirements>,
+    changed_trains: &HashSet<i64>,
+) -> HashSet<i64> {
+    let changed_windows: Vec<_> = changed_trains
+        .iter()
+        .filter_map(|id| trains_requirements.get(id))
+        .map(requirements_time_window)
+        .collect();
+    trains_requirements
+        .iter()
+        .filter(|(id, requirements)| {
+            if changed_trains.contains(id) {
+                return true;
+            }
+            let (begin, end) = requirements_time_window(requirements);
+            changed_windows
+                .iter()
+                .any(|(changed_begin, changed_end)| begin <= *changed_end && *changed_begin <= end)
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::core::v2::conflict_detection::ConflictType;
+    use crate::core::v2::simulation::SpacingRequirement;
+
+    fn train_key(simulation: &str) -> TrainKey {
+        TrainKey {
+            simulation: simulation.to_string(),
+            start_time: "2024-05-01T08:00:00Z".parse().unwrap(),
+        }
+    }
+
+    fn spacing_conflict(train_ids: Vec<i64>) -> Conflict {
+        Conflict {
+            train_ids,
+            work_schedule_ids: vec![],
+            start_time: "2024-05-01T08:00:00Z".parse().unwrap(),
+            end_time: "2024-05-01T08:01:00Z".parse().unwrap(),
+            conflict_type: ConflictType::Spacing,
+        }
+    }
+
+    fn requirements(start_time: &str, begin_time: u64, end_time: u64) -> TrainRequirements {
+        TrainRequirements {
+            start_time: start_time.parse().unwrap(),
+            spacing_requirements: vec![SpacingRequirement {
+                zone: "zone".to_string(),
+                begin_time,
+                end_time,
+            }],
+            routing_requirements: vec![],
+        }
+    }
+
+    #[test]
+    fn only_conflicts_of_unchanged_trains_are_kept() {
+        let cache = ConflictsCache {
+            train_keys: HashMap::from([
+                (1, train_key("a")),
+                (2, train_key("b")),
+                (3, train_key("c")),
+                (4, train_key("d")),
+            ]),
+            conflicts: vec![
+                spacing_conflict(vec![1, 2]),
+                spacing_conflict(vec![1, 3]),
+                spacing_conflict(vec![2, 4]),
+            ],
+        };
+        // Train 3 changed, train 4 was removed and train 5 was added
+        let train_keys = HashMap::from([
+            (1, train_key("a")),
+            (2, train_key("b")),
+            (3, train_key("c2")),
+            (5, train_key("e")),
+        ]);
+
+        let changed_trains = cache.changed_trains(&train_keys);
+        assert_eq!(changed_trains, HashSet::from([3, 5]));
+        let conflicts: Vec<_> = cache
+            .unchanged_conflicts(&train_keys, &changed_trains)
+            .into_iter()
+            .map(|conflict| conflict.train_ids)
+            .collect();
+        assert_eq!(conflicts, vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn start_time_change_is_a_change() {
+        let cache = ConflictsCache {
+            train_keys: HashMap::from([(1, train_key("a"))]),
+            conflicts: vec![],
+        };
+        let train_keys = HashMap::from([(
+            1,
+            TrainKey {
+                start_time: "2024-05-01T09:00:00Z".parse().unwrap(),
+                ..train_key("a")
+            },
+        )]);
+        assert_eq!(cache.changed_trains(&train_keys), HashSet::from([1]));
+    }
+
+    #[test]
+    fn trains_overlapping_changed_trains_are_checked() {
+        let trains_requirements = HashMap::from([
+            (1, requirements("2024-05-01T08:00:00Z", 0, 600_000)),
+            (2, requirements("2024-05-01T08:05:00Z", 0, 600_000)),
+            (3, requirements("2024-05-01T07:00:00Z", 0, 600_000)),
+            (
+                4,
+                requirements("2024-05-01T07:00:00Z", 3_600_000, 4_000_000),
+            ),
+        ]);
+        assert_eq!(
+            trains_to_check(&trains_requirements, &HashSet::from([1])),
+            HashSet::from([1, 2, 4])
+        );
+    }
+}
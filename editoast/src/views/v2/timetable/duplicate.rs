@@ -0,0 +1,254 @@
+//! Duplication of a timetable, checking the paths of its trains on the infra of the copy
+
+use std::collections::HashMap;
+
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::TimetableError;
+use super::TimetableIdParam;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::timetable::Timetable;
+use crate::modelsv2::timetable::TimetableWithTrains;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::train_schedule::TrainScheduleChangeset;
+use crate::modelsv2::train_schedule_pattern::TrainSchedulePattern;
+use crate::modelsv2::train_schedule_pattern::TrainSchedulePatternChangeset;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+use crate::views::v2::path::pathfinding::unresolved_path_items;
+use crate::views::v2::path::pathfinding_from_train;
+use crate::CoreClient;
+use crate::RedisClient;
+use crate::RetrieveBatch;
+
+crate::routes! {
+    duplicate,
+}
+
+editoast_common::schemas! {
+    TimetableDuplicateResult,
+    InvalidTrainSchedule,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct InfraIdQueryParam {
+    /// The infra the paths of the trains are checked on
+    infra_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct TimetableDuplicateResult {
+    /// The id of the new timetable
+    timetable_id: i64,
+    /// The ids of the train schedules of the new timetable
+    train_ids: Vec<i64>,
+    /// The train schedules of the new timetable whose path is invalid on the infra
+    invalid_train_schedules: Vec<InvalidTrainSchedule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct InvalidTrainSchedule {
+    train_id: i64,
+    /// The indexes of the path items which can't be located on the infra, flagged as deleted in the copy
+    unresolved_path_items: Vec<usize>,
+    pathfinding_result: PathfindingResult,
+}
+
+/// Copy a timetable and its train schedules, checking their path on an infra
+///
+/// The path items which can't be located on the infra are flagged as deleted in the copies of the
+/// train schedules, and the train schedules whose path can't be found are reported.
+#[utoipa::path(
+    tag = "timetablev2",
+    params(TimetableIdParam, InfraIdQueryParam),
+    responses(
+        (status = 200, description = "The new timetable", body = TimetableDuplicateResult),
+        (status = 404, description = "The timetable or the infra was not found"),
+    ),
+)]
+#[post("/duplicate")]
+async fn duplicate(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    core_client: Data<CoreClient>,
+    timetable_id: Path<TimetableIdParam>,
+    query: Query<InfraIdQueryParam>,
+) -> Result<Json<TimetableDuplicateResult>> {
+    let conn = &mut db_pool.get().await?;
+    let core_client = core_client.into_inner();
+    let timetable_id = timetable_id.id;
+    let infra_id = query.infra_id;
+
+    let timetable = TimetableWithTrains::retrieve_or_fail(conn, timetable_id, || {
+        TimetableError::NotFound { timetable_id }
+    })
+    .await?;
+    let infra = Infra::retrieve_or_fail(conn, infra_id, || TimetableError::InfraNotFound {
+        infra_id,
+    })
+    .await?;
+    let (trains, _): (Vec<TrainSchedule>, _) =
+        TrainSchedule::retrieve_batch(conn, timetable.train_ids).await?;
+
+    // Check the path of each train on the infra
+    let mut redis_conn = redis_client.get_connection().await?;
+    let mut invalid_paths = HashMap::new();
+    for train in &trains {
+        let path_items: Vec<_> = train
+            .path
+            .iter()
+            .map(|path_item| path_item.location.clone())
+            .collect();
+        let unresolved = unresolved_path_items(conn, infra_id, &path_items).await?;
+        let pathfinding_result = pathfinding_from_train(
+            conn,
+            &mut redis_conn,
+            core_client.clone(),
+            &infra,
+            train.clone(),
+        )
+        .await?;
+        if !unresolved.is_empty() || !matches!(pathfinding_result, PathfindingResult::Success(_)) {
+            invalid_paths.insert(train.id, (unresolved, pathfinding_result));
+        }
+    }
+
+    let patterns_ids: Vec<_> = trains
+        .iter()
+        .filter_map(|train| train.pattern_id)
+        .unique()
+        .collect();
+    let result = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let new_timetable = Timetable::changeset()
+                    .electrical_profile_set_id(timetable.electrical_profile_set_id)
+                    .create(conn)
+                    .await?;
+
+                // The copies of the train schedules stay linked to copies of their pattern
+                let (patterns, _): (Vec<TrainSchedulePattern>, _) =
+                    TrainSchedulePattern::retrieve_batch(conn, patterns_ids).await?;
+                let mut new_pattern_ids = HashMap::with_capacity(patterns.len());
+                for pattern in patterns {
+                    let pattern_id = pattern.id;
+                    let new_pattern = TrainSchedulePatternChangeset::from(pattern)
+                        .timetable_id(new_timetable.id)
+                        .create(conn)
+                        .await?;
+                    new_pattern_ids.insert(pattern_id, new_pattern.id);
+                }
+
+                let mut train_ids = Vec::with_capacity(trains.len());
+                let mut invalid_train_schedules = vec![];
+                for mut train in trains {
+                    let invalid_path = invalid_paths.remove(&train.id);
+                    if let Some((unresolved, _)) = &invalid_path {
+                        for index in unresolved {
+                            train.path[*index].deleted = true;
+                        }
+                    }
+                    let pattern_id = train
+                        .pattern_id
+                        .and_then(|pattern_id| new_pattern_ids.get(&pattern_id).copied());
+                    let new_train = TrainScheduleChangeset::from(train)
+                        .timetable_id(new_timetable.id)
+                        .pattern_id(pattern_id)
+                        .create(conn)
+                        .await?;
+                    train_ids.push(new_train.id);
+                    if let Some((unresolved_path_items, pathfinding_result)) = invalid_path {
+                        invalid_train_schedules.push(InvalidTrainSchedule {
+                            train_id: new_train.id,
+                            unresolved_path_items,
+                            pathfinding_result,
+                        });
+                    }
+                }
+
+                Ok(TimetableDuplicateResult {
+                    timetable_id: new_timetable.id,
+                    train_ids,
+                    invalid_train_schedules,
+                })
+            }
+            .scope_boxed()
+        })
+        .await?;
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut as _;
+
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_empty_infra;
+    use crate::modelsv2::fixtures::create_simple_train_schedule;
+    use crate::modelsv2::fixtures::create_timetable;
+    use crate::views::test_app::TestAppBuilder;
+
+    #[rstest]
+    async fn duplicate_timetable_on_empty_infra() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let train = create_simple_train_schedule(pool.get_ok().deref_mut(), timetable.id).await;
+        let infra = create_empty_infra(pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri(&format!(
+                "/v2/timetable/{}/duplicate?infra_id={}",
+                timetable.id, infra.id
+            ))
+            .to_request();
+        let result: TimetableDuplicateResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        assert_ne!(result.timetable_id, timetable.id);
+        assert_eq!(result.train_ids.len(), 1);
+        assert_eq!(result.invalid_train_schedules.len(), 1);
+        let invalid = &result.invalid_train_schedules[0];
+        assert_eq!(invalid.train_id, result.train_ids[0]);
+        assert_eq!(invalid.unresolved_path_items, vec![0, 1, 2, 3]);
+
+        let copy = TrainSchedule::retrieve(pool.get_ok().deref_mut(), invalid.train_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.timetable_id, result.timetable_id);
+        assert_eq!(copy.train_name, train.train_name);
+        assert!(copy.path.iter().all(|path_item| path_item.deleted));
+    }
+
+    #[rstest]
+    async fn duplicate_unexisting_timetable() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let infra = create_empty_infra(pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri(&format!("/v2/timetable/0/duplicate?infra_id={}", infra.id))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NOT_FOUND);
+    }
+}
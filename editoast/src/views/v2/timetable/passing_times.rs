@@ -0,0 +1,568 @@
+//! Export of the passing times of the trains of a timetable as a spreadsheet
+//!
+//! The table gives, for each operational point of a reference path, the arrival and departure
+//! times of every train of the timetable, both theoretical and with regularity margins.
+
+use std::collections::HashMap;
+use std::ops::DerefMut as _;
+
+use actix_web::get;
+use actix_web::web::Data;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use actix_web::HttpResponse;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use editoast_derive::EditoastError;
+use itertools::Itertools;
+use rust_xlsxwriter::Format;
+use rust_xlsxwriter::Workbook;
+use rust_xlsxwriter::XlsxError;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use super::TimetableIdParam;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::core::v2::pathfinding::PathfindingResultSuccess;
+use crate::core::v2::simulation::SimulationResponse;
+use crate::core::CoreClient;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::timetable::TimetableWithTrains;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+use crate::views::v2::path::pathfinding_from_train;
+use crate::views::v2::path::properties::operational_points_on_path;
+use crate::views::v2::train_schedule::train_simulation_batch;
+use crate::RedisClient;
+
+crate::routes! {
+    "/passing_times" => {
+        passing_times,
+    },
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "passing_times")]
+enum PassingTimesError {
+    #[error("Timetable '{timetable_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    TimetableNotFound { timetable_id: i64 },
+    #[error("Infra '{infra_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    InfraNotFound { infra_id: i64 },
+    #[error("Train schedule '{train_id}' is not part of the timetable")]
+    #[editoast_error(status = 404)]
+    ReferenceTrainNotFound { train_id: i64 },
+    #[error(
+        "The path or the simulation of the reference train '{train_id}' could not be computed"
+    )]
+    #[editoast_error(status = 400)]
+    InvalidReferenceTrain { train_id: i64 },
+    #[error("The passing times could not be exported: {message}")]
+    #[editoast_error(status = 500)]
+    ExportFailed { message: String },
+}
+
+/// The file format of the passing times table
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum PassingTimesFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+impl PassingTimesFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PassingTimesQueryParams {
+    infra_id: i64,
+    /// The train giving the operational points of the table and their order
+    reference_train_id: i64,
+    /// The file format of the table
+    #[serde(default)]
+    #[param(inline)]
+    format: PassingTimesFormat,
+}
+
+/// Export the passing times of the trains of a timetable at the operational points of a reference path
+///
+/// The table has an arrival and a departure row for each operational point met by the reference
+/// train, and a theoretical and a with margins column for each train, ordered by departure time.
+/// Times are given in UTC. A train passing through an operational point without stopping only has a
+/// departure time. Trains whose path or simulation failed are left out.
+#[utoipa::path(
+    tag = "timetablev2",
+    params(TimetableIdParam, PassingTimesQueryParams),
+    responses(
+        (status = 200, description = "The passing times table as a CSV or XLSX file", body = [u8]),
+        (status = 404, description = "The timetable, the infra or the reference train was not found"),
+    ),
+)]
+#[get("")]
+async fn passing_times(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    core_client: Data<CoreClient>,
+    timetable_id: Path<TimetableIdParam>,
+    query: Query<PassingTimesQueryParams>,
+) -> Result<HttpResponse> {
+    let db_pool = db_pool.into_inner();
+    let redis_client = redis_client.into_inner();
+    let core_client = core_client.into_inner();
+    let timetable_id = timetable_id.into_inner().id;
+    let PassingTimesQueryParams {
+        infra_id,
+        reference_train_id,
+        format,
+    } = query.into_inner();
+    let conn = &mut db_pool.get().await?;
+
+    // 1. Retrieve the timetable, the infra and the trains
+    let timetable = TimetableWithTrains::retrieve_or_fail(conn, timetable_id, || {
+        PassingTimesError::TimetableNotFound { timetable_id }
+    })
+    .await?;
+    if !timetable.train_ids.contains(&reference_train_id) {
+        return Err(PassingTimesError::ReferenceTrainNotFound {
+            train_id: reference_train_id,
+        }
+        .into());
+    }
+    let infra = Infra::retrieve_or_fail(conn, infra_id, || PassingTimesError::InfraNotFound {
+        infra_id,
+    })
+    .await?;
+    let (mut trains, _): (Vec<TrainSchedule>, _) =
+        TrainSchedule::retrieve_batch(conn, timetable.train_ids).await?;
+    trains.sort_by(|a, b| {
+        (a.start_time, &a.train_name, a.id).cmp(&(b.start_time, &b.train_name, b.id))
+    });
+
+    // 2. Simulate the trains and retrieve the operational points on their path
+    let simulations = train_simulation_batch(
+        db_pool.clone(),
+        redis_client.clone(),
+        core_client.clone(),
+        &trains,
+        &infra,
+    )
+    .await?;
+    let mut redis_conn = redis_client.get_connection().await?;
+    let mut reference_operational_points = None;
+    let mut train_columns = vec![];
+    for (train, simulation) in trains.into_iter().zip(simulations) {
+        let SimulationResponse::Success {
+            base, provisional, ..
+        } = simulation
+        else {
+            continue;
+        };
+        let pathfinding_result = pathfinding_from_train(
+            db_pool.get().await?.deref_mut(),
+            &mut redis_conn,
+            core_client.clone(),
+            &infra,
+            train.clone(),
+        )
+        .await?;
+        let PathfindingResult::Success(PathfindingResultSuccess {
+            track_section_ranges,
+            ..
+        }) = pathfinding_result
+        else {
+            continue;
+        };
+        let operational_points =
+            operational_points_on_path(&mut redis_conn, &core_client, &infra, track_section_ranges)
+                .await?;
+
+        if train.id == reference_train_id {
+            reference_operational_points = Some(
+                operational_points
+                    .iter()
+                    .unique_by(|op| op.id.to_string())
+                    .map(|op| {
+                        let name = op
+                            .extensions
+                            .identifier
+                            .as_ref()
+                            .map(|identifier| identifier.name.0.clone())
+                            .unwrap_or_else(|| op.id.to_string());
+                        (op.id.to_string(), name)
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        // The first position of each operational point on the path of the train
+        let mut positions = HashMap::new();
+        for op in operational_points {
+            positions.entry(op.id.to_string()).or_insert(op.position);
+        }
+        train_columns.push((train, base, provisional, positions));
+    }
+    let Some(reference_operational_points) = reference_operational_points else {
+        return Err(PassingTimesError::InvalidReferenceTrain {
+            train_id: reference_train_id,
+        }
+        .into());
+    };
+
+    // 3. Build the table
+    let trains = train_columns
+        .into_iter()
+        .map(|(train, base, provisional, positions)| {
+            let passing_times = reference_operational_points
+                .iter()
+                .map(|(op_id, _)| {
+                    let Some(position) = positions.get(op_id) else {
+                        return (None, None);
+                    };
+                    (
+                        passing_time(&base.positions, &base.times, train.start_time, *position),
+                        passing_time(
+                            &provisional.positions,
+                            &provisional.times,
+                            train.start_time,
+                            *position,
+                        ),
+                    )
+                })
+                .collect();
+            TrainPassingTimes {
+                train_name: train.train_name,
+                passing_times,
+            }
+        })
+        .collect();
+    let table = PassingTimesTable {
+        operational_points: reference_operational_points
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect(),
+        trains,
+    };
+
+    // 4. Export it
+    let content = match format {
+        PassingTimesFormat::Csv => table.to_csv().map_err(|err| err.to_string()),
+        PassingTimesFormat::Xlsx => table.to_xlsx().map_err(|err| err.to_string()),
+    }
+    .map_err(|message| PassingTimesError::ExportFailed { message })?;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .append_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"timetable_{timetable_id}_passing_times.{}\"",
+                format.extension()
+            ),
+        ))
+        .body(content))
+}
+
+/// The times at which a train arrives at and leaves a position of its path
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PassingTime {
+    /// `None` when the train doesn't stop at the position
+    arrival: Option<DateTime<Utc>>,
+    /// `None` when the position is the end of the path of the train
+    departure: Option<DateTime<Utc>>,
+}
+
+/// Compute the passing time of a simulated train at a position of its path
+///
+/// `positions` (in mm) and `times` (in ms since `start_time`) come from a simulation report. The
+/// train is stopped at the position when several points of the report share it.
+fn passing_time(
+    positions: &[u64],
+    times: &[u64],
+    start_time: DateTime<Utc>,
+    position: u64,
+) -> Option<PassingTime> {
+    let (first, last) = (*positions.first()?, *positions.last()?);
+    if position < first || position > last {
+        return None;
+    }
+    let interpolate = |index: usize| {
+        let (p0, p1) = (positions[index], positions[index + 1]);
+        let (t0, t1) = (times[index], times[index + 1]);
+        if p1 == p0 {
+            return t0;
+        }
+        t0 + (t1 - t0) * (position - p0) / (p1 - p0)
+    };
+    // The first point at or after the position, and the last point at or before it
+    let after = positions.partition_point(|&p| p < position);
+    let before = positions.partition_point(|&p| p <= position) - 1;
+    let arrival = if positions[after] == position {
+        times[after]
+    } else {
+        interpolate(after - 1)
+    };
+    let departure = if positions[before] == position {
+        times[before]
+    } else {
+        interpolate(before)
+    };
+
+    let to_datetime = |time: u64| start_time + Duration::milliseconds(time as i64);
+    Some(if position == last && first != last {
+        PassingTime {
+            arrival: Some(to_datetime(arrival)),
+            departure: None,
+        }
+    } else if arrival == departure {
+        PassingTime {
+            arrival: None,
+            departure: Some(to_datetime(departure)),
+        }
+    } else {
+        PassingTime {
+            arrival: Some(to_datetime(arrival)),
+            departure: Some(to_datetime(departure)),
+        }
+    })
+}
+
+/// The passing times of a train at the operational points of the table
+struct TrainPassingTimes {
+    train_name: String,
+    /// The theoretical and with margins passing times, `None` where the train doesn't pass
+    passing_times: Vec<(Option<PassingTime>, Option<PassingTime>)>,
+}
+
+/// A row of the table: the arrival or departure times of the trains at an operational point
+struct PassingTimesRow<'a> {
+    operational_point: &'a str,
+    event: &'static str,
+    /// The theoretical and with margins times of each train
+    times: Vec<Option<DateTime<Utc>>>,
+}
+
+/// A station × train table of passing times
+struct PassingTimesTable {
+    /// The names of the operational points, ordered along the reference path
+    operational_points: Vec<String>,
+    trains: Vec<TrainPassingTimes>,
+}
+
+impl PassingTimesTable {
+    const TIME_FORMAT: &'static str = "%Y-%m-%d %H:%M:%S";
+
+    fn header(&self) -> Vec<String> {
+        let mut header = vec!["Operational point".to_string(), "Event".to_string()];
+        for train in &self.trains {
+            header.push(format!("{} (theoretical)", train.train_name));
+            header.push(format!("{} (with margins)", train.train_name));
+        }
+        header
+    }
+
+    /// The arrival and departure rows of each operational point, with a time per column
+    fn rows(&self) -> Vec<PassingTimesRow<'_>> {
+        let mut rows = vec![];
+        for (index, name) in self.operational_points.iter().enumerate() {
+            let times = |event: fn(&PassingTime) -> Option<DateTime<Utc>>| {
+                self.trains
+                    .iter()
+                    .flat_map(|train| {
+                        let (theoretical, with_margins) = train.passing_times[index];
+                        [theoretical, with_margins]
+                    })
+                    .map(|passing_time| passing_time.as_ref().and_then(event))
+                    .collect()
+            };
+            rows.push(PassingTimesRow {
+                operational_point: name,
+                event: "Arrival",
+                times: times(|time| time.arrival),
+            });
+            rows.push(PassingTimesRow {
+                operational_point: name,
+                event: "Departure",
+                times: times(|time| time.departure),
+            });
+        }
+        rows
+    }
+
+    fn to_csv(&self) -> std::result::Result<Vec<u8>, csv::Error> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(self.header())?;
+        for row in self.rows() {
+            let times = row.times.into_iter().map(|time| {
+                time.map(|time| time.format(Self::TIME_FORMAT).to_string())
+                    .unwrap_or_default()
+            });
+            writer.write_record(
+                [row.operational_point.to_string(), row.event.to_string()]
+                    .into_iter()
+                    .chain(times),
+            )?;
+        }
+        writer.into_inner().map_err(|err| err.into_error().into())
+    }
+
+    fn to_xlsx(&self) -> std::result::Result<Vec<u8>, XlsxError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name("Passing times")?;
+        let header_format = Format::new().set_bold();
+        let time_format = Format::new().set_num_format("hh:mm:ss");
+
+        for (col, title) in self.header().into_iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, title, &header_format)?;
+        }
+        for (index, row) in self.rows().into_iter().enumerate() {
+            let row_num = index as u32 + 1;
+            worksheet.write_string(row_num, 0, row.operational_point)?;
+            worksheet.write_string(row_num, 1, row.event)?;
+            for (col, time) in row.times.into_iter().enumerate() {
+                if let Some(time) = time {
+                    worksheet.write_datetime_with_format(
+                        row_num,
+                        col as u16 + 2,
+                        time.naive_utc(),
+                        &time_format,
+                    )?;
+                }
+            }
+        }
+        worksheet.set_freeze_panes(1, 2)?;
+        workbook.save_to_buffer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::passing_time;
+    use super::PassingTime;
+    use super::PassingTimesTable;
+    use super::TrainPassingTimes;
+
+    fn at(hour: u32, min: u32, sec: u32) -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(2024, 5, 1, hour, min, sec).unwrap())
+    }
+
+    // The train departs at 08:00, stops 2 minutes at 10km and arrives at 20km
+    const POSITIONS: [u64; 5] = [0, 5_000_000, 10_000_000, 10_000_000, 20_000_000];
+    const TIMES: [u64; 5] = [0, 300_000, 600_000, 720_000, 1_320_000];
+
+    #[rstest]
+    #[case::departure(0, None, at(8, 0, 0))]
+    #[case::passage_at_point(5_000_000, None, at(8, 5, 0))]
+    #[case::interpolated_passage(2_500_000, None, at(8, 2, 30))]
+    #[case::stop(10_000_000, at(8, 10, 0), at(8, 12, 0))]
+    #[case::arrival(20_000_000, at(8, 22, 0), None)]
+    fn passing_time_along_path(
+        #[case] position: u64,
+        #[case] arrival: Option<DateTime<Utc>>,
+        #[case] departure: Option<DateTime<Utc>>,
+    ) {
+        let start_time = at(8, 0, 0).unwrap();
+
+        assert_eq!(
+            passing_time(&POSITIONS, &TIMES, start_time, position),
+            Some(PassingTime { arrival, departure })
+        );
+    }
+
+    #[test]
+    fn passing_time_out_of_path() {
+        let start_time = at(8, 0, 0).unwrap();
+
+        assert_eq!(
+            passing_time(&POSITIONS, &TIMES, start_time, 20_000_001),
+            None
+        );
+    }
+
+    #[test]
+    fn passing_times_table_to_csv() {
+        let start_time = at(8, 0, 0).unwrap();
+        let with_margins_times: Vec<_> = TIMES.iter().map(|time| time + time / 10).collect();
+        let passing_times = [0, 10_000_000, 20_000_000]
+            .into_iter()
+            .map(|position| {
+                (
+                    passing_time(&POSITIONS, &TIMES, start_time, position),
+                    passing_time(&POSITIONS, &with_margins_times, start_time, position),
+                )
+            })
+            .chain([(None, None)])
+            .collect();
+        let table = PassingTimesTable {
+            operational_points: vec!["A".into(), "B".into(), "C".into(), "D".into()],
+            trains: vec![TrainPassingTimes {
+                train_name: "train".into(),
+                passing_times,
+            }],
+        };
+
+        let csv = String::from_utf8(table.to_csv().unwrap()).unwrap();
+
+        assert_eq!(
+            csv,
+            "Operational point,Event,train (theoretical),train (with margins)\n\
+             A,Arrival,,\n\
+             A,Departure,2024-05-01 08:00:00,2024-05-01 08:00:00\n\
+             B,Arrival,2024-05-01 08:10:00,2024-05-01 08:11:00\n\
+             B,Departure,2024-05-01 08:12:00,2024-05-01 08:13:12\n\
+             C,Arrival,2024-05-01 08:22:00,2024-05-01 08:24:12\n\
+             C,Departure,,\n\
+             D,Arrival,,\n\
+             D,Departure,,\n"
+        );
+    }
+
+    #[test]
+    fn passing_times_table_to_xlsx() {
+        let table = PassingTimesTable {
+            operational_points: vec!["A".into()],
+            trains: vec![TrainPassingTimes {
+                train_name: "train".into(),
+                passing_times: vec![(
+                    Some(PassingTime {
+                        arrival: None,
+                        departure: at(8, 0, 0),
+                    }),
+                    None,
+                )],
+            }],
+        };
+
+        let xlsx = table.to_xlsx().unwrap();
+
+        // An XLSX file is a zip archive
+        assert!(xlsx.starts_with(b"PK"));
+    }
+}
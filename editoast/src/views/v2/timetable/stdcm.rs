@@ -31,7 +31,8 @@ use crate::modelsv2::train_schedule::TrainSchedule;
 use crate::modelsv2::work_schedules::WorkSchedule;
 use crate::modelsv2::DbConnectionPoolV2;
 use crate::modelsv2::RollingStockModel;
-use crate::modelsv2::{DbConnection, Infra, List};
+use crate::modelsv2::TrainComposition;
+use crate::modelsv2::{DbConnection, Infra};
 use crate::views::v2::path::pathfinding::extract_location_from_path_items;
 use crate::views::v2::path::pathfinding::TrackOffsetExtractionError;
 use crate::views::v2::train_schedule::{train_simulation, train_simulation_batch};
@@ -61,6 +62,13 @@ enum STDCMError {
     TimetableNotFound { timetable_id: i64 },
     #[error("Rolling stock {rolling_stock_id} does not exist")]
     RollingStockNotFound { rolling_stock_id: i64 },
+    #[error("Version {version} of rolling stock {rolling_stock_id} does not exist")]
+    RollingStockRevisionNotFound { rolling_stock_id: i64, version: i64 },
+    #[error("Composition {composition_id} of rolling stock {rolling_stock_id} does not exist")]
+    CompositionNotFound {
+        composition_id: i64,
+        rolling_stock_id: i64,
+    },
     #[error("Path item {index} is invalid")]
     InvalidPathItem {
         index: usize,
@@ -75,6 +83,10 @@ pub struct STDCMRequestPayload {
     start_time: Option<DateTime<Utc>>,
     steps: Vec<PathfindingItem>,
     rolling_stock_id: i64,
+    /// The version of the rolling stock to use, its current version if not specified
+    rolling_stock_version: Option<i64>,
+    /// The composition the train is made of, which must be made of the rolling stock
+    composition_id: Option<i64>,
     comfort: Comfort,
     /// By how long we can shift the departure time in milliseconds
     /// Deprecated, first step data should be used instead
@@ -181,12 +193,34 @@ async fn stdcm(
     )
     .await?;
 
-    let rolling_stock = RollingStockModel::retrieve_or_fail(conn, data.rolling_stock_id, || {
-        STDCMError::RollingStockNotFound {
-            rolling_stock_id: data.rolling_stock_id,
+    let rolling_stock_id = data.rolling_stock_id;
+    let rolling_stock = match data.rolling_stock_version {
+        Some(version) => RollingStockModel::retrieve_revision(conn, rolling_stock_id, version)
+            .await?
+            .ok_or(STDCMError::RollingStockRevisionNotFound {
+                rolling_stock_id,
+                version,
+            })?,
+        None => {
+            RollingStockModel::retrieve_or_fail(conn, rolling_stock_id, || {
+                STDCMError::RollingStockNotFound { rolling_stock_id }
+            })
+            .await?
         }
-    })
-    .await?;
+    };
+    let composition = match data.composition_id {
+        Some(composition_id) => {
+            let composition = TrainComposition::retrieve(conn, composition_id)
+                .await?
+                .filter(|composition| composition.rolling_stock_id == rolling_stock_id)
+                .ok_or(STDCMError::CompositionNotFound {
+                    composition_id,
+                    rolling_stock_id,
+                })?;
+            Some(composition)
+        }
+        None => None,
+    };
 
     // 2. Build core request
     let mut trains_requirements = HashMap::new();
@@ -212,6 +246,7 @@ async fn stdcm(
         &data,
         &infra,
         &rolling_stock,
+        composition.as_ref(),
         timetable_id,
     )
     .await?;
@@ -230,6 +265,10 @@ async fn stdcm(
     let path_items = parse_stdcm_steps(conn, &data, &infra).await?;
 
     // 4. Build STDCM request
+    let rolling_stock = match composition {
+        Some(composition) => composition.aggregate(rolling_stock),
+        None => rolling_stock,
+    };
     let stdcm_response = STDCMRequest {
         infra: infra.id,
         expected_version: infra.version,
@@ -300,6 +339,7 @@ async fn get_maximum_run_time(
     data: &STDCMRequestPayload,
     infra: &Infra,
     rolling_stock: &RollingStockModel,
+    composition: Option<&TrainComposition>,
     timetable_id: i64,
 ) -> Result<MaxRunningTimeResult> {
     if let Some(maximum_run_time) = data.maximum_run_time {
@@ -327,6 +367,10 @@ async fn get_maximum_run_time(
         speed_limit_tag: data.speed_limit_tags.clone(),
         power_restrictions: vec![],
         options: Default::default(),
+        pattern_id: None,
+        rolling_stock_id: Some(rolling_stock.id),
+        rolling_stock_version: Some(rolling_stock.version),
+        composition_id: composition.map(|composition| composition.id),
     };
 
     let conn = &mut db_pool.clone().get().await?;
@@ -384,27 +428,29 @@ async fn build_work_schedules(
     maximum_run_time: u64,
 ) -> Result<Vec<STDCMWorkSchedule>> {
     let maximum_simulation_time = maximum_run_time + maximum_departure_delay;
-    let res = Ok(WorkSchedule::list(conn, Default::default())
-        .await?
-        .iter()
-        .map(|ws| {
-            let schedule = STDCMWorkSchedule {
-                start_time: elapsed_since_time_ms(&ws.start_date_time, &time),
-                end_time: elapsed_since_time_ms(&ws.end_date_time, &time),
-                track_ranges: ws
-                    .track_ranges
-                    .iter()
-                    .map(|track| UndirectedTrackRange {
-                        track_section: track.track.to_string(),
-                        begin: (track.begin * 1000.0) as u64,
-                        end: (track.end * 1000.0) as u64,
-                    })
-                    .collect(),
-            };
-            schedule
-        })
-        .filter(|ws| ws.end_time > 0 && ws.start_time < maximum_simulation_time)
-        .collect());
+    let end_time = time + Duration::milliseconds(maximum_simulation_time as i64);
+    let res = Ok(
+        WorkSchedule::list_in_time_window(conn, time.naive_utc(), end_time.naive_utc())
+            .await?
+            .iter()
+            .map(|ws| {
+                let schedule = STDCMWorkSchedule {
+                    start_time: elapsed_since_time_ms(&ws.start_date_time, &time),
+                    end_time: elapsed_since_time_ms(&ws.end_date_time, &time),
+                    track_ranges: ws
+                        .track_ranges
+                        .iter()
+                        .map(|track| UndirectedTrackRange {
+                            track_section: track.track.to_string(),
+                            begin: (track.begin * 1000.0) as u64,
+                            end: (track.end * 1000.0) as u64,
+                        })
+                        .collect(),
+                };
+                schedule
+            })
+            .collect(),
+    );
     res
 }
 
@@ -0,0 +1,202 @@
+//! Conflicts between trains and the track ranges closed by work schedules
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use editoast_schemas::infra::Direction;
+
+use crate::core::v2::conflict_detection::Conflict;
+use crate::core::v2::conflict_detection::ConflictType;
+use crate::core::v2::pathfinding::TrackRange;
+use crate::core::v2::simulation::ReportTrain;
+use crate::modelsv2::work_schedules::WorkSchedule;
+
+/// A simulated train, as needed to check it against work schedules
+pub struct TrainOccupancy<'a> {
+    pub train_id: i64,
+    pub start_time: DateTime<Utc>,
+    /// The path of the train as track ranges
+    pub track_section_ranges: &'a [TrackRange],
+    pub report_train: &'a ReportTrain,
+    /// Length of the train in mm
+    pub length: u64,
+}
+
+impl TrainOccupancy<'_> {
+    /// The time at which the head of the train first reaches a position of its path, in ms since its start time
+    fn time_at_position(&self, position: u64) -> u64 {
+        let ReportTrain {
+            positions, times, ..
+        } = self.report_train;
+        let index = positions.partition_point(|p| *p < position);
+        if index == 0 {
+            return times[0];
+        }
+        if index == positions.len() {
+            return times[index - 1];
+        }
+        let (start_position, end_position) = (positions[index - 1], positions[index]);
+        let (start_time, end_time) = (times[index - 1], times[index]);
+        let ratio = (position - start_position) as f64 / (end_position - start_position) as f64;
+        start_time + ((end_time - start_time) as f64 * ratio).round() as u64
+    }
+
+    /// The ranges of the path (in mm) crossing the track ranges of a work schedule
+    fn path_ranges(&self, work_schedule: &WorkSchedule) -> Vec<(u64, u64)> {
+        let mut path_ranges = vec![];
+        let mut path_offset = 0;
+        for range in self.track_section_ranges {
+            for closed in work_schedule
+                .track_ranges
+                .iter()
+                .filter(|closed| closed.track == range.track_section)
+            {
+                let begin = range.begin.max((closed.begin * 1000.).round() as u64);
+                let end = range.end.min((closed.end * 1000.).round() as u64);
+                if begin >= end {
+                    continue;
+                }
+                path_ranges.push(match range.direction {
+                    Direction::StartToStop => (
+                        path_offset + begin - range.begin,
+                        path_offset + end - range.begin,
+                    ),
+                    Direction::StopToStart => (
+                        path_offset + range.end - end,
+                        path_offset + range.end - begin,
+                    ),
+                });
+            }
+            path_offset += range.end - range.begin;
+        }
+        path_ranges
+    }
+
+    /// The conflict of the train with a work schedule, if the train runs on the closed track ranges during the works
+    ///
+    /// A track range is occupied from the time the head of the train enters it until its tail leaves it.
+    pub fn work_schedule_conflict(&self, work_schedule: &WorkSchedule) -> Option<Conflict> {
+        let path_length = *self.report_train.positions.last()?;
+        let (enter_time, leave_time) = self
+            .path_ranges(work_schedule)
+            .into_iter()
+            .map(|(begin, end)| {
+                (
+                    self.time_at_position(begin),
+                    self.time_at_position((end + self.length).min(path_length)),
+                )
+            })
+            .reduce(|(enter_a, leave_a), (enter_b, leave_b)| {
+                (enter_a.min(enter_b), leave_a.max(leave_b))
+            })?;
+
+        let start_time = (self.start_time + Duration::milliseconds(enter_time as i64))
+            .max(work_schedule.start_date_time.and_utc());
+        let end_time = (self.start_time + Duration::milliseconds(leave_time as i64))
+            .min(work_schedule.end_date_time.and_utc());
+        if start_time >= end_time {
+            return None;
+        }
+        Some(Conflict {
+            train_ids: vec![self.train_id],
+            work_schedule_ids: vec![work_schedule.id],
+            start_time,
+            end_time,
+            conflict_type: ConflictType::WorkSchedule,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use editoast_schemas::infra::TrackRange as WorkScheduleTrackRange;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn report_train() -> ReportTrain {
+        // The train runs 10m/s, then stops for 60s after 1km
+        ReportTrain {
+            positions: vec![0, 1_000_000, 1_000_000, 3_000_000],
+            times: vec![0, 100_000, 160_000, 360_000],
+            speeds: vec![10., 10., 0., 10.],
+            energy_consumption: 0.,
+            scheduled_points_honored: true,
+        }
+    }
+
+    fn work_schedule(
+        start: &str,
+        end: &str,
+        track_ranges: Vec<WorkScheduleTrackRange>,
+    ) -> WorkSchedule {
+        WorkSchedule {
+            id: 42,
+            start_date_time: start.parse::<DateTime<Utc>>().unwrap().naive_utc(),
+            end_date_time: end.parse::<DateTime<Utc>>().unwrap().naive_utc(),
+            track_ranges,
+            ..Default::default()
+        }
+    }
+
+    fn conflict_times(work_schedule: &WorkSchedule) -> Option<(String, String)> {
+        let track_section_ranges = [
+            TrackRange::new("A", 0, 2_000_000, Direction::StartToStop),
+            TrackRange::new("B", 500_000, 1_500_000, Direction::StopToStart),
+        ];
+        let report_train = report_train();
+        let train = TrainOccupancy {
+            train_id: 1,
+            start_time: "2024-05-01T08:00:00Z".parse().unwrap(),
+            track_section_ranges: &track_section_ranges,
+            report_train: &report_train,
+            length: 100_000,
+        };
+        train.work_schedule_conflict(work_schedule).map(|conflict| {
+            assert_eq!(conflict.train_ids, vec![1]);
+            assert_eq!(conflict.work_schedule_ids, vec![42]);
+            (
+                conflict.start_time.format("%H:%M:%S").to_string(),
+                conflict.end_time.format("%H:%M:%S").to_string(),
+            )
+        })
+    }
+
+    #[rstest]
+    #[case::during_works(
+        vec![WorkScheduleTrackRange::new("A", 0., 500.)],
+        Some(("08:00:00", "08:01:00"))
+    )]
+    #[case::while_stopped(
+        vec![WorkScheduleTrackRange::new("A", 900., 950.)],
+        Some(("08:01:30", "08:02:45"))
+    )]
+    #[case::reversed_track(
+        vec![WorkScheduleTrackRange::new("B", 500., 1000.)],
+        Some(("08:05:10", "08:06:00"))
+    )]
+    #[case::other_track(vec![WorkScheduleTrackRange::new("C", 0., 500.)], None)]
+    #[case::not_on_path(vec![WorkScheduleTrackRange::new("B", 0., 500.)], None)]
+    fn work_schedule_conflicts(
+        #[case] track_ranges: Vec<WorkScheduleTrackRange>,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        let work_schedule =
+            work_schedule("2024-05-01T07:00:00Z", "2024-05-01T08:10:00Z", track_ranges);
+        assert_eq!(
+            conflict_times(&work_schedule),
+            expected.map(|(start, end)| (start.to_string(), end.to_string()))
+        );
+    }
+
+    #[test]
+    fn no_conflict_after_works() {
+        let work_schedule = work_schedule(
+            "2024-05-01T06:00:00Z",
+            "2024-05-01T08:00:00Z",
+            vec![WorkScheduleTrackRange::new("A", 0., 2000.)],
+        );
+        assert_eq!(conflict_times(&work_schedule), None);
+    }
+}
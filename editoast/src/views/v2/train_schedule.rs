@@ -1,4 +1,5 @@
 mod projection;
+mod validation;
 
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
@@ -57,6 +58,7 @@ crate::routes! {
         simulation_summary,
         get_batch,
         projection::routes(),
+        validation::routes(),
         "/{id}" => {
             get,
             put,
@@ -75,6 +77,7 @@ editoast_common::schemas! {
     SimulationSummaryResult,
     InfraIdQueryParam,
     projection::schemas(),
+    validation::schemas(),
 }
 
 #[derive(Debug, Error, EditoastError)]
@@ -102,6 +105,8 @@ struct TrainScheduleIdParam {
 pub struct TrainScheduleResult {
     id: i64,
     timetable_id: i64,
+    /// The pattern the train schedule was generated from
+    pattern_id: Option<i64>,
     #[serde(flatten)]
     pub train_schedule: TrainScheduleBase,
 }
@@ -111,10 +116,14 @@ impl From<TrainSchedule> for TrainScheduleResult {
         Self {
             id: value.id,
             timetable_id: value.timetable_id,
+            pattern_id: value.pattern_id,
             train_schedule: TrainScheduleBase {
                 train_name: value.train_name,
                 labels: value.labels.into_iter().flatten().collect(),
                 rolling_stock_name: value.rolling_stock_name,
+                rolling_stock_id: value.rolling_stock_id,
+                rolling_stock_version: value.rolling_stock_version,
+                composition_id: value.composition_id,
                 start_time: value.start_time,
                 schedule: value.schedule,
                 margins: value.margins,
@@ -155,6 +164,9 @@ impl From<TrainScheduleForm> for TrainScheduleChangeset {
             .path(ts.path)
             .power_restrictions(ts.power_restrictions)
             .rolling_stock_name(ts.rolling_stock_name)
+            .rolling_stock_id(ts.rolling_stock_id)
+            .rolling_stock_version(ts.rolling_stock_version)
+            .composition_id(ts.composition_id)
             .schedule(ts.schedule)
             .speed_limit_tag(ts.speed_limit_tag.map(|s| s.0))
             .start_time(ts.start_time)
@@ -259,7 +271,8 @@ async fn put(
     let conn = &mut db_pool.get().await?;
 
     let train_id = train_schedule_id.id;
-    let ts_changeset: TrainScheduleChangeset = data.into_inner().into();
+    // A train schedule edited on its own no longer follows its pattern
+    let ts_changeset = TrainScheduleChangeset::from(data.into_inner()).pattern_id(None);
 
     let ts_result = ts_changeset
         .update_or_fail(conn, train_id, || TrainScheduleError::NotFound {
@@ -318,6 +331,68 @@ pub async fn train_simulation(
     train_schedule: &TrainSchedule,
     infra: &Infra,
 ) -> Result<SimulationResponse> {
+    let (simulation_response, _) =
+        train_simulation_with_cache_key(conn, redis_client, core, train_schedule, infra).await?;
+    Ok(simulation_response)
+}
+
+/// Compute the simulation of a given train schedule, along with its key in the simulation cache
+///
+/// The key identifies the simulation input, it is `None` if no path could be found for the train.
+pub async fn train_simulation_with_cache_key(
+    conn: &mut DbConnection,
+    redis_client: Arc<RedisClient>,
+    core: Arc<CoreClient>,
+    train_schedule: &TrainSchedule,
+    infra: &Infra,
+) -> Result<(SimulationResponse, Option<String>)> {
+    // Get electrical_profile_set_id
+    let timetable_id = train_schedule.timetable_id;
+    let timetable = Timetable::retrieve(conn, timetable_id)
+        .await?
+        .expect("Timetable should exist since it's a foreign key");
+    simulation_with_cache_key(
+        conn,
+        redis_client,
+        core,
+        train_schedule,
+        infra,
+        timetable.electrical_profile_set_id,
+    )
+    .await
+}
+
+/// Compute the simulation of a train schedule which isn't stored in a timetable
+///
+/// The electrical profile set is usually the one of the timetable, so it has to be given.
+pub async fn detached_train_simulation(
+    conn: &mut DbConnection,
+    redis_client: Arc<RedisClient>,
+    core: Arc<CoreClient>,
+    train_schedule: &TrainSchedule,
+    infra: &Infra,
+    electrical_profile_set_id: Option<i64>,
+) -> Result<SimulationResponse> {
+    let (simulation_response, _) = simulation_with_cache_key(
+        conn,
+        redis_client,
+        core,
+        train_schedule,
+        infra,
+        electrical_profile_set_id,
+    )
+    .await?;
+    Ok(simulation_response)
+}
+
+async fn simulation_with_cache_key(
+    conn: &mut DbConnection,
+    redis_client: Arc<RedisClient>,
+    core: Arc<CoreClient>,
+    train_schedule: &TrainSchedule,
+    infra: &Infra,
+    electrical_profile_set_id: Option<i64>,
+) -> Result<(SimulationResponse, Option<String>)> {
     let mut redis_conn = redis_client.get_connection().await?;
     // Compute path
     let pathfinding_result = pathfinding_from_train(
@@ -345,13 +420,23 @@ pub async fn train_simulation(
             path_items_positions,
         ),
         _ => {
-            return Ok(SimulationResponse::PathfindingFailed { pathfinding_result });
+            return Ok((
+                SimulationResponse::PathfindingFailed { pathfinding_result },
+                None,
+            ));
         }
     };
 
     // Build simulation request
-    let simulation_request =
-        build_simulation_request(conn, infra, train_schedule, &path_items_positions, path).await?;
+    let simulation_request = build_simulation_request(
+        conn,
+        infra,
+        train_schedule,
+        &path_items_positions,
+        path,
+        electrical_profile_set_id,
+    )
+    .await?;
 
     // Compute unique hash of the simulation input
     let hash = train_simulation_input_hash(infra.id, &infra.version, &simulation_request);
@@ -361,7 +446,7 @@ pub async fn train_simulation(
         .await?;
     if let Some(simulation_result) = result {
         info!("Simulation hit cache");
-        return Ok(simulation_result);
+        return Ok((simulation_result, Some(hash)));
     }
 
     // Compute simulation from core
@@ -373,7 +458,7 @@ pub async fn train_simulation(
         .await?;
 
     // Return the response
-    Ok(result)
+    Ok((result, Some(hash)))
 }
 
 async fn build_simulation_request(
@@ -382,17 +467,18 @@ async fn build_simulation_request(
     train_schedule: &TrainSchedule,
     path_items_position: &[u64],
     path: SimulationPath,
+    electrical_profile_set_id: Option<i64>,
 ) -> Result<SimulationRequest> {
     // Get rolling stock
     let rolling_stock_name = train_schedule.rolling_stock_name.clone();
-    let rolling_stock = RollingStockModel::retrieve(conn, rolling_stock_name.clone())
-        .await?
-        .expect("Rolling stock should exist since the pathfinding succeeded");
-    // Get electrical_profile_set_id
-    let timetable_id = train_schedule.timetable_id;
-    let timetable = Timetable::retrieve(conn, timetable_id)
-        .await?
-        .expect("Timetable should exist since it's a foreign key");
+    let rolling_stock = RollingStockModel::retrieve_for_train(
+        conn,
+        rolling_stock_name.clone(),
+        train_schedule.rolling_stock_revision(),
+        train_schedule.composition_id,
+    )
+    .await?
+    .expect("Rolling stock should exist since the pathfinding succeeded");
 
     assert_eq!(path_items_position.len(), train_schedule.path.len());
 
@@ -454,7 +540,7 @@ async fn build_simulation_request(
         power_restrictions,
         options: train_schedule.options.clone(),
         rolling_stock: rolling_stock.into(),
-        electrical_profile_set_id: timetable.electrical_profile_set_id,
+        electrical_profile_set_id,
     })
 }
 
@@ -591,6 +677,30 @@ pub async fn train_simulation_batch(
     train_schedules: &[TrainSchedule],
     infra: &Infra,
 ) -> Result<Vec<SimulationResponse>> {
+    let simulations = train_simulation_batch_with_cache_keys(
+        db_pool,
+        redis_client,
+        core_client,
+        train_schedules,
+        infra,
+    )
+    .await?;
+    Ok(simulations
+        .into_iter()
+        .map(|(simulation_response, _)| simulation_response)
+        .collect())
+}
+
+/// Compute train simulation in batch given a list of train schedules, along with their keys in the simulation cache
+///
+/// See [train_simulation_with_cache_key].
+pub async fn train_simulation_batch_with_cache_keys(
+    db_pool: Arc<DbConnectionPoolV2>,
+    redis_client: Arc<RedisClient>,
+    core_client: Arc<CoreClient>,
+    train_schedules: &[TrainSchedule],
+    infra: &Infra,
+) -> Result<Vec<(SimulationResponse, Option<String>)>> {
     let pending_simulations =
         train_schedules
             .iter()
@@ -599,7 +709,7 @@ pub async fn train_simulation_batch(
                 let redis_client = redis_client.clone();
                 let core_client = core_client.clone();
                 async move {
-                    train_simulation(
+                    train_simulation_with_cache_key(
                         conn.await
                             .expect("Failed to get database connection")
                             .deref_mut(),
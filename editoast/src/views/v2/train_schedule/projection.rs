@@ -283,28 +283,36 @@ async fn project_path(
 
     // 4.1 Fetch rolling stock length
     let mut project_path_result = HashMap::new();
-    let rolling_stock_names: HashSet<_> = hit_cache
+    let rolling_stocks: HashSet<_> = hit_cache
         .keys()
         .map(|id| {
-            train_map
-                .get(id)
-                .expect("Train not found")
-                .rolling_stock_name
-                .clone()
+            let train = train_map.get(id).expect("Train not found");
+            (
+                train.rolling_stock_name.clone(),
+                train.rolling_stock_revision(),
+                train.composition_id,
+            )
         })
         .collect();
-    let (rs, missing): (Vec<_>, _) =
-        RollingStockModel::retrieve_batch(db_pool.get().await?.deref_mut(), rolling_stock_names)
-            .await?;
-    assert!(missing.is_empty(), "Missing rolling stock models");
-    let rolling_stock_length: HashMap<_, _> =
-        rs.into_iter().map(|rs| (rs.name, rs.length)).collect();
+    let mut rolling_stock_length = HashMap::with_capacity(rolling_stocks.len());
+    let conn = &mut db_pool.get().await?;
+    for (name, revision, composition_id) in rolling_stocks {
+        let rolling_stock =
+            RollingStockModel::retrieve_for_train(conn, name.clone(), revision, composition_id)
+                .await?
+                .expect("Missing rolling stock model");
+        rolling_stock_length.insert((name, revision, composition_id), rolling_stock.length);
+    }
 
     // 4.2 Build the projection response
     for (id, cached) in hit_cache {
         let train = train_map.get(&id).expect("Train not found");
         let length = rolling_stock_length
-            .get(&train.rolling_stock_name)
+            .get(&(
+                train.rolling_stock_name.clone(),
+                train.rolling_stock_revision(),
+                train.composition_id,
+            ))
             .expect("Rolling stock length not found");
 
         project_path_result.insert(
@@ -0,0 +1,457 @@
+//! Validation of a train schedule without storing it
+//!
+//! The deserialization of a train schedule only checks that it is well formed. The validation also
+//! looks for its rolling stock and its path on an infra, and checks the consistency of its schedule
+//! and margins.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Query;
+use chrono::Duration;
+use editoast_schemas::train_schedule::TrainScheduleBase;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::InfraIdQueryParam;
+use super::TrainScheduleError;
+use crate::core::v2::pathfinding::PathfindingResult;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::modelsv2::Infra;
+use crate::modelsv2::TrainComposition;
+use crate::views::v2::path::pathfinding::unresolved_path_items;
+use crate::views::v2::path::pathfinding_from_train;
+use crate::CoreClient;
+use crate::RedisClient;
+use crate::RollingStockModel;
+
+crate::routes! {
+    validate,
+}
+
+editoast_common::schemas! {
+    TrainScheduleValidationReport,
+    TrainScheduleIssue,
+    TrainScheduleIssueCode,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+struct TrainScheduleValidationReport {
+    /// The issues preventing the train schedule from being simulated
+    errors: Vec<TrainScheduleIssue>,
+    /// The issues which may not be intended
+    warnings: Vec<TrainScheduleIssue>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+struct TrainScheduleIssue {
+    /// The field of the train schedule the issue is about (e.g. `schedule[2].arrival`), if any
+    field: Option<String>,
+    code: TrainScheduleIssueCode,
+    message: String,
+}
+
+impl TrainScheduleIssue {
+    fn new(field: impl Into<String>, code: TrainScheduleIssueCode, message: String) -> Self {
+        Self {
+            field: Some(field.into()),
+            code,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum TrainScheduleIssueCode {
+    InvalidFormat,
+    RollingStockNotFound,
+    CompositionNotFound,
+    NotEnoughPathItems,
+    UnresolvedPathItem,
+    DeletedPathItem,
+    PathNotFound,
+    IncompatiblePath,
+    ArrivalBeforeDeparture,
+    UnorderedMarginBoundary,
+    MarginBoundaryAtPathEnd,
+}
+
+/// Validate a train schedule without storing it
+///
+/// The train schedule is deserialized, its rolling stock and its path on the infra are looked for,
+/// and the consistency of its schedule and margins is checked.
+#[utoipa::path(
+    tag = "train_schedulev2",
+    params(InfraIdQueryParam),
+    request_body = TrainScheduleBase,
+    responses(
+        (status = 200, description = "The errors and warnings of the train schedule", body = TrainScheduleValidationReport),
+        (status = 404, description = "The infra was not found"),
+    )
+)]
+#[post("/validate")]
+async fn validate(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    core_client: Data<CoreClient>,
+    query: Query<InfraIdQueryParam>,
+    data: Json<serde_json::Value>,
+) -> Result<Json<TrainScheduleValidationReport>> {
+    let conn = &mut db_pool.get().await?;
+    let infra_id = query.infra_id;
+    let infra = Infra::retrieve_or_fail(conn, infra_id, || TrainScheduleError::InfraNotFound {
+        infra_id,
+    })
+    .await?;
+
+    let mut report = TrainScheduleValidationReport::default();
+    let train_schedule: TrainScheduleBase = match serde_json::from_value(data.into_inner()) {
+        Ok(train_schedule) => train_schedule,
+        Err(error) => {
+            report.errors.push(TrainScheduleIssue {
+                field: None,
+                code: TrainScheduleIssueCode::InvalidFormat,
+                message: error.to_string(),
+            });
+            return Ok(Json(report));
+        }
+    };
+
+    let rolling_stock = RollingStockModel::retrieve_version(
+        conn,
+        train_schedule.rolling_stock_name.clone(),
+        train_schedule.rolling_stock_revision(),
+    )
+    .await?;
+    if let (Some(rolling_stock), Some(composition_id)) =
+        (&rolling_stock, train_schedule.composition_id)
+    {
+        let composition = TrainComposition::retrieve(conn, composition_id).await?;
+        if composition.map(|composition| composition.rolling_stock_id) != Some(rolling_stock.id) {
+            report.errors.push(TrainScheduleIssue::new(
+                "composition_id",
+                TrainScheduleIssueCode::CompositionNotFound,
+                format!(
+                    "Composition {composition_id} of rolling stock '{}' could not be found",
+                    rolling_stock.name
+                ),
+            ));
+        }
+    }
+    if rolling_stock.is_none() {
+        let rolling_stock_name = &train_schedule.rolling_stock_name;
+        let (field, message) = match train_schedule.rolling_stock_revision() {
+            Some((rolling_stock_id, version)) => (
+                "rolling_stock_version",
+                format!("Version {version} of rolling stock {rolling_stock_id} could not be found"),
+            ),
+            None => (
+                "rolling_stock_name",
+                format!("Rolling stock '{rolling_stock_name}' could not be found"),
+            ),
+        };
+        report.errors.push(TrainScheduleIssue::new(
+            field,
+            TrainScheduleIssueCode::RollingStockNotFound,
+            message,
+        ));
+    }
+
+    let path_items: Vec<_> = train_schedule
+        .path
+        .iter()
+        .map(|path_item| path_item.location.clone())
+        .collect();
+    for index in unresolved_path_items(conn, infra_id, &path_items).await? {
+        report.errors.push(TrainScheduleIssue::new(
+            format!("path[{index}]"),
+            TrainScheduleIssueCode::UnresolvedPathItem,
+            "The path item can't be located on the infra".to_string(),
+        ));
+    }
+
+    let (errors, warnings) = schedule_issues(&train_schedule);
+    report.errors.extend(errors);
+    report.warnings.extend(warnings);
+
+    // The path is only looked for when its items and the rolling stock are valid
+    if report.errors.is_empty() {
+        if let Some(issue) = path_issue(
+            conn,
+            redis_client.into_inner(),
+            core_client.into_inner(),
+            &infra,
+            train_schedule,
+        )
+        .await?
+        {
+            report.errors.push(issue);
+        }
+    }
+    Ok(Json(report))
+}
+
+/// The issue of the path of a train schedule on an infra, if no path can be found
+async fn path_issue(
+    conn: &mut DbConnection,
+    redis_client: Arc<RedisClient>,
+    core_client: Arc<CoreClient>,
+    infra: &Infra,
+    train_schedule: TrainScheduleBase,
+) -> Result<Option<TrainScheduleIssue>> {
+    let mut redis_conn = redis_client.get_connection().await?;
+    let train_schedule = TrainSchedule {
+        rolling_stock_name: train_schedule.rolling_stock_name,
+        rolling_stock_id: train_schedule.rolling_stock_id,
+        rolling_stock_version: train_schedule.rolling_stock_version,
+        composition_id: train_schedule.composition_id,
+        path: train_schedule.path,
+        ..Default::default()
+    };
+    let pathfinding_result =
+        pathfinding_from_train(conn, &mut redis_conn, core_client, infra, train_schedule).await?;
+    let (code, message) = match pathfinding_result {
+        PathfindingResult::Success(_) => return Ok(None),
+        PathfindingResult::IncompatibleElectrification { .. } => (
+            TrainScheduleIssueCode::IncompatiblePath,
+            "The path isn't compatible with the electrification of the rolling stock",
+        ),
+        PathfindingResult::IncompatibleLoadingGauge { .. } => (
+            TrainScheduleIssueCode::IncompatiblePath,
+            "The path isn't compatible with the loading gauge of the rolling stock",
+        ),
+        PathfindingResult::IncompatibleSignalingSystem { .. } => (
+            TrainScheduleIssueCode::IncompatiblePath,
+            "The path isn't compatible with the signaling systems of the rolling stock",
+        ),
+        _ => (
+            TrainScheduleIssueCode::PathNotFound,
+            "No path could be found on the infra",
+        ),
+    };
+    Ok(Some(TrainScheduleIssue::new(
+        "path",
+        code,
+        message.to_string(),
+    )))
+}
+
+/// The errors and warnings of the path, schedule and margins of a train schedule
+fn schedule_issues(
+    train_schedule: &TrainScheduleBase,
+) -> (Vec<TrainScheduleIssue>, Vec<TrainScheduleIssue>) {
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    let path = &train_schedule.path;
+    if path.len() < 2 {
+        errors.push(TrainScheduleIssue::new(
+            "path",
+            TrainScheduleIssueCode::NotEnoughPathItems,
+            "The path must have at least two items".to_string(),
+        ));
+    }
+    for (index, path_item) in path.iter().enumerate() {
+        if path_item.deleted {
+            warnings.push(TrainScheduleIssue::new(
+                format!("path[{index}]"),
+                TrainScheduleIssueCode::DeletedPathItem,
+                format!("Path item '{}' is flagged as deleted", path_item.id.0),
+            ));
+        }
+    }
+    let path_indexes: HashMap<_, _> = path
+        .iter()
+        .enumerate()
+        .map(|(index, path_item)| (&path_item.id, index))
+        .collect();
+
+    // The train must reach each scheduled point after leaving the previous one
+    let mut schedule: Vec<_> = train_schedule.schedule.iter().enumerate().collect();
+    schedule.sort_by_key(|(_, item)| path_indexes[&item.at]);
+    let mut previous_departure: Option<Duration> = None;
+    for (index, item) in schedule {
+        if let Some(arrival) = &item.arrival {
+            let arrival = **arrival;
+            if previous_departure.is_some_and(|departure| arrival <= departure) {
+                errors.push(TrainScheduleIssue::new(
+                    format!("schedule[{index}].arrival"),
+                    TrainScheduleIssueCode::ArrivalBeforeDeparture,
+                    format!(
+                        "The arrival at '{}' must be after the departure from the previous scheduled point",
+                        item.at.0
+                    ),
+                ));
+            }
+            let stop_for = item
+                .stop_for
+                .as_deref()
+                .copied()
+                .unwrap_or_else(Duration::zero);
+            previous_departure = Some(arrival + stop_for);
+        } else if let (Some(departure), Some(stop_for)) = (previous_departure, &item.stop_for) {
+            // Without arrival time, the stop still delays the departure from the next points
+            previous_departure = Some(departure + **stop_for);
+        }
+    }
+
+    // Margins boundaries split the path in consecutive ranges
+    let last_index = path.len().saturating_sub(1);
+    let mut previous_boundary = None;
+    for (index, boundary) in train_schedule.margins.boundaries.iter().enumerate() {
+        let path_index = path_indexes[boundary];
+        if previous_boundary.is_some_and(|previous| path_index <= previous) {
+            errors.push(TrainScheduleIssue::new(
+                format!("margins.boundaries[{index}]"),
+                TrainScheduleIssueCode::UnorderedMarginBoundary,
+                format!(
+                    "Boundary '{}' must be after the previous boundary on the path",
+                    boundary.0
+                ),
+            ));
+        }
+        if path_index == 0 || path_index == last_index {
+            warnings.push(TrainScheduleIssue::new(
+                format!("margins.boundaries[{index}]"),
+                TrainScheduleIssueCode::MarginBoundaryAtPathEnd,
+                format!(
+                    "Boundary '{}' is at an end of the path, one of the margins is empty",
+                    boundary.0
+                ),
+            ));
+        }
+        previous_boundary = Some(path_index);
+    }
+    (errors, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use editoast_schemas::primitives::PositiveDuration;
+    use editoast_schemas::train_schedule::MarginValue;
+    use editoast_schemas::train_schedule::Margins;
+    use editoast_schemas::train_schedule::PathItem;
+    use editoast_schemas::train_schedule::PathItemLocation;
+    use editoast_schemas::train_schedule::ScheduleItem;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn path_item(id: &str) -> PathItem {
+        PathItem {
+            id: id.into(),
+            deleted: false,
+            location: PathItemLocation::OperationalPointId {
+                operational_point: id.into(),
+            },
+        }
+    }
+
+    fn schedule_item(at: &str, arrival: Option<i64>, stop_for: Option<i64>) -> ScheduleItem {
+        let duration = |minutes| PositiveDuration::try_from(Duration::minutes(minutes)).unwrap();
+        ScheduleItem {
+            at: at.into(),
+            arrival: arrival.map(duration),
+            stop_for: stop_for.map(duration),
+            ..Default::default()
+        }
+    }
+
+    fn train_schedule(schedule: Vec<ScheduleItem>, boundaries: Vec<&str>) -> TrainScheduleBase {
+        TrainScheduleBase {
+            path: ["a", "b", "c", "d"].into_iter().map(path_item).collect(),
+            schedule,
+            margins: Margins {
+                values: vec![MarginValue::None; boundaries.len() + 1],
+                boundaries: boundaries.into_iter().map(Into::into).collect(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn codes(issues: Vec<TrainScheduleIssue>) -> Vec<(String, TrainScheduleIssueCode)> {
+        issues
+            .into_iter()
+            .map(|issue| (issue.field.unwrap(), issue.code))
+            .collect()
+    }
+
+    #[test]
+    fn consistent_train_schedule() {
+        let train_schedule = train_schedule(
+            vec![
+                schedule_item("b", Some(10), Some(5)),
+                schedule_item("d", Some(20), None),
+            ],
+            vec!["c"],
+        );
+        let (errors, warnings) = schedule_issues(&train_schedule);
+        assert!(errors.is_empty());
+        assert!(warnings.is_empty());
+    }
+
+    #[rstest]
+    #[case::decreasing_arrivals(vec![schedule_item("d", Some(10), None), schedule_item("b", Some(20), None)], "schedule[0].arrival")]
+    #[case::stop_too_long(vec![schedule_item("b", Some(10), Some(10)), schedule_item("d", Some(20), None)], "schedule[1].arrival")]
+    #[case::stop_without_arrival(vec![schedule_item("a", None, None), schedule_item("b", Some(10), Some(5)), schedule_item("c", None, Some(5)), schedule_item("d", Some(20), None)], "schedule[3].arrival")]
+    fn arrival_before_departure(#[case] schedule: Vec<ScheduleItem>, #[case] field: &str) {
+        let (errors, _) = schedule_issues(&train_schedule(schedule, vec![]));
+        assert_eq!(
+            codes(errors),
+            vec![(
+                field.to_string(),
+                TrainScheduleIssueCode::ArrivalBeforeDeparture
+            )]
+        );
+    }
+
+    #[test]
+    fn margin_boundaries() {
+        let (errors, warnings) = schedule_issues(&train_schedule(vec![], vec!["c", "b", "d"]));
+        assert_eq!(
+            codes(errors),
+            vec![(
+                "margins.boundaries[1]".to_string(),
+                TrainScheduleIssueCode::UnorderedMarginBoundary
+            )]
+        );
+        assert_eq!(
+            codes(warnings),
+            vec![(
+                "margins.boundaries[2]".to_string(),
+                TrainScheduleIssueCode::MarginBoundaryAtPathEnd
+            )]
+        );
+    }
+
+    #[test]
+    fn deleted_path_item_and_short_path() {
+        let mut train_schedule = train_schedule(vec![], vec![]);
+        train_schedule.path.truncate(1);
+        train_schedule.path[0].deleted = true;
+        let (errors, warnings) = schedule_issues(&train_schedule);
+        assert_eq!(
+            codes(errors),
+            vec![(
+                "path".to_string(),
+                TrainScheduleIssueCode::NotEnoughPathItems
+            )]
+        );
+        assert_eq!(
+            codes(warnings),
+            vec![(
+                "path[0]".to_string(),
+                TrainScheduleIssueCode::DeletedPathItem
+            )]
+        );
+    }
+}
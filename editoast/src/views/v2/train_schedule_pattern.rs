@@ -0,0 +1,434 @@
+//! Periodic train schedules
+//!
+//! A pattern repeats a train schedule template over time. The generated train schedules, its
+//! instances, are regenerated whenever the pattern is updated and deleted along with it.
+
+use actix_web::delete;
+use actix_web::get;
+use actix_web::post;
+use actix_web::put;
+use actix_web::web::Data;
+use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::HttpResponse;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use editoast_derive::EditoastError;
+use editoast_schemas::train_schedule::Repetition;
+use editoast_schemas::train_schedule::TrainScheduleBase;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+
+use crate::error::InternalError;
+use crate::error::Result;
+use crate::modelsv2::prelude::*;
+use crate::modelsv2::timetable::Timetable;
+use crate::modelsv2::train_schedule::TrainSchedule;
+use crate::modelsv2::train_schedule::TrainScheduleChangeset;
+use crate::modelsv2::train_schedule_pattern::TrainSchedulePattern;
+use crate::modelsv2::DbConnection;
+use crate::modelsv2::DbConnectionPoolV2;
+use crate::views::v2::train_schedule::TrainScheduleForm;
+
+/// The maximum number of repetitions of a pattern, before keeping its days of the week
+const MAX_REPETITIONS: i64 = 1000;
+
+crate::routes! {
+    "/v2/train_schedule_pattern" => {
+        post,
+        "/{id}" => {
+            get,
+            put,
+            delete,
+        }
+    },
+}
+
+editoast_common::schemas! {
+    TrainSchedulePatternForm,
+    TrainSchedulePatternResult,
+}
+
+#[derive(Debug, Error, EditoastError)]
+#[editoast_error(base_id = "train_schedule_pattern")]
+enum TrainSchedulePatternError {
+    #[error("Train schedule pattern '{pattern_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    NotFound { pattern_id: i64 },
+    #[error("Timetable '{timetable_id}', could not be found")]
+    #[editoast_error(status = 404)]
+    TimetableNotFound { timetable_id: i64 },
+    #[error("The repetition interval must not be zero")]
+    #[editoast_error(status = 400)]
+    InvalidInterval,
+    #[error("A pattern can't be repeated more than {max} times")]
+    #[editoast_error(status = 400)]
+    TooManyRepetitions { max: i64 },
+    #[error("The repetition doesn't generate any train schedule")]
+    #[editoast_error(status = 400)]
+    EmptyRepetition,
+}
+
+#[derive(IntoParams, Deserialize)]
+struct TrainSchedulePatternIdParam {
+    /// A train schedule pattern ID
+    id: i64,
+}
+
+/// Creation and update form of a train schedule pattern
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct TrainSchedulePatternForm {
+    timetable_id: i64,
+    /// The repeated train schedule, its `start_time` is replaced by the departure times of the repetition
+    template: TrainScheduleBase,
+    repetition: Repetition,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct TrainSchedulePatternResult {
+    id: i64,
+    timetable_id: i64,
+    template: TrainScheduleBase,
+    repetition: Repetition,
+    /// The train schedules generated from the pattern, ordered by departure time
+    train_ids: Vec<i64>,
+}
+
+impl TrainSchedulePatternResult {
+    async fn new(conn: &mut DbConnection, pattern: TrainSchedulePattern) -> Result<Self> {
+        let train_ids = pattern.instance_ids(conn).await?;
+        Ok(Self {
+            id: pattern.id,
+            timetable_id: pattern.timetable_id,
+            template: pattern.template,
+            repetition: pattern.repetition,
+            train_ids,
+        })
+    }
+}
+
+/// Build the train schedules repeating the template of a pattern
+fn instances(pattern: &TrainSchedulePattern) -> Result<Vec<TrainScheduleChangeset>> {
+    let repetition = &pattern.repetition;
+    let interval = repetition.interval.num_milliseconds();
+    if interval == 0 {
+        return Err(TrainSchedulePatternError::InvalidInterval.into());
+    }
+    let repetitions =
+        (repetition.end_time - repetition.start_time).num_milliseconds() / interval + 1;
+    if repetitions > MAX_REPETITIONS {
+        return Err(TrainSchedulePatternError::TooManyRepetitions {
+            max: MAX_REPETITIONS,
+        }
+        .into());
+    }
+
+    let instances: Vec<_> = repetition
+        .departure_times()
+        .enumerate()
+        .map(|(index, start_time)| {
+            let form = TrainScheduleForm {
+                timetable_id: Some(pattern.timetable_id),
+                train_schedule: TrainScheduleBase {
+                    train_name: format!("{} {}", pattern.template.train_name, index + 1),
+                    start_time,
+                    ..pattern.template.clone()
+                },
+            };
+            TrainScheduleChangeset::from(form).pattern_id(Some(pattern.id))
+        })
+        .collect();
+    if instances.is_empty() {
+        return Err(TrainSchedulePatternError::EmptyRepetition.into());
+    }
+    Ok(instances)
+}
+
+/// Create a train schedule pattern and the train schedules repeating its template
+#[utoipa::path(
+    tag = "timetablev2,train_schedulev2",
+    request_body = TrainSchedulePatternForm,
+    responses(
+        (status = 200, description = "The created pattern", body = TrainSchedulePatternResult),
+        (status = 404, description = "The timetable was not found"),
+    )
+)]
+#[post("")]
+async fn post(
+    db_pool: Data<DbConnectionPoolV2>,
+    data: Json<TrainSchedulePatternForm>,
+) -> Result<Json<TrainSchedulePatternResult>> {
+    let TrainSchedulePatternForm {
+        timetable_id,
+        template,
+        repetition,
+    } = data.into_inner();
+    let conn = &mut db_pool.get().await?;
+
+    Timetable::retrieve_or_fail(conn, timetable_id, || {
+        TrainSchedulePatternError::TimetableNotFound { timetable_id }
+    })
+    .await?;
+
+    let pattern = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let pattern = TrainSchedulePattern::changeset()
+                    .timetable_id(timetable_id)
+                    .template(template)
+                    .repetition(repetition)
+                    .create(conn)
+                    .await?;
+                let _: Vec<TrainSchedule> =
+                    TrainSchedule::create_batch(conn, instances(&pattern)?).await?;
+                Ok(pattern)
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    Ok(Json(TrainSchedulePatternResult::new(conn, pattern).await?))
+}
+
+/// Return a train schedule pattern with the train schedules generated from it
+#[utoipa::path(
+    tag = "train_schedulev2",
+    params(TrainSchedulePatternIdParam),
+    responses(
+        (status = 200, description = "The train schedule pattern", body = TrainSchedulePatternResult),
+        (status = 404, description = "The pattern was not found"),
+    )
+)]
+#[get("")]
+async fn get(
+    db_pool: Data<DbConnectionPoolV2>,
+    pattern_id: Path<TrainSchedulePatternIdParam>,
+) -> Result<Json<TrainSchedulePatternResult>> {
+    let pattern_id = pattern_id.id;
+    let conn = &mut db_pool.get().await?;
+
+    let pattern = TrainSchedulePattern::retrieve_or_fail(conn, pattern_id, || {
+        TrainSchedulePatternError::NotFound { pattern_id }
+    })
+    .await?;
+    Ok(Json(TrainSchedulePatternResult::new(conn, pattern).await?))
+}
+
+/// Update a train schedule pattern and regenerate its train schedules
+///
+/// The train schedules generated from the previous version of the pattern are replaced.
+#[utoipa::path(
+    tag = "train_schedulev2",
+    params(TrainSchedulePatternIdParam),
+    request_body = TrainSchedulePatternForm,
+    responses(
+        (status = 200, description = "The updated pattern", body = TrainSchedulePatternResult),
+        (status = 404, description = "The pattern or the timetable was not found"),
+    )
+)]
+#[put("")]
+async fn put(
+    db_pool: Data<DbConnectionPoolV2>,
+    pattern_id: Path<TrainSchedulePatternIdParam>,
+    data: Json<TrainSchedulePatternForm>,
+) -> Result<Json<TrainSchedulePatternResult>> {
+    let pattern_id = pattern_id.id;
+    let TrainSchedulePatternForm {
+        timetable_id,
+        template,
+        repetition,
+    } = data.into_inner();
+    let conn = &mut db_pool.get().await?;
+
+    Timetable::retrieve_or_fail(conn, timetable_id, || {
+        TrainSchedulePatternError::TimetableNotFound { timetable_id }
+    })
+    .await?;
+
+    let pattern = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                let pattern = TrainSchedulePattern::changeset()
+                    .timetable_id(timetable_id)
+                    .template(template)
+                    .repetition(repetition)
+                    .update_or_fail(conn, pattern_id, || TrainSchedulePatternError::NotFound {
+                        pattern_id,
+                    })
+                    .await?;
+                pattern.delete_instances(conn).await?;
+                let _: Vec<TrainSchedule> =
+                    TrainSchedule::create_batch(conn, instances(&pattern)?).await?;
+                Ok(pattern)
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    Ok(Json(TrainSchedulePatternResult::new(conn, pattern).await?))
+}
+
+/// Delete a train schedule pattern and the train schedules generated from it
+#[utoipa::path(
+    tag = "train_schedulev2",
+    params(TrainSchedulePatternIdParam),
+    responses(
+        (status = 204, description = "The pattern and its train schedules have been deleted"),
+        (status = 404, description = "The pattern was not found"),
+    )
+)]
+#[delete("")]
+async fn delete(
+    db_pool: Data<DbConnectionPoolV2>,
+    pattern_id: Path<TrainSchedulePatternIdParam>,
+) -> Result<HttpResponse> {
+    let pattern_id = pattern_id.id;
+    let conn = &mut db_pool.get().await?;
+
+    TrainSchedulePattern::delete_static_or_fail(conn, pattern_id, || {
+        TrainSchedulePatternError::NotFound { pattern_id }
+    })
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use actix_http::StatusCode;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::modelsv2::fixtures::create_timetable;
+    use crate::modelsv2::fixtures::simple_train_schedule_base;
+    use crate::views::test_app::TestAppBuilder;
+
+    fn pattern_form(timetable_id: i64, end_time: &str) -> Value {
+        json!({
+            "timetable_id": timetable_id,
+            "template": simple_train_schedule_base(),
+            "repetition": {
+                "interval": "PT30M",
+                "start_time": "2024-05-01T06:00:00Z",
+                "end_time": end_time,
+            }
+        })
+    }
+
+    #[rstest]
+    async fn train_schedule_pattern_post() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+
+        let request = TestRequest::post()
+            .uri("/v2/train_schedule_pattern")
+            .set_json(pattern_form(timetable.id, "2024-05-01T07:00:00Z"))
+            .to_request();
+        let pattern: TrainSchedulePatternResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        let (trains, _): (Vec<TrainSchedule>, _) =
+            TrainSchedule::retrieve_batch(pool.get_ok().deref_mut(), pattern.train_ids)
+                .await
+                .expect("Failed to retrieve train schedules");
+        let mut trains: Vec<_> = trains
+            .into_iter()
+            .map(|train| {
+                assert_eq!(train.pattern_id, Some(pattern.id));
+                (train.start_time.to_rfc3339(), train.train_name)
+            })
+            .collect();
+        trains.sort();
+        assert_eq!(
+            trains,
+            vec![
+                ("2024-05-01T06:00:00+00:00".into(), "ABC3615 1".into()),
+                ("2024-05-01T06:30:00+00:00".into(), "ABC3615 2".into()),
+                ("2024-05-01T07:00:00+00:00".into(), "ABC3615 3".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    async fn train_schedule_pattern_put_regenerates_instances() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let request = TestRequest::post()
+            .uri("/v2/train_schedule_pattern")
+            .set_json(pattern_form(timetable.id, "2024-05-01T07:00:00Z"))
+            .to_request();
+        let pattern: TrainSchedulePatternResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        let request = TestRequest::put()
+            .uri(&format!("/v2/train_schedule_pattern/{}", pattern.id))
+            .set_json(pattern_form(timetable.id, "2024-05-01T06:30:00Z"))
+            .to_request();
+        let updated: TrainSchedulePatternResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        assert_eq!(updated.train_ids.len(), 2);
+        for train_id in pattern.train_ids {
+            let exists = TrainSchedule::exists(pool.get_ok().deref_mut(), train_id)
+                .await
+                .expect("Failed to check if the train schedule exists");
+            assert!(!exists);
+        }
+    }
+
+    #[rstest]
+    async fn train_schedule_pattern_delete_instances() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let request = TestRequest::post()
+            .uri("/v2/train_schedule_pattern")
+            .set_json(pattern_form(timetable.id, "2024-05-01T07:00:00Z"))
+            .to_request();
+        let pattern: TrainSchedulePatternResult =
+            app.fetch(request).assert_status(StatusCode::OK).json_into();
+
+        let request = TestRequest::delete()
+            .uri(&format!("/v2/train_schedule_pattern/{}", pattern.id))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NO_CONTENT);
+
+        for train_id in pattern.train_ids {
+            let exists = TrainSchedule::exists(pool.get_ok().deref_mut(), train_id)
+                .await
+                .expect("Failed to check if the train schedule exists");
+            assert!(!exists);
+        }
+    }
+
+    #[rstest]
+    #[case::zero_interval("PT0S", "2024-05-01T07:00:00Z")]
+    #[case::too_many_repetitions("PT1S", "2024-05-02T06:00:00Z")]
+    #[case::empty("PT1H", "2024-05-01T05:00:00Z")]
+    async fn train_schedule_pattern_invalid_repetition(
+        #[case] interval: &str,
+        #[case] end_time: &str,
+    ) {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let timetable = create_timetable(pool.get_ok().deref_mut()).await;
+        let mut form = pattern_form(timetable.id, end_time);
+        form["repetition"]["interval"] = json!(interval);
+
+        let request = TestRequest::post()
+            .uri("/v2/train_schedule_pattern")
+            .set_json(form)
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,39 +1,71 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::DerefMut;
+
+use actix_web::delete;
+use actix_web::get;
 use actix_web::post;
+use actix_web::put;
 use actix_web::web::Data;
 use actix_web::web::Json;
+use actix_web::web::Path;
+use actix_web::web::Query;
+use actix_web::HttpResponse;
 use chrono::NaiveDateTime;
 use chrono::Utc;
 use derivative::Derivative;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
 use editoast_derive::EditoastError;
 use serde::de::Error as SerdeError;
 use serde::Deserialize;
 use serde::Serialize;
 use std::result::Result as StdResult;
 use thiserror::Error;
+use utoipa::IntoParams;
 use utoipa::ToSchema;
 
+use crate::client::MapLayersConfig;
 use crate::error::InternalError;
 use crate::error::Result;
+use crate::generated_data::WorkScheduleLayer;
+use crate::map;
+use crate::map::InvalidationZone;
+use crate::map::MapLayers;
+use crate::modelsv2::prelude::*;
 use crate::modelsv2::work_schedules::WorkSchedule;
 use crate::modelsv2::work_schedules::WorkScheduleGroup;
 use crate::modelsv2::work_schedules::WorkScheduleType;
-use crate::modelsv2::Changeset;
-use crate::modelsv2::Create;
-use crate::modelsv2::CreateBatch;
+use crate::modelsv2::DbConnection;
 use crate::modelsv2::DbConnectionPoolV2;
-use crate::modelsv2::Model;
+use crate::modelsv2::TrackSectionModel;
+use crate::views::pagination::PaginatedList as _;
+use crate::views::pagination::PaginationQueryParam;
+use crate::views::pagination::PaginationStats;
+use crate::RedisClient;
 use editoast_schemas::infra::TrackRange;
 
 crate::routes! {
     "/work_schedules" => {
         create,
+        list,
+        query,
+        "/{work_schedule_group_id}" => {
+            get,
+            update,
+            delete,
+        },
     }
 }
 
 editoast_common::schemas! {
+    WorkSchedule,
     WorkScheduleCreateForm,
     WorkScheduleCreateResponse,
+    WorkScheduleGroup,
+    WorkScheduleGroupWithSchedules,
     WorkScheduleItemForm,
+    WorkScheduleQueryForm,
 }
 
 #[derive(Debug, Error, EditoastError)]
@@ -42,6 +74,12 @@ pub enum WorkScheduleError {
     #[error("Name '{name}' already used")]
     #[editoast_error(status = 400)]
     NameAlreadyUsed { name: String },
+    #[error("Work schedule group '{work_schedule_group_id}' could not be found")]
+    #[editoast_error(status = 404)]
+    NotFound { work_schedule_group_id: i64 },
+    #[error("The start date of the time window must be before its end date")]
+    #[editoast_error(status = 400)]
+    InvalidTimeWindow,
 }
 
 pub fn map_diesel_error(e: InternalError, name: impl AsRef<str>) -> InternalError {
@@ -57,6 +95,74 @@ pub fn map_diesel_error(e: InternalError, name: impl AsRef<str>) -> InternalErro
     }
 }
 
+/// Name of the map layer displaying the work schedules
+const WORK_SCHEDULES_LAYER: &str = "work_schedules";
+
+/// Get the track sections on which some work schedules lie
+fn work_schedule_tracks<'a>(
+    work_schedules: impl IntoIterator<Item = &'a WorkSchedule>,
+) -> HashSet<String> {
+    work_schedules
+        .into_iter()
+        .flat_map(|work_schedule| work_schedule.track_ranges.iter())
+        .map(|track_range| track_range.track.0.clone())
+        .collect()
+}
+
+/// Project work schedules on the infras holding their track sections and invalidate the cached
+/// tiles of their map layer
+///
+/// The layer rows of deleted work schedules are removed by cascade.
+/// `track_ids` are the track sections of the added and removed work schedules: only the tiles
+/// covering these track sections are invalidated, on the infras holding them.
+async fn update_work_schedule_layer(
+    conn: &mut DbConnection,
+    redis_client: &RedisClient,
+    map_layers: &MapLayers,
+    map_layers_config: &MapLayersConfig,
+    work_schedule_ids: &[i64],
+    track_ids: HashSet<String>,
+) -> Result<()> {
+    if track_ids.is_empty() {
+        return Ok(());
+    }
+    let track_ids: Vec<_> = track_ids.into_iter().collect();
+    let settings = SelectionSettings::new()
+        .filter(move || TrackSectionModel::OBJ_ID.eq_any(track_ids.clone()));
+    let mut invalidation_zones: HashMap<i64, InvalidationZone> = HashMap::new();
+    for track in TrackSectionModel::list(conn, settings).await? {
+        invalidation_zones
+            .entry(track.infra_id)
+            .or_default()
+            .geo
+            .union(&track.schema.geo_bbox());
+    }
+
+    let infra_ids: Vec<_> = invalidation_zones.keys().copied().collect();
+    WorkScheduleLayer::insert(conn, &infra_ids, work_schedule_ids).await?;
+
+    let work_schedule_layers = MapLayers {
+        layers: map_layers
+            .layers
+            .iter()
+            .filter(|(name, _)| *name == WORK_SCHEDULES_LAYER)
+            .map(|(name, layer)| (name.clone(), layer.clone()))
+            .collect(),
+    };
+    let mut redis = redis_client.get_connection().await?;
+    for (infra_id, invalidation_zone) in invalidation_zones {
+        map::invalidate_zone(
+            &mut redis,
+            &work_schedule_layers,
+            infra_id,
+            &invalidation_zone,
+            map_layers_config,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
 #[derive(Serialize, Derivative, ToSchema)]
 struct WorkScheduleItemForm {
     pub start_date_time: NaiveDateTime,
@@ -128,6 +234,61 @@ struct WorkScheduleCreateResponse {
     work_schedule_group_id: i64,
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+struct WorkScheduleGroupIdParam {
+    /// A work schedule group ID
+    work_schedule_group_id: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct WorkScheduleGroupWithSchedules {
+    #[serde(flatten)]
+    group: WorkScheduleGroup,
+    work_schedules: Vec<WorkSchedule>,
+}
+
+impl WorkScheduleGroupWithSchedules {
+    async fn retrieve(conn: &mut DbConnection, work_schedule_group_id: i64) -> Result<Self> {
+        let group = WorkScheduleGroup::retrieve_or_fail(conn, work_schedule_group_id, || {
+            WorkScheduleError::NotFound {
+                work_schedule_group_id,
+            }
+        })
+        .await?;
+        let settings = SelectionSettings::new()
+            .filter(move || WorkSchedule::WORK_SCHEDULE_GROUP_ID.eq(work_schedule_group_id))
+            .order_by(|| WorkSchedule::ID.asc());
+        let work_schedules = WorkSchedule::list(conn, settings).await?;
+        Ok(Self {
+            group,
+            work_schedules,
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(test, derive(Deserialize))]
+struct WorkScheduleGroupListResponse {
+    #[serde(flatten)]
+    stats: PaginationStats,
+    results: Vec<WorkScheduleGroup>,
+}
+
+/// Filters the work schedules by time window and track sections
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct WorkScheduleQueryForm {
+    /// Start of the time window
+    start_date_time: NaiveDateTime,
+    /// End of the time window
+    end_date_time: NaiveDateTime,
+    /// Only keep the work schedules with a track range on one of these track sections
+    ///
+    /// If not provided, the work schedules on any track section are kept.
+    #[serde(default)]
+    track_sections: Option<Vec<String>>,
+}
+
 #[utoipa::path(
     tag = "work_schedules",
     request_body = WorkScheduleCreateForm,
@@ -138,6 +299,9 @@ struct WorkScheduleCreateResponse {
 #[post("")]
 async fn create(
     db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
     data: Json<WorkScheduleCreateForm>,
 ) -> Result<Json<WorkScheduleCreateResponse>> {
     let conn = &mut db_pool.get().await?;
@@ -159,14 +323,239 @@ async fn create(
         .into_iter()
         .map(|work_schedule| work_schedule.into_work_schedule_changeset(work_schedule_group.id))
         .collect::<Vec<_>>();
-    let _work_schedules: Vec<_> =
+    let work_schedules: Vec<_> =
         WorkSchedule::create_batch(conn, work_schedules_changesets).await?;
+    let work_schedule_ids: Vec<_> = work_schedules
+        .iter()
+        .map(|work_schedule: &WorkSchedule| work_schedule.id)
+        .collect();
+    update_work_schedule_layer(
+        conn,
+        &redis_client,
+        &map_layers,
+        &map_layers_config,
+        &work_schedule_ids,
+        work_schedule_tracks(&work_schedules),
+    )
+    .await?;
 
     Ok(Json(WorkScheduleCreateResponse {
         work_schedule_group_id: work_schedule_group.id,
     }))
 }
 
+/// List the work schedule groups
+#[utoipa::path(
+    tag = "work_schedules",
+    params(PaginationQueryParam),
+    responses(
+        (status = 200, description = "The work schedule groups, paginated", body = inline(WorkScheduleGroupListResponse)),
+    )
+)]
+#[get("")]
+async fn list(
+    db_pool: Data<DbConnectionPoolV2>,
+    pagination_params: Query<PaginationQueryParam>,
+) -> Result<Json<WorkScheduleGroupListResponse>> {
+    let settings = pagination_params
+        .validate(1000)?
+        .warn_page_size(100)
+        .into_selection_settings()
+        .order_by(|| WorkScheduleGroup::ID.asc());
+    let (results, stats) =
+        WorkScheduleGroup::list_paginated(db_pool.get().await?.deref_mut(), settings).await?;
+    Ok(Json(WorkScheduleGroupListResponse { stats, results }))
+}
+
+/// Retrieve a work schedule group with its work schedules
+#[utoipa::path(
+    tag = "work_schedules",
+    params(WorkScheduleGroupIdParam),
+    responses(
+        (status = 200, body = WorkScheduleGroupWithSchedules, description = "The work schedule group"),
+        (status = 404, description = "The work schedule group was not found"),
+    )
+)]
+#[get("")]
+async fn get(
+    db_pool: Data<DbConnectionPoolV2>,
+    path: Path<WorkScheduleGroupIdParam>,
+) -> Result<Json<WorkScheduleGroupWithSchedules>> {
+    let conn = &mut db_pool.get().await?;
+    let group = WorkScheduleGroupWithSchedules::retrieve(conn, path.work_schedule_group_id).await?;
+    Ok(Json(group))
+}
+
+/// Replace the name and the work schedules of a work schedule group
+#[utoipa::path(
+    tag = "work_schedules",
+    params(WorkScheduleGroupIdParam),
+    request_body = WorkScheduleCreateForm,
+    responses(
+        (status = 200, body = WorkScheduleGroupWithSchedules, description = "The updated work schedule group"),
+        (status = 404, description = "The work schedule group was not found"),
+    )
+)]
+#[put("")]
+async fn update(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+    path: Path<WorkScheduleGroupIdParam>,
+    data: Json<WorkScheduleCreateForm>,
+) -> Result<Json<WorkScheduleGroupWithSchedules>> {
+    let work_schedule_group_id = path.work_schedule_group_id;
+    let WorkScheduleCreateForm {
+        work_schedule_group_name,
+        work_schedules,
+    } = data.into_inner();
+    let conn = &mut db_pool.get().await?;
+
+    let previous_tracks = conn
+        .transaction::<_, InternalError, _>(|conn| {
+            async move {
+                WorkScheduleGroup::changeset()
+                    .name(work_schedule_group_name.clone())
+                    .update_or_fail(conn, work_schedule_group_id, || {
+                        WorkScheduleError::NotFound {
+                            work_schedule_group_id,
+                        }
+                    })
+                    .await
+                    .map_err(|e| map_diesel_error(e, work_schedule_group_name))?;
+
+                let settings = SelectionSettings::new().filter(move || {
+                    WorkSchedule::WORK_SCHEDULE_GROUP_ID.eq(work_schedule_group_id)
+                });
+                let previous_work_schedules = WorkSchedule::list(conn, settings).await?;
+                let ids: Vec<_> = previous_work_schedules
+                    .iter()
+                    .map(|work_schedule| work_schedule.id)
+                    .collect();
+                WorkSchedule::delete_batch(conn, ids).await?;
+
+                let work_schedules_changesets = work_schedules
+                    .into_iter()
+                    .map(|work_schedule| {
+                        work_schedule.into_work_schedule_changeset(work_schedule_group_id)
+                    })
+                    .collect::<Vec<_>>();
+                let _work_schedules: Vec<_> =
+                    WorkSchedule::create_batch(conn, work_schedules_changesets).await?;
+                Ok(work_schedule_tracks(&previous_work_schedules))
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    let group = WorkScheduleGroupWithSchedules::retrieve(conn, work_schedule_group_id).await?;
+    let work_schedule_ids: Vec<_> = group
+        .work_schedules
+        .iter()
+        .map(|work_schedule| work_schedule.id)
+        .collect();
+    let mut track_ids = work_schedule_tracks(&group.work_schedules);
+    track_ids.extend(previous_tracks);
+    update_work_schedule_layer(
+        conn,
+        &redis_client,
+        &map_layers,
+        &map_layers_config,
+        &work_schedule_ids,
+        track_ids,
+    )
+    .await?;
+    Ok(Json(group))
+}
+
+/// Delete a work schedule group and its work schedules
+#[utoipa::path(
+    tag = "work_schedules",
+    params(WorkScheduleGroupIdParam),
+    responses(
+        (status = 204, description = "The work schedule group was deleted"),
+        (status = 404, description = "The work schedule group was not found"),
+    )
+)]
+#[delete("")]
+async fn delete(
+    db_pool: Data<DbConnectionPoolV2>,
+    redis_client: Data<RedisClient>,
+    map_layers: Data<MapLayers>,
+    map_layers_config: Data<MapLayersConfig>,
+    path: Path<WorkScheduleGroupIdParam>,
+) -> Result<HttpResponse> {
+    let work_schedule_group_id = path.work_schedule_group_id;
+    let conn = &mut db_pool.get().await?;
+    let group = WorkScheduleGroupWithSchedules::retrieve(conn, work_schedule_group_id).await?;
+    WorkScheduleGroup::delete_static_or_fail(conn, work_schedule_group_id, || {
+        WorkScheduleError::NotFound {
+            work_schedule_group_id,
+        }
+    })
+    .await?;
+    update_work_schedule_layer(
+        conn,
+        &redis_client,
+        &map_layers,
+        &map_layers_config,
+        &[],
+        work_schedule_tracks(&group.work_schedules),
+    )
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the work schedules intersecting a time window, sorted by start date
+///
+/// These are the work schedules taken into account by STDCM for a search in this time window.
+#[utoipa::path(
+    tag = "work_schedules",
+    request_body = WorkScheduleQueryForm,
+    responses(
+        (status = 200, body = Vec<WorkSchedule>, description = "The work schedules matching the query"),
+        (status = 400, description = "The start date is not before the end date"),
+    )
+)]
+#[post("/query")]
+async fn query(
+    db_pool: Data<DbConnectionPoolV2>,
+    data: Json<WorkScheduleQueryForm>,
+) -> Result<Json<Vec<WorkSchedule>>> {
+    let WorkScheduleQueryForm {
+        start_date_time,
+        end_date_time,
+        track_sections,
+    } = data.into_inner();
+    if start_date_time >= end_date_time {
+        return Err(WorkScheduleError::InvalidTimeWindow.into());
+    }
+
+    let work_schedules = WorkSchedule::list_in_time_window(
+        db_pool.get().await?.deref_mut(),
+        start_date_time,
+        end_date_time,
+    )
+    .await?;
+    let work_schedules = match track_sections {
+        None => work_schedules,
+        Some(track_sections) => {
+            let track_sections: HashSet<_> = track_sections.into_iter().collect();
+            work_schedules
+                .into_iter()
+                .filter(|work_schedule| {
+                    work_schedule
+                        .track_ranges
+                        .iter()
+                        .any(|track_range| track_sections.contains(&track_range.track.0))
+                })
+                .collect()
+        }
+    };
+    Ok(Json(work_schedules))
+}
+
 #[cfg(test)]
 pub mod test {
     use actix_web::http::StatusCode;
@@ -177,7 +566,6 @@ pub mod test {
     use std::ops::DerefMut;
 
     use super::*;
-    use crate::modelsv2::prelude::*;
     use crate::views::test_app::TestAppBuilder;
 
     #[rstest]
@@ -274,4 +662,211 @@ pub mod test {
             "editoast:work_schedule:NameAlreadyUsed"
         );
     }
+
+    async fn create_work_schedule_group(conn: &mut DbConnection, name: &str) -> i64 {
+        let work_schedule_group = WorkScheduleGroup::changeset()
+            .name(name.to_string())
+            .creation_date(Utc::now().naive_utc())
+            .create(conn)
+            .await
+            .expect("Failed to create work schedule group");
+        let work_schedules = [
+            (
+                "morning",
+                "2024-01-01T08:00:00",
+                "2024-01-01T09:00:00",
+                "TA0",
+            ),
+            (
+                "afternoon",
+                "2024-01-01T14:00:00",
+                "2024-01-01T16:00:00",
+                "TA1",
+            ),
+        ]
+        .into_iter()
+        .map(|(obj_id, start, end, track)| {
+            WorkSchedule::changeset()
+                .start_date_time(start.parse().unwrap())
+                .end_date_time(end.parse().unwrap())
+                .track_ranges(vec![TrackRange::new(track, 0.0, 100.0)])
+                .obj_id(obj_id.to_string())
+                .work_schedule_type(WorkScheduleType::Catenary)
+                .work_schedule_group_id(work_schedule_group.id)
+        })
+        .collect::<Vec<_>>();
+        let _: Vec<_> = WorkSchedule::create_batch(conn, work_schedules)
+            .await
+            .expect("Failed to create work schedules");
+        work_schedule_group.id
+    }
+
+    #[rstest]
+    async fn work_schedule_get() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let group_id =
+            create_work_schedule_group(pool.get_ok().deref_mut(), "work schedule group get").await;
+
+        let request = TestRequest::get()
+            .uri(&format!("/work_schedules/{group_id}"))
+            .to_request();
+        let group = app
+            .fetch(request)
+            .assert_status(StatusCode::OK)
+            .json_into::<WorkScheduleGroupWithSchedules>();
+
+        assert_eq!(group.group.id, group_id);
+        assert_eq!(group.group.name, "work schedule group get");
+        let obj_ids: Vec<_> = group
+            .work_schedules
+            .iter()
+            .map(|work_schedule| work_schedule.obj_id.as_str())
+            .collect();
+        assert_eq!(obj_ids, vec!["morning", "afternoon"]);
+    }
+
+    #[rstest]
+    async fn work_schedule_list() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let group_id =
+            create_work_schedule_group(pool.get_ok().deref_mut(), "work schedule group list").await;
+
+        let request = TestRequest::get()
+            .uri("/work_schedules?page_size=1000")
+            .to_request();
+        let response = app
+            .fetch(request)
+            .assert_status(StatusCode::OK)
+            .json_into::<WorkScheduleGroupListResponse>();
+
+        assert!(response.results.iter().any(|group| group.id == group_id));
+    }
+
+    #[rstest]
+    async fn work_schedule_update() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let group_id =
+            create_work_schedule_group(pool.get_ok().deref_mut(), "work schedule group update")
+                .await;
+
+        let request = TestRequest::put()
+            .uri(&format!("/work_schedules/{group_id}"))
+            .set_json(json!({
+                "work_schedule_group_name": "work schedule group updated",
+                "work_schedules": [{
+                    "start_date_time": "2024-01-02T08:00:00",
+                    "end_date_time": "2024-01-02T09:00:00",
+                    "track_ranges": [],
+                    "obj_id": "replacement",
+                    "work_schedule_type": "TRACK"
+                }]
+            }))
+            .to_request();
+        let group = app
+            .fetch(request)
+            .assert_status(StatusCode::OK)
+            .json_into::<WorkScheduleGroupWithSchedules>();
+
+        assert_eq!(group.group.name, "work schedule group updated");
+        assert_eq!(group.work_schedules.len(), 1);
+        assert_eq!(group.work_schedules[0].obj_id, "replacement");
+    }
+
+    #[rstest]
+    async fn work_schedule_delete() {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let group_id =
+            create_work_schedule_group(pool.get_ok().deref_mut(), "work schedule group delete")
+                .await;
+
+        let request = TestRequest::delete()
+            .uri(&format!("/work_schedules/{group_id}"))
+            .to_request();
+        app.fetch(request).assert_status(StatusCode::NO_CONTENT);
+
+        let exists = WorkScheduleGroup::exists(pool.get_ok().deref_mut(), group_id)
+            .await
+            .expect("Failed to check the work schedule group existence");
+        assert!(!exists);
+
+        let request = TestRequest::delete()
+            .uri(&format!("/work_schedules/{group_id}"))
+            .to_request();
+        let response = app
+            .fetch(request)
+            .assert_status(StatusCode::NOT_FOUND)
+            .json_into::<InternalError>();
+        assert_eq!(&response.error_type, "editoast:work_schedule:NotFound");
+    }
+
+    #[rstest]
+    #[case::whole_day(json!({
+        "start_date_time": "2024-01-01T00:00:00",
+        "end_date_time": "2024-01-02T00:00:00",
+    }), vec!["morning", "afternoon"])]
+    #[case::afternoon(json!({
+        "start_date_time": "2024-01-01T15:00:00",
+        "end_date_time": "2024-01-01T18:00:00",
+    }), vec!["afternoon"])]
+    #[case::touching_end(json!({
+        "start_date_time": "2024-01-01T09:00:00",
+        "end_date_time": "2024-01-01T14:00:00",
+    }), vec![])]
+    #[case::track_sections(json!({
+        "start_date_time": "2024-01-01T00:00:00",
+        "end_date_time": "2024-01-02T00:00:00",
+        "track_sections": ["TA0", "TA2"],
+    }), vec!["morning"])]
+    async fn work_schedule_query(
+        #[case] query_form: serde_json::Value,
+        #[case] expected_obj_ids: Vec<&str>,
+    ) {
+        let app = TestAppBuilder::default_app();
+        let pool = app.db_pool();
+        let group_id =
+            create_work_schedule_group(pool.get_ok().deref_mut(), "work schedule group query")
+                .await;
+
+        let request = TestRequest::post()
+            .uri("/work_schedules/query")
+            .set_json(query_form)
+            .to_request();
+        let work_schedules = app
+            .fetch(request)
+            .assert_status(StatusCode::OK)
+            .json_into::<Vec<WorkSchedule>>();
+
+        let obj_ids: Vec<_> = work_schedules
+            .iter()
+            .filter(|work_schedule| work_schedule.work_schedule_group_id == group_id)
+            .map(|work_schedule| work_schedule.obj_id.as_str())
+            .collect();
+        assert_eq!(obj_ids, expected_obj_ids);
+    }
+
+    #[rstest]
+    async fn work_schedule_query_invalid_time_window() {
+        let app = TestAppBuilder::default_app();
+
+        let request = TestRequest::post()
+            .uri("/work_schedules/query")
+            .set_json(json!({
+                "start_date_time": "2024-01-01T10:00:00",
+                "end_date_time": "2024-01-01T08:00:00",
+            }))
+            .to_request();
+        let response = app
+            .fetch(request)
+            .assert_status(StatusCode::BAD_REQUEST)
+            .json_into::<InternalError>();
+
+        assert_eq!(
+            &response.error_type,
+            "editoast:work_schedule:InvalidTimeWindow"
+        );
+    }
 }
@@ -16,6 +16,7 @@ export const addTagTypes = [
   'sprites',
   'stdcm',
   'timetable',
+  'train_composition',
   'train_schedule',
   'pathfindingv2',
   'scenariosv2',
@@ -126,7 +127,11 @@ const injectedRtkApi = api
         query: (queryArg) => ({
           url: `/infra/refresh`,
           method: 'POST',
-          params: { force: queryArg.force, infras: queryArg.infras },
+          params: {
+            force: queryArg.force,
+            infras: queryArg.infras,
+            warm_tiles: queryArg.warmTiles,
+          },
         }),
         invalidatesTags: ['infra'],
       }),
@@ -186,6 +191,15 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['infra'],
       }),
+      getInfraByInfraIdDiffAndOtherInfraId: build.query<
+        GetInfraByInfraIdDiffAndOtherInfraIdApiResponse,
+        GetInfraByInfraIdDiffAndOtherInfraIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/diff/${queryArg.otherInfraId}`,
+        }),
+        providesTags: ['infra'],
+      }),
       getInfraByInfraIdErrors: build.query<
         GetInfraByInfraIdErrorsApiResponse,
         GetInfraByInfraIdErrorsApiArg
@@ -202,6 +216,38 @@ const injectedRtkApi = api
         }),
         providesTags: ['infra'],
       }),
+      getInfraByInfraIdHistory: build.query<
+        GetInfraByInfraIdHistoryApiResponse,
+        GetInfraByInfraIdHistoryApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/history/`,
+          params: { page: queryArg.page, page_size: queryArg.pageSize },
+        }),
+        providesTags: ['infra'],
+      }),
+      postInfraByInfraIdHistoryRedo: build.mutation<
+        PostInfraByInfraIdHistoryRedoApiResponse,
+        PostInfraByInfraIdHistoryRedoApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/history/redo`,
+          method: 'POST',
+          params: { count: queryArg.count },
+        }),
+        invalidatesTags: ['infra'],
+      }),
+      postInfraByInfraIdHistoryUndo: build.mutation<
+        PostInfraByInfraIdHistoryUndoApiResponse,
+        PostInfraByInfraIdHistoryUndoApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/history/undo`,
+          method: 'POST',
+          params: { count: queryArg.count },
+        }),
+        invalidatesTags: ['infra'],
+      }),
       getInfraByInfraIdLinesAndLineCodeBbox: build.query<
         GetInfraByInfraIdLinesAndLineCodeBboxApiResponse,
         GetInfraByInfraIdLinesAndLineCodeBboxApiArg
@@ -225,6 +271,18 @@ const injectedRtkApi = api
         query: (queryArg) => ({ url: `/infra/${queryArg.infraId}/lock`, method: 'POST' }),
         invalidatesTags: ['infra'],
       }),
+      postInfraByInfraIdMerge: build.mutation<
+        PostInfraByInfraIdMergeApiResponse,
+        PostInfraByInfraIdMergeApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/merge`,
+          method: 'POST',
+          body: queryArg.mergeRequest,
+          params: { dry_run: queryArg.dryRun },
+        }),
+        invalidatesTags: ['infra'],
+      }),
       postInfraByInfraIdObjectsAndObjectType: build.mutation<
         PostInfraByInfraIdObjectsAndObjectTypeApiResponse,
         PostInfraByInfraIdObjectsAndObjectTypeApiArg
@@ -285,6 +343,55 @@ const injectedRtkApi = api
         }),
         providesTags: ['infra', 'routes'],
       }),
+      getInfraByInfraIdSnapshots: build.query<
+        GetInfraByInfraIdSnapshotsApiResponse,
+        GetInfraByInfraIdSnapshotsApiArg
+      >({
+        query: (queryArg) => ({ url: `/infra/${queryArg.infraId}/snapshots/` }),
+        providesTags: ['infra'],
+      }),
+      postInfraByInfraIdSnapshots: build.mutation<
+        PostInfraByInfraIdSnapshotsApiResponse,
+        PostInfraByInfraIdSnapshotsApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/snapshots/`,
+          method: 'POST',
+          body: queryArg.infraSnapshotForm,
+        }),
+        invalidatesTags: ['infra'],
+      }),
+      deleteInfraByInfraIdSnapshotsAndSnapshotId: build.mutation<
+        DeleteInfraByInfraIdSnapshotsAndSnapshotIdApiResponse,
+        DeleteInfraByInfraIdSnapshotsAndSnapshotIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/snapshots/${queryArg.snapshotId}/`,
+          method: 'DELETE',
+        }),
+        invalidatesTags: ['infra'],
+      }),
+      postInfraByInfraIdSnapshotsAndSnapshotIdClone: build.mutation<
+        PostInfraByInfraIdSnapshotsAndSnapshotIdCloneApiResponse,
+        PostInfraByInfraIdSnapshotsAndSnapshotIdCloneApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/snapshots/${queryArg.snapshotId}/clone`,
+          method: 'POST',
+          params: { name: queryArg.name },
+        }),
+        invalidatesTags: ['infra'],
+      }),
+      postInfraByInfraIdSnapshotsAndSnapshotIdRestore: build.mutation<
+        PostInfraByInfraIdSnapshotsAndSnapshotIdRestoreApiResponse,
+        PostInfraByInfraIdSnapshotsAndSnapshotIdRestoreApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/infra/${queryArg.infraId}/snapshots/${queryArg.snapshotId}/restore`,
+          method: 'POST',
+        }),
+        invalidatesTags: ['infra'],
+      }),
       getInfraByInfraIdSpeedLimitTags: build.query<
         GetInfraByInfraIdSpeedLimitTagsApiResponse,
         GetInfraByInfraIdSpeedLimitTagsApiArg
@@ -333,7 +440,11 @@ const injectedRtkApi = api
       >({
         query: (queryArg) => ({
           url: `/layers/layer/${queryArg.layerSlug}/mvt/${queryArg.viewSlug}/`,
-          params: { infra: queryArg.infra },
+          params: {
+            infra: queryArg.infra,
+            start_date_time: queryArg.startDateTime,
+            end_date_time: queryArg.endDateTime,
+          },
         }),
         providesTags: ['layers'],
       }),
@@ -343,7 +454,11 @@ const injectedRtkApi = api
       >({
         query: (queryArg) => ({
           url: `/layers/tile/${queryArg.layerSlug}/${queryArg.viewSlug}/${queryArg.z}/${queryArg.x}/${queryArg.y}/`,
-          params: { infra: queryArg.infra },
+          params: {
+            infra: queryArg.infra,
+            start_date_time: queryArg.startDateTime,
+            end_date_time: queryArg.endDateTime,
+          },
         }),
         providesTags: ['layers'],
       }),
@@ -587,6 +702,17 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['rolling_stock'],
       }),
+      postRollingStockCompare: build.query<
+        PostRollingStockCompareApiResponse,
+        PostRollingStockCompareApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/rolling_stock/compare`,
+          method: 'POST',
+          body: queryArg.rollingStockComparisonForm,
+        }),
+        providesTags: ['rolling_stock'],
+      }),
       getRollingStockNameByRollingStockName: build.query<
         GetRollingStockNameByRollingStockNameApiResponse,
         GetRollingStockNameByRollingStockNameApiArg
@@ -630,6 +756,13 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['rolling_stock'],
       }),
+      getRollingStockByRollingStockIdCheck: build.query<
+        GetRollingStockByRollingStockIdCheckApiResponse,
+        GetRollingStockByRollingStockIdCheckApiArg
+      >({
+        query: (queryArg) => ({ url: `/rolling_stock/${queryArg.rollingStockId}/check` }),
+        providesTags: ['rolling_stock'],
+      }),
       postRollingStockByRollingStockIdLivery: build.mutation<
         PostRollingStockByRollingStockIdLiveryApiResponse,
         PostRollingStockByRollingStockIdLiveryApiArg
@@ -652,6 +785,32 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['rolling_stock'],
       }),
+      getRollingStockByRollingStockIdRevisions: build.query<
+        GetRollingStockByRollingStockIdRevisionsApiResponse,
+        GetRollingStockByRollingStockIdRevisionsApiArg
+      >({
+        query: (queryArg) => ({ url: `/rolling_stock/${queryArg.rollingStockId}/revisions/` }),
+        providesTags: ['rolling_stock'],
+      }),
+      getRollingStockByRollingStockIdRevisionsCompare: build.query<
+        GetRollingStockByRollingStockIdRevisionsCompareApiResponse,
+        GetRollingStockByRollingStockIdRevisionsCompareApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/rolling_stock/${queryArg.rollingStockId}/revisions/compare/`,
+          params: { from: queryArg.from, to: queryArg.to },
+        }),
+        providesTags: ['rolling_stock'],
+      }),
+      getRollingStockByRollingStockIdRevisionsAndVersion: build.query<
+        GetRollingStockByRollingStockIdRevisionsAndVersionApiResponse,
+        GetRollingStockByRollingStockIdRevisionsAndVersionApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/rolling_stock/${queryArg.rollingStockId}/revisions/${queryArg.version}/`,
+        }),
+        providesTags: ['rolling_stock'],
+      }),
       postSearch: build.mutation<PostSearchApiResponse, PostSearchApiArg>({
         query: (queryArg) => ({
           url: `/search`,
@@ -708,6 +867,46 @@ const injectedRtkApi = api
         query: (queryArg) => ({ url: `/timetable/${queryArg.id}/conflicts` }),
         providesTags: ['timetable'],
       }),
+      postTrainComposition: build.mutation<
+        PostTrainCompositionApiResponse,
+        PostTrainCompositionApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/train_composition/`,
+          method: 'POST',
+          body: queryArg.trainCompositionForm,
+        }),
+        invalidatesTags: ['train_composition'],
+      }),
+      getTrainCompositionByTrainCompositionId: build.query<
+        GetTrainCompositionByTrainCompositionIdApiResponse,
+        GetTrainCompositionByTrainCompositionIdApiArg
+      >({
+        query: (queryArg) => ({ url: `/train_composition/${queryArg.trainCompositionId}/` }),
+        providesTags: ['train_composition'],
+      }),
+      deleteTrainCompositionByTrainCompositionId: build.mutation<
+        DeleteTrainCompositionByTrainCompositionIdApiResponse,
+        DeleteTrainCompositionByTrainCompositionIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/train_composition/${queryArg.trainCompositionId}/`,
+          method: 'DELETE',
+          params: { force: queryArg.force },
+        }),
+        invalidatesTags: ['train_composition'],
+      }),
+      putTrainCompositionByTrainCompositionId: build.mutation<
+        PutTrainCompositionByTrainCompositionIdApiResponse,
+        PutTrainCompositionByTrainCompositionIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/train_composition/${queryArg.trainCompositionId}/`,
+          method: 'PUT',
+          body: queryArg.trainCompositionForm,
+        }),
+        invalidatesTags: ['train_composition'],
+      }),
       deleteTrainSchedule: build.mutation<
         DeleteTrainScheduleApiResponse,
         DeleteTrainScheduleApiArg
@@ -883,8 +1082,45 @@ const injectedRtkApi = api
       >({
         query: (queryArg) => ({
           url: `/v2/timetable/${queryArg.id}/conflicts`,
+          params: { infra_id: queryArg.infraId, incremental: queryArg.incremental },
+        }),
+        providesTags: ['timetablev2'],
+      }),
+      postV2TimetableByIdConflictsExplanation: build.mutation<
+        PostV2TimetableByIdConflictsExplanationApiResponse,
+        PostV2TimetableByIdConflictsExplanationApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/timetable/${queryArg.id}/conflicts/explanation`,
+          method: 'POST',
+          body: queryArg.conflictV2,
           params: { infra_id: queryArg.infraId },
         }),
+        invalidatesTags: ['timetablev2'],
+      }),
+      postV2TimetableByIdDuplicate: build.mutation<
+        PostV2TimetableByIdDuplicateApiResponse,
+        PostV2TimetableByIdDuplicateApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/timetable/${queryArg.id}/duplicate`,
+          method: 'POST',
+          params: { infra_id: queryArg.infraId },
+        }),
+        invalidatesTags: ['timetablev2'],
+      }),
+      getV2TimetableByIdPassingTimes: build.query<
+        GetV2TimetableByIdPassingTimesApiResponse,
+        GetV2TimetableByIdPassingTimesApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/timetable/${queryArg.id}/passing_times/`,
+          params: {
+            infra_id: queryArg.infraId,
+            reference_train_id: queryArg.referenceTrainId,
+            format: queryArg.format,
+          },
+        }),
         providesTags: ['timetablev2'],
       }),
       postV2TimetableByIdStdcm: build.mutation<
@@ -910,6 +1146,17 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['timetablev2', 'train_schedulev2'],
       }),
+      patchV2TimetableByIdTrainSchedule: build.mutation<
+        PatchV2TimetableByIdTrainScheduleApiResponse,
+        PatchV2TimetableByIdTrainScheduleApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/timetable/${queryArg.id}/train_schedule`,
+          method: 'PATCH',
+          body: queryArg.trainScheduleBatchUpdateForm,
+        }),
+        invalidatesTags: ['timetablev2', 'train_schedulev2'],
+      }),
       postV2TrainSchedule: build.query<PostV2TrainScheduleApiResponse, PostV2TrainScheduleApiArg>({
         query: (queryArg) => ({ url: `/v2/train_schedule/`, method: 'POST', body: queryArg.body }),
         providesTags: ['train_schedulev2'],
@@ -947,6 +1194,18 @@ const injectedRtkApi = api
         }),
         providesTags: ['train_schedulev2'],
       }),
+      postV2TrainScheduleValidate: build.query<
+        PostV2TrainScheduleValidateApiResponse,
+        PostV2TrainScheduleValidateApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/train_schedule/validate`,
+          method: 'POST',
+          body: queryArg.trainScheduleBase,
+          params: { infra_id: queryArg.infraId },
+        }),
+        providesTags: ['train_schedulev2'],
+      }),
       getV2TrainScheduleById: build.query<
         GetV2TrainScheduleByIdApiResponse,
         GetV2TrainScheduleByIdApiArg
@@ -985,12 +1244,58 @@ const injectedRtkApi = api
         }),
         providesTags: ['train_schedulev2'],
       }),
+      postV2TrainSchedulePattern: build.mutation<
+        PostV2TrainSchedulePatternApiResponse,
+        PostV2TrainSchedulePatternApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/train_schedule_pattern/`,
+          method: 'POST',
+          body: queryArg.trainSchedulePatternForm,
+        }),
+        invalidatesTags: ['timetablev2', 'train_schedulev2'],
+      }),
+      getV2TrainSchedulePatternById: build.query<
+        GetV2TrainSchedulePatternByIdApiResponse,
+        GetV2TrainSchedulePatternByIdApiArg
+      >({
+        query: (queryArg) => ({ url: `/v2/train_schedule_pattern/${queryArg.id}/` }),
+        providesTags: ['train_schedulev2'],
+      }),
+      putV2TrainSchedulePatternById: build.mutation<
+        PutV2TrainSchedulePatternByIdApiResponse,
+        PutV2TrainSchedulePatternByIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/train_schedule_pattern/${queryArg.id}/`,
+          method: 'PUT',
+          body: queryArg.trainSchedulePatternForm,
+        }),
+        invalidatesTags: ['train_schedulev2'],
+      }),
+      deleteV2TrainSchedulePatternById: build.mutation<
+        DeleteV2TrainSchedulePatternByIdApiResponse,
+        DeleteV2TrainSchedulePatternByIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/v2/train_schedule_pattern/${queryArg.id}/`,
+          method: 'DELETE',
+        }),
+        invalidatesTags: ['train_schedulev2'],
+      }),
       getVersion: build.query<GetVersionApiResponse, GetVersionApiArg>({
         query: () => ({ url: `/version` }),
       }),
       getVersionCore: build.query<GetVersionCoreApiResponse, GetVersionCoreApiArg>({
         query: () => ({ url: `/version/core` }),
       }),
+      getWorkSchedules: build.query<GetWorkSchedulesApiResponse, GetWorkSchedulesApiArg>({
+        query: (queryArg) => ({
+          url: `/work_schedules/`,
+          params: { page: queryArg.page, page_size: queryArg.pageSize },
+        }),
+        providesTags: ['work_schedules'],
+      }),
       postWorkSchedules: build.mutation<PostWorkSchedulesApiResponse, PostWorkSchedulesApiArg>({
         query: (queryArg) => ({
           url: `/work_schedules/`,
@@ -999,6 +1304,45 @@ const injectedRtkApi = api
         }),
         invalidatesTags: ['work_schedules'],
       }),
+      postWorkSchedulesQuery: build.query<
+        PostWorkSchedulesQueryApiResponse,
+        PostWorkSchedulesQueryApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/work_schedules/query`,
+          method: 'POST',
+          body: queryArg.workScheduleQueryForm,
+        }),
+        providesTags: ['work_schedules'],
+      }),
+      getWorkSchedulesByWorkScheduleGroupId: build.query<
+        GetWorkSchedulesByWorkScheduleGroupIdApiResponse,
+        GetWorkSchedulesByWorkScheduleGroupIdApiArg
+      >({
+        query: (queryArg) => ({ url: `/work_schedules/${queryArg.workScheduleGroupId}/` }),
+        providesTags: ['work_schedules'],
+      }),
+      putWorkSchedulesByWorkScheduleGroupId: build.mutation<
+        PutWorkSchedulesByWorkScheduleGroupIdApiResponse,
+        PutWorkSchedulesByWorkScheduleGroupIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/work_schedules/${queryArg.workScheduleGroupId}/`,
+          method: 'PUT',
+          body: queryArg.workScheduleCreateForm,
+        }),
+        invalidatesTags: ['work_schedules'],
+      }),
+      deleteWorkSchedulesByWorkScheduleGroupId: build.mutation<
+        DeleteWorkSchedulesByWorkScheduleGroupIdApiResponse,
+        DeleteWorkSchedulesByWorkScheduleGroupIdApiArg
+      >({
+        query: (queryArg) => ({
+          url: `/work_schedules/${queryArg.workScheduleGroupId}/`,
+          method: 'DELETE',
+        }),
+        invalidatesTags: ['work_schedules'],
+      }),
     }),
     overrideExisting: false,
   });
@@ -1084,6 +1428,8 @@ export type PostInfraRefreshApiArg = {
     
     If not provided, all available infras will be refreshed. */
   infras?: number[];
+  /** Whether the map layer tiles of the refreshed infras should be generated and cached in the background */
+  warmTiles?: boolean;
 };
 export type GetInfraVoltagesApiResponse = /** status 200 Voltages list */ string[];
 export type GetInfraVoltagesApiArg = void;
@@ -1136,6 +1482,16 @@ export type PostInfraByInfraIdCloneApiArg = {
   /** The name of the new infra */
   name: string;
 };
+export type GetInfraByInfraIdDiffAndOtherInfraIdApiResponse =
+  /** status 200 The changes by object type */ {
+    [key: string]: ObjectsDiff;
+  };
+export type GetInfraByInfraIdDiffAndOtherInfraIdApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** The infra to compare with */
+  otherInfraId: number;
+};
 export type GetInfraByInfraIdErrorsApiResponse =
   /** status 200 A paginated list of errors */ PaginationStats & {
     results: {
@@ -1154,6 +1510,32 @@ export type GetInfraByInfraIdErrorsApiArg = {
   /** Filter errors and warnings related to a given object */
   objectId?: string | null;
 };
+export type GetInfraByInfraIdHistoryApiResponse =
+  /** status 200 The edits of the infra, paginated */ PaginationStats & {
+    results: InfraEdit[];
+  };
+export type GetInfraByInfraIdHistoryApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  page?: number;
+  pageSize?: number | null;
+};
+export type PostInfraByInfraIdHistoryRedoApiResponse =
+  /** status 200 The applied edits, the earliest first */ InfraEdit[];
+export type PostInfraByInfraIdHistoryRedoApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** The number of edits to undo or redo */
+  count?: number;
+};
+export type PostInfraByInfraIdHistoryUndoApiResponse =
+  /** status 200 The reverted edits, the latest first */ InfraEdit[];
+export type PostInfraByInfraIdHistoryUndoApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** The number of edits to undo or redo */
+  count?: number;
+};
 export type GetInfraByInfraIdLinesAndLineCodeBboxApiResponse =
   /** status 200 The BBox of the line */ BoundingBox;
 export type GetInfraByInfraIdLinesAndLineCodeBboxApiArg = {
@@ -1172,6 +1554,15 @@ export type PostInfraByInfraIdLockApiArg = {
   /** An existing infra ID */
   infraId: number;
 };
+export type PostInfraByInfraIdMergeApiResponse =
+  /** status 200 The applied operations and the conflicts */ MergeReport;
+export type PostInfraByInfraIdMergeApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** Only compute the merge report, without editing the infra */
+  dryRun?: boolean;
+  mergeRequest: MergeRequest;
+};
 export type PostInfraByInfraIdObjectsAndObjectTypeApiResponse =
   /** status 200 The list of objects */ InfraObjectWithGeometry[];
 export type PostInfraByInfraIdObjectsAndObjectTypeApiArg = {
@@ -1242,6 +1633,43 @@ export type GetInfraByInfraIdRoutesAndWaypointTypeWaypointIdApiArg = {
   /** Waypoint ID */
   waypointId: string;
 };
+export type GetInfraByInfraIdSnapshotsApiResponse =
+  /** status 200 The snapshots of the infra */ InfraSnapshot[];
+export type GetInfraByInfraIdSnapshotsApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+};
+export type PostInfraByInfraIdSnapshotsApiResponse =
+  /** status 201 The created snapshot */ InfraSnapshot;
+export type PostInfraByInfraIdSnapshotsApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  infraSnapshotForm: InfraSnapshotForm;
+};
+export type DeleteInfraByInfraIdSnapshotsAndSnapshotIdApiResponse = unknown;
+export type DeleteInfraByInfraIdSnapshotsAndSnapshotIdApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** An existing snapshot ID */
+  snapshotId: number;
+};
+export type PostInfraByInfraIdSnapshotsAndSnapshotIdCloneApiResponse = unknown;
+export type PostInfraByInfraIdSnapshotsAndSnapshotIdCloneApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** An existing snapshot ID */
+  snapshotId: number;
+  /** The name of the new infra */
+  name: string;
+};
+export type PostInfraByInfraIdSnapshotsAndSnapshotIdRestoreApiResponse =
+  /** status 200 The restored infra */ Infra;
+export type PostInfraByInfraIdSnapshotsAndSnapshotIdRestoreApiArg = {
+  /** An existing infra ID */
+  infraId: number;
+  /** An existing snapshot ID */
+  snapshotId: number;
+};
 export type GetInfraByInfraIdSpeedLimitTagsApiResponse =
   /** status 200 List all speed limit tags */ string[];
 export type GetInfraByInfraIdSpeedLimitTagsApiArg = {
@@ -1287,12 +1715,20 @@ export type GetLayersLayerByLayerSlugMvtAndViewSlugApiResponse =
   };
 export type GetLayersLayerByLayerSlugMvtAndViewSlugApiArg = {
   infra: number;
+  /** Only keep the features ending after this date */
+  startDateTime?: string | null;
+  /** Only keep the features starting before this date */
+  endDateTime?: string | null;
   layerSlug: string;
   viewSlug: string;
 };
 export type GetLayersTileByLayerSlugAndViewSlugZXYApiResponse = unknown;
 export type GetLayersTileByLayerSlugAndViewSlugZXYApiArg = {
   infra: number;
+  /** Only keep the features ending after this date */
+  startDateTime?: string | null;
+  /** Only keep the features starting before this date */
+  endDateTime?: string | null;
   layerSlug: string;
   viewSlug: string;
   x: number;
@@ -1472,6 +1908,11 @@ export type PostRollingStockApiArg = {
   locked?: boolean;
   rollingStockForm: RollingStockForm;
 };
+export type PostRollingStockCompareApiResponse =
+  /** status 200 The run of each rolling stock on the path */ RollingStockComparison[];
+export type PostRollingStockCompareApiArg = {
+  rollingStockComparisonForm: RollingStockComparisonForm;
+};
 export type GetRollingStockNameByRollingStockNameApiResponse =
   /** status 200 The requested rolling stock */ RollingStockWithLiveries;
 export type GetRollingStockNameByRollingStockNameApiArg = {
@@ -1498,6 +1939,11 @@ export type PatchRollingStockByRollingStockIdApiArg = {
   rollingStockId: number;
   rollingStockForm: RollingStockForm;
 };
+export type GetRollingStockByRollingStockIdCheckApiResponse =
+  /** status 200 The errors and warnings of the rolling stock */ RollingStockCheckReport;
+export type GetRollingStockByRollingStockIdCheckApiArg = {
+  rollingStockId: number;
+};
 export type PostRollingStockByRollingStockIdLiveryApiResponse =
   /** status 200 The created rolling stock */ RollingStockLivery;
 export type PostRollingStockByRollingStockIdLiveryApiArg = {
@@ -1510,6 +1956,27 @@ export type PatchRollingStockByRollingStockIdLockedApiArg = {
   rollingStockId: number;
   rollingStockLockedUpdateForm: RollingStockLockedUpdateForm;
 };
+export type GetRollingStockByRollingStockIdRevisionsApiResponse =
+  /** status 200 The revisions of the rolling stock */ RollingStockRevisionInfo[];
+export type GetRollingStockByRollingStockIdRevisionsApiArg = {
+  rollingStockId: number;
+};
+export type GetRollingStockByRollingStockIdRevisionsCompareApiResponse =
+  /** status 200 The fields which changed between the two versions */ RollingStockFieldDiff[];
+export type GetRollingStockByRollingStockIdRevisionsCompareApiArg = {
+  rollingStockId: number;
+  /** The version compared from */
+  from: number;
+  /** The version compared to */
+  to: number;
+};
+export type GetRollingStockByRollingStockIdRevisionsAndVersionApiResponse =
+  /** status 200 The rolling stock at this version */ RollingStock;
+export type GetRollingStockByRollingStockIdRevisionsAndVersionApiArg = {
+  rollingStockId: number;
+  /** A version of the rolling stock */
+  version: number;
+};
 export type PostSearchApiResponse = /** status 200 The search results */ SearchResultItem[];
 export type PostSearchApiArg = {
   page?: number;
@@ -1583,6 +2050,31 @@ export type GetTimetableByIdConflictsApiArg = {
   /** Timetable id */
   id: number;
 };
+export type PostTrainCompositionApiResponse =
+  /** status 200 The created train composition */ TrainComposition;
+export type PostTrainCompositionApiArg = {
+  trainCompositionForm: TrainCompositionForm;
+};
+export type GetTrainCompositionByTrainCompositionIdApiResponse =
+  /** status 200 The train composition */ TrainComposition;
+export type GetTrainCompositionByTrainCompositionIdApiArg = {
+  /** A train composition ID */
+  trainCompositionId: number;
+};
+export type DeleteTrainCompositionByTrainCompositionIdApiResponse = unknown;
+export type DeleteTrainCompositionByTrainCompositionIdApiArg = {
+  /** A train composition ID */
+  trainCompositionId: number;
+  /** Delete the train composition even if train schedules are made of it */
+  force?: boolean;
+};
+export type PutTrainCompositionByTrainCompositionIdApiResponse =
+  /** status 200 The updated train composition */ TrainComposition;
+export type PutTrainCompositionByTrainCompositionIdApiArg = {
+  /** A train composition ID */
+  trainCompositionId: number;
+  trainCompositionForm: TrainCompositionForm;
+};
 export type DeleteTrainScheduleApiResponse = unknown;
 export type DeleteTrainScheduleApiArg = {
   body: {
@@ -1726,6 +2218,35 @@ export type GetV2TimetableByIdConflictsApiArg = {
   /** A timetable ID */
   id: number;
   infraId: number;
+  /** Only check again the trains which changed since the last computation of the conflicts */
+  incremental?: boolean;
+};
+export type PostV2TimetableByIdConflictsExplanationApiResponse =
+  /** status 200 The explanation of the conflict */ ConflictExplanation;
+export type PostV2TimetableByIdConflictsExplanationApiArg = {
+  /** A timetable ID */
+  id: number;
+  infraId: number;
+  conflictV2: ConflictV2;
+};
+export type PostV2TimetableByIdDuplicateApiResponse =
+  /** status 200 The new timetable */ TimetableDuplicateResult;
+export type PostV2TimetableByIdDuplicateApiArg = {
+  /** A timetable ID */
+  id: number;
+  /** The infra the paths of the trains are checked on */
+  infraId: number;
+};
+export type GetV2TimetableByIdPassingTimesApiResponse =
+  /** status 200 The passing times table as a CSV or XLSX file */ Blob;
+export type GetV2TimetableByIdPassingTimesApiArg = {
+  /** A timetable ID */
+  id: number;
+  infraId: number;
+  /** The train giving the operational points of the table and their order */
+  referenceTrainId: number;
+  /** The file format of the table */
+  format?: 'csv' | 'xlsx';
 };
 export type PostV2TimetableByIdStdcmApiResponse =
   /** status 201 The simulation result */
@@ -1749,6 +2270,8 @@ export type PostV2TimetableByIdStdcmApiArg = {
   id: number;
   body: {
     comfort: Comfort;
+    /** The composition the train is made of, which must be made of the rolling stock */
+    composition_id?: number | null;
     /** Can be a percentage `X%`, a time in minutes per 100 kilometer `Xmin/100km` or `None` */
     margin?: string | null;
     /** By how long we can shift the departure time in milliseconds
@@ -1757,6 +2280,8 @@ export type PostV2TimetableByIdStdcmApiArg = {
     /** Specifies how long the total run time can be in milliseconds */
     maximum_run_time?: number | null;
     rolling_stock_id: number;
+    /** The version of the rolling stock to use, its current version if not specified */
+    rolling_stock_version?: number | null;
     /** Train categories for speed limits */
     speed_limit_tags?: string | null;
     /** Deprecated, first step arrival time should be used instead */
@@ -1781,6 +2306,13 @@ export type PostV2TimetableByIdTrainScheduleApiArg = {
   id: number;
   body: TrainScheduleBase[];
 };
+export type PatchV2TimetableByIdTrainScheduleApiResponse =
+  /** status 200 The ids of the updated train schedules */ TrainScheduleBatchUpdateResult;
+export type PatchV2TimetableByIdTrainScheduleApiArg = {
+  /** A timetable ID */
+  id: number;
+  trainScheduleBatchUpdateForm: TrainScheduleBatchUpdateForm;
+};
 export type PostV2TrainScheduleApiResponse =
   /** status 200 Retrieve a list of train schedule */ TrainScheduleResult[];
 export type PostV2TrainScheduleApiArg = {
@@ -1810,6 +2342,12 @@ export type PostV2TrainScheduleSimulationSummaryApiArg = {
     infra_id: number;
   };
 };
+export type PostV2TrainScheduleValidateApiResponse =
+  /** status 200 The errors and warnings of the train schedule */ TrainScheduleValidationReport;
+export type PostV2TrainScheduleValidateApiArg = {
+  infraId: number;
+  trainScheduleBase: TrainScheduleBase;
+};
 export type GetV2TrainScheduleByIdApiResponse =
   /** status 200 The train schedule */ TrainScheduleResult;
 export type GetV2TrainScheduleByIdApiArg = {
@@ -1836,15 +2374,69 @@ export type GetV2TrainScheduleByIdSimulationApiArg = {
   id: number;
   infraId: number;
 };
+export type PostV2TrainSchedulePatternApiResponse =
+  /** status 200 The created pattern */ TrainSchedulePatternResult;
+export type PostV2TrainSchedulePatternApiArg = {
+  trainSchedulePatternForm: TrainSchedulePatternForm;
+};
+export type GetV2TrainSchedulePatternByIdApiResponse =
+  /** status 200 The train schedule pattern */ TrainSchedulePatternResult;
+export type GetV2TrainSchedulePatternByIdApiArg = {
+  /** A train schedule pattern ID */
+  id: number;
+};
+export type PutV2TrainSchedulePatternByIdApiResponse =
+  /** status 200 The updated pattern */ TrainSchedulePatternResult;
+export type PutV2TrainSchedulePatternByIdApiArg = {
+  /** A train schedule pattern ID */
+  id: number;
+  trainSchedulePatternForm: TrainSchedulePatternForm;
+};
+export type DeleteV2TrainSchedulePatternByIdApiResponse = unknown;
+export type DeleteV2TrainSchedulePatternByIdApiArg = {
+  /** A train schedule pattern ID */
+  id: number;
+};
 export type GetVersionApiResponse = /** status 200 Return the service version */ Version;
 export type GetVersionApiArg = void;
 export type GetVersionCoreApiResponse = /** status 200 Return the core service version */ Version;
 export type GetVersionCoreApiArg = void;
+export type GetWorkSchedulesApiResponse =
+  /** status 200 The work schedule groups, paginated */ PaginationStats & {
+    results: WorkScheduleGroup[];
+  };
+export type GetWorkSchedulesApiArg = {
+  page?: number;
+  pageSize?: number | null;
+};
 export type PostWorkSchedulesApiResponse =
   /** status 201 The id of the created work schedule group */ WorkScheduleCreateResponse;
 export type PostWorkSchedulesApiArg = {
   workScheduleCreateForm: WorkScheduleCreateForm;
 };
+export type PostWorkSchedulesQueryApiResponse =
+  /** status 200 The work schedules matching the query */ WorkSchedule[];
+export type PostWorkSchedulesQueryApiArg = {
+  workScheduleQueryForm: WorkScheduleQueryForm;
+};
+export type GetWorkSchedulesByWorkScheduleGroupIdApiResponse =
+  /** status 200 The work schedule group */ WorkScheduleGroupWithSchedules;
+export type GetWorkSchedulesByWorkScheduleGroupIdApiArg = {
+  /** A work schedule group ID */
+  workScheduleGroupId: number;
+};
+export type PutWorkSchedulesByWorkScheduleGroupIdApiResponse =
+  /** status 200 The updated work schedule group */ WorkScheduleGroupWithSchedules;
+export type PutWorkSchedulesByWorkScheduleGroupIdApiArg = {
+  /** A work schedule group ID */
+  workScheduleGroupId: number;
+  workScheduleCreateForm: WorkScheduleCreateForm;
+};
+export type DeleteWorkSchedulesByWorkScheduleGroupIdApiResponse = unknown;
+export type DeleteWorkSchedulesByWorkScheduleGroupIdApiArg = {
+  /** A work schedule group ID */
+  workScheduleGroupId: number;
+};
 export type NewDocumentResponse = {
   document_key: number;
 };
@@ -2325,6 +2917,14 @@ export type ObjectRef = {
   obj_id: string;
   type: ObjectType;
 };
+export type ObjectsDiff = {
+  /** Objects only present in the second infra */
+  created: Operation[];
+  /** Objects only present in the first infra */
+  deleted: Operation[];
+  /** Objects present in both infras with a different content */
+  modified: Operation[];
+};
 export type InfraErrorType =
   | {
       error_type: 'duplicated_group';
@@ -2414,6 +3014,21 @@ export type InfraErrorTypeLabel =
   | 'overlapping_switches'
   | 'unknown_port_name'
   | 'unused_port';
+export type InfraEdit = {
+  /** The user who applied the operations, if known */
+  author?: string | null;
+  creation_date: string;
+  id: number;
+  infra_id: number;
+  /** The version of the infra once the operations were applied */
+  infra_version: string;
+  /** The operations reverting the applied ones */
+  inverse_operations: Operation[];
+  /** The applied operations */
+  operations: Operation[];
+  /** Whether the operations were reverted by an undo */
+  undone: boolean;
+};
 export type BoundingBox = (number & number)[][];
 export type GeoJsonPoint = {
   coordinates: GeoJsonPointValue;
@@ -2446,6 +3061,36 @@ export type GeoJson =
   | GeoJsonMultiLineString
   | GeoJsonPolygon
   | GeoJsonMultiPolygon;
+export type MergeConflictType =
+  | {
+      conflict_type: 'modified_on_both_sides';
+    }
+  | {
+      conflict_type: 'deleted_in_source';
+    }
+  | {
+      conflict_type: 'deleted_in_target';
+    }
+  | {
+      conflict_type: 'dangling_reference';
+      reference: ObjectRef;
+    };
+export type MergeConflict = MergeConflictType & {
+  obj_id: string;
+  obj_type: ObjectType;
+};
+export type MergeReport = {
+  /** Changes that were not applied */
+  conflicts: MergeConflict[];
+  /** Operations applied to the infra, or that would be applied in dry run mode */
+  operations: Operation[];
+};
+export type MergeRequest = {
+  /** The infra the source infra was cloned from */
+  base_infra_id: number;
+  /** The infra holding the changes to merge */
+  source_infra_id: number;
+};
 export type InfraObjectWithGeometry = {
   geographic: GeoJson;
   obj_id: string;
@@ -2470,6 +3115,18 @@ export type RoutePath = {
   switches_directions: (string & string)[][];
   track_ranges: DirectionalTrackRange[];
 };
+export type InfraSnapshot = {
+  creation_date: string;
+  id: number;
+  infra_id: number;
+  /** The version of the infra when the snapshot was taken */
+  infra_version: string;
+  name: string;
+};
+export type InfraSnapshotForm = {
+  /** The name of the snapshot, unique among the snapshots of the infra */
+  name: string;
+};
 export type TrackOffset = {
   /** Offset in mm */
   offset: number;
@@ -2856,6 +3513,76 @@ export type RollingStockForm = {
   startup_time: number;
   supported_signaling_systems: RollingStockSupportedSignalingSystems;
 };
+export type RollingStockComparisonResult =
+  | {
+      /** Total energy consumption */
+      energy_consumption: number;
+      /** Energy consumption per km of path */
+      energy_consumption_per_km: number;
+      /** Length of the path in mm */
+      length: number;
+      /** Highest speed reached in m/s */
+      max_speed: number;
+      /** Mean speed in m/s */
+      mean_speed: number;
+      /** Positions along the path in mm */
+      positions: number[];
+      /** Running time in ms */
+      running_time: number;
+      /** Speeds in m/s at each position */
+      speeds: number[];
+      status: 'success';
+      /** Times in ms at each position */
+      times: number[];
+    }
+  | {
+      pathfinding_result: PathfindingResult;
+      status: 'pathfinding_failed';
+    }
+  | {
+      core_error: InternalError;
+      status: 'simulation_failed';
+    };
+export type RollingStockComparison = {
+  result: RollingStockComparisonResult;
+  rolling_stock_id: number;
+  rolling_stock_name: string;
+};
+export type PathItem = (
+  | TrackOffset
+  | {
+      operational_point: string;
+    }
+  | {
+      /** An optional secondary code to identify a more specific location */
+      secondary_code?: string | null;
+      trigram: string;
+    }
+  | {
+      /** An optional secondary code to identify a more specific location */
+      secondary_code?: string | null;
+      /** The [UIC](https://en.wikipedia.org/wiki/List_of_UIC_country_codes) code of an operational point */
+      uic: number;
+    }
+) & {
+  /** Metadata given to mark a point as wishing to be deleted by the user.
+      It's useful for soft deleting the point (waiting to fix / remove all references)
+      If true, the train schedule is consider as invalid and must be edited */
+  deleted?: boolean;
+  id: string;
+};
+export type RollingStockComparisonForm = {
+  comfort?: Comfort;
+  /** The electrical profile set used by the simulations, if any */
+  electrical_profile_set_id?: number | null;
+  infra_id: number;
+  /** The path followed by every rolling stock */
+  path: PathItem[];
+  /** The rolling stocks to compare, at most 10 */
+  rolling_stock_ids: number[];
+  /** Train category for speed limits */
+  speed_limit_tag?: string | null;
+};
 export type RollingStockWithLiveries = RollingStock & {
   liveries: RollingStockLiveryMetadata[];
 };
@@ -2902,7 +3629,32 @@ export type RollingStockError =
         usage: TrainScheduleScenarioStudyProject[];
       };
     }
-  | 'BasePowerClassEmpty';
+  | 'BasePowerClassEmpty'
+  | {
+      RevisionNotFound: {
+        rolling_stock_id: number;
+        version: number;
+      };
+    };
+export type RollingStockIssueCode =
+  | 'increasing_effort'
+  | 'max_speed_not_reached'
+  | 'above_adhesion_limit'
+  | 'negative_resistance'
+  | 'missing_power_restriction_curve'
+  | 'missing_electrical_time';
+export type RollingStockIssue = {
+  code: RollingStockIssueCode;
+  /** The field of the rolling stock the issue is about (e.g. `effort_curves.modes.thermal`) */
+  field: string;
+  message: string;
+};
+export type RollingStockCheckReport = {
+  /** The issues making the simulations of the rolling stock wrong */
+  errors: RollingStockIssue[];
+  /** The issues which may not be intended */
+  warnings: RollingStockIssue[];
+};
 export type RollingStockLivery = {
   compound_image_id?: number | null;
   id: number;
@@ -2917,6 +3669,18 @@ export type RollingStockLockedUpdateForm = {
   /** New locked value */
   locked: boolean;
 };
+export type RollingStockRevisionInfo = {
+  creation_date: string;
+  /** The name of the rolling stock at this version */
+  name: string;
+  version: number;
+};
+/** A field with a different value in two rolling stocks */
+export type RollingStockFieldDiff = {
+  field: string;
+  from: any;
+  to: any;
+};
 export type SearchResultItemTrack = {
   infra_id: number;
   line_code: number;
@@ -3343,6 +4107,35 @@ export type Conflict = {
   train_ids: number[];
   train_names: string[];
 };
+export type TrainCompositionWagon = {
+  /** The number of wagons */
+  count: number;
+  inertia_coefficient: number;
+  /** Length of a wagon in m */
+  length: number;
+  /** Mass of a wagon in kg */
+  mass: number;
+  /** Maximum speed of the wagons in m/s, if they limit the speed of the train */
+  max_speed?: number | null;
+  rolling_resistance: RollingResistance;
+};
+export type TrainComposition = {
+  id: number;
+  name: string;
+  /** The rolling stock of the traction units */
+  rolling_stock_id: number;
+  /** The number of coupled traction units */
+  unit_count: number;
+  wagons: TrainCompositionWagon[];
+};
+export type TrainCompositionForm = {
+  name: string;
+  /** The rolling stock of the traction units */
+  rolling_stock_id: number;
+  /** The number of coupled traction units */
+  unit_count: number;
+  wagons?: TrainCompositionWagon[];
+};
 export type TrainSchedulePatch = {
   allowances?: Allowance[] | null;
   comfort?: RollingStockComfortType | null;
@@ -3605,13 +4398,69 @@ export type TimetableDetailedResult = {
   train_ids: number[];
 };
 export type ConflictV2 = {
-  conflict_type: 'Spacing' | 'Routing';
+  conflict_type: 'Spacing' | 'Routing' | 'WorkSchedule';
   /** Datetime of the end of the conflict */
   end_time: string;
   /** Datetime of the start of the conflict */
   start_time: string;
   /** List of train ids involved in the conflict */
   train_ids: number[];
+  /** List of work schedule ids involved in the conflict */
+  work_schedule_ids?: number[];
+};
+export type ConflictZone = {
+  geometry: GeoJsonLineString;
+  /** The track ranges of the zone, on the path of the train */
+  track_section_ranges: TrackRange[];
+  zone: string;
+};
+export type TrainConflictExplanation = {
+  /** The routes required by the train during the conflict */
+  routes: string[];
+  train_id: number;
+  /** The zones required by the train during the conflict, located on its path */
+  zones: ConflictZone[];
+};
+export type ConflictResolution =
+  | {
+      /** The new departure time of the train */
+      start_time: string;
+      train_id: number;
+      type: 'shift_departure';
+    }
+  | {
+      /** The path item where the train stops */
+      at: string;
+      /** The new duration of the stop */
+      stop_for: string;
+      train_id: number;
+      type: 'add_stop';
+    }
+  | {
+      /** The new path of the train, with via points on the detour */
+      path: PathItem[];
+      train_id: number;
+      type: 'reroute';
+    };
+export type ConflictExplanation = {
+  /** Candidate resolutions, each of them solves the conflicts of the train it changes */
+  resolutions: ConflictResolution[];
+  /** What each train of the conflict requires during the conflict */
+  trains: TrainConflictExplanation[];
+};
+export type InvalidTrainSchedule = {
+  pathfinding_result: PathfindingResult;
+  train_id: number;
+  /** The indexes of the path items which can't be located on the infra, flagged as deleted in the copy */
+  unresolved_path_items: number[];
+};
+export type TimetableDuplicateResult = {
+  /** The train schedules of the new timetable whose path is invalid on the infra */
+  invalid_train_schedules: InvalidTrainSchedule[];
+  /** The id of the new timetable */
+  timetable_id: number;
+  /** The ids of the train schedules of the new timetable */
+  train_ids: number[];
 };
 export type ReportTrainV2 = {
   /** Total energy consumption */
@@ -3700,6 +4549,8 @@ export type PathfindingItem = {
 export type Distribution = 'STANDARD' | 'MARECO';
 export type TrainScheduleBase = {
   comfort?: 'STANDARD' | 'AIR_CONDITIONING' | 'HEATING';
+  /** The composition the train is made of, simulated instead of the rolling stock if specified */
+  composition_id?: number | null;
   constraint_distribution: Distribution;
   initial_speed?: number;
   labels?: string[];
@@ -3740,7 +4591,11 @@ export type TrainScheduleBase = {
     to: string;
     value: string;
   }[];
+  /** The rolling stock the train is pinned to whatever its name, which requires `rolling_stock_version` */
+  rolling_stock_id?: number | null;
   rolling_stock_name: string;
+  /** The version of the rolling stock the train is pinned to, which requires `rolling_stock_id` */
+  rolling_stock_version?: number | null;
   schedule?: {
     /** The expected arrival time at the stop.
         This will be used to compute the final simulation time. */
@@ -3762,8 +4617,46 @@ export type TrainScheduleBase = {
 };
 export type TrainScheduleResult = TrainScheduleBase & {
   id: number;
+  /** The pattern the train schedule was generated from */
+  pattern_id?: number | null;
   timetable_id: number;
 };
+export type TrainScheduleBatchUpdateResult = {
+  /** The ids of the updated train schedules */
+  train_ids: number[];
+};
+export type Margins = {
+  boundaries: string[];
+  /** The values of the margins. Must contains one more element than the boundaries
+    Can be a percentage `X%`, a time in minutes per 100 kilometer `Xmin/100km` or `none` */
+  values: string[];
+};
+/** The changes applied to each selected train schedule */
+export type TrainScheduleBatchPatch = {
+  margins?: Margins | null;
+  /** The new rolling stock, which also removes the composition of the train schedules */
+  rolling_stock_name?: string | null;
+  /** The new speed limit tag, `null` to remove it */
+  speed_limit_tag?: string | null;
+  /** Shift of the start time in ms, negative to start earlier */
+  time_shift?: number | null;
+};
+/** Criteria selecting train schedules, a train schedule must match all of them */
+export type TrainScheduleSelection = {
+  /** A label of the train schedules */
+  label?: string | null;
+  rolling_stock_name?: string | null;
+  /** The train schedules starting at or after this time */
+  start_after?: string | null;
+  /** The train schedules starting before this time */
+  start_before?: string | null;
+  /** A case insensitive SQL `LIKE` pattern matching the name of the train schedules (e.g. `RER%`) */
+  train_name_pattern?: string | null;
+};
+export type TrainScheduleBatchUpdateForm = {
+  patch: TrainScheduleBatchPatch;
+  selection?: TrainScheduleSelection;
+};
 export type ProjectPathTrainResult = {
   /** List of signal updates along the path */
   signal_updates: {
@@ -3836,13 +4729,73 @@ export type SimulationSummaryResult =
       rolling_stock_name: string;
       status: 'rolling_stock_not_found';
     };
+export type TrainScheduleIssueCode =
+  | 'invalid_format'
+  | 'rolling_stock_not_found'
+  | 'composition_not_found'
+  | 'not_enough_path_items'
+  | 'unresolved_path_item'
+  | 'deleted_path_item'
+  | 'path_not_found'
+  | 'incompatible_path'
+  | 'arrival_before_departure'
+  | 'unordered_margin_boundary'
+  | 'margin_boundary_at_path_end';
+export type TrainScheduleIssue = {
+  code: TrainScheduleIssueCode;
+  /** The field of the train schedule the issue is about (e.g. `schedule[2].arrival`), if any */
+  field?: string | null;
+  message: string;
+};
+export type TrainScheduleValidationReport = {
+  /** The issues preventing the train schedule from being simulated */
+  errors: TrainScheduleIssue[];
+  /** The issues which may not be intended */
+  warnings: TrainScheduleIssue[];
+};
 export type TrainScheduleForm = TrainScheduleBase & {
   /** Timetable attached to the train schedule */
   timetable_id?: number | null;
 };
+export type DayOfWeek =
+  | 'monday'
+  | 'tuesday'
+  | 'wednesday'
+  | 'thursday'
+  | 'friday'
+  | 'saturday'
+  | 'sunday';
+export type Repetition = {
+  /** The days of the week (in UTC) on which trains depart, every day if empty */
+  days_of_week?: DayOfWeek[];
+  /** No train departs after this time */
+  end_time: string;
+  /** The time between the departures of two consecutive trains */
+  interval: string;
+  /** The departure time of the first train */
+  start_time: string;
+};
+export type TrainSchedulePatternResult = {
+  id: number;
+  repetition: Repetition;
+  template: TrainScheduleBase;
+  timetable_id: number;
+  /** The train schedules generated from the pattern, ordered by departure time */
+  train_ids: number[];
+};
+export type TrainSchedulePatternForm = {
+  repetition: Repetition;
+  template: TrainScheduleBase;
+  timetable_id: number;
+};
 export type Version = {
   git_describe: string | null;
 };
+export type WorkScheduleGroup = {
+  creation_date: string;
+  id: number;
+  name: string;
+};
 export type WorkScheduleCreateResponse = {
   work_schedule_group_id: number;
 };
@@ -3857,3 +4810,25 @@ export type WorkScheduleCreateForm = {
   work_schedule_group_name: string;
   work_schedules: WorkScheduleItemForm[];
 };
+export type WorkSchedule = {
+  end_date_time: string;
+  id: number;
+  obj_id: string;
+  start_date_time: string;
+  track_ranges: TrackRange[];
+  work_schedule_group_id: number;
+  work_schedule_type: 'CATENARY' | 'TRACK';
+};
+export type WorkScheduleQueryForm = {
+  /** End of the time window */
+  end_date_time: string;
+  /** Start of the time window */
+  start_date_time: string;
+  /** Only keep the work schedules with a track range on one of these track sections
+    
+    If not provided, the work schedules on any track section are kept. */
+  track_sections?: string[] | null;
+};
+export type WorkScheduleGroupWithSchedules = WorkScheduleGroup & {
+  work_schedules: WorkSchedule[];
+};
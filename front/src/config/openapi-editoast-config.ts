@@ -10,7 +10,13 @@ const config: ConfigFile = {
   tag: true,
   endpointOverrides: [
     {
-      pattern: ['postV2TrainSchedule', 'postV2TrainScheduleSimulationSummary'],
+      pattern: [
+        'postRollingStockCompare',
+        'postV2TrainSchedule',
+        'postV2TrainScheduleSimulationSummary',
+        'postV2TrainScheduleValidate',
+        'postWorkSchedulesQuery',
+      ],
       type: 'query',
     },
   ],